@@ -50,6 +50,29 @@ local function set_clipboard(register)
     end
 end
 
+local function has_image_paste_handler()
+    return type(vim.g.neovide_image_paste_handler) == "function"
+        or #vim.api.nvim_get_autocmds({ event = "User", pattern = "NeovideImagePaste" }) > 0
+end
+
+-- An image in the clipboard is saved to a file and handed to the handler, or to the
+-- `User NeovideImagePaste` autocmds
+local function paste_image()
+    if not has_image_paste_handler() then
+        return false
+    end
+    local image = rpcrequest("neovide.save_clipboard_image")
+    if type(image) ~= "table" then
+        return false
+    end
+    if type(vim.g.neovide_image_paste_handler) == "function" then
+        vim.g.neovide_image_paste_handler(image.path)
+    else
+        vim.api.nvim_exec_autocmds("User", { pattern = "NeovideImagePaste", data = image })
+    end
+    return true
+end
+
 local function get_clipboard(register)
     return function()
         return rpcrequest("neovide.get_clipboard", register)
@@ -78,6 +101,16 @@ if args.register_clipboard and not vim.g.neovide_no_custom_clipboard then
     }
     vim.g.loaded_clipboard_provider = nil
     vim.cmd.runtime("autoload/provider/clipboard.vim")
+
+    -- Pasting a clipboard that holds an image and no text hands the image to plugins instead.
+    -- Only pastes check for an image, reading the register doesn't.
+    local paste = vim.paste
+    vim.paste = function(lines, phase)
+        if phase <= 1 and vim.deep_equal(lines, { "" }) and paste_image() then
+            return true
+        end
+        return paste(lines, phase)
+    end
 end
 
 if args.register_right_click then
@@ -93,6 +126,48 @@ vim.api.nvim_create_user_command("NeovideFocus", function()
     rpcnotify("neovide.focus_window")
 end, {})
 
+vim.api.nvim_create_user_command("NeovideToggleVSync", function()
+    rpcnotify("neovide.toggle_vsync")
+end, {})
+
+vim.api.nvim_create_user_command("NeovideToggleOnTop", function()
+    rpcnotify("neovide.toggle_on_top")
+end, {})
+
+vim.api.nvim_create_user_command("NeovideInfo", function()
+    rpcnotify("neovide.info")
+end, {})
+
+vim.api.nvim_create_user_command("NeovideReloadSettings", function()
+    rpcnotify("neovide.reload_settings")
+end, {})
+
+-- The path is resolved here, Neovide might run in another directory than Neovim
+vim.api.nvim_create_user_command("NeovideExportHighlights", function(opts)
+    local path = vim.fn.fnamemodify(vim.fn.expand(opts.args), ":p")
+    local ok, err = pcall(rpcrequest, "neovide.export_highlights", path)
+    if not ok then
+        vim.notify(tostring(err), vim.log.levels.ERROR, { title = "Neovide" })
+    end
+end, { nargs = 1, complete = "file" })
+
+vim.api.nvim_create_user_command("NeovideCommandPalette", function()
+    -- The builtin commands, and the global and buffer local user commands
+    local commands = {}
+    for _, name in ipairs(vim.fn.getcompletion("", "command")) do
+        commands[name] = true
+    end
+    for name in pairs(vim.api.nvim_get_commands({})) do
+        commands[name] = true
+    end
+    for name in pairs(vim.api.nvim_buf_get_commands(0, {})) do
+        commands[name] = true
+    end
+    local names = vim.tbl_keys(commands)
+    table.sort(names)
+    rpcnotify("neovide.command_palette", names)
+end, {})
+
 
 if vim.fn.has("mac") == 1 then
     local URL_PATTERN = "https?://[%w-_%.]+%.%w[%w-_%.%%%?%.:/+=&%%[%]#]*"
@@ -282,6 +357,461 @@ if vim.fn.has("macunix") == 1 then
     update_document_state()
 end
 
+local buffer_bar_group = vim.api.nvim_create_augroup("NeovideBufferBar", { clear = true })
+-- The externalized tabline doesn't include the modified flag of the buffers
+local function notify_modified_buffers()
+    local modified = {}
+    for _, buffer in ipairs(vim.api.nvim_list_bufs()) do
+        if vim.bo[buffer].buflisted and vim.bo[buffer].modified then
+            table.insert(modified, buffer)
+        end
+    end
+    pcall(rpcnotify, "neovide.modified_buffers", modified)
+end
+
+local function update_buffer_bar()
+    vim.api.nvim_clear_autocmds({ group = buffer_bar_group })
+
+    if vim.g.neovide_buffer_bar then
+        vim.api.nvim_create_autocmd("BufModifiedSet", {
+            group = buffer_bar_group,
+            callback = notify_modified_buffers,
+        })
+        notify_modified_buffers()
+    end
+end
+
+vim.fn.WatchGlobal("neovide_buffer_bar", update_buffer_bar)
+update_buffer_bar()
+
+local gutter_icons_group = vim.api.nvim_create_augroup("NeovideGutterIcons", { clear = true })
+local gutter_icons_pending = false
+local severity_names = { "Error", "Warn", "Info", "Hint" }
+
+local function sign_color(severity)
+    local ok, hl = pcall(vim.api.nvim_get_hl, 0, { name = "DiagnosticSign" .. severity_names[severity], link = false })
+    return ok and hl.fg or nil
+end
+
+-- The sign columns that are one sign, so two cells, wide
+local single_sign_columns = { yes = true, auto = true, ["yes:1"] = true, ["auto:1"] = true }
+
+-- Sign placements aren't part of the UI protocol, so the rows of the diagnostic signs of each
+-- window are sent separately. The icons are drawn over the first two columns of the text area,
+-- which only hold the sign column when it's one sign wide and there's no fold column in front of
+-- it. The other windows, and 'rightleft' ones with the sign column on the right, keep their text
+-- signs.
+local function gutter_signs(win)
+    local signs = {}
+    local info = vim.fn.getwininfo(win)[1]
+    if
+        not vim.g.neovide_gui_gutter_icons
+        or not info
+        or info.textoff < 2
+        or not single_sign_columns[vim.wo[win].signcolumn]
+        or vim.wo[win].foldcolumn ~= "0"
+        or vim.wo[win].rightleft
+        or vim.api.nvim_win_get_config(win).relative ~= ""
+        or not vim.diagnostic.config().signs
+    then
+        return signs
+    end
+
+    local window_row = vim.fn.win_screenpos(win)[1]
+    for _, diagnostic in ipairs(vim.diagnostic.get(vim.api.nvim_win_get_buf(win))) do
+        local line = diagnostic.lnum + 1
+        if line >= info.topline and line <= info.botline then
+            local row = vim.fn.screenpos(win, line, 1).row
+            if row > 0 then
+                table.insert(signs, {
+                    row = row - window_row,
+                    severity = diagnostic.severity,
+                    color = sign_color(diagnostic.severity),
+                })
+            end
+        end
+    end
+    return signs
+end
+
+local function notify_gutter_signs()
+    gutter_icons_pending = false
+    for _, win in ipairs(vim.api.nvim_tabpage_list_wins(0)) do
+        pcall(rpcnotify, "neovide.gutter_signs", win, gutter_signs(win))
+    end
+end
+
+-- Several of the events usually fire together, so they are batched into one update
+local function schedule_gutter_signs()
+    if not gutter_icons_pending then
+        gutter_icons_pending = true
+        vim.schedule(notify_gutter_signs)
+    end
+end
+
+local function update_gutter_icons()
+    vim.api.nvim_clear_autocmds({ group = gutter_icons_group })
+
+    if vim.g.neovide_gui_gutter_icons then
+        vim.api.nvim_create_autocmd({
+            "DiagnosticChanged",
+            "WinScrolled",
+            "WinResized",
+            "BufWinEnter",
+            "TabEnter",
+            "TextChanged",
+            "TextChangedI",
+            "ColorScheme",
+        }, {
+            group = gutter_icons_group,
+            callback = schedule_gutter_signs,
+        })
+    end
+    -- Also clears the icons when disabled
+    schedule_gutter_signs()
+end
+
+vim.fn.WatchGlobal("neovide_gui_gutter_icons", update_gutter_icons)
+update_gutter_icons()
+
+local number_fade_group = vim.api.nvim_create_augroup("NeovideNumberFade", { clear = true })
+local number_fade_pending = false
+
+-- The columns of the line numbers after the left margin of the window, like Neovim sizes them.
+-- They are the last part of the text offset, after the fold and the sign column. Windows with a
+-- 'statuscolumn' or 'rightleft' don't fade their numbers.
+local function number_column(win)
+    local info = vim.fn.getwininfo(win)[1]
+    local wo = vim.wo[win]
+    if
+        not vim.g.neovide_number_fade
+        or not info
+        or not (wo.number or wo.relativenumber)
+        or wo.statuscolumn ~= ""
+        or wo.rightleft
+    then
+        return nil
+    end
+    local lines = wo.number and vim.api.nvim_buf_line_count(vim.api.nvim_win_get_buf(win))
+        or vim.api.nvim_win_get_height(win)
+    local width = math.min(math.max(#tostring(lines), wo.numberwidth - 1) + 1, info.textoff)
+    return info.textoff - width, width
+end
+
+local function notify_number_columns()
+    number_fade_pending = false
+    for _, win in ipairs(vim.api.nvim_tabpage_list_wins(0)) do
+        local start, width = number_column(win)
+        if start then
+            pcall(rpcnotify, "neovide.number_column", win, start, width)
+        else
+            pcall(rpcnotify, "neovide.number_column", win)
+        end
+    end
+end
+
+local function schedule_number_columns()
+    if not number_fade_pending then
+        number_fade_pending = true
+        vim.schedule(notify_number_columns)
+    end
+end
+
+local function update_number_fade()
+    vim.api.nvim_clear_autocmds({ group = number_fade_group })
+
+    if vim.g.neovide_number_fade then
+        vim.api.nvim_create_autocmd({
+            "WinNew",
+            "WinResized",
+            "BufWinEnter",
+            "TabEnter",
+            "TextChanged",
+            "TextChangedI",
+        }, {
+            group = number_fade_group,
+            callback = schedule_number_columns,
+        })
+        vim.api.nvim_create_autocmd("OptionSet", {
+            group = number_fade_group,
+            pattern = {
+                "number",
+                "relativenumber",
+                "numberwidth",
+                "signcolumn",
+                "foldcolumn",
+                "statuscolumn",
+                "rightleft",
+            },
+            callback = schedule_number_columns,
+        })
+    end
+    -- Also clears the columns when disabled
+    schedule_number_columns()
+end
+
+vim.fn.WatchGlobal("neovide_number_fade", update_number_fade)
+update_number_fade()
+
+local tab_indicators_group = vim.api.nvim_create_augroup("NeovideTabIndicators", { clear = true })
+local tab_indicators_pending = false
+
+local function tab_indicator_color()
+    local ok, hl = pcall(vim.api.nvim_get_hl, 0, { name = "NonText", link = false })
+    return ok and hl.fg or nil
+end
+
+-- Without 'list', tabs are drawn as blank cells which the grid doesn't tell apart from spaces, so
+-- the screen columns of the tabs in the visible lines of each window are sent separately.
+-- Windows with 'list' already show them with 'listchars'.
+local function tab_indicators(win)
+    local tabs = {}
+    local info = vim.fn.getwininfo(win)[1]
+    if
+        not vim.g.neovide_show_tabs_indicator
+        or not info
+        or vim.wo[win].list
+        or vim.api.nvim_win_get_config(win).relative ~= ""
+    then
+        return tabs
+    end
+
+    local window_position = vim.fn.win_screenpos(win)
+    local color = tab_indicator_color()
+    local lines = vim.api.nvim_buf_get_lines(vim.api.nvim_win_get_buf(win), info.topline - 1, info.botline, false)
+    vim.api.nvim_win_call(win, function()
+        for index, text in ipairs(lines) do
+            local line = info.topline + index - 1
+            -- The tabs of closed folds are hidden
+            local column = vim.fn.foldclosed(line) == -1 and text:find("\t", 1, true) or nil
+            while column do
+                local position = vim.fn.screenpos(win, line, column)
+                if position.row > 0 then
+                    table.insert(tabs, {
+                        row = position.row - window_position[1],
+                        column = position.col - window_position[2],
+                        end_column = position.endcol - window_position[2],
+                        color = color,
+                    })
+                end
+                column = text:find("\t", column + 1, true)
+            end
+        end
+    end)
+    return tabs
+end
+
+local function notify_tab_indicators()
+    tab_indicators_pending = false
+    for _, win in ipairs(vim.api.nvim_tabpage_list_wins(0)) do
+        pcall(rpcnotify, "neovide.tab_indicators", win, tab_indicators(win))
+    end
+end
+
+local function schedule_tab_indicators()
+    if not tab_indicators_pending then
+        tab_indicators_pending = true
+        vim.schedule(notify_tab_indicators)
+    end
+end
+
+local function update_tab_indicators()
+    vim.api.nvim_clear_autocmds({ group = tab_indicators_group })
+
+    if vim.g.neovide_show_tabs_indicator then
+        vim.api.nvim_create_autocmd({
+            "WinScrolled",
+            "WinResized",
+            "BufWinEnter",
+            "TabEnter",
+            "TextChanged",
+            "TextChangedI",
+            "ColorScheme",
+        }, {
+            group = tab_indicators_group,
+            callback = schedule_tab_indicators,
+        })
+        vim.api.nvim_create_autocmd("OptionSet", {
+            group = tab_indicators_group,
+            pattern = { "list", "tabstop", "vartabstop", "wrap" },
+            callback = schedule_tab_indicators,
+        })
+    end
+    -- Also clears the indicators when disabled
+    schedule_tab_indicators()
+end
+
+vim.fn.WatchGlobal("neovide_show_tabs_indicator", update_tab_indicators)
+update_tab_indicators()
+
+-- Neovim only uses 'winblend' for floating windows, Neovide blends the background of the other
+-- windows too. The text of 'rightleft' windows is mirrored, which the shaper has to know about, and
+-- g:neovide_crisp_listchars draws the markers of the 'listchars' of each window. Nothing is sent
+-- for windows that never changed 'winblend' or 'rightleft' from the default.
+local windows_with_options = {}
+
+local function notify_window_option(win, name, value, default)
+    windows_with_options[win] = windows_with_options[win] or {}
+    if value ~= default then
+        windows_with_options[win][name] = true
+    end
+    if windows_with_options[win][name] then
+        pcall(rpcnotify, "neovide.window_" .. name, win, value)
+    end
+end
+
+local function notify_window_options()
+    for _, win in ipairs(vim.api.nvim_tabpage_list_wins(0)) do
+        if vim.api.nvim_win_get_config(win).relative == "" then
+            notify_window_option(win, "blend", vim.wo[win].winblend, 0)
+        end
+        notify_window_option(win, "rightleft", vim.wo[win].rightleft, false)
+        if vim.g.neovide_crisp_listchars then
+            pcall(rpcnotify, "neovide.window_listchars", win, vim.wo[win].listchars)
+        end
+    end
+end
+
+vim.api.nvim_create_autocmd({ "OptionSet" }, {
+    pattern = { "winblend", "rightleft", "listchars" },
+    nested = true,
+    callback = notify_window_options,
+})
+vim.api.nvim_create_autocmd({ "VimEnter", "WinNew", "TabEnter", "BufWinEnter" }, {
+    -- The grid of a new window is only known after the next redraw
+    callback = function()
+        vim.schedule(notify_window_options)
+    end,
+})
+vim.api.nvim_create_autocmd({ "WinClosed" }, {
+    callback = function(args)
+        windows_with_options[tonumber(args.match)] = nil
+    end,
+})
+vim.fn.WatchGlobal("neovide_crisp_listchars", function()
+    vim.schedule(notify_window_options)
+end)
+
+-- Inactive splits show a dim cursor where their cursor was left, with g:neovide_ghost_cursors
+vim.api.nvim_create_autocmd({ "WinLeave" }, {
+    callback = function()
+        local win = vim.api.nvim_get_current_win()
+        if vim.g.neovide_ghost_cursors and vim.api.nvim_win_get_config(win).relative == "" then
+            -- The position in the text area, below the winbar
+            pcall(rpcnotify, "neovide.ghost_cursor", win, vim.fn.winline() - 1, vim.fn.wincol() - 1)
+        end
+    end,
+})
+vim.api.nvim_create_autocmd({ "WinEnter" }, {
+    callback = function()
+        if vim.g.neovide_ghost_cursors then
+            pcall(rpcnotify, "neovide.ghost_cursor", vim.api.nvim_get_current_win())
+        end
+    end,
+})
+vim.api.nvim_create_autocmd({ "WinClosed" }, {
+    callback = function(args)
+        if vim.g.neovide_ghost_cursors then
+            pcall(rpcnotify, "neovide.ghost_cursor", tonumber(args.match))
+        end
+    end,
+})
+
+-- For screencasts, the last executed command stays on screen for g:neovide_cmdline_linger_ms
+local function cmdline_linger_enabled()
+    return (tonumber(vim.g.neovide_cmdline_linger_ms) or 0) > 0
+end
+
+vim.api.nvim_create_autocmd({ "CmdlineEnter" }, {
+    callback = function()
+        if cmdline_linger_enabled() then
+            pcall(rpcnotify, "neovide.cmdline_enter")
+        end
+    end,
+})
+vim.api.nvim_create_autocmd({ "CmdlineLeave" }, {
+    -- Only commands and searches, input() prompts could be asking for a password
+    pattern = { ":", "/", "?" },
+    callback = function(args)
+        if cmdline_linger_enabled() and not vim.v.event.abort then
+            pcall(rpcnotify, "neovide.cmdline_leave", args.match .. vim.fn.getcmdline())
+        end
+    end,
+})
+
+-- Terminal programs set the title with OSC 0 and 2, which Neovim only stores in b:term_title.
+-- Like the title Neovim sets itself, it's only shown with 'title', and 'titlestring' wins.
+vim.api.nvim_create_autocmd({ "TermRequest" }, {
+    callback = function(args)
+        if
+            args.buf ~= vim.api.nvim_get_current_buf()
+            or not vim.o.title
+            or vim.o.titlestring ~= ""
+        then
+            return
+        end
+        -- The sequence is in a table since Neovim 0.11
+        local sequence = type(args.data) == "table" and args.data.sequence or args.data
+        if type(sequence) == "string" and sequence:match("^\027%][02];") then
+            pcall(rpcnotify, "neovide.terminal_request", sequence)
+        end
+    end,
+})
+
+-- Neovim keeps the cursor centered in the middle of the buffer, and Neovide moves the text at the
+-- start and the end of the buffer. Only the local 'scrolloff' of the windows is changed, and put
+-- back when it's turned off. The original value is kept in `w:neovide_typewriter_scrolloff`, which
+-- is only set on the windows Neovide changed.
+local typewriter_group = vim.api.nvim_create_augroup("NeovideTypewriterScroll", { clear = true })
+local typewriter_scrolloff = 999
+
+local function center_cursor_line(win)
+    local is_float = vim.api.nvim_win_get_config(win).relative ~= ""
+    if vim.w[win].neovide_typewriter_scrolloff ~= nil or is_float then
+        return
+    end
+    local local_option = { scope = "local", win = win }
+    local scrolloff = vim.api.nvim_get_option_value("scrolloff", local_option)
+    -- A new split inherits the centering of the window it was split from, and uses the global
+    -- value again once it's turned off
+    if scrolloff == typewriter_scrolloff then
+        scrolloff = -1
+    end
+    vim.w[win].neovide_typewriter_scrolloff = scrolloff
+    vim.api.nvim_set_option_value("scrolloff", typewriter_scrolloff, local_option)
+end
+
+local function restore_scrolloff(win)
+    local scrolloff = vim.w[win].neovide_typewriter_scrolloff
+    if scrolloff == nil then
+        return
+    end
+    vim.api.nvim_set_option_value("scrolloff", scrolloff, { scope = "local", win = win })
+    vim.w[win].neovide_typewriter_scrolloff = nil
+end
+
+local function update_typewriter_scroll()
+    vim.api.nvim_clear_autocmds({ group = typewriter_group })
+    if vim.g.neovide_typewriter_scroll then
+        for _, win in ipairs(vim.api.nvim_list_wins()) do
+            center_cursor_line(win)
+        end
+        vim.api.nvim_create_autocmd("WinEnter", {
+            group = typewriter_group,
+            callback = function()
+                center_cursor_line(vim.api.nvim_get_current_win())
+            end,
+        })
+    else
+        for _, win in ipairs(vim.api.nvim_list_wins()) do
+            restore_scrolloff(win)
+        end
+    end
+end
+
+vim.fn.WatchGlobal("neovide_typewriter_scroll", update_typewriter_scroll)
+update_typewriter_scroll()
+
 -- Create auto command for retrieving exit code from neovim on quit.
 vim.api.nvim_create_autocmd({ "VimLeavePre" }, {
     pattern = "*",
@@ -292,13 +822,136 @@ vim.api.nvim_create_autocmd({ "VimLeavePre" }, {
     end,
 })
 
-M.private.dropfile = function(filename, tabs)
+M.private.dropfile = function(filename, tabs, cursor)
     vim.api.nvim_cmd({
         cmd = "drop",
         args = { vim.fn.fnameescape(filename) },
         -- Always open as the last tabpage
         mods = tabs and { tab = #vim.api.nvim_list_tabpages() } or {},
     }, {})
+    if cursor then
+        vim.fn.cursor(cursor[1], cursor[2])
+    end
+end
+
+M.private.copy_selection = function()
+    local mode = vim.fn.mode()
+    -- "\22" is the blockwise visual mode, <C-v>
+    if mode ~= "v" and mode ~= "V" and mode ~= "\22" then
+        return
+    end
+    local lines = vim.fn.getregion(vim.fn.getpos("v"), vim.fn.getpos("."), { type = mode })
+    if mode == "V" then
+        -- A trailing newline makes the selection paste linewise again
+        table.insert(lines, "")
+    end
+    set_clipboard("*")(lines)
+end
+
+M.private.switch_tab = function(tab)
+    if vim.api.nvim_tabpage_is_valid(tab) then
+        vim.api.nvim_set_current_tabpage(tab)
+    end
+end
+
+M.private.close_tab = function(tab)
+    if vim.api.nvim_tabpage_is_valid(tab) then
+        vim.cmd.tabclose(vim.api.nvim_tabpage_get_number(tab))
+    end
+end
+
+M.private.switch_buffer = function(buffer)
+    if vim.api.nvim_buf_is_valid(buffer) then
+        vim.api.nvim_set_current_buf(buffer)
+    end
+end
+
+-- Resizes the split when its separator is dragged
+M.private.resize_window = function(win, vertical, size)
+    if not vim.api.nvim_win_is_valid(win) or vim.api.nvim_win_get_config(win).relative ~= "" then
+        return
+    end
+    if vertical then
+        vim.api.nvim_win_set_width(win, size)
+    else
+        vim.api.nvim_win_set_height(win, size)
+    end
+end
+
+-- The split at the screen position, or nil when a focusable floating window covers it
+local function split_at(row, col)
+    local split = nil
+    for _, win in ipairs(vim.api.nvim_tabpage_list_wins(0)) do
+        local info = vim.fn.getwininfo(win)[1]
+        local top = info.winrow - 1
+        local left = info.wincol - 1
+        if
+            row >= top
+            and row < top + info.winbar + info.height
+            and col >= left
+            and col < left + info.width
+        then
+            local config = vim.api.nvim_win_get_config(win)
+            if config.relative == "" then
+                split = win
+            elseif config.focusable then
+                return nil
+            end
+        end
+    end
+    return split
+end
+
+local focus_timer = nil
+local focus_delay = 100
+
+-- Focuses the split under the mouse once it rested there, so that the splits crossed on the way
+-- don't take the focus
+M.private.focus_window = function(row, col)
+    if focus_timer == nil then
+        focus_timer = vim.loop.new_timer()
+    end
+    focus_timer:stop()
+    focus_timer:start(
+        focus_delay,
+        0,
+        vim.schedule_wrap(function()
+            if vim.api.nvim_get_mode().mode:sub(1, 1) == "c" then
+                return
+            end
+            local win = split_at(row, col)
+            if win ~= nil and win ~= vim.api.nvim_get_current_win() then
+                pcall(vim.api.nvim_set_current_win, win)
+            end
+        end)
+    )
+end
+
+M.private.paste_selection = function()
+    local lines = get_clipboard("*")()[1]
+    local count = #lines
+    -- Stream large selections in chunks, the same way a terminal paste arrives
+    local chunk_lines = 1000
+    for first = 1, count, chunk_lines do
+        local last = math.min(first + chunk_lines - 1, count)
+        local chunk = table.concat(lines, "\n", first, last)
+        local phase
+        if first == 1 and last == count then
+            phase = -1
+        elseif first == 1 then
+            phase = 1
+        elseif last == count then
+            phase = 3
+        else
+            phase = 2
+        end
+        if last < count then
+            chunk = chunk .. "\n"
+        end
+        if not vim.api.nvim_paste(chunk, false, phase) then
+            return
+        end
+    end
 end
 
 M.private.can_set_background = function()
@@ -446,6 +1099,52 @@ pcall(vim.api.nvim_create_autocmd, 'Progress', {
     end,
 })
 
+---Sends keys to Neovim the way Neovide sends the keyboard input, for scripts and tests.
+---@param keys string Key notation, like "<Esc>ihello<Esc>"
+M.input = function(keys)
+    rpcnotify("neovide.input", keys)
+end
+
+---Where the cursor is drawn in the window right now, following its animation, for tools that
+---anchor native windows to it. Nil until the first frame is drawn.
+---@return {logical: table, physical: table, scale_factor: number}|nil
+M.cursor_screen_position = function()
+    return rpcrequest("neovide.cursor_screen_position")
+end
+
+---Draws a rectangle on top of the window, or moves the one drawn with the same id.
+---Positions and sizes are in pixels from the top left corner of the window.
+---@param id string
+---@param color integer|nil Like 0xff8800, the foreground color when nil
+M.draw_rect = function(id, x, y, width, height, color)
+    rpcnotify("neovide.overlay_set", id, {
+        kind = "rect", x = x, y = y, width = width, height = height, color = color,
+    })
+end
+
+---Draws a line of text in the font of the grid, with its top left corner at x, y.
+---@param id string
+---@param color integer|nil
+M.draw_text = function(id, x, y, text, color)
+    rpcnotify("neovide.overlay_set", id, { kind = "text", x = x, y = y, text = text, color = color })
+end
+
+---Draws a line from x1, y1 to x2, y2, 1 pixel wide by default.
+---@param id string
+---@param color integer|nil
+---@param width number|nil
+M.draw_line = function(id, x1, y1, x2, y2, color, width)
+    rpcnotify("neovide.overlay_set", id, {
+        kind = "line", x1 = x1, y1 = y1, x2 = x2, y2 = y2, color = color, width = width,
+    })
+end
+
+---Removes what was drawn with the id, or everything drawn by the functions above when it's nil.
+---@param id string|nil
+M.clear_overlay = function(id)
+    rpcnotify("neovide.overlay_clear", id)
+end
+
 ---@param preedit_raw_text string
 ---@param cursor_offset_start integer This values show the cursor begin position. The position is byte-wise indexed.
 ---@param cursor_offset_end integer This values show the cursor end position. The position is byte-wise indexed.
@@ -1,3 +1,10 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
 use rmpv::Value;
 
 use crate::clipboard::Clipboard;
@@ -49,3 +56,86 @@ pub fn set_clipboard_contents(
 
     Ok(Value::Nil)
 }
+
+// Pasted images are left for the plugin to read or move, and removed on a later paste
+const PASTED_IMAGE_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+const PASTED_IMAGE_PREFIX: &str = "neovide-paste-";
+const PASTED_IMAGE_SUFFIX: &str = ".png";
+
+/// Saves the image in the clipboard to a new temporary file, so that it can be handed to a plugin
+/// through `User NeovideImagePaste`. Returns nil when the clipboard holds no image. Reading the
+/// image can wait for the clipboard owner, so it doesn't hold the lock of the clipboard.
+pub fn save_clipboard_image() -> Result<Value, String> {
+    let Some(image) = Clipboard::get_image() else {
+        return Ok(Value::Nil);
+    };
+    let dir = std::env::temp_dir();
+    remove_old_pasted_images(&dir, SystemTime::now());
+    let path = save_pasted_image(&dir, &image).map_err(|error| error.to_string())?;
+    Ok(image_paste_payload(&path))
+}
+
+// The file gets a random name and is created only when nothing exists at its path, so nobody can
+// make it point elsewhere beforehand
+fn save_pasted_image(dir: &Path, image: &[u8]) -> std::io::Result<PathBuf> {
+    let mut file = tempfile::Builder::new()
+        .prefix(PASTED_IMAGE_PREFIX)
+        .suffix(PASTED_IMAGE_SUFFIX)
+        .tempfile_in(dir)?;
+    file.write_all(image)?;
+    let (_, path) = file.keep().map_err(|error| error.error)?;
+    Ok(path)
+}
+
+fn remove_old_pasted_images(dir: &Path, now: SystemTime) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let is_pasted_image = name.to_str().is_some_and(|name| {
+            name.starts_with(PASTED_IMAGE_PREFIX) && name.ends_with(PASTED_IMAGE_SUFFIX)
+        });
+        // The metadata of the entry itself, a link is removed and not followed
+        let is_old =
+            entry.metadata().and_then(|metadata| metadata.modified()).is_ok_and(|modified| {
+                now.duration_since(modified).is_ok_and(|age| age >= PASTED_IMAGE_LIFETIME)
+            });
+        if is_pasted_image && is_old {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+// The data of the autocmd
+fn image_paste_payload(path: &Path) -> Value {
+    Value::Map(vec![(Value::from("path"), Value::from(path.to_string_lossy().into_owned()))])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pasted_images_get_new_files_and_old_ones_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = save_pasted_image(dir.path(), b"first").unwrap();
+        let second = save_pasted_image(dir.path(), b"second").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read(&second).unwrap(), b"second");
+        let name = first.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with(PASTED_IMAGE_PREFIX) && name.ends_with(PASTED_IMAGE_SUFFIX));
+
+        let unrelated = dir.path().join("notes.png");
+        fs::write(&unrelated, b"").unwrap();
+        let modified = fs::metadata(&first).unwrap().modified().unwrap();
+        remove_old_pasted_images(dir.path(), modified + PASTED_IMAGE_LIFETIME);
+        assert!(!first.exists() && !second.exists());
+        assert!(unrelated.exists());
+
+        assert_eq!(
+            image_paste_payload(Path::new("/tmp/neovide-paste-a.png")),
+            Value::Map(vec![(Value::from("path"), Value::from("/tmp/neovide-paste-a.png"))])
+        );
+    }
+}
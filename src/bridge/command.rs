@@ -130,14 +130,19 @@ fn build_nvim_command_parts(
 }
 
 fn build_open_args(cmdline_settings: &CmdLineSettings, open_mode: OpenMode) -> Vec<String> {
-    let (files_to_open, tabs) = match open_mode {
+    let (files_to_open, tabs, cursor) = match open_mode {
         OpenMode::None => return Vec::new(),
-        OpenMode::Startup => (cmdline_settings.files_to_open.clone(), cmdline_settings.tabs),
-        OpenMode::Args(args) => (args.files_to_open, args.tabs),
+        OpenMode::Startup => (
+            cmdline_settings.files_to_open.clone(),
+            cmdline_settings.tabs,
+            cmdline_settings.open_url.as_ref().and_then(|url| url.cursor()),
+        ),
+        OpenMode::Args(args) => (args.files_to_open, args.tabs, None),
     };
 
     tabs.then(|| "-p".to_string())
         .into_iter()
+        .chain(cursor.map(|(line, column)| format!("+call cursor({line}, {column})")))
         .chain(handle_wslpaths(files_to_open, cmdline_settings.wsl))
         .collect()
 }
@@ -310,6 +315,16 @@ mod tests {
         assert_eq!(args, vec!["--embed", "-p", "./foo.txt", "./bar.md"]);
     }
 
+    #[test]
+    fn build_nvim_command_parts_moves_cursor_for_open_urls() {
+        let cmdline_settings =
+            parse_cmdline_settings(&["neovide", "neovide://open?file=/tmp/foo.txt&line=42"]);
+
+        let (_, args) = build_nvim_command_parts(&cmdline_settings, true, OpenMode::Startup);
+
+        assert_eq!(args, vec!["--embed", "-p", "+call cursor(42, 1)", "/tmp/foo.txt"]);
+    }
+
     #[test]
     fn build_nvim_command_parts_skips_auto_open_args_when_requested() {
         let cmdline_settings =
@@ -2,6 +2,7 @@ use std::{
     convert::TryInto,
     error,
     fmt::{self, Debug},
+    ops::Range,
 };
 
 use log::{debug, warn};
@@ -12,6 +13,9 @@ use strum::AsRefStr;
 use super::RestartDetails;
 use crate::{
     editor::{Colors, CursorMode, CursorShape, Style, UnderlineStyle},
+    renderer::{
+        gutter_icons::GutterSign, lua_overlays::OverlayCommand, tab_indicators::TabIndicator,
+    },
     window::UserEvent,
 };
 
@@ -151,6 +155,42 @@ pub enum GuiOption {
     Unknown(String, Value),
 }
 
+/// A tab page as reported by the externalized tabline
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TabInfo {
+    pub handle: u64,
+    pub name: String,
+}
+
+/// A listed buffer as reported by the externalized tabline
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BufferInfo {
+    pub handle: u64,
+    pub name: String,
+}
+
+/// A highlight that the attributes of `hl_attr_define` were combined from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HighlightInfo {
+    /// "ui", "syntax" or "terminal"
+    pub kind: String,
+    /// The highlight group, after following the links
+    pub hi_name: Option<String>,
+    /// The builtin UI element, for the "ui" kind
+    pub ui_name: Option<String>,
+}
+
+impl HighlightInfo {
+    /// The name of the highlight group, UI elements without a group go by their own name
+    pub fn group(&self) -> Option<&str> {
+        match (&self.hi_name, self.kind.as_str()) {
+            (Some(hi_name), _) => Some(hi_name),
+            (None, "ui") => self.ui_name.as_deref(),
+            (None, _) => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum WindowAnchor {
     NorthWest,
@@ -257,6 +297,7 @@ pub enum RedrawEvent {
         id: u64,
         style: Style,
         name: Option<String>,
+        infos: Vec<HighlightInfo>,
     },
     HighlightGroupSet {
         name: String,
@@ -273,6 +314,8 @@ pub enum RedrawEvent {
         /// The column to start redrawing at.
         column_start: u64,
         cells: Vec<GridLineCell>,
+        /// The line continues on the next row, for lines wrapped by `'wrap'`
+        wrap: bool,
     },
     /// Highlight a range of cells without redrawing text.
     GridHighlight {
@@ -311,6 +354,7 @@ pub enum RedrawEvent {
     /// previously hidden, it should now be shown again.
     WindowPosition {
         grid: u64,
+        window: u64,
         start_row: u64,
         start_column: u64,
         width: u64,
@@ -363,7 +407,6 @@ pub enum RedrawEvent {
         row: u64,
         /// Indicates whether the message area has been scrolled to cover other grids.
         scrolled: bool,
-        #[allow(unused)]
         separator_character: String,
         z_index: Option<u64>,
         comp_index: Option<u64>,
@@ -383,15 +426,13 @@ pub enum RedrawEvent {
     /// win_viewport is received.
     WindowViewport {
         grid: u64,
-        #[allow(unused)]
+        window: u64,
         top_line: f64,
-        #[allow(unused)]
         bottom_line: f64,
         #[allow(unused)]
         current_line: f64,
         #[allow(unused)]
         current_column: f64,
-        #[allow(unused)]
         line_count: Option<f64>,
         scroll_delta: Option<f64>,
     },
@@ -463,14 +504,56 @@ pub enum RedrawEvent {
     MessageHistoryShow {
         entries: Vec<(MessageKind, StyledContent)>,
     },
+    /// The tab pages or listed buffers changed, only sent when the tabline is externalized.
+    TablineUpdate {
+        current_tab: u64,
+        tabs: Vec<TabInfo>,
+        /// Not sent by Neovim versions before 0.10
+        current_buffer: Option<u64>,
+        buffers: Vec<BufferInfo>,
+    },
     Suspend,
     NeovimSessionStarted,
     StartupMessageUiRestored,
     NeovideSetRedraw(bool),
     NeovideIntroBannerAllowed(bool),
+    NeovideModifiedBuffers(Vec<u64>),
+    NeovideGutterSigns {
+        window: u64,
+        signs: Vec<GutterSign>,
+    },
+    NeovideTabIndicators {
+        window: u64,
+        tabs: Vec<TabIndicator>,
+    },
+    NeovideWindowBlend {
+        window: u64,
+        blend: u8,
+    },
+    NeovideWindowRightLeft {
+        window: u64,
+        right_left: bool,
+    },
+    /// The cells of the line numbers after the left margin of the window, if it shows them
+    NeovideNumberColumn {
+        window: u64,
+        columns: Option<Range<u64>>,
+    },
+    NeovideWindowListChars {
+        window: u64,
+        listchars: String,
+    },
+    NeovideGhostCursor {
+        window: u64,
+        position: Option<(u64, u64)>,
+    },
+    NeovideCmdlineEnter,
+    NeovideCmdlineLeave(String),
+    NeovideOverlay(OverlayCommand),
+    NeovidePredictedEcho(char),
 }
 
-fn unpack_color(packed_color: u64) -> Color4f {
+pub(super) fn unpack_color(packed_color: u64) -> Color4f {
     let packed_color = packed_color as u32;
     let r = ((packed_color & 0x00ff_0000) >> 16) as f32;
     let g = ((packed_color & 0xff00) >> 8) as f32;
@@ -661,7 +744,32 @@ fn parse_default_colors(default_colors_arguments: Vec<Value>) -> Result<RedrawEv
     })
 }
 
-fn parse_style(style_map: Value, _info_array: Value) -> Result<Style> {
+/// Parses the `infos` of `hl_attr_define`, in the order Neovim combined the highlights. The array
+/// is only filled with `ext_hlstate`.
+fn parse_highlight_infos(info_array: Value) -> Vec<HighlightInfo> {
+    let Value::Array(infos) = info_array else {
+        return Vec::new();
+    };
+    infos
+        .into_iter()
+        .filter_map(|info| {
+            let Value::Map(info) = info else { return None };
+            let field = |name: &str| {
+                info.iter()
+                    .find(|(key, _)| key.as_str() == Some(name))
+                    .and_then(|(_, value)| value.as_str())
+                    .map(str::to_owned)
+            };
+            Some(HighlightInfo {
+                kind: field("kind")?,
+                hi_name: field("hi_name"),
+                ui_name: field("ui_name"),
+            })
+        })
+        .collect()
+}
+
+fn parse_style(style_map: Value, infos: &[HighlightInfo]) -> Result<Style> {
     let attributes = parse_map(style_map)?;
 
     let mut style = Style::new(Colors::new(None, None, None));
@@ -708,6 +816,17 @@ fn parse_style(style_map: Value, _info_array: Value) -> Result<Style> {
             debug!("Invalid attribute format");
         }
     }
+    for group in infos.iter().filter_map(HighlightInfo::group) {
+        if !style.highlight_groups.iter().any(|name| name == group) {
+            style.highlight_groups.push(group.to_owned());
+        }
+    }
+    let ui_elements = infos.iter().filter(|info| info.kind == "ui");
+    for ui_name in ui_elements.filter_map(|info| info.ui_name.as_deref()) {
+        if !style.ui_elements.iter().any(|name| name == ui_name) {
+            style.ui_elements.push(ui_name.to_owned());
+        }
+    }
 
     Ok(style)
 }
@@ -749,11 +868,13 @@ fn parse_hl_name(infos: Value) -> Option<String> {
 fn parse_hl_attr_define(hl_attr_define_arguments: Vec<Value>) -> Result<RedrawEvent> {
     let [id, attributes, _terminal_attributes, infos] = extract_values(hl_attr_define_arguments)?;
 
-    let style = parse_style(attributes, infos.clone())?;
+    let highlight_infos = parse_highlight_infos(infos.clone());
+    let style = parse_style(attributes, &highlight_infos)?;
     Ok(RedrawEvent::HighlightAttributesDefine {
         id: parse_u64(id)?,
         style,
         name: parse_hl_name(infos),
+        infos: highlight_infos,
     })
 }
 
@@ -782,7 +903,8 @@ fn parse_grid_line_cell(grid_line_cell: Value) -> Result<GridLineCell> {
 }
 
 fn parse_grid_line(grid_line_arguments: Vec<Value>) -> Result<RedrawEvent> {
-    let [grid_id, row, column_start, cells] = extract_values(grid_line_arguments)?;
+    let ([grid_id, row, column_start, cells], [wrap]) =
+        extract_values_with_optional(grid_line_arguments)?;
 
     Ok(RedrawEvent::GridLine {
         grid: parse_u64(grid_id)?,
@@ -792,6 +914,7 @@ fn parse_grid_line(grid_line_arguments: Vec<Value>) -> Result<RedrawEvent> {
             .into_iter()
             .map(parse_grid_line_cell)
             .collect::<Result<Vec<GridLineCell>>>()?,
+        wrap: wrap.map(parse_bool).transpose()?.unwrap_or(false),
     })
 }
 
@@ -859,11 +982,11 @@ fn parse_grid_scroll(grid_scroll_arguments: Vec<Value>) -> Result<RedrawEvent> {
 }
 
 fn parse_win_pos(win_pos_arguments: Vec<Value>) -> Result<RedrawEvent> {
-    let [grid, _window, start_row, start_column, width, height] =
-        extract_values(win_pos_arguments)?;
+    let [grid, window, start_row, start_column, width, height] = extract_values(win_pos_arguments)?;
 
     Ok(RedrawEvent::WindowPosition {
         grid: parse_u64(grid)?,
+        window: parse_handle(window)?,
         start_row: parse_u64(start_row)?,
         start_column: parse_u64(start_column)?,
         width: parse_u64(width)?,
@@ -936,12 +1059,13 @@ fn parse_msg_set_pos(msg_set_pos_arguments: Vec<Value>) -> Result<RedrawEvent> {
 
 fn parse_win_viewport(win_viewport_arguments: Vec<Value>) -> Result<RedrawEvent> {
     let (
-        [grid, _window, top_line, bottom_line, current_line, current_column],
+        [grid, window, top_line, bottom_line, current_line, current_column],
         [line_count, scroll_delta],
     ) = extract_values_with_optional(win_viewport_arguments)?;
 
     Ok(RedrawEvent::WindowViewport {
         grid: parse_u64(grid)?,
+        window: parse_handle(window)?,
         top_line: parse_f64(top_line)?,
         bottom_line: parse_f64(bottom_line)?,
         current_line: parse_f64(current_line)?,
@@ -1068,6 +1192,60 @@ fn parse_msg_history_show(msg_history_show_arguments: Vec<Value>) -> Result<Redr
     })
 }
 
+/// Tabpage, window and buffer handles are sent as msgpack extension types wrapping an integer
+fn parse_handle(handle_value: Value) -> Result<u64> {
+    match &handle_value {
+        Value::Ext(_, data) => rmpv::decode::read_value(&mut data.as_slice())
+            .ok()
+            .and_then(|value| value.as_u64())
+            .ok_or_else(|| ParseError::U64(handle_value.clone())),
+        _ => parse_u64(handle_value),
+    }
+}
+
+/// Parses a `{ <handle_key>: handle, name: string }` entry of the tabline
+fn parse_tabline_entry(entry_value: Value, handle_key: &str) -> Result<(u64, String)> {
+    let mut handle = None;
+    let mut name = None;
+    for (key, value) in parse_map(entry_value.clone())? {
+        match parse_string(key)?.as_str() {
+            "name" => name = Some(parse_string(value)?),
+            key if key == handle_key => handle = Some(parse_handle(value)?),
+            _ => {}
+        }
+    }
+
+    handle.zip(name).ok_or(ParseError::Map(entry_value))
+}
+
+fn parse_tab_info(tab_info_value: Value) -> Result<TabInfo> {
+    let (handle, name) = parse_tabline_entry(tab_info_value, "tab")?;
+    Ok(TabInfo { handle, name })
+}
+
+fn parse_buffer_info(buffer_info_value: Value) -> Result<BufferInfo> {
+    let (handle, name) = parse_tabline_entry(buffer_info_value, "buffer")?;
+    Ok(BufferInfo { handle, name })
+}
+
+fn parse_tabline_update(tabline_update_arguments: Vec<Value>) -> Result<RedrawEvent> {
+    let ([current_tab, tabs], [current_buffer, buffers]) =
+        extract_values_with_optional(tabline_update_arguments)?;
+
+    Ok(RedrawEvent::TablineUpdate {
+        current_tab: parse_handle(current_tab)?,
+        tabs: parse_array(tabs)?.into_iter().map(parse_tab_info).collect::<Result<_>>()?,
+        current_buffer: current_buffer.map(parse_handle).transpose()?,
+        buffers: buffers
+            .map(parse_array)
+            .transpose()?
+            .unwrap_or_default()
+            .into_iter()
+            .map(parse_buffer_info)
+            .collect::<Result<_>>()?,
+    })
+}
+
 pub fn parse_redraw_event(event_value: Value) -> Result<Vec<RedrawEvent>> {
     let mut event_contents = parse_array(event_value)?.into_iter();
     let event_name = event_contents
@@ -1124,6 +1302,7 @@ pub fn parse_redraw_event(event_value: Value) -> Result<Vec<RedrawEvent>> {
             "msg_showcmd" => Some(parse_msg_showcmd(event_parameters)),
             "msg_ruler" => Some(parse_msg_ruler(event_parameters)),
             "msg_history_show" => Some(parse_msg_history_show(event_parameters)),
+            "tabline_update" => Some(parse_tabline_update(event_parameters)),
             "suspend" => Some(Ok(RedrawEvent::Suspend)),
             _ => None,
         };
@@ -1159,7 +1338,15 @@ pub fn parse_progress_bar_event(value: Option<&Value>) -> Option<UserEvent> {
 mod tests {
     use rmpv::Value;
 
-    use super::{MessageKind, RedrawEvent, parse_msg_show};
+    use super::{
+        BufferInfo, HighlightInfo, MessageKind, RedrawEvent, TabInfo, parse_highlight_infos,
+        parse_msg_show, parse_style, parse_tabline_update,
+    };
+
+    fn tabpage(handle: u8) -> Value {
+        // Tabpage handles are msgpack extension type 2 wrapping a positive fixint
+        Value::Ext(2, vec![handle])
+    }
 
     #[test]
     fn message_kind_marks_error_variants() {
@@ -1211,4 +1398,147 @@ mod tests {
             event => panic!("expected MessageShow, got {event:?}"),
         }
     }
+
+    #[test]
+    fn tabline_update_parses_tabpage_handles() {
+        let tab = |handle, name: &str| {
+            Value::Map(vec![
+                (Value::from("tab"), tabpage(handle)),
+                (Value::from("name"), Value::from(name)),
+            ])
+        };
+        let event = parse_tabline_update(vec![
+            tabpage(2),
+            Value::Array(vec![tab(1, "main.rs"), tab(2, "[No Name]")]),
+            Value::Ext(0, vec![1]),
+            Value::Array(vec![]),
+        ])
+        .unwrap();
+
+        match event {
+            RedrawEvent::TablineUpdate { current_tab, tabs, .. } => {
+                assert_eq!(current_tab, 2);
+                assert_eq!(
+                    tabs,
+                    vec![
+                        TabInfo { handle: 1, name: "main.rs".to_owned() },
+                        TabInfo { handle: 2, name: "[No Name]".to_owned() },
+                    ]
+                );
+            }
+            event => panic!("expected TablineUpdate, got {event:?}"),
+        }
+    }
+
+    #[test]
+    fn tabline_update_rejects_tabs_without_a_handle() {
+        let tabs = Value::Array(vec![Value::Map(vec![(Value::from("name"), Value::from("a"))])]);
+        assert!(parse_tabline_update(vec![tabpage(1), tabs]).is_err());
+    }
+
+    #[test]
+    fn tabline_update_parses_the_buffer_list() {
+        let buffer = |handle, name: &str| {
+            Value::Map(vec![
+                (Value::from("buffer"), Value::Ext(0, vec![handle])),
+                (Value::from("name"), Value::from(name)),
+            ])
+        };
+        let event = parse_tabline_update(vec![
+            tabpage(1),
+            Value::Array(vec![]),
+            Value::Ext(0, vec![3]),
+            Value::Array(vec![buffer(1, "/tmp/a.rs"), buffer(3, "")]),
+        ])
+        .unwrap();
+
+        match event {
+            RedrawEvent::TablineUpdate { current_buffer, buffers, .. } => {
+                assert_eq!(current_buffer, Some(3));
+                assert_eq!(
+                    buffers,
+                    vec![
+                        BufferInfo { handle: 1, name: "/tmp/a.rs".to_owned() },
+                        BufferInfo { handle: 3, name: String::new() },
+                    ]
+                );
+            }
+            event => panic!("expected TablineUpdate, got {event:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_style_records_the_highlight_groups() {
+        let info = |kind: &str, name: &str| {
+            Value::Map(vec![
+                (Value::from("kind"), Value::from(kind)),
+                (Value::from("hi_name"), Value::from(name)),
+                (Value::from("id"), Value::from(12)),
+            ])
+        };
+        let attributes = Value::Map(vec![(Value::from("italic"), Value::from(true))]);
+        let infos = Value::Array(vec![
+            info("syntax", "Comment"),
+            info("ui", "CursorLine"),
+            info("syntax", "Comment"),
+        ]);
+
+        let style = parse_style(attributes.clone(), &parse_highlight_infos(infos)).unwrap();
+        assert!(style.italic);
+        assert_eq!(style.highlight_groups, vec!["Comment", "CursorLine"]);
+
+        let style = parse_style(attributes.clone(), &[]).unwrap();
+        assert!(style.highlight_groups.is_empty());
+
+        // DiffAdd linked to another group keeps its element name
+        let linked = HighlightInfo {
+            kind: "ui".to_owned(),
+            hi_name: Some("GitSignsAddLn".to_owned()),
+            ui_name: Some("DiffAdd".to_owned()),
+        };
+        let style = parse_style(attributes, &[linked]).unwrap();
+        assert_eq!(style.highlight_groups, vec!["GitSignsAddLn"]);
+        assert_eq!(style.ui_elements, vec!["DiffAdd"]);
+    }
+
+    #[test]
+    fn highlight_infos_are_parsed_from_the_infos_array() {
+        let infos = Value::Array(vec![
+            Value::Map(vec![
+                (Value::from("kind"), Value::from("ui")),
+                (Value::from("ui_name"), Value::from("CursorLine")),
+                (Value::from("hi_name"), Value::from("CursorLine")),
+                (Value::from("id"), Value::from(40)),
+            ]),
+            Value::Map(vec![
+                (Value::from("kind"), Value::from("syntax")),
+                (Value::from("hi_name"), Value::from("Comment")),
+                (Value::from("id"), Value::from(12)),
+            ]),
+        ]);
+
+        assert_eq!(
+            parse_highlight_infos(infos),
+            vec![
+                HighlightInfo {
+                    kind: "ui".to_owned(),
+                    hi_name: Some("CursorLine".to_owned()),
+                    ui_name: Some("CursorLine".to_owned()),
+                },
+                HighlightInfo {
+                    kind: "syntax".to_owned(),
+                    hi_name: Some("Comment".to_owned()),
+                    ui_name: None,
+                },
+            ]
+        );
+        assert_eq!(parse_highlight_infos(Value::Nil), vec![]);
+
+        let info = HighlightInfo {
+            kind: "ui".to_owned(),
+            hi_name: None,
+            ui_name: Some("Pmenu".to_owned()),
+        };
+        assert_eq!(info.group(), Some("Pmenu"));
+    }
 }
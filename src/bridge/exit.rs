@@ -0,0 +1,79 @@
+use std::process::ExitStatus;
+
+// Only the tail of stderr is shown, the rest is in the log
+const MAX_STDERR_LINES: usize = 8;
+
+/// How the Neovim process of a window ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum NeovimExit {
+    /// Neovim quit on its own, or the connection was closed
+    Quit,
+    /// Neovim was killed by a signal or exited with an error
+    Crashed { reason: String, stderr: Vec<String> },
+}
+
+impl NeovimExit {
+    pub fn new(status: Option<ExitStatus>, stderr: Vec<String>) -> Self {
+        let Some(status) = status else {
+            return Self::Quit;
+        };
+
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+
+        Self::from_code(status.code(), signal, stderr)
+    }
+
+    /// Exit codes below 128 are chosen by Neovim itself, like `:cquit 3`, and are not crashes.
+    /// Higher or negative codes come from the OS, for example an unhandled exception on Windows.
+    pub fn from_code(code: Option<i32>, signal: Option<i32>, mut stderr: Vec<String>) -> Self {
+        let reason = match (code, signal) {
+            (_, Some(signal)) => format!("Neovim was killed by signal {signal}"),
+            (Some(code), None) if !(0..128).contains(&code) => {
+                format!("Neovim exited with code {code}")
+            }
+            _ => return Self::Quit,
+        };
+        stderr.drain(..stderr.len().saturating_sub(MAX_STDERR_LINES));
+        Self::Crashed { reason, stderr }
+    }
+
+    pub fn is_crash(&self) -> bool {
+        matches!(self, Self::Crashed { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NeovimExit;
+
+    #[test]
+    fn normal_exit_codes_are_not_crashes() {
+        assert_eq!(NeovimExit::from_code(Some(0), None, vec![]), NeovimExit::Quit);
+        assert_eq!(NeovimExit::from_code(Some(3), None, vec!["E37".into()]), NeovimExit::Quit);
+        assert_eq!(NeovimExit::from_code(None, None, vec![]), NeovimExit::Quit);
+    }
+
+    #[test]
+    fn signals_and_os_exit_codes_are_crashes() {
+        assert_eq!(
+            NeovimExit::from_code(None, Some(11), vec![]),
+            NeovimExit::Crashed { reason: "Neovim was killed by signal 11".into(), stderr: vec![] }
+        );
+        assert!(NeovimExit::from_code(Some(134), None, vec![]).is_crash());
+        assert!(NeovimExit::from_code(Some(-1073741819), None, vec![]).is_crash());
+    }
+
+    #[test]
+    fn only_the_last_stderr_lines_are_kept() {
+        let stderr = (0..20).map(|line| line.to_string()).collect();
+        let NeovimExit::Crashed { stderr, .. } = NeovimExit::from_code(Some(139), None, stderr)
+        else {
+            panic!("expected a crash");
+        };
+        assert_eq!(stderr.first().map(String::as_str), Some("12"));
+        assert_eq!(stderr.len(), 8);
+    }
+}
@@ -1,4 +1,8 @@
+use std::collections::HashMap;
 use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
 use std::sync::{
     Arc, Mutex, RwLock,
     atomic::{AtomicBool, Ordering},
@@ -16,15 +20,22 @@ use crate::window::ForceClickKind;
 use crate::{
     LoggingReceiver, LoggingSender,
     bridge::{
-        GuiOption, NeovimWriter, ParallelCommand, RedrawEvent,
-        clipboard::{get_clipboard_contents, set_clipboard_contents},
-        events::parse_redraw_event,
+        GuiOption, NeovimWriter, ParallelCommand, RedrawEvent, SerialCommand,
+        clipboard::{get_clipboard_contents, save_clipboard_image, set_clipboard_contents},
+        events::{parse_redraw_event, unpack_color},
         parse_progress_bar_event, send_ui,
     },
     clipboard::ClipboardHandle,
+    editor::{Style, highlight_table_json},
     error_handling::ResultPanicExplanation,
+    renderer::{
+        gutter_icons::{DiagnosticSeverity, GutterSign},
+        lua_overlays::{DEFAULT_LINE_WIDTH, OverlayCommand, OverlayItem},
+        tab_indicators::TabIndicator,
+    },
     running_tracker::RunningTracker,
     settings::{FontConfigState, Settings},
+    units::PixelRect,
     window::{EventPayload, RouteId, UserEvent, WindowCommand},
 };
 
@@ -36,6 +47,7 @@ enum ClipboardRequestError {
     LockUnavailable(String),
     CannotGetContents,
     CannotSetContents,
+    CannotSaveImage,
 }
 
 impl fmt::Display for ClipboardRequestError {
@@ -45,6 +57,7 @@ impl fmt::Display for ClipboardRequestError {
             Self::LockUnavailable(source) => write!(f, "clipboard unavailable: {source}"),
             Self::CannotGetContents => write!(f, "cannot get clipboard contents"),
             Self::CannotSetContents => write!(f, "cannot set clipboard contents"),
+            Self::CannotSaveImage => write!(f, "cannot save the clipboard image"),
         }
     }
 }
@@ -76,6 +89,36 @@ struct NeovimState {
     pre_attach_cmdheight: Option<Value>,
 }
 
+/// Where the cursor is drawn in the window, published by the window every frame for
+/// `neovide.cursor_screen_position`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CursorScreenPosition {
+    pub physical: PixelRect<f32>,
+    pub scale_factor: f64,
+}
+
+impl CursorScreenPosition {
+    fn to_value(self) -> Value {
+        let rect_value = |rect: PixelRect<f32>| {
+            let size = rect.size();
+            Value::Map(vec![
+                (Value::from("x"), Value::from(rect.min.x)),
+                (Value::from("y"), Value::from(rect.min.y)),
+                (Value::from("width"), Value::from(size.width)),
+                (Value::from("height"), Value::from(size.height)),
+            ])
+        };
+        let scale_factor = self.scale_factor as f32;
+        let logical =
+            PixelRect::new(self.physical.min / scale_factor, self.physical.max / scale_factor);
+        Value::Map(vec![
+            (Value::from("logical"), rect_value(logical)),
+            (Value::from("physical"), rect_value(self.physical)),
+            (Value::from("scale_factor"), Value::from(self.scale_factor)),
+        ])
+    }
+}
+
 #[derive(Clone)]
 pub struct NeovimHandler {
     // The EventLoopProxy is not sync on all platforms, so wrap it in a mutex
@@ -90,6 +133,9 @@ pub struct NeovimHandler {
     #[allow(dead_code)]
     settings: Arc<Settings>,
     clipboard: ClipboardHandle,
+    cursor_screen_position: Arc<Mutex<Option<CursorScreenPosition>>>,
+    // A copy of the highlight table for `neovide.export_highlights`
+    defined_styles: Arc<Mutex<HashMap<u64, Arc<Style>>>>,
 }
 
 impl std::fmt::Debug for NeovimHandler {
@@ -121,6 +167,8 @@ impl NeovimHandler {
             route_id,
             settings,
             clipboard,
+            cursor_screen_position: Arc::new(Mutex::new(None)),
+            defined_styles: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -167,6 +215,10 @@ impl NeovimHandler {
     pub fn send_redraw_event(&self, event: RedrawEvent) {
         let _ = self.redraw_event_sender.send(event);
     }
+
+    pub fn set_cursor_screen_position(&self, position: CursorScreenPosition) {
+        *self.cursor_screen_position.lock().unwrap() = Some(position);
+    }
 }
 
 #[async_trait]
@@ -192,12 +244,31 @@ impl Handler for NeovimHandler {
                     .map_err(|_| ClipboardRequestError::CannotSetContents)
             })
             .map_err(Value::from),
+            "neovide.save_clipboard_image" => tokio::task::spawn_blocking(save_clipboard_image)
+                .await
+                .map_err(|_| ClipboardRequestError::CannotSaveImage)
+                .and_then(|result| result.map_err(|_| ClipboardRequestError::CannotSaveImage))
+                .map_err(Value::from),
             "neovide.quit" => {
                 let error_code =
                     arguments[0].as_i64().expect("Could not parse error code from neovim");
                 self.running_tracker.quit_with_code(error_code as u8, "Quit from neovim");
                 Ok(Value::Nil)
             }
+            // Nil until the window drew its first frame
+            "neovide.cursor_screen_position" => Ok(self
+                .cursor_screen_position
+                .lock()
+                .unwrap()
+                .map_or(Value::Nil, CursorScreenPosition::to_value)),
+            "neovide.export_highlights" => match arguments.first().and_then(Value::as_str) {
+                Some(path) => {
+                    export_highlights(&self.defined_styles.lock().unwrap(), Path::new(path))
+                        .map(|()| Value::Nil)
+                        .map_err(Value::from)
+                }
+                None => Err(Value::from("neovide.export_highlights expects a path")),
+            },
             _ => Ok(Value::from("rpcrequest not handled")),
         }
     }
@@ -230,6 +301,15 @@ impl Handler for NeovimHandler {
                                 let _ = self.proxy.lock().unwrap().send_event(payload);
                             }
                             _ => {
+                                if let RedrawEvent::HighlightAttributesDefine {
+                                    id, style, ..
+                                } = &parsed_event
+                                {
+                                    self.defined_styles
+                                        .lock()
+                                        .unwrap()
+                                        .insert(*id, Arc::new(style.clone()));
+                                }
                                 let _ = self.redraw_event_sender.send(parsed_event);
                             }
                         }
@@ -261,6 +341,27 @@ impl Handler for NeovimHandler {
             "neovide.focus_window" => {
                 self.send_window_command(WindowCommand::FocusWindow);
             }
+            "neovide.toggle_vsync" => {
+                self.send_window_command(WindowCommand::ToggleVSync);
+            }
+            "neovide.toggle_on_top" => {
+                self.send_window_command(WindowCommand::ToggleAlwaysOnTop);
+            }
+            "neovide.info" => {
+                self.send_window_command(WindowCommand::ShowInfo);
+            }
+            "neovide.command_palette" => match parse_command_palette_args(&arguments) {
+                Some(commands) => {
+                    self.send_window_command(WindowCommand::ShowCommandPalette(commands));
+                }
+                None => {
+                    warn!("neovide.command_palette called with invalid arguments: {arguments:?}")
+                }
+            },
+            "neovide.reload_settings" => {
+                let proxy = self.proxy.lock().unwrap().clone();
+                self.settings.reload_values(&neovim, &proxy, self.route_id).await;
+            }
             #[cfg(target_os = "macos")]
             "neovide.document_state" => match parse_document_state_args(&arguments) {
                 Some((path, modified)) => {
@@ -286,6 +387,10 @@ impl Handler for NeovimHandler {
                 }
                 None => warn!("neovide.force_click called with invalid arguments: {arguments:?}"),
             },
+            "neovide.input" => match parse_input_args(&arguments) {
+                Some(command) => send_ui(command, self),
+                None => warn!("neovide.input called with invalid arguments: {arguments:?}"),
+            },
             "neovide.exec_detach_handler" => {
                 send_ui(ParallelCommand::Quit, self);
             }
@@ -295,6 +400,108 @@ impl Handler for NeovimHandler {
                     let _ = self.redraw_event_sender.send(RedrawEvent::NeovideSetRedraw(value));
                 }
             }
+            "neovide.modified_buffers" => match parse_modified_buffers_args(&arguments) {
+                Some(buffers) => {
+                    let _ =
+                        self.redraw_event_sender.send(RedrawEvent::NeovideModifiedBuffers(buffers));
+                }
+                None => {
+                    warn!("neovide.modified_buffers called with invalid arguments: {arguments:?}")
+                }
+            },
+            "neovide.gutter_signs" => match parse_gutter_signs_args(&arguments) {
+                Some((window, signs)) => {
+                    let _ = self
+                        .redraw_event_sender
+                        .send(RedrawEvent::NeovideGutterSigns { window, signs });
+                }
+                None => warn!("neovide.gutter_signs called with invalid arguments: {arguments:?}"),
+            },
+            "neovide.tab_indicators" => match parse_tab_indicators_args(&arguments) {
+                Some((window, tabs)) => {
+                    let _ = self
+                        .redraw_event_sender
+                        .send(RedrawEvent::NeovideTabIndicators { window, tabs });
+                }
+                None => {
+                    warn!("neovide.tab_indicators called with invalid arguments: {arguments:?}")
+                }
+            },
+            "neovide.window_blend" => match parse_window_blend_args(&arguments) {
+                Some((window, blend)) => {
+                    let _ = self
+                        .redraw_event_sender
+                        .send(RedrawEvent::NeovideWindowBlend { window, blend });
+                }
+                None => warn!("neovide.window_blend called with invalid arguments: {arguments:?}"),
+            },
+            "neovide.window_rightleft" => match parse_window_rightleft_args(&arguments) {
+                Some((window, right_left)) => {
+                    let _ = self
+                        .redraw_event_sender
+                        .send(RedrawEvent::NeovideWindowRightLeft { window, right_left });
+                }
+                None => {
+                    warn!("neovide.window_rightleft called with invalid arguments: {arguments:?}")
+                }
+            },
+            "neovide.number_column" => match parse_number_column_args(&arguments) {
+                Some((window, columns)) => {
+                    let _ = self
+                        .redraw_event_sender
+                        .send(RedrawEvent::NeovideNumberColumn { window, columns });
+                }
+                None => warn!("neovide.number_column called with invalid arguments: {arguments:?}"),
+            },
+            "neovide.window_listchars" => match parse_window_listchars_args(&arguments) {
+                Some((window, listchars)) => {
+                    let _ = self
+                        .redraw_event_sender
+                        .send(RedrawEvent::NeovideWindowListChars { window, listchars });
+                }
+                None => {
+                    warn!("neovide.window_listchars called with invalid arguments: {arguments:?}")
+                }
+            },
+            "neovide.ghost_cursor" => match parse_ghost_cursor_args(&arguments) {
+                Some((window, position)) => {
+                    let _ = self
+                        .redraw_event_sender
+                        .send(RedrawEvent::NeovideGhostCursor { window, position });
+                }
+                None => warn!("neovide.ghost_cursor called with invalid arguments: {arguments:?}"),
+            },
+            "neovide.cmdline_enter" => {
+                let _ = self.redraw_event_sender.send(RedrawEvent::NeovideCmdlineEnter);
+            }
+            "neovide.cmdline_leave" => match arguments.first().and_then(Value::as_str) {
+                Some(text) => {
+                    let _ = self
+                        .redraw_event_sender
+                        .send(RedrawEvent::NeovideCmdlineLeave(text.to_owned()));
+                }
+                None => warn!("neovide.cmdline_leave called with invalid arguments: {arguments:?}"),
+            },
+            "neovide.terminal_request" => {
+                match arguments.first().and_then(Value::as_str).and_then(parse_terminal_title) {
+                    Some(title) => {
+                        let _ = self.redraw_event_sender.send(RedrawEvent::SetTitle { title });
+                    }
+                    None => trace!("Ignored terminal request {arguments:?}"),
+                }
+            }
+            "neovide.overlay_set" => match parse_overlay_set_args(&arguments) {
+                Some(command) => {
+                    let _ = self.redraw_event_sender.send(RedrawEvent::NeovideOverlay(command));
+                }
+                None => warn!("neovide.overlay_set called with invalid arguments: {arguments:?}"),
+            },
+            "neovide.overlay_clear" => match parse_overlay_clear_args(&arguments) {
+                Some(command) => {
+                    let _ = self.redraw_event_sender.send(RedrawEvent::NeovideOverlay(command));
+                }
+                None => warn!("neovide.overlay_clear called with invalid arguments: {arguments:?}"),
+            },
             "neovide.intro_banner_allowed" => {
                 if let Some(value) = arguments.first()
                     && let Some(allowed) = value.as_bool()
@@ -325,6 +532,13 @@ impl Handler for NeovimHandler {
     }
 }
 
+fn export_highlights(styles: &HashMap<u64, Arc<Style>>, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&highlight_table_json(styles))
+        .map_err(|error| error.to_string())?;
+    fs::write(path, json)
+        .map_err(|error| format!("cannot export the highlights to {}: {error}", path.display()))
+}
+
 #[cfg(target_os = "macos")]
 fn parse_force_click_args(
     arguments: &[Value],
@@ -354,6 +568,179 @@ fn parse_document_state_args(arguments: &[Value]) -> Option<(String, bool)> {
     Some((path.as_str().unwrap_or("").to_string(), modified.as_bool().unwrap_or(false)))
 }
 
+/// Scripted input takes the path of the keyboard, so Neovim handles the keys in whatever mode it's
+/// in when they arrive
+fn parse_input_args(arguments: &[Value]) -> Option<SerialCommand> {
+    let [keys, ..] = arguments else {
+        return None;
+    };
+
+    Some(SerialCommand::Keyboard(keys.as_str()?.to_string()))
+}
+
+fn parse_command_palette_args(arguments: &[Value]) -> Option<Vec<String>> {
+    let [commands, ..] = arguments else {
+        return None;
+    };
+
+    commands.as_array()?.iter().map(|command| command.as_str().map(str::to_owned)).collect()
+}
+
+fn parse_modified_buffers_args(arguments: &[Value]) -> Option<Vec<u64>> {
+    let [buffers, ..] = arguments else {
+        return None;
+    };
+
+    buffers.as_array()?.iter().map(Value::as_u64).collect()
+}
+
+/// Parses a window handle and a list of `{ row, severity, color }` signs
+fn parse_gutter_signs_args(arguments: &[Value]) -> Option<(u64, Vec<GutterSign>)> {
+    let [window, signs, ..] = arguments else {
+        return None;
+    };
+
+    let signs = signs
+        .as_array()?
+        .iter()
+        .map(|sign| {
+            let field = |name: &str| {
+                sign.as_map()?.iter().find(|(key, _)| key.as_str() == Some(name)).map(|(_, v)| v)
+            };
+            Some(GutterSign {
+                row: field("row")?.as_u64()?,
+                severity: DiagnosticSeverity::from_index(field("severity")?.as_u64()?)?,
+                color: field("color").and_then(Value::as_u64).map(unpack_color),
+            })
+        })
+        .collect::<Option<_>>()?;
+    Some((window.as_u64()?, signs))
+}
+
+/// Parses a window handle and a list of `{ row, column, end_column, color }` tabs
+fn parse_tab_indicators_args(arguments: &[Value]) -> Option<(u64, Vec<TabIndicator>)> {
+    let [window, tabs, ..] = arguments else {
+        return None;
+    };
+
+    let tabs = tabs
+        .as_array()?
+        .iter()
+        .map(|tab| {
+            let field = |name: &str| {
+                tab.as_map()?.iter().find(|(key, _)| key.as_str() == Some(name)).map(|(_, v)| v)
+            };
+            Some(TabIndicator {
+                row: field("row")?.as_u64()?,
+                column: field("column")?.as_u64()?,
+                end_column: field("end_column")?.as_u64()?,
+                color: field("color").and_then(Value::as_u64).map(unpack_color),
+            })
+        })
+        .collect::<Option<_>>()?;
+    Some((window.as_u64()?, tabs))
+}
+
+fn parse_window_blend_args(arguments: &[Value]) -> Option<(u64, u8)> {
+    let [window, blend, ..] = arguments else {
+        return None;
+    };
+
+    Some((window.as_u64()?, blend.as_u64()?.min(100) as u8))
+}
+
+fn parse_window_rightleft_args(arguments: &[Value]) -> Option<(u64, bool)> {
+    let [window, right_left, ..] = arguments else {
+        return None;
+    };
+
+    Some((window.as_u64()?, right_left.as_bool()?))
+}
+
+/// A window and the start and width of its number column, or only the window without one
+fn parse_number_column_args(arguments: &[Value]) -> Option<(u64, Option<Range<u64>>)> {
+    match arguments {
+        [window, start, width, ..] => {
+            let start = start.as_u64()?;
+            Some((window.as_u64()?, Some(start..start + width.as_u64()?)))
+        }
+        [window] => Some((window.as_u64()?, None)),
+        _ => None,
+    }
+}
+
+fn parse_window_listchars_args(arguments: &[Value]) -> Option<(u64, String)> {
+    let [window, listchars, ..] = arguments else {
+        return None;
+    };
+
+    Some((window.as_u64()?, listchars.as_str()?.to_owned()))
+}
+
+/// A window and the row and column its cursor was left at, or only the window when the cursor
+/// entered it again or it closed
+fn parse_ghost_cursor_args(arguments: &[Value]) -> Option<(u64, Option<(u64, u64)>)> {
+    match arguments {
+        [window, row, column, ..] => {
+            Some((window.as_u64()?, Some((row.as_u64()?, column.as_u64()?))))
+        }
+        [window] => Some((window.as_u64()?, None)),
+        _ => None,
+    }
+}
+
+/// Returns the title set by an OSC 0 or 2 sequence of a program running in a terminal buffer,
+/// which Neovim forwards with the TermRequest event
+fn parse_terminal_title(sequence: &str) -> Option<String> {
+    let title = sequence.strip_prefix("\x1b]0;").or_else(|| sequence.strip_prefix("\x1b]2;"))?;
+    // Accept the sequence with or without its terminator, BEL or ST
+    let title =
+        title.strip_suffix('\x07').or_else(|| title.strip_suffix("\x1b\\")).unwrap_or(title);
+    Some(title.to_owned())
+}
+
+fn parse_overlay_set_args(arguments: &[Value]) -> Option<OverlayCommand> {
+    let [id, item, ..] = arguments else {
+        return None;
+    };
+
+    let field = |name: &str| {
+        item.as_map()?.iter().find(|(key, _)| key.as_str() == Some(name)).map(|(_, v)| v)
+    };
+    let number = |name: &str| field(name)?.as_f64().map(|value| value as f32);
+    let color = field("color").and_then(Value::as_u64).map(unpack_color);
+    let item = match field("kind")?.as_str()? {
+        "rect" => OverlayItem::Rect {
+            x: number("x")?,
+            y: number("y")?,
+            width: number("width")?,
+            height: number("height")?,
+            color,
+        },
+        "text" => OverlayItem::Text {
+            x: number("x")?,
+            y: number("y")?,
+            text: field("text")?.as_str()?.to_owned(),
+            color,
+        },
+        "line" => OverlayItem::Line {
+            from: (number("x1")?, number("y1")?),
+            to: (number("x2")?, number("y2")?),
+            width: number("width").unwrap_or(DEFAULT_LINE_WIDTH),
+            color,
+        },
+        _ => return None,
+    };
+    Some(OverlayCommand::Set { id: id.as_str()?.to_owned(), item })
+}
+
+fn parse_overlay_clear_args(arguments: &[Value]) -> Option<OverlayCommand> {
+    match arguments.first() {
+        None | Some(Value::Nil) => Some(OverlayCommand::Clear(None)),
+        Some(id) => Some(OverlayCommand::Clear(Some(id.as_str()?.to_owned()))),
+    }
+}
+
 async fn skip_default_guifont(
     event: &RedrawEvent,
     settings: &Settings,
@@ -386,12 +773,24 @@ fn is_guifont_option_set(event: &RedrawEvent) -> bool {
 #[cfg(test)]
 mod tests {
     use std::{
+        collections::HashMap,
         panic::{AssertUnwindSafe, catch_unwind},
         sync::{Arc, Mutex},
     };
 
-    use super::{ClipboardRequestError, handle_clipboard_request};
-    use crate::clipboard::{Clipboard, ClipboardError, ClipboardHandle, ProviderState};
+    use rmpv::Value;
+
+    use super::{
+        ClipboardRequestError, CursorScreenPosition, export_highlights, handle_clipboard_request,
+        parse_ghost_cursor_args, parse_input_args, parse_number_column_args,
+        parse_overlay_clear_args, parse_overlay_set_args, parse_terminal_title,
+    };
+    use crate::{
+        bridge::SerialCommand,
+        clipboard::{Clipboard, ClipboardError, ClipboardHandle, ProviderState},
+        renderer::lua_overlays::{DEFAULT_LINE_WIDTH, OverlayCommand, OverlayItem},
+        units::{PixelPos, PixelRect},
+    };
 
     fn unavailable_clipboard(error: ClipboardError) -> Arc<Mutex<Clipboard>> {
         Arc::new(Mutex::new(Clipboard::from_provider_states_for_test(
@@ -439,4 +838,160 @@ mod tests {
 
         assert!(matches!(error, ClipboardRequestError::LockUnavailable(_)));
     }
+
+    #[test]
+    fn scripted_input_is_sent_as_keyboard_input() {
+        let command = parse_input_args(&[Value::from("<Esc>ihello<Esc>")]);
+        assert!(
+            matches!(command, Some(SerialCommand::Keyboard(keys)) if keys == "<Esc>ihello<Esc>")
+        );
+
+        assert!(parse_input_args(&[]).is_none());
+        assert!(parse_input_args(&[Value::from(1)]).is_none());
+    }
+
+    fn overlay_item(fields: &[(&str, Value)]) -> Value {
+        Value::Map(fields.iter().map(|(key, value)| (Value::from(*key), value.clone())).collect())
+    }
+
+    #[test]
+    fn overlay_items_are_deserialized() {
+        let rect = overlay_item(&[
+            ("kind", Value::from("rect")),
+            ("x", Value::from(10)),
+            ("y", Value::from(20.5)),
+            ("width", Value::from(100)),
+            ("height", Value::from(4)),
+            ("color", Value::from(0xff0000)),
+        ]);
+        let Some(OverlayCommand::Set { id, item }) =
+            parse_overlay_set_args(&[Value::from("hud"), rect])
+        else {
+            panic!("expected a rect");
+        };
+        assert_eq!(id, "hud");
+        let OverlayItem::Rect { x, y, width, height, color } = item else {
+            panic!("expected a rect");
+        };
+        assert_eq!((x, y, width, height), (10.0, 20.5, 100.0, 4.0));
+        assert_eq!(color.map(|color| (color.r, color.g, color.b)), Some((1.0, 0.0, 0.0)));
+
+        let line = overlay_item(&[
+            ("kind", Value::from("line")),
+            ("x1", Value::from(0)),
+            ("y1", Value::from(0)),
+            ("x2", Value::from(5)),
+            ("y2", Value::from(5)),
+        ]);
+        assert_eq!(
+            parse_overlay_set_args(&[Value::from("line"), line]),
+            Some(OverlayCommand::Set {
+                id: "line".to_owned(),
+                item: OverlayItem::Line {
+                    from: (0.0, 0.0),
+                    to: (5.0, 5.0),
+                    width: DEFAULT_LINE_WIDTH,
+                    color: None,
+                },
+            })
+        );
+
+        let text = overlay_item(&[
+            ("kind", Value::from("text")),
+            ("x", Value::from(1)),
+            ("y", Value::from(2)),
+            ("text", Value::from("Recording")),
+        ]);
+        assert!(matches!(
+            parse_overlay_set_args(&[Value::from("text"), text]),
+            Some(OverlayCommand::Set { item: OverlayItem::Text { .. }, .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_overlay_items_are_rejected() {
+        let missing_height = overlay_item(&[
+            ("kind", Value::from("rect")),
+            ("x", Value::from(0)),
+            ("y", Value::from(0)),
+            ("width", Value::from(1)),
+        ]);
+        assert_eq!(parse_overlay_set_args(&[Value::from("a"), missing_height]), None);
+        let unknown = overlay_item(&[("kind", Value::from("circle"))]);
+        assert_eq!(parse_overlay_set_args(&[Value::from("a"), unknown]), None);
+        assert_eq!(parse_overlay_set_args(&[Value::from("a")]), None);
+    }
+
+    #[test]
+    fn overlays_are_cleared_by_id_or_all_at_once() {
+        assert_eq!(
+            parse_overlay_clear_args(&[Value::from("hud")]),
+            Some(OverlayCommand::Clear(Some("hud".to_owned())))
+        );
+        assert_eq!(parse_overlay_clear_args(&[]), Some(OverlayCommand::Clear(None)));
+        assert_eq!(parse_overlay_clear_args(&[Value::Nil]), Some(OverlayCommand::Clear(None)));
+        assert_eq!(parse_overlay_clear_args(&[Value::from(1)]), None);
+    }
+
+    #[test]
+    fn terminal_title_sequences_are_recognized() {
+        assert_eq!(parse_terminal_title("\x1b]0;htop").as_deref(), Some("htop"));
+        assert_eq!(parse_terminal_title("\x1b]2;~/src: vim\x07").as_deref(), Some("~/src: vim"));
+        assert_eq!(parse_terminal_title("\x1b]2;ssh host\x1b\\").as_deref(), Some("ssh host"));
+        // Other OSC sequences, like the working directory or the clipboard, are not titles
+        assert_eq!(parse_terminal_title("\x1b]7;file://host/tmp"), None);
+        assert_eq!(parse_terminal_title("\x1b]52;c;aGVsbG8="), None);
+    }
+
+    #[test]
+    fn the_cursor_screen_position_is_given_in_logical_and_physical_pixels() {
+        let position = CursorScreenPosition {
+            physical: PixelRect::new(PixelPos::new(40.0, 60.0), PixelPos::new(56.0, 92.0)),
+            scale_factor: 2.0,
+        };
+        let rect = |x: f32, y: f32, width: f32, height: f32| {
+            Value::Map(vec![
+                (Value::from("x"), Value::from(x)),
+                (Value::from("y"), Value::from(y)),
+                (Value::from("width"), Value::from(width)),
+                (Value::from("height"), Value::from(height)),
+            ])
+        };
+        assert_eq!(
+            position.to_value(),
+            Value::Map(vec![
+                (Value::from("logical"), rect(20.0, 30.0, 8.0, 16.0)),
+                (Value::from("physical"), rect(40.0, 60.0, 16.0, 32.0)),
+                (Value::from("scale_factor"), Value::from(2.0)),
+            ])
+        );
+    }
+
+    #[test]
+    fn exporting_highlights_to_a_missing_directory_reports_the_path() {
+        let path = std::env::temp_dir().join("neovide-missing-directory").join("highlights.json");
+        let error = export_highlights(&HashMap::new(), &path).unwrap_err();
+        assert!(error.contains(&path.display().to_string()), "{error}");
+    }
+
+    #[test]
+    fn ghost_cursor_args_are_a_window_and_an_optional_position() {
+        assert_eq!(
+            parse_ghost_cursor_args(&[Value::from(1000), Value::from(4), Value::from(7)]),
+            Some((1000, Some((4, 7))))
+        );
+        assert_eq!(parse_ghost_cursor_args(&[Value::from(1000)]), Some((1000, None)));
+        assert_eq!(parse_ghost_cursor_args(&[]), None);
+        assert_eq!(parse_ghost_cursor_args(&[Value::from(1000), Value::from(-1)]), None);
+    }
+
+    #[test]
+    fn number_column_args_are_a_window_and_an_optional_start_and_width() {
+        assert_eq!(
+            parse_number_column_args(&[Value::from(1000), Value::from(2), Value::from(4)]),
+            Some((1000, Some(2..6)))
+        );
+        assert_eq!(parse_number_column_args(&[Value::from(1000)]), Some((1000, None)));
+        assert_eq!(parse_number_column_args(&[]), None);
+    }
 }
@@ -2,10 +2,12 @@ mod api_info;
 mod clipboard;
 mod command;
 mod events;
+mod exit;
 mod handler;
 mod restart;
 pub mod session;
 mod setup;
+mod stderr;
 mod ui_commands;
 
 use std::{
@@ -27,7 +29,7 @@ use crate::{
 };
 use anyhow::{Context, Result, bail};
 use futures::StreamExt;
-pub use handler::NeovimHandler;
+pub use handler::{CursorScreenPosition, NeovimHandler};
 use itertools::Itertools;
 use log::info;
 use mundy::{Interest, Preferences};
@@ -46,13 +48,14 @@ use winit::event_loop::EventLoopProxy;
 pub use command::create_tokio_nvim_command;
 pub use command::{OpenArgs, OpenMode, create_blocking_nvim_command};
 pub use events::*;
+pub use exit::NeovimExit;
 pub use restart::RestartDetails;
 pub use session::NeovimWriter;
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 pub use ui_commands::send_or_queue_file_drop;
 pub use ui_commands::{
     ParallelCommand, SerialCommand, require_active_handler, send_ui, set_active_route_handler,
-    start_ui_command_handler, unregister_route_handler,
+    set_cursor_screen_position, start_ui_command_handler, unregister_route_handler,
 };
 
 const NEOVIM_REQUIRED_VERSION: (u64, u64, u64) = (0, 10, 0);
@@ -97,6 +100,7 @@ pub struct NeovimRuntime {
     pub runtime: Option<Runtime>,
     clipboard: ClipboardHandle,
     background_preference: Arc<Mutex<String>>,
+    watches_reduced_motion: bool,
 }
 
 async fn neovim_instance(
@@ -239,6 +243,7 @@ async fn create_neovim_session(
     options.set_linegrid_external(true);
     options.set_multigrid_external(!cmdline_settings.no_multi_grid);
     options.set_rgb(true);
+    options.set_tabline_external(settings.get::<WindowSettings>().externalize_tabline());
     if capture_startup_messages {
         // Temporarily externalize messages so startup errors before the first grid update are not
         // lost behind a hit-enter prompt. After the first rendered batch, we restore nvim's
@@ -266,11 +271,20 @@ async fn create_neovim_session(
         .await
         .context("Could not attach ui to neovim process")?;
 
-    #[cfg(target_os = "macos")]
+    #[cfg(unix)]
     ui_commands::mark_file_drop_handler_ready(&handler);
 
     info!("Neovim process attached");
 
+    // Errors that happened before the UI attached only went to stderr, show them in :messages
+    let stderr = session.stderr.lock().ok().map(|mut stderr| stderr.take_unreported());
+    if let Some(stderr) = stderr
+        && !stderr.is_empty()
+        && let Err(error) = show_error_message(&session.neovim, &stderr).await
+    {
+        log::error!("Failed to show the stderr output of Neovim: {error}");
+    }
+
     Ok(session)
 }
 
@@ -281,15 +295,16 @@ async fn run(route_id: RouteId, session: NeovimSession, proxy: EventLoopProxy<Ev
     // because tokio's build_child() is skipped to use NamedPipeServer instead.
     // Need to wrap the std Child's blocking wait() for use with tokio select
     #[cfg(target_os = "windows")]
-    let future = session
-        .neovim_process
-        .take()
-        .map(|mut child| tokio::task::spawn_blocking(move || child.wait()));
+    let future = session.neovim_process.take().map(|mut child| async move {
+        tokio::task::spawn_blocking(move || child.wait()).await.ok().and_then(Result::ok)
+    });
 
     #[cfg(not(target_os = "windows"))]
-    let future = session.neovim_process.take().map(|mut child| async move { child.wait().await });
+    let future =
+        session.neovim_process.take().map(|mut child| async move { child.wait().await.ok() });
 
-    if let Some(future) = future {
+    let status = if let Some(future) = future {
+        tokio::pin!(future);
         // We primarily wait for the stdio to finish, but due to bugs,
         // for example, this one in in Neovim 0.9.5
         // https://github.com/neovim/neovim/issues/26743
@@ -297,8 +312,12 @@ async fn run(route_id: RouteId, session: NeovimSession, proxy: EventLoopProxy<Ev
         // So wait for some additional time, both to make the bug obvious and to prevent incomplete
         // data.
         select! {
-            _ = &mut session.io_handle => {}
-            _ = future => {
+            _ = &mut session.io_handle => {
+                // The process usually exits right after closing the stream, give it some time to
+                // report how it ended
+                timeout(Duration::from_millis(500), future).await.ok().flatten()
+            }
+            status = &mut future => {
                 // Wait a little bit more if we detect that Neovim exits before the stream, to
                 // allow us to finish reading from it.
                 log::info!("The Neovim process quit before the IO stream, waiting for a half second");
@@ -308,18 +327,29 @@ async fn run(route_id: RouteId, session: NeovimSession, proxy: EventLoopProxy<Ev
                 {
                     log::info!("The IO stream was never closed, forcing Neovide to exit");
                 }
+                status
             }
-        };
+        }
     } else {
         session.io_handle.await.ok();
-    }
+        None
+    };
 
     // Try to ensure that the stderr output has finished
-    if let Some(stderr_task) = &mut session.stderr_task {
-        timeout(Duration::from_millis(500), stderr_task).await.ok();
+    let stderr = match session.stderr_task.take() {
+        Some(stderr_task) => timeout(Duration::from_millis(500), stderr_task)
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .unwrap_or_default(),
+        None => Vec::new(),
     };
 
-    proxy.send_event(EventPayload::for_route(UserEvent::NeovimExited, route_id)).ok();
+    let exit = NeovimExit::new(status, stderr);
+    if let NeovimExit::Crashed { reason, .. } = &exit {
+        log::error!("{reason}");
+    }
+    proxy.send_event(EventPayload::for_route(UserEvent::NeovimExited(exit), route_id)).ok();
 }
 
 pub async fn set_background_if_allowed(background: &str, neovim: &Neovim<NeovimWriter>) {
@@ -375,6 +405,20 @@ async fn update_colorscheme(
     }
 }
 
+// The reduce motion setting of the OS, sent to the windows when it changes
+async fn update_reduced_motion(
+    mut stream: mundy::PreferencesStream,
+    event_loop_proxy: EventLoopProxy<EventPayload>,
+) {
+    while let Some(preferences) = stream.next().await {
+        let reduce_motion = preferences.reduced_motion.is_reduce();
+        let event = EventPayload::all(UserEvent::ReducedMotionChanged(reduce_motion));
+        if event_loop_proxy.send_event(event).is_err() {
+            break;
+        }
+    }
+}
+
 impl NeovimRuntime {
     pub fn new(clipboard: ClipboardHandle) -> Result<Self, Error> {
         let runtime = Builder::new_multi_thread().enable_all().build()?;
@@ -383,6 +427,7 @@ impl NeovimRuntime {
             runtime: Some(runtime),
             clipboard,
             background_preference: Arc::new(Mutex::new("dark".to_string())),
+            watches_reduced_motion: false,
         })
     }
 
@@ -399,6 +444,11 @@ impl NeovimRuntime {
         mode: OpenMode,
     ) -> Result<NeovimHandler> {
         let mut colorscheme_stream = self.colorscheme_stream();
+        if !self.watches_reduced_motion {
+            self.watches_reduced_motion = true;
+            let stream = self.reduced_motion_stream();
+            self.runtime().spawn(update_reduced_motion(stream, event_loop_proxy.clone()));
+        }
         let editor_handler = start_editor_handler(
             route_id,
             event_loop_proxy.clone(),
@@ -456,7 +506,7 @@ impl NeovimRuntime {
         handler: NeovimHandler,
         grid_size: GridSize<u32>,
         settings: Arc<Settings>,
-        restart_details: RestartDetails,
+        restart_details: Option<RestartDetails>,
         cwd: Option<&Path>,
     ) -> Result<()> {
         let background = self.current_background();
@@ -466,7 +516,7 @@ impl NeovimRuntime {
             Some(grid_size),
             settings,
             &background,
-            Some(&restart_details),
+            restart_details.as_ref(),
             cwd,
             OpenMode::None,
         ))?;
@@ -491,6 +541,11 @@ impl NeovimRuntime {
         Preferences::stream(Interest::ColorScheme)
     }
 
+    fn reduced_motion_stream(&self) -> mundy::PreferencesStream {
+        let _guard = self.runtime().enter();
+        Preferences::stream(Interest::ReducedMotion)
+    }
+
     fn set_background_preference(&self, background: &str) {
         if let Ok(mut guard) = self.background_preference.lock() {
             guard.clear();
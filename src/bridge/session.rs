@@ -8,6 +8,7 @@ use std::process::Child;
 use std::{
     io::{Error, Result},
     process::Stdio,
+    sync::{Arc, Mutex},
 };
 
 use anyhow::Context;
@@ -23,6 +24,8 @@ use tokio::{
 };
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
+use super::stderr::StderrBuffer;
+
 pub type NeovimWriter = Box<dyn futures::AsyncWrite + Send + Unpin + 'static>;
 
 type BoxedReader = Box<dyn AsyncRead + Send + Unpin + 'static>;
@@ -33,6 +36,7 @@ pub struct NeovimSession {
     pub io_handle: JoinHandle<std::result::Result<(), Box<LoopError>>>,
     pub neovim_process: Option<Child>,
     pub stderr_task: Option<JoinHandle<Vec<String>>>,
+    pub stderr: Arc<Mutex<StderrBuffer>>,
     #[cfg(not(target_os = "windows"))]
     pub stdin_fd: Option<rustix::fd::OwnedFd>,
 }
@@ -55,15 +59,18 @@ impl NeovimSession {
         let stdin_fd = instance.forward_stdin();
         let (reader, writer, stderr_reader, neovim_process) = instance.connect().await?;
         // Spawn a background task to read from stderr
+        let stderr = Arc::new(Mutex::new(StderrBuffer::default()));
         let stderr_task = stderr_reader.map(|reader| {
+            let stderr = stderr.clone();
             tokio::spawn(async move {
-                let mut lines = Vec::new();
                 let mut reader = BufReader::new(reader).lines();
                 while let Some(line) = reader.next_line().await.unwrap_or_default() {
                     log::error!("{line}");
-                    lines.push(line);
+                    if let Ok(mut stderr) = stderr.lock() {
+                        stderr.push(line);
+                    }
                 }
-                lines
+                stderr.lock().map(|stderr| stderr.lines()).unwrap_or_default()
             })
         });
         let handshake_message = "NeovideToNeovimMagicHandshakeMessage";
@@ -92,6 +99,7 @@ impl NeovimSession {
                     io_handle,
                     neovim_process,
                     stderr_task,
+                    stderr,
                     #[cfg(not(target_os = "windows"))]
                     stdin_fd,
                 })
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+// A runaway Neovim can write to stderr without end, so only the tail is kept
+const MAX_LINES: usize = 100;
+const MAX_LINE_LENGTH: usize = 1000;
+
+/// The last lines Neovim wrote to stderr, shared between the reader task and the bridge
+#[derive(Debug, Default)]
+pub struct StderrBuffer {
+    lines: VecDeque<String>,
+    // The lines at the end of `lines` that were not reported yet
+    unreported: usize,
+    // Unreported lines that were pushed out of the buffer
+    dropped: usize,
+}
+
+impl StderrBuffer {
+    pub fn push(&mut self, mut line: String) {
+        if line.len() > MAX_LINE_LENGTH {
+            let mut end = MAX_LINE_LENGTH;
+            while !line.is_char_boundary(end) {
+                end -= 1;
+            }
+            line.truncate(end);
+            line.push('…');
+        }
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+            if self.unreported == MAX_LINES {
+                self.dropped += 1;
+            }
+        }
+        self.lines.push_back(line);
+        self.unreported = (self.unreported + 1).min(MAX_LINES);
+    }
+
+    /// All the lines that are kept, reported or not
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.iter().cloned().collect()
+    }
+
+    /// The lines that arrived since the last call, with a note about the ones that didn't fit
+    pub fn take_unreported(&mut self) -> Vec<String> {
+        let mut lines = Vec::with_capacity(self.unreported + 1);
+        if self.dropped > 0 {
+            lines.push(format!("({} earlier lines of stderr were dropped)", self.dropped));
+        }
+        lines.extend(self.lines.iter().skip(self.lines.len() - self.unreported).cloned());
+        self.unreported = 0;
+        self.dropped = 0;
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreported_lines_are_taken_once() {
+        let mut buffer = StderrBuffer::default();
+        buffer.push("E5113: Error while calling lua chunk".to_owned());
+        buffer.push("stack traceback:".to_owned());
+        assert_eq!(
+            buffer.take_unreported(),
+            ["E5113: Error while calling lua chunk", "stack traceback:"]
+        );
+        assert!(buffer.take_unreported().is_empty());
+
+        buffer.push("later".to_owned());
+        assert_eq!(buffer.take_unreported(), ["later"]);
+        assert_eq!(buffer.lines().len(), 3);
+    }
+
+    #[test]
+    fn the_buffer_keeps_the_last_lines_and_counts_the_dropped_ones() {
+        let mut buffer = StderrBuffer::default();
+        for line in 0..MAX_LINES + 5 {
+            buffer.push(line.to_string());
+        }
+        let lines = buffer.take_unreported();
+        assert_eq!(lines.len(), MAX_LINES + 1);
+        assert_eq!(lines[0], "(5 earlier lines of stderr were dropped)");
+        assert_eq!(lines[1], "5");
+        assert_eq!(lines.last().map(String::as_str), Some("104"));
+    }
+
+    #[test]
+    fn reported_lines_pushed_out_are_not_counted_as_dropped() {
+        let mut buffer = StderrBuffer::default();
+        for line in 0..MAX_LINES {
+            buffer.push(line.to_string());
+        }
+        buffer.take_unreported();
+        buffer.push("new".to_owned());
+        assert_eq!(buffer.take_unreported(), ["new"]);
+        assert_eq!(buffer.lines().len(), MAX_LINES);
+    }
+
+    #[test]
+    fn long_lines_are_truncated_on_a_char_boundary() {
+        let mut buffer = StderrBuffer::default();
+        buffer.push("é".repeat(MAX_LINE_LENGTH));
+        let line = &buffer.lines()[0];
+        assert!(line.len() <= MAX_LINE_LENGTH + '…'.len_utf8());
+        assert!(line.ends_with('…'));
+    }
+}
@@ -3,7 +3,7 @@ use std::{
     sync::{Arc, LazyLock, Mutex},
 };
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{Context, Result};
@@ -15,8 +15,8 @@ use strum::AsRefStr;
 use tokio::sync::mpsc::unbounded_channel;
 
 use super::{
-    NeovimHandler, RedrawEvent, Settings, StartupMessage, set_background_if_allowed,
-    show_error_message, show_startup_message,
+    CursorScreenPosition, NeovimHandler, RedrawEvent, Settings, StartupMessage,
+    set_background_if_allowed, show_error_message, show_startup_message,
 };
 use crate::{
     bridge::{NeovimWriter, nvim_dict},
@@ -45,17 +45,17 @@ pub static ROUTE_HANDLER_REGISTRY: LazyLock<Mutex<HashMap<RouteId, NeovimHandler
 
 /// Startup buffer for macOS cold start. file drops that can arrive before any
 /// ready to replay them safely.
-#[cfg(target_os = "macos")]
-type PendingFileDrop = (String, Option<bool>);
+#[cfg(unix)]
+type PendingFileDrop = (String, Option<bool>, Option<(u64, u64)>);
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 static PENDING_FILE_DROPS: LazyLock<Mutex<Vec<PendingFileDrop>>> =
     LazyLock::new(|| Mutex::new(Vec::new()));
 
 /// macOS cold-start file opens must wait until the first embedded neovim has
 /// completed ui_attach, otherwise they can run before filetype/syntax
 /// autocommands exist.
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 static FILE_DROP_HANDLER_READY: AtomicBool = AtomicBool::new(false);
 
 pub fn get_active_handler() -> Option<NeovimHandler> {
@@ -66,38 +66,38 @@ pub fn require_active_handler() -> NeovimHandler {
     get_active_handler().expect("NeovimHandler has not been initialized")
 }
 
-#[cfg(target_os = "macos")]
-pub fn send_or_queue_file_drop(path: String, tabs: Option<bool>) {
+#[cfg(unix)]
+pub fn send_or_queue_file_drop(path: String, tabs: Option<bool>, cursor: Option<(u64, u64)>) {
     if FILE_DROP_HANDLER_READY.load(Ordering::SeqCst)
         && let Some(handler) = get_active_handler()
     {
-        send_ui(ParallelCommand::FileDrop { path, tabs }, &handler);
+        send_ui(ParallelCommand::FileDrop { path, tabs, cursor }, &handler);
         return;
     }
 
-    PENDING_FILE_DROPS.lock().unwrap().push((path, tabs));
+    PENDING_FILE_DROPS.lock().unwrap().push((path, tabs, cursor));
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 fn flush_pending_file_drops(handler: &NeovimHandler) {
     let pending = {
         let mut pending = PENDING_FILE_DROPS.lock().unwrap();
         std::mem::take(&mut *pending)
     };
 
-    for (path, tabs) in pending {
-        send_ui(ParallelCommand::FileDrop { path, tabs }, handler);
+    for (path, tabs, cursor) in pending {
+        send_ui(ParallelCommand::FileDrop { path, tabs, cursor }, handler);
     }
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 fn flush_pending_file_drops_when_ready(handler: &NeovimHandler) {
     if FILE_DROP_HANDLER_READY.load(Ordering::SeqCst) {
         flush_pending_file_drops(handler);
     }
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 pub(crate) fn mark_file_drop_handler_ready(handler: &NeovimHandler) {
     FILE_DROP_HANDLER_READY.store(true, Ordering::SeqCst);
     flush_pending_file_drops(handler);
@@ -150,6 +150,21 @@ pub enum SerialCommand {
         position: (u32, u32),
         modifier_string: String,
     },
+    CopySelection,
+    PasteSelection,
+    SwitchTab(u64),
+    CloseTab(u64),
+    SwitchBuffer(u64),
+    /// Sets the width, or the height, of a split
+    ResizeWindow {
+        window: u64,
+        vertical: bool,
+        size: u32,
+    },
+    FocusWindow {
+        position: (u32, u32),
+    },
+    ExCommand(String),
     #[cfg(target_os = "macos")]
     ForceClickCommand,
 }
@@ -252,6 +267,44 @@ impl SerialCommand {
                 )
                 .await
                 .context("Mouse Drag Failed"),
+            SerialCommand::CopySelection => nvim
+                .exec_lua("neovide.private.copy_selection()", vec![])
+                .await
+                .map(|_| ())
+                .context("CopySelection failed"),
+            SerialCommand::PasteSelection => nvim
+                .exec_lua("neovide.private.paste_selection()", vec![])
+                .await
+                .map(|_| ())
+                .context("PasteSelection failed"),
+            SerialCommand::SwitchTab(tab) => nvim
+                .exec_lua("neovide.private.switch_tab(...)", call_args![tab])
+                .await
+                .map(|_| ())
+                .context("SwitchTab failed"),
+            SerialCommand::CloseTab(tab) => nvim
+                .exec_lua("neovide.private.close_tab(...)", call_args![tab])
+                .await
+                .map(|_| ())
+                .context("CloseTab failed"),
+            SerialCommand::SwitchBuffer(buffer) => nvim
+                .exec_lua("neovide.private.switch_buffer(...)", call_args![buffer])
+                .await
+                .map(|_| ())
+                .context("SwitchBuffer failed"),
+            SerialCommand::ResizeWindow { window, vertical, size } => nvim
+                .exec_lua("neovide.private.resize_window(...)", call_args![window, vertical, size])
+                .await
+                .map(|_| ())
+                .context("ResizeWindow failed"),
+            SerialCommand::FocusWindow { position: (column, row) } => nvim
+                .exec_lua("neovide.private.focus_window(...)", call_args![row, column])
+                .await
+                .map(|_| ())
+                .context("FocusWindow failed"),
+            SerialCommand::ExCommand(command) => {
+                nvim.command(&command).await.context("ExCommand failed")
+            }
             #[cfg(target_os = "macos")]
             SerialCommand::ForceClickCommand => {
                 nvim.command("NeovideForceClick").await.context("Force click command failed")
@@ -268,16 +321,29 @@ impl SerialCommand {
 pub enum ParallelCommand {
     Quit,
     Resize { width: u64, height: u64 },
-    FileDrop { path: String, tabs: Option<bool> },
+    FileDrop { path: String, tabs: Option<bool>, cursor: Option<(u64, u64)> },
     FocusLost,
     FocusGained,
     DisplayAvailableFonts(Vec<String>),
     ShowError { lines: Vec<String> },
+    ShowInfo { lines: Vec<String> },
     SetBackground { background: String },
+    ExternalizeTabline(bool),
     FlushStartupMessages { messages: Vec<StartupMessage> },
     ReplayStartupMessages { messages: Vec<StartupMessage> },
 }
 
+async fn show_info_message(
+    nvim: &Neovim<NeovimWriter>,
+    lines: &[String],
+) -> Result<(), Box<CallError>> {
+    let chunks: Vec<Value> =
+        lines.iter().map(|line| Value::Array(vec![format!("{line}\n").into()])).collect();
+    let opts: Vec<(Value, Value)> = nvim_dict! {};
+    nvim.call("nvim_echo", call_args![chunks, true, opts]).await??;
+    Ok(())
+}
+
 async fn display_available_fonts(
     nvim: &Neovim<NeovimWriter>,
     fonts: Vec<String>,
@@ -428,7 +494,7 @@ impl ParallelCommand {
             ParallelCommand::FocusGained => {
                 nvim.ui_set_focus(true).await.context("FocusGained failed")
             }
-            ParallelCommand::FileDrop { path, tabs } => nvim
+            ParallelCommand::FileDrop { path, tabs, cursor } => nvim
                 .exec_lua(
                     "neovide.private.dropfile(...)",
                     call_args![
@@ -436,7 +502,10 @@ impl ParallelCommand {
                             .first()
                             .unwrap()
                             .to_string(),
-                        tabs.unwrap_or(settings.get::<CmdLineSettings>().tabs)
+                        tabs.unwrap_or(settings.get::<CmdLineSettings>().tabs),
+                        cursor.map_or(Value::Nil, |(line, column)| {
+                            Value::from(vec![Value::from(line), Value::from(column)])
+                        })
                     ],
                 )
                 .await
@@ -452,10 +521,17 @@ impl ParallelCommand {
                 // We should go back to it whenever that bug gets fixed.
                 show_error_message(nvim, &lines).await.context("ShowError failed")
             }
+            ParallelCommand::ShowInfo { lines } => {
+                show_info_message(nvim, &lines).await.context("ShowInfo failed")
+            }
             ParallelCommand::SetBackground { background } => {
                 set_background_if_allowed(&background, nvim).await;
                 Ok(())
             }
+            ParallelCommand::ExternalizeTabline(enabled) => nvim
+                .ui_set_option("ext_tabline", Value::from(enabled))
+                .await
+                .context("ExternalizeTabline failed"),
             ParallelCommand::FlushStartupMessages { messages } => {
                 flush_startup_messages(nvim, handler, messages).await
             }
@@ -506,7 +582,7 @@ pub fn start_ui_command_handler(
 ) {
     handler.update_current_neovim(nvim, can_support_ime_api);
     register_route_handler(route_id, handler.clone());
-    #[cfg(target_os = "macos")]
+    #[cfg(unix)]
     flush_pending_file_drops_when_ready(&handler);
     if handler.mark_ui_command_started() {
         return;
@@ -586,6 +662,13 @@ pub fn set_active_route_handler(route_id: RouteId) {
     }
 }
 
+/// Keeps the cursor position of the window answering `neovide.cursor_screen_position` up to date
+pub fn set_cursor_screen_position(route_id: RouteId, position: CursorScreenPosition) {
+    if let Some(handler) = ROUTE_HANDLER_REGISTRY.lock().unwrap().get(&route_id) {
+        handler.set_cursor_screen_position(position);
+    }
+}
+
 pub fn unregister_route_handler(route_id: RouteId) {
     let mut by_route = ROUTE_HANDLER_REGISTRY.lock().unwrap();
     by_route.remove(&route_id);
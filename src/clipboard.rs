@@ -1,6 +1,8 @@
 use std::error::Error;
 use std::fmt;
 use std::sync::{Arc, Mutex, Weak};
+#[cfg(target_os = "linux")]
+use std::time::Duration;
 
 use copypasta::{ClipboardContext, ClipboardProvider};
 #[cfg(target_os = "linux")]
@@ -9,6 +11,8 @@ use copypasta::{
     x11_clipboard::{Primary as X11SelectionClipboard, X11ClipboardContext},
 };
 use log::warn;
+#[cfg(target_os = "macos")]
+use objc2_app_kit::{NSPasteboard, NSPasteboardTypePNG};
 use raw_window_handle::HasDisplayHandle;
 #[cfg(target_os = "linux")]
 use raw_window_handle::{RawDisplayHandle, WaylandDisplayHandle};
@@ -22,6 +26,8 @@ pub type ClipboardResult<T> = std::result::Result<T, ClipboardError>;
 const CLIPBOARD_PROVIDER: &str = "clipboard";
 #[cfg(target_os = "linux")]
 const PRIMARY_SELECTION_PROVIDER: &str = "primary selection";
+#[cfg(target_os = "linux")]
+const IMAGE_LOAD_TIMEOUT: Duration = Duration::from_millis(500);
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ClipboardError {
@@ -303,6 +309,31 @@ impl Clipboard {
             .map_err(|error| ClipboardError::set_contents_failed(provider, register, error))
     }
 
+    /// The image in the clipboard as PNG, when there is one. Images are read through X11 on Linux,
+    /// which Wayland compositors forward with XWayland.
+    #[cfg(target_os = "linux")]
+    pub fn get_image() -> Option<Vec<u8>> {
+        let clipboard = x11_clipboard::Clipboard::new().ok()?;
+        let atoms = &clipboard.getter.atoms;
+        let png = clipboard.getter.get_atom("image/png").ok()?;
+        clipboard
+            .load(atoms.clipboard, png, atoms.property, IMAGE_LOAD_TIMEOUT)
+            .ok()
+            .filter(|image| !image.is_empty())
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn get_image() -> Option<Vec<u8>> {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        let image = unsafe { pasteboard.dataForType(NSPasteboardTypePNG) }?;
+        Some(image.to_vec()).filter(|image| !image.is_empty())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    pub fn get_image() -> Option<Vec<u8>> {
+        None
+    }
+
     #[cfg(test)]
     pub fn from_provider_states_for_test(
         clipboard: ProviderState,
@@ -336,7 +367,6 @@ mod tests {
     }
 
     impl TestClipboardProvider {
-        #[cfg(target_os = "linux")]
         fn with_contents(contents: &str) -> Self {
             Self { contents: contents.to_string(), get_error: None, set_error: None }
         }
@@ -409,6 +439,32 @@ mod tests {
         assert!(error.contains("read failed"));
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn selection_register_routes_to_primary_selection() {
+        let mut clipboard = Clipboard::from_provider_states_for_test(
+            ProviderState::available_for_test(TestClipboardProvider::with_contents("copied")),
+            ProviderState::available_for_test(TestClipboardProvider::with_contents("")),
+        );
+
+        clipboard.set_contents("selected".to_string(), "*").unwrap();
+
+        assert_eq!(clipboard.get_contents("*").unwrap(), "selected");
+        assert_eq!(clipboard.get_contents("+").unwrap(), "copied");
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    #[test]
+    fn selection_register_routes_to_clipboard() {
+        let mut clipboard = Clipboard::from_provider_states_for_test(
+            ProviderState::available_for_test(TestClipboardProvider::with_contents("")),
+        );
+
+        clipboard.set_contents("selected".to_string(), "*").unwrap();
+
+        assert_eq!(clipboard.get_contents("+").unwrap(), "selected");
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     fn partial_failure_keeps_available_provider_working() {
@@ -1,7 +1,11 @@
 use std::{iter, process::ExitStatus};
 
 use crate::{
-    bridge::create_blocking_nvim_command, dimensions::Dimensions, frame::Frame, settings::*,
+    bridge::create_blocking_nvim_command,
+    dimensions::Dimensions,
+    frame::Frame,
+    settings::*,
+    url_scheme::{OpenUrl, is_neovide_url},
     version::BUILD_VERSION,
 };
 
@@ -10,7 +14,7 @@ use clap::{
     ArgAction, Parser, ValueEnum,
     builder::{FalseyValueParser, Styles, styling},
 };
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 use clap::{CommandFactory, parser::ValueSource};
 use winit::window::CursorIcon;
 
@@ -57,8 +61,8 @@ pub struct CmdLineSettings {
     pub server: Option<String>,
 
     /// Open files in an existing Neovide app instance if one is already running
-    #[cfg(target_os = "macos")]
-    #[arg(long = "reuse-instance", action = ArgAction::SetTrue, default_value = "0", value_parser = FalseyValueParser::new())]
+    #[cfg(unix)]
+    #[arg(long = "reuse-instance", visible_alias = "single-instance", action = ArgAction::SetTrue, default_value = "0", value_parser = FalseyValueParser::new())]
     pub reuse_instance: bool,
 
     /// Open files in a new window when reusing an existing Neovide instance
@@ -95,10 +99,14 @@ pub struct CmdLineSettings {
     #[arg(long = "no-fork", action = ArgAction::SetTrue, value_parser = FalseyValueParser::new())]
     _no_fork: bool,
 
+    /// Only render frames when something changes or is animating [DEFAULT]
+    #[arg(long = "idle", env = "NEOVIDE_IDLE", action = ArgAction::SetTrue, default_value = "1", value_parser = FalseyValueParser::new())]
+    pub idle: bool,
+
     /// Render every frame, takes more power and CPU time but possibly helps with frame timing
     /// issues
-    #[arg(long = "no-idle", env = "NEOVIDE_IDLE", action = ArgAction::SetFalse, value_parser = FalseyValueParser::new())]
-    pub idle: bool,
+    #[arg(long = "no-idle", action = ArgAction::SetTrue, value_parser = FalseyValueParser::new())]
+    _no_idle: bool,
 
     /// Enable opening multiple files supplied in tabs [DEFAULT]
     #[arg(long = "tabs", env = "NEOVIDE_TABS", action = ArgAction::SetTrue, default_value = "1", value_parser = FalseyValueParser::new())]
@@ -252,6 +260,10 @@ pub struct CmdLineSettings {
     /// Change to this directory during startup.
     #[arg(long = "chdir", env = "NEOVIDE_CHDIR")]
     pub chdir: Option<String>,
+
+    /// The `neovide://` URL Neovide was started with, its file replaces `files_to_open`
+    #[arg(skip)]
+    pub open_url: Option<OpenUrl>,
 }
 
 // geometry, size and maximized are mutually exclusive
@@ -322,6 +334,12 @@ impl Default for CmdLineSettings {
 pub fn handle_command_line_arguments(args: Vec<String>, settings: &Settings) -> Result<()> {
     let mut cmdline = CmdLineSettings::try_parse_from(args)?;
 
+    if let Some(url) = cmdline.files_to_open.iter().find(|file| is_neovide_url(file)) {
+        let url = OpenUrl::parse(url)?;
+        cmdline.files_to_open = vec![url.file.clone()];
+        cmdline.open_url = Some(url);
+    }
+
     if cmdline._no_tabs {
         cmdline.tabs = false;
     }
@@ -335,6 +353,10 @@ pub fn handle_command_line_arguments(args: Vec<String>, settings: &Settings) ->
         cmdline.fork = false;
     }
 
+    if cmdline._no_idle {
+        cmdline.idle = false;
+    }
+
     if cmdline._no_srgb {
         cmdline.srgb = false;
     }
@@ -347,7 +369,7 @@ pub fn handle_command_line_arguments(args: Vec<String>, settings: &Settings) ->
     Ok(())
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 pub fn argv_chdir() -> Option<String> {
     let matches = CmdLineSettings::command().try_get_matches_from(std::env::args_os()).ok()?;
 
@@ -485,7 +507,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
+    #[cfg(unix)]
     fn test_reuse_instance_flag() {
         let settings = Settings::new();
         let args: Vec<String> =
@@ -496,6 +518,17 @@ mod tests {
         assert_eq!(settings.get::<CmdLineSettings>().files_to_open, vec!["./foo.txt"]);
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_single_instance_flag() {
+        let settings = Settings::new();
+        let args: Vec<String> =
+            ["neovide", "--single-instance", "./foo.txt"].iter().map(|s| s.to_string()).collect();
+
+        handle_command_line_arguments(args, &settings).expect("Could not parse arguments");
+        assert!(settings.get::<CmdLineSettings>().reuse_instance);
+    }
+
     #[test]
     #[cfg(target_os = "macos")]
     fn test_new_window_flag() {
@@ -903,4 +936,66 @@ mod tests {
         handle_command_line_arguments(args, &settings).expect("Could not parse arguments");
         assert!(!settings.get::<CmdLineSettings>().system_native_tabs);
     }
+
+    #[test]
+    fn test_idle_default() {
+        let settings = Settings::new();
+        let args: Vec<String> = ["neovide"].iter().map(|s| s.to_string()).collect();
+
+        handle_command_line_arguments(args, &settings).expect("Could not parse arguments");
+        assert!(settings.get::<CmdLineSettings>().idle);
+    }
+
+    #[test]
+    fn test_no_idle() {
+        let settings = Settings::new();
+        let args: Vec<String> = ["neovide", "--no-idle"].iter().map(|s| s.to_string()).collect();
+
+        handle_command_line_arguments(args, &settings).expect("Could not parse arguments");
+        assert!(!settings.get::<CmdLineSettings>().idle);
+    }
+
+    #[test]
+    fn test_no_idle_environment() {
+        let settings = Settings::new();
+        let args: Vec<String> = ["neovide"].iter().map(|s| s.to_string()).collect();
+
+        let _env = ScopedEnv::set("NEOVIDE_IDLE", "0");
+        handle_command_line_arguments(args, &settings).expect("Could not parse arguments");
+        assert!(!settings.get::<CmdLineSettings>().idle);
+    }
+
+    #[test]
+    fn test_override_idle_environment() {
+        let settings = Settings::new();
+        let args: Vec<String> = ["neovide", "--idle"].iter().map(|s| s.to_string()).collect();
+
+        let _env = ScopedEnv::set("NEOVIDE_IDLE", "0");
+        handle_command_line_arguments(args, &settings).expect("Could not parse arguments");
+        assert!(settings.get::<CmdLineSettings>().idle);
+    }
+
+    #[test]
+    fn test_open_url() {
+        let settings = Settings::new();
+        let args: Vec<String> = ["neovide", "neovide://open?file=/tmp/foo.txt&line=42&column=3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        handle_command_line_arguments(args, &settings).expect("Could not parse arguments");
+        let cmdline = settings.get::<CmdLineSettings>();
+        assert_eq!(cmdline.files_to_open, vec!["/tmp/foo.txt"]);
+        assert!(cmdline.neovim_args.is_empty());
+        assert_eq!(cmdline.open_url.and_then(|url| url.cursor()), Some((42, 3)));
+    }
+
+    #[test]
+    fn test_invalid_open_url() {
+        let settings = Settings::new();
+        let args: Vec<String> =
+            ["neovide", "neovide://open?line=42"].iter().map(|s| s.to_string()).collect();
+
+        assert!(handle_command_line_arguments(args, &settings).is_err());
+    }
 }
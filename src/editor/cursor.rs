@@ -47,6 +47,8 @@ pub struct Cursor {
     pub enabled: bool,
     pub double_width: bool,
     pub grid_cell: GridCell,
+    /// The cursor got here by moving along a line wrapped over several rows
+    pub followed_wrap: bool,
 }
 
 impl Default for Cursor {
@@ -69,6 +71,7 @@ impl Cursor {
             enabled: true,
             double_width: false,
             grid_cell: (" ".to_string(), None),
+            followed_wrap: false,
         }
     }
 
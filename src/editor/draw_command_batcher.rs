@@ -1,10 +1,8 @@
 use crate::{
-    editor::DrawCommand,
+    editor::{DrawCommand, EventSender},
     window::{EventPayload, RouteId},
 };
 
-use winit::event_loop::EventLoopProxy;
-
 pub struct DrawCommandBatcher {
     batch: Vec<DrawCommand>,
     enabled: bool,
@@ -20,28 +18,26 @@ impl DrawCommandBatcher {
         self.batch.push(draw_command);
     }
 
-    pub fn set_enabled(
-        &mut self,
-        enabled: bool,
-        route_id: RouteId,
-        proxy: &EventLoopProxy<EventPayload>,
-    ) {
+    pub fn set_enabled(&mut self, enabled: bool, route_id: RouteId, sender: &EventSender) {
         log::info!("Set redraw {enabled}");
         if enabled && !self.enabled {
             for queued in self.queued.drain(..) {
-                proxy.send_event(EventPayload::for_route(queued.into(), route_id)).ok();
+                sender.send_event(EventPayload::for_route(queued.into(), route_id));
             }
         }
         self.enabled = enabled;
     }
 
-    pub fn send_batch(&mut self, route_id: RouteId, proxy: &EventLoopProxy<EventPayload>) {
+    pub fn send_batch(&mut self, route_id: RouteId, sender: &EventSender) {
         if self.enabled {
-            proxy
-                .send_event(EventPayload::for_route(self.batch.split_off(0).into(), route_id))
-                .ok();
+            sender.send_event(EventPayload::for_route(self.batch.split_off(0).into(), route_id));
         } else {
             self.queued.push(self.batch.split_off(0));
         }
     }
+
+    #[cfg(test)]
+    pub fn take_batch(&mut self) -> Vec<DrawCommand> {
+        std::mem::take(&mut self.batch)
+    }
 }
@@ -14,11 +14,13 @@ macro_rules! default_cell {
 #[derive(Clone)]
 struct GridLine {
     characters: Vec<GridCell>,
+    /// The line continues on the next row, it's wrapped there by Neovim
+    wrap: bool,
 }
 
 impl GridLine {
     pub fn new(length: usize) -> GridLine {
-        GridLine { characters: vec![default_cell!(); length] }
+        GridLine { characters: vec![default_cell!(); length], wrap: false }
     }
 }
 
@@ -63,9 +65,22 @@ impl CharacterGrid {
             for ch in &mut line.characters {
                 *ch = value.clone()
             }
+            line.wrap = false;
         }
     }
 
+    pub fn set_wrap(&mut self, row_index: usize, wrap: bool) {
+        if let Some(line) = self.lines.get_mut(row_index) {
+            line.wrap = wrap;
+        }
+    }
+
+    /// Whether the row is wrapped onto the next one, as the `wrap` flag of the last `grid_line`
+    /// that reached its end
+    pub fn wraps(&self, row_index: usize) -> bool {
+        self.lines.get(row_index).is_some_and(|line| line.wrap)
+    }
+
     pub fn row(&self, row_index: usize) -> Option<&[GridCell]> {
         self.lines.get(row_index).map(|line| line.characters.as_slice())
     }
@@ -95,11 +110,13 @@ impl CharacterGrid {
 
         let mut top_to_bottom;
         let mut bottom_to_top;
+        // Scrolling by more than the region moves nothing, the ranges are clamped so that they
+        // don't wrap around
         let y_iter: &mut dyn Iterator<Item = usize> = if rows > 0 {
-            top_to_bottom = (top as isize + rows) as usize..bottom;
+            top_to_bottom = (top as isize + rows).min(bottom as isize) as usize..bottom;
             &mut top_to_bottom
         } else {
-            bottom_to_top = (top..(bottom as isize + rows) as usize).rev();
+            bottom_to_top = (top..(bottom as isize + rows).max(top as isize) as usize).rev();
             &mut bottom_to_top
         };
 
@@ -108,11 +125,16 @@ impl CharacterGrid {
             let mut cols_left;
             let mut cols_right;
             if dest_y >= 0 && dest_y < self.height as isize {
+                // Only whole rows take their wrap flag along
+                if left == 0 && right == self.width && cols == 0 {
+                    let wrap = self.wraps(y);
+                    self.set_wrap(dest_y as usize, wrap);
+                }
                 let x_iter: &mut dyn Iterator<Item = usize> = if cols > 0 {
-                    cols_left = (left as isize + cols) as usize..right;
+                    cols_left = (left as isize + cols).min(right as isize) as usize..right;
                     &mut cols_left
                 } else {
-                    cols_right = (left..(right as isize + cols) as usize).rev();
+                    cols_right = (left..(right as isize + cols).max(left as isize) as usize).rev();
                     &mut cols_right
                 };
 
@@ -401,6 +423,37 @@ mod tests {
         assert_grid_cell_contents(&grid, 0, 3, "m");
     }
 
+    #[test]
+    fn scroll_columns_shifts_only_the_region() {
+        let mut grid = create_initialized_grid(["abcdef", "ghijkl", "mnopqr"].as_ref());
+
+        grid.scroll_region(1, 3, 1, 5, 0, 2);
+        // Outside of the region nothing moves
+        assert_grid_cell_contents(&grid, 1, 0, "b");
+        assert_grid_cell_contents(&grid, 0, 1, "g");
+        assert_grid_cell_contents(&grid, 5, 1, "l");
+
+        // The cells move two columns to the left
+        assert_grid_cell_contents(&grid, 1, 1, "j");
+        assert_grid_cell_contents(&grid, 2, 1, "k");
+        assert_grid_cell_contents(&grid, 1, 2, "p");
+        assert_grid_cell_contents(&grid, 2, 2, "q");
+
+        // The revealed cells are left for the following grid_line
+        assert_grid_cell_contents(&grid, 3, 1, "j");
+        assert_grid_cell_contents(&grid, 4, 1, "k");
+    }
+
+    #[test]
+    fn scrolling_past_the_region_moves_nothing() {
+        let mut grid = create_initialized_grid(["abcd", "efgh"].as_ref());
+
+        grid.scroll_region(0, 2, 1, 3, 0, -5);
+        grid.scroll_region(0, 2, 1, 3, 5, 0);
+        assert_grid_cell_contents(&grid, 1, 0, "b");
+        assert_grid_cell_contents(&grid, 2, 1, "g");
+    }
+
     #[test]
     fn scrolling_one_screen_down_works() {
         let mut grid = create_initialized_grid(["1", "2", "3", "4"].as_ref());
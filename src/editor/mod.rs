@@ -2,6 +2,7 @@ mod cursor;
 mod draw_command_batcher;
 mod grid;
 mod intro;
+mod prediction;
 mod style;
 mod window;
 
@@ -26,7 +27,9 @@ use crate::{
     },
     clipboard::ClipboardHandle,
     profiling::{tracy_named_frame, tracy_zone},
-    renderer::{DrawCommand, WindowDrawCommand, rendered_window::BASE_GRID_ID},
+    renderer::{
+        DrawCommand, WindowDrawCommand, rendered_window::BASE_GRID_ID, text_cells::AmbiWidth,
+    },
     running_tracker::RunningTracker,
     settings::Settings,
     units::{GridRect, GridSize},
@@ -35,10 +38,11 @@ use crate::{
 
 pub use cursor::{Cursor, CursorMode, CursorShape};
 pub use draw_command_batcher::DrawCommandBatcher;
-pub use style::{Colors, Style, UnderlineStyle};
+pub use style::{Colors, Style, UnderlineStyle, highlight_table_json};
 pub use window::*;
 
 use intro::{IntroMessageExtender, IntroProcessing};
+use prediction::{EchoPrediction, EchoUpdate};
 pub const MSG_ZINDEX: u64 = 200; // See the documenation for nvim_open_win
 
 fn styled_content_to_plain_text(content: StyledContent) -> String {
@@ -66,7 +70,7 @@ impl StartupMessageCapture {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct SortOrder {
     pub z_index: u64,
     composition_order: u64,
@@ -135,8 +139,33 @@ impl WindowAnchor {
     }
 }
 
+/// Where the editor sends its draw batches and window commands, the tests read them from a
+/// channel instead of an event loop
+#[derive(Clone)]
+pub enum EventSender {
+    EventLoop(EventLoopProxy<EventPayload>),
+    #[cfg(test)]
+    Channel(std::sync::mpsc::Sender<EventPayload>),
+}
+
+impl EventSender {
+    pub fn send_event(&self, payload: EventPayload) {
+        match self {
+            Self::EventLoop(proxy) => {
+                let _ = proxy.send_event(payload);
+            }
+            #[cfg(test)]
+            Self::Channel(sender) => {
+                let _ = sender.send(payload);
+            }
+        }
+    }
+}
+
 pub struct Editor {
     pub windows: HashMap<u64, Window>,
+    // The grids of the Neovim windows, by window handle
+    window_grids: HashMap<u64, u64>,
     pub cursor: Cursor,
     pub defined_styles: HashMap<u64, Arc<Style>>,
     pub mode_list: Vec<CursorMode>,
@@ -145,12 +174,15 @@ pub struct Editor {
     current_mode: EditorMode,
     pub ui_ready: bool,
     startup_message_capture: StartupMessageCapture,
-    event_loop_proxy: EventLoopProxy<EventPayload>,
+    event_sender: EventSender,
     route_id: RouteId,
     #[allow(dead_code)]
     settings: Arc<Settings>,
     composition_order: u64,
     intro_message_extender: IntroMessageExtender,
+    echo_prediction: EchoPrediction,
+    // The highlight of the separator drawn above the scrolled messages
+    message_separator_highlight: Option<u64>,
     #[cfg(target_os = "macos")]
     match_paren_highlight_ids: HashSet<u64>,
     #[cfg(target_os = "macos")]
@@ -169,8 +201,13 @@ impl Editor {
         event_loop_proxy: EventLoopProxy<EventPayload>,
         settings: Arc<Settings>,
     ) -> Self {
+        Self::with_sender(route_id, EventSender::EventLoop(event_loop_proxy), settings)
+    }
+
+    fn with_sender(route_id: RouteId, event_sender: EventSender, settings: Arc<Settings>) -> Self {
         Editor {
             windows: HashMap::new(),
+            window_grids: HashMap::new(),
             cursor: Cursor::new(),
             defined_styles: HashMap::new(),
             #[cfg(target_os = "macos")]
@@ -190,19 +227,40 @@ impl Editor {
             ui_ready: false,
             startup_message_capture: StartupMessageCapture::BeforeFirstGrid,
             settings,
-            event_loop_proxy,
+            event_sender,
             route_id,
             composition_order: 0,
             intro_message_extender: IntroMessageExtender::new(),
+            echo_prediction: EchoPrediction::new(),
+            message_separator_highlight: None,
         }
     }
 
     fn send_window_command(&self, command: WindowCommand) {
         let payload = EventPayload::for_route(UserEvent::WindowCommand(command), self.route_id);
-        let _ = self.event_loop_proxy.send_event(payload);
+        self.event_sender.send_event(payload);
+    }
+
+    fn send_batch(&mut self) {
+        self.draw_command_batcher.send_batch(self.route_id, &self.event_sender);
+    }
+
+    /// The text of each row of the grid, the second cell of a double width character being empty
+    #[cfg(test)]
+    pub fn visible_text(&self, grid: u64) -> Vec<String> {
+        self.windows.get(&grid).map(Window::text).unwrap_or_default()
+    }
+
+    /// The style of a cell, `None` being the default colors
+    #[cfg(test)]
+    pub fn cell_style(&self, grid: u64, row: u64, column: u64) -> Option<Arc<Style>> {
+        self.windows.get(&grid)?.get_cursor_grid_cell(column, row).1
     }
 
     pub fn handle_redraw_event(&mut self, event: RedrawEvent) {
+        if !matches!(event, RedrawEvent::Flush | RedrawEvent::NeovidePredictedEcho(_)) {
+            self.echo_prediction.redraw_event();
+        }
         match event {
             RedrawEvent::SetTitle { mut title } => {
                 tracy_zone!("EditorSetTitle");
@@ -232,6 +290,7 @@ impl Editor {
                 } else {
                     self.current_mode_index = None
                 }
+                self.echo_prediction.answered();
                 self.current_mode = mode.clone();
                 self.draw_command_batcher.queue(DrawCommand::ModeChanged(mode));
             }
@@ -257,12 +316,13 @@ impl Editor {
                 tracy_zone!("EditorFlush");
                 trace!("Image flushed");
                 tracy_named_frame!("neovim draw command flush");
+                self.update_predicted_echo();
                 self.send_cursor_info();
                 self.advance_startup_capture_on_flush();
 
                 {
                     trace!("send_batch");
-                    self.draw_command_batcher.send_batch(self.route_id, &self.event_loop_proxy);
+                    self.send_batch();
                 }
 
                 #[cfg(target_os = "macos")]
@@ -282,10 +342,11 @@ impl Editor {
                 self.draw_command_batcher
                     .queue(DrawCommand::DefaultStyleChanged(Style::new(colors)));
                 self.redraw_screen();
-                self.draw_command_batcher.send_batch(self.route_id, &self.event_loop_proxy);
+                self.send_batch();
             }
-            RedrawEvent::HighlightAttributesDefine { id, style, name } => {
+            RedrawEvent::HighlightAttributesDefine { id, style, name, infos } => {
                 tracy_zone!("EditorHighlightAttributesDefine");
+                trace!("Highlight {id} combined from {infos:?}");
                 self.defined_styles.insert(id, Arc::new(style));
 
                 #[cfg(target_os = "macos")]
@@ -296,20 +357,26 @@ impl Editor {
             }
             RedrawEvent::HighlightGroupSet { name, id } => {
                 tracy_zone!("EditorHighlightGroupSet");
+                if name == "MsgSeparator" {
+                    self.message_separator_highlight = Some(id);
+                }
                 self.handle_highlight_group_set(name.as_str(), id);
             }
             RedrawEvent::CursorGoto { grid, column: left, row: top } => {
                 tracy_zone!("EditorCursorGoto");
+                self.echo_prediction.answered();
                 self.set_cursor_position(grid, left, top);
             }
             RedrawEvent::Resize { grid, width, height } => {
                 tracy_zone!("EditorResize");
+                self.echo_prediction.grid_changed(grid);
                 self.resize_window(grid, width, height);
             }
-            RedrawEvent::GridLine { grid, row, column_start, cells } => {
+            RedrawEvent::GridLine { grid, row, column_start, cells, wrap } => {
                 tracy_zone!("EditorGridLine");
                 self.set_ui_ready();
-                self.draw_grid_line(grid, row, column_start, &cells);
+                self.echo_prediction.row_drawn(grid, row);
+                self.draw_grid_line(grid, row, column_start, &cells, wrap);
                 self.handle_intro_banner_for_line(grid, row, &cells);
             }
             RedrawEvent::GridHighlight { grid, row, column_start, column_end, highlight_id } => {
@@ -328,6 +395,7 @@ impl Editor {
             }
             RedrawEvent::Clear { grid } => {
                 tracy_zone!("EditorClear");
+                self.echo_prediction.grid_changed(grid);
                 let window = self.windows.get_mut(&grid);
                 if let Some(window) = window {
                     window.clear(&mut self.draw_command_batcher);
@@ -342,6 +410,7 @@ impl Editor {
             }
             RedrawEvent::Destroy { grid } => {
                 tracy_zone!("EditorDestroy");
+                self.echo_prediction.grid_changed(grid);
                 self.intro_message_extender.reset(grid);
                 #[cfg(target_os = "macos")]
                 {
@@ -353,6 +422,7 @@ impl Editor {
             }
             RedrawEvent::Scroll { grid, top, bottom, left, right, rows, columns } => {
                 tracy_zone!("EditorScroll");
+                self.echo_prediction.grid_changed(grid);
                 #[cfg(target_os = "macos")]
                 {
                     self.match_paren_cache.remove(&grid);
@@ -368,9 +438,21 @@ impl Editor {
                     );
                 }
             }
-            RedrawEvent::WindowPosition { grid, start_row, start_column, width, height } => {
+            RedrawEvent::WindowPosition {
+                grid,
+                window,
+                start_row,
+                start_column,
+                width,
+                height,
+            } => {
                 tracy_zone!("EditorWindowPosition");
-                self.set_window_position(grid, start_column, start_row, width, height)
+                self.window_grids.insert(window, grid);
+                self.set_window_position(grid, start_column, start_row, width, height);
+                self.draw_command_batcher.queue(DrawCommand::Window {
+                    grid_id: grid,
+                    command: WindowDrawCommand::Split { window },
+                });
             }
             RedrawEvent::WindowFloatPosition {
                 grid,
@@ -417,10 +499,16 @@ impl Editor {
                 self.close_window(grid)
             }
             RedrawEvent::MessageSetPosition {
-                grid, row, scrolled, z_index, comp_index, ..
+                grid,
+                row,
+                scrolled,
+                separator_character,
+                z_index,
+                comp_index,
             } => {
                 tracy_zone!("EditorMessageSetPosition");
-                self.set_message_position(grid, row, scrolled, z_index, comp_index)
+                self.set_message_position(grid, row, scrolled, z_index, comp_index);
+                self.set_message_separator(grid, row, scrolled, separator_character);
             }
             RedrawEvent::MessageShow { kind, content, replace_last, append } => {
                 tracy_zone!("EditorMessageShow");
@@ -447,16 +535,35 @@ impl Editor {
             }
             RedrawEvent::WindowViewport {
                 grid,
-                // Don't send viewport events if they don't have a scroll delta
-                scroll_delta: Some(scroll_delta),
+                window,
+                top_line,
+                bottom_line,
+                line_count,
+                scroll_delta,
                 ..
             } => {
                 tracy_zone!("EditorWindowViewport");
-                self.set_ui_ready();
+                self.window_grids.insert(window, grid);
+                // The bottom line is one past the last line in view, and past the end of the
+                // buffer when it's followed by filler lines
                 self.draw_command_batcher.queue(DrawCommand::Window {
                     grid_id: grid,
-                    command: WindowDrawCommand::Viewport { scroll_delta },
+                    command: WindowDrawCommand::ViewportEdges {
+                        at_buffer_start: top_line <= 0.0,
+                        at_buffer_end: line_count
+                            .is_some_and(|line_count| bottom_line >= line_count),
+                        at_scroll_end: line_count
+                            .is_some_and(|line_count| top_line + 1.0 >= line_count),
+                    },
                 });
+                // Don't send viewport events if they don't have a scroll delta
+                if let Some(scroll_delta) = scroll_delta {
+                    self.set_ui_ready();
+                    self.draw_command_batcher.queue(DrawCommand::Window {
+                        grid_id: grid,
+                        command: WindowDrawCommand::Viewport { scroll_delta },
+                    });
+                }
             }
             RedrawEvent::WindowViewportMargins { grid, top, bottom, left, right } => {
                 tracy_zone!("EditorWindowViewportMargins");
@@ -465,12 +572,89 @@ impl Editor {
                     command: WindowDrawCommand::ViewportMargins { top, bottom, left, right },
                 });
             }
+            RedrawEvent::TablineUpdate { current_tab, tabs, current_buffer, buffers } => {
+                tracy_zone!("EditorTablineUpdate");
+                self.draw_command_batcher.queue(DrawCommand::TablineUpdate {
+                    current_tab,
+                    tabs,
+                    current_buffer,
+                    buffers,
+                });
+            }
+            RedrawEvent::NeovideModifiedBuffers(buffers) => {
+                self.draw_command_batcher.queue(DrawCommand::ModifiedBuffers(buffers));
+            }
+            RedrawEvent::NeovideGutterSigns { window, signs } => {
+                if let Some(&grid) = self.window_grids.get(&window) {
+                    self.draw_command_batcher.queue(DrawCommand::Window {
+                        grid_id: grid,
+                        command: WindowDrawCommand::GutterSigns(signs),
+                    });
+                }
+            }
+            RedrawEvent::NeovideTabIndicators { window, tabs } => {
+                if let Some(&grid) = self.window_grids.get(&window) {
+                    self.draw_command_batcher.queue(DrawCommand::Window {
+                        grid_id: grid,
+                        command: WindowDrawCommand::TabIndicators(tabs),
+                    });
+                }
+            }
+            RedrawEvent::NeovideWindowBlend { window, blend } => {
+                if let Some(&grid) = self.window_grids.get(&window) {
+                    self.draw_command_batcher.queue(DrawCommand::Window {
+                        grid_id: grid,
+                        command: WindowDrawCommand::Blend(blend),
+                    });
+                }
+            }
+            RedrawEvent::NeovideWindowRightLeft { window, right_left } => {
+                if let Some(&grid) = self.window_grids.get(&window) {
+                    self.draw_command_batcher.queue(DrawCommand::Window {
+                        grid_id: grid,
+                        command: WindowDrawCommand::RightLeft(right_left),
+                    });
+                }
+            }
+            RedrawEvent::NeovideNumberColumn { window, columns } => {
+                if let Some(&grid) = self.window_grids.get(&window) {
+                    self.draw_command_batcher.queue(DrawCommand::Window {
+                        grid_id: grid,
+                        command: WindowDrawCommand::NumberColumn(columns),
+                    });
+                }
+            }
+            RedrawEvent::NeovideWindowListChars { window, listchars } => {
+                if let Some(&grid) = self.window_grids.get(&window) {
+                    self.draw_command_batcher.queue(DrawCommand::Window {
+                        grid_id: grid,
+                        command: WindowDrawCommand::ListChars(listchars),
+                    });
+                }
+            }
+            RedrawEvent::NeovideGhostCursor { window, position } => {
+                if let Some(&grid_id) = self.window_grids.get(&window) {
+                    self.draw_command_batcher.queue(DrawCommand::GhostCursor { grid_id, position });
+                }
+            }
+            RedrawEvent::NeovideCmdlineEnter => {
+                self.draw_command_batcher.queue(DrawCommand::CmdlineEntered);
+            }
+            RedrawEvent::NeovideCmdlineLeave(text) => {
+                self.draw_command_batcher.queue(DrawCommand::CmdlineLeft(text));
+            }
+            RedrawEvent::NeovideOverlay(command) => {
+                self.draw_command_batcher.queue(DrawCommand::LuaOverlay(command));
+            }
+            RedrawEvent::NeovidePredictedEcho(character) => {
+                self.predict_echo(character);
+            }
             // Interpreting suspend as a window minimize request
             RedrawEvent::Suspend => {
                 self.send_window_command(WindowCommand::Minimize);
             }
             RedrawEvent::NeovideSetRedraw(enable) => {
-                self.draw_command_batcher.set_enabled(enable, self.route_id, &self.event_loop_proxy)
+                self.draw_command_batcher.set_enabled(enable, self.route_id, &self.event_sender)
             }
             RedrawEvent::NeovideIntroBannerAllowed(allowed) => {
                 self.intro_message_extender.set_sponsor_allowed(
@@ -484,6 +668,7 @@ impl Editor {
     }
 
     fn close_window(&mut self, grid: u64) {
+        self.window_grids.retain(|_, window_grid| *window_grid != grid);
         if let Some(window) = self.windows.remove(&grid) {
             window.close(&mut self.draw_command_batcher);
         }
@@ -659,6 +844,24 @@ impl Editor {
         }
     }
 
+    /// The builtin TUI draws a line of 'fillchars' msgsep in the MsgSeparator highlight above the
+    /// messages once they scroll over the other windows, there's none on the top row
+    fn set_message_separator(
+        &mut self,
+        grid: u64,
+        grid_top: u64,
+        scrolled: bool,
+        separator_character: String,
+    ) {
+        let style =
+            self.message_separator_highlight.and_then(|id| self.defined_styles.get(&id).cloned());
+        if let Some(window) = self.windows.get_mut(&grid) {
+            let separator = (scrolled && grid_top > 0 && !separator_character.is_empty())
+                .then_some((separator_character, style));
+            window.set_message_separator(&mut self.draw_command_batcher, separator);
+        }
+    }
+
     fn get_window_top_left(&self, grid: u64) -> Option<(f64, f64)> {
         let window = self.windows.get(&grid)?;
         let window_anchor_info = &window.anchor_info;
@@ -723,11 +926,77 @@ impl Editor {
             }
         }
 
+        let (_, previous_top) = self.cursor.grid_position;
+        self.cursor.followed_wrap = self.cursor.parent_window_id == grid
+            && self
+                .windows
+                .get(&grid)
+                .is_some_and(|window| window.wraps_between(previous_top, grid_top));
         self.cursor.parent_window_id = grid;
         self.cursor.grid_position = (grid_left, grid_top);
     }
 
-    fn draw_grid_line(&mut self, grid: u64, row: u64, column_start: u64, cells: &[GridLineCell]) {
+    /// Draws the typed character after the cursor before Neovim draws it, with the default colors
+    fn predict_echo(&mut self, character: char) {
+        if !matches!(self.current_mode, EditorMode::Insert) {
+            return;
+        }
+        let grid = self.cursor.parent_window_id;
+        let (column, row) = self.cursor.grid_position;
+        let Some(window) = self
+            .windows
+            .get(&grid)
+            .filter(|window| matches!(window.window_type, WindowType::Editor))
+        else {
+            return;
+        };
+        // The cursor can't be moved past the window
+        let typed = self.echo_prediction.typed_count();
+        if column + typed + 1 >= window.get_width() || row >= window.get_height() {
+            return;
+        }
+        self.echo_prediction.predict(grid, (column, row), character);
+        if let Some(update) = self.echo_prediction.show_now() {
+            self.draw_echo_update(update);
+            self.send_cursor_info();
+            self.send_batch();
+        }
+    }
+
+    fn update_predicted_echo(&mut self) {
+        if let Some(update) = self.echo_prediction.flush() {
+            self.draw_echo_update(update);
+        }
+    }
+
+    fn draw_echo_update(&mut self, update: EchoUpdate) {
+        match update {
+            EchoUpdate::Show { grid, position: (column, row), text } => {
+                if let Some(window) = self.windows.get_mut(&grid) {
+                    window.draw_predicted_row(
+                        &mut self.draw_command_batcher,
+                        row as usize,
+                        column as usize,
+                        &text,
+                    );
+                }
+            }
+            EchoUpdate::Hide { grid, row } => {
+                if let Some(window) = self.windows.get(&grid) {
+                    window.redraw_row(&mut self.draw_command_batcher, row as usize);
+                }
+            }
+        }
+    }
+
+    fn draw_grid_line(
+        &mut self,
+        grid: u64,
+        row: u64,
+        column_start: u64,
+        cells: &[GridLineCell],
+        wrap: bool,
+    ) {
         #[cfg(target_os = "macos")]
         self.update_match_paren_cache_from_grid_line(grid, row, column_start, cells);
 
@@ -737,6 +1006,7 @@ impl Editor {
                 row,
                 column_start,
                 cells.to_vec(),
+                wrap,
                 &self.defined_styles,
             );
         }
@@ -1213,7 +1483,18 @@ impl Editor {
             self.cursor.double_width = false;
             self.cursor.grid_cell = (" ".to_string(), None);
         }
-        self.draw_command_batcher.queue(DrawCommand::UpdateCursor(self.cursor.clone()));
+        let mut cursor = self.cursor.clone();
+        // The predicted characters push the cursor and the cell under it to the right
+        if let Some((grid, (column, row), typed)) = self.echo_prediction.cursor()
+            && let Some(window) = self.windows.get(&grid)
+        {
+            let (character, style, double_width) = window.get_cursor_grid_cell(column, row);
+            cursor.parent_window_id = grid;
+            cursor.grid_position = (column + typed, row);
+            cursor.grid_cell = (character, style);
+            cursor.double_width = double_width;
+        }
+        self.draw_command_batcher.queue(DrawCommand::UpdateCursor(cursor));
     }
 
     fn set_option(&mut self, gui_option: GuiOption) {
@@ -1229,11 +1510,23 @@ impl Editor {
                     self.redraw_screen();
                 }
             }
+            GuiOption::GuiFontWide(guifontwide) => {
+                self.draw_command_batcher.queue(DrawCommand::FontWideChanged(guifontwide));
+
+                self.redraw_screen();
+            }
             GuiOption::LineSpace(linespace) => {
                 self.draw_command_batcher.queue(DrawCommand::LineSpaceChanged(linespace as f32));
 
                 self.redraw_screen();
             }
+            GuiOption::AmbiWidth(ambiwidth) => {
+                self.draw_command_batcher
+                    .queue(DrawCommand::AmbiWidthChanged(AmbiWidth::from(ambiwidth.as_str())));
+            }
+            GuiOption::ShowTabLine(show_tabline) => {
+                self.draw_command_batcher.queue(DrawCommand::ShowTabline(show_tabline));
+            }
             _ => (),
         }
     }
@@ -1314,7 +1607,7 @@ impl Editor {
     fn replay_startup_messages(&self) {
         let batch = vec![DrawCommand::ReplayStartupMessages];
         let payload = EventPayload::for_route(batch.into(), self.route_id);
-        let _ = self.event_loop_proxy.send_event(payload);
+        self.event_sender.send_event(payload);
     }
 }
 
@@ -1373,3 +1666,346 @@ fn window_theme_for_background(background_color: Option<Color4f>) -> Option<Them
         _ => Some(Theme::Dark),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::style::Colors;
+
+    fn create_editor() -> Editor {
+        create_editor_with_events().0
+    }
+
+    /// The editor with the receiving end of the batches and window commands it sends
+    fn create_editor_with_events() -> (Editor, std::sync::mpsc::Receiver<EventPayload>) {
+        let settings = Arc::new(Settings::new());
+        settings.register::<WindowSettings>();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        (Editor::with_sender(RouteId::next(), EventSender::Channel(sender), settings), receiver)
+    }
+
+    fn cell(text: &str, highlight_id: Option<u64>, repeat: Option<u64>) -> GridLineCell {
+        GridLineCell { text: text.to_owned(), highlight_id, repeat }
+    }
+
+    fn grid_line(row: u64, text: &str) -> RedrawEvent {
+        RedrawEvent::GridLine {
+            grid: 1,
+            row,
+            column_start: 0,
+            cells: text.chars().map(|ch| cell(&ch.to_string(), Some(0), None)).collect(),
+            wrap: false,
+        }
+    }
+
+    #[test]
+    fn grid_lines_outside_the_grid_are_clipped() {
+        let mut editor = create_editor();
+        editor.handle_redraw_event(RedrawEvent::Resize { grid: 1, width: 4, height: 2 });
+        editor.handle_redraw_event(grid_line(0, "abcd"));
+        editor.handle_redraw_event(grid_line(1, "efgh"));
+
+        // Lines for the size before a resize
+        editor.handle_redraw_event(grid_line(2, "ijkl"));
+        editor.handle_redraw_event(grid_line(1, "mnopqr"));
+        editor.handle_redraw_event(RedrawEvent::GridLine {
+            grid: 1,
+            row: 0,
+            column_start: 2,
+            cells: vec![cell("-", Some(0), Some(u64::MAX))],
+            wrap: false,
+        });
+        editor.handle_redraw_event(RedrawEvent::Flush);
+
+        assert_eq!(editor.visible_text(1), ["ab--", "mnop"]);
+    }
+
+    #[test]
+    fn grid_lines_and_scrolls_yield_the_visible_text() {
+        let mut editor = create_editor();
+        editor.handle_redraw_event(RedrawEvent::Resize { grid: 1, width: 4, height: 3 });
+        for (row, text) in ["abcd", "efgh", "ijkl"].into_iter().enumerate() {
+            editor.handle_redraw_event(grid_line(row as u64, text));
+        }
+        editor.handle_redraw_event(RedrawEvent::Flush);
+        assert_eq!(editor.visible_text(1), ["abcd", "efgh", "ijkl"]);
+
+        editor.handle_redraw_event(RedrawEvent::Scroll {
+            grid: 1,
+            top: 0,
+            bottom: 3,
+            left: 0,
+            right: 4,
+            rows: 1,
+            columns: 0,
+        });
+        editor.handle_redraw_event(grid_line(2, "mnop"));
+        assert_eq!(editor.visible_text(1), ["efgh", "ijkl", "mnop"]);
+        assert!(editor.visible_text(2).is_empty());
+    }
+
+    #[test]
+    fn cells_keep_the_style_of_their_highlight() {
+        let mut editor = create_editor();
+        let red = Style::new(Colors {
+            foreground: Some(Color4f::new(1.0, 0.0, 0.0, 1.0)),
+            background: None,
+            special: None,
+        });
+        editor.handle_redraw_event(RedrawEvent::HighlightAttributesDefine {
+            id: 1,
+            style: red.clone(),
+            name: None,
+            infos: Vec::new(),
+        });
+        editor.handle_redraw_event(RedrawEvent::Resize { grid: 1, width: 4, height: 1 });
+        editor.handle_redraw_event(RedrawEvent::GridLine {
+            grid: 1,
+            row: 0,
+            column_start: 0,
+            cells: vec![
+                cell("a", Some(0), None),
+                cell("b", Some(1), Some(2)),
+                cell("c", None, None),
+            ],
+            wrap: false,
+        });
+
+        assert_eq!(editor.visible_text(1), ["abbc"]);
+        assert_eq!(editor.cell_style(1, 0, 0), None);
+        assert_eq!(editor.cell_style(1, 0, 1).as_deref(), Some(&red));
+        assert_eq!(editor.cell_style(1, 0, 3).as_deref(), Some(&red));
+    }
+
+    #[test]
+    fn a_guifont_change_rebuilds_the_font_and_redraws_the_grids() {
+        let mut editor = create_editor();
+        editor.handle_redraw_event(RedrawEvent::Resize { grid: 1, width: 4, height: 2 });
+        editor.draw_command_batcher.take_batch();
+
+        editor.handle_redraw_event(RedrawEvent::OptionSet {
+            gui_option: GuiOption::GuiFont("Fira Code:h14".to_owned()),
+        });
+        let batch = editor.draw_command_batcher.take_batch();
+        assert_eq!(batch[0], DrawCommand::FontChanged("Fira Code:h14".to_owned()));
+        // The grids are drawn again with the new cell size
+        assert_eq!(batch[1], DrawCommand::Window { grid_id: 1, command: WindowDrawCommand::Clear });
+        let redrawn_lines = batch
+            .iter()
+            .filter(|command| {
+                matches!(
+                    command,
+                    DrawCommand::Window { command: WindowDrawCommand::DrawLine { .. }, .. }
+                )
+            })
+            .count();
+        assert_eq!(redrawn_lines, 2);
+    }
+
+    #[test]
+    fn batches_are_sent_on_flush_and_held_back_while_redraw_is_disabled() {
+        let (mut editor, events) = create_editor_with_events();
+        let sent_lines = |events: &std::sync::mpsc::Receiver<EventPayload>| {
+            events
+                .try_iter()
+                .filter_map(|event| match event.payload {
+                    UserEvent::DrawCommandBatch(batch) => Some(batch),
+                    _ => None,
+                })
+                .flatten()
+                .filter(|command| {
+                    matches!(
+                        command,
+                        DrawCommand::Window { command: WindowDrawCommand::DrawLine { .. }, .. }
+                    )
+                })
+                .count()
+        };
+        editor.handle_redraw_event(RedrawEvent::Resize { grid: 1, width: 4, height: 1 });
+        editor.handle_redraw_event(grid_line(0, "abcd"));
+        assert_eq!(sent_lines(&events), 0);
+        editor.handle_redraw_event(RedrawEvent::Flush);
+        assert_eq!(sent_lines(&events), 1);
+
+        editor.handle_redraw_event(RedrawEvent::NeovideSetRedraw(false));
+        editor.handle_redraw_event(grid_line(0, "efgh"));
+        editor.handle_redraw_event(RedrawEvent::Flush);
+        assert_eq!(sent_lines(&events), 0);
+        editor.handle_redraw_event(RedrawEvent::NeovideSetRedraw(true));
+        assert_eq!(sent_lines(&events), 1);
+    }
+
+    fn start_typing(text: &str, column: u64) -> (Editor, std::sync::mpsc::Receiver<EventPayload>) {
+        let (mut editor, events) = create_editor_with_events();
+        editor.handle_redraw_event(RedrawEvent::Resize { grid: 1, width: 6, height: 1 });
+        editor.handle_redraw_event(grid_line(0, text));
+        editor.handle_redraw_event(RedrawEvent::CursorGoto { grid: 1, column, row: 0 });
+        editor.handle_redraw_event(RedrawEvent::ModeChange {
+            mode: EditorMode::Insert,
+            mode_index: 0,
+        });
+        editor.handle_redraw_event(RedrawEvent::Flush);
+        events.try_iter().for_each(drop);
+        (editor, events)
+    }
+
+    /// The lines and the last cursor position in the batches sent since the last call
+    fn sent_lines(
+        events: &std::sync::mpsc::Receiver<EventPayload>,
+    ) -> (Vec<String>, Option<(u64, u64)>) {
+        let mut lines = Vec::new();
+        let mut cursor = None;
+        for event in events.try_iter() {
+            let UserEvent::DrawCommandBatch(batch) = event.payload else {
+                continue;
+            };
+            for command in batch {
+                match command {
+                    DrawCommand::Window {
+                        command: WindowDrawCommand::DrawLine { line, .. },
+                        ..
+                    } => lines.push(line.text),
+                    DrawCommand::UpdateCursor(sent) => cursor = Some(sent.grid_position),
+                    _ => {}
+                }
+            }
+        }
+        (lines, cursor)
+    }
+
+    fn unrelated_event() -> RedrawEvent {
+        RedrawEvent::HighlightGroupSet { name: "Comment".to_owned(), id: 3 }
+    }
+
+    #[test]
+    fn predicted_echo_is_replaced_by_the_line_neovim_draws() {
+        let (mut editor, events) = start_typing("abcd", 1);
+
+        editor.handle_redraw_event(RedrawEvent::NeovidePredictedEcho('('));
+        assert_eq!(sent_lines(&events), (vec!["a(bcd ".to_owned()], Some((2, 0))));
+        // The grid keeps what Neovim drew
+        assert_eq!(editor.visible_text(1), ["abcd  "]);
+
+        // An autopair plugin inserted the closing parenthesis as well
+        editor.handle_redraw_event(grid_line(0, "a()bcd"));
+        editor.handle_redraw_event(RedrawEvent::CursorGoto { grid: 1, column: 2, row: 0 });
+        editor.handle_redraw_event(RedrawEvent::Flush);
+        assert_eq!(sent_lines(&events), (vec!["a()bcd".to_owned()], Some((2, 0))));
+    }
+
+    #[test]
+    fn predicted_echo_waits_for_the_flush_of_a_started_batch() {
+        let (mut editor, events) = start_typing("ab", 2);
+
+        editor.handle_redraw_event(unrelated_event());
+        editor.handle_redraw_event(RedrawEvent::NeovidePredictedEcho('c'));
+        assert_eq!(sent_lines(&events), (Vec::new(), None));
+        editor.handle_redraw_event(RedrawEvent::Flush);
+        assert_eq!(sent_lines(&events), (vec!["abc   ".to_owned()], Some((3, 0))));
+
+        // Typed outside of a batch, the characters typed before are drawn with it
+        editor.handle_redraw_event(RedrawEvent::NeovidePredictedEcho('d'));
+        assert_eq!(sent_lines(&events), (vec!["abcd  ".to_owned()], Some((4, 0))));
+    }
+
+    #[test]
+    fn mispredicted_echo_is_taken_back_once_neovim_answers() {
+        let (mut editor, events) = start_typing("ab", 2);
+
+        // The first key of a mapping like `inoremap jk <Esc>` inserts nothing yet
+        editor.handle_redraw_event(RedrawEvent::NeovidePredictedEcho('j'));
+        assert_eq!(sent_lines(&events), (vec!["abj   ".to_owned()], Some((3, 0))));
+
+        // A flush that doesn't answer the key keeps the prediction
+        editor.handle_redraw_event(unrelated_event());
+        editor.handle_redraw_event(RedrawEvent::Flush);
+        assert_eq!(sent_lines(&events), (Vec::new(), Some((3, 0))));
+
+        editor.handle_redraw_event(RedrawEvent::ModeChange {
+            mode: EditorMode::Normal,
+            mode_index: 0,
+        });
+        editor.handle_redraw_event(RedrawEvent::Flush);
+        assert_eq!(sent_lines(&events), (vec!["ab    ".to_owned()], Some((2, 0))));
+
+        // Nothing is predicted outside of insert mode
+        editor.handle_redraw_event(RedrawEvent::NeovidePredictedEcho('x'));
+        assert_eq!(sent_lines(&events), (Vec::new(), None));
+    }
+
+    #[test]
+    fn the_message_separator_is_drawn_above_scrolled_messages() {
+        let mut editor = create_editor();
+        let separator_style =
+            Style::new(Colors::new(None, Some(Color4f::new(0.2, 0.2, 0.2, 1.0)), None));
+        editor.handle_redraw_event(RedrawEvent::Resize { grid: 1, width: 4, height: 5 });
+        editor.handle_redraw_event(RedrawEvent::HighlightAttributesDefine {
+            id: 2,
+            style: separator_style.clone(),
+            name: None,
+            infos: Vec::new(),
+        });
+        editor.handle_redraw_event(RedrawEvent::HighlightGroupSet {
+            name: "MsgSeparator".to_owned(),
+            id: 2,
+        });
+        let message_position = |scrolled| RedrawEvent::MessageSetPosition {
+            grid: 3,
+            row: 2,
+            scrolled,
+            separator_character: "─".to_owned(),
+            z_index: None,
+            comp_index: None,
+        };
+        let separator = |editor: &mut Editor| {
+            editor.draw_command_batcher.take_batch().into_iter().find_map(|command| match command {
+                DrawCommand::Window {
+                    grid_id: 3,
+                    command: WindowDrawCommand::MessageSeparator(separator),
+                } => Some(separator),
+                _ => None,
+            })
+        };
+
+        editor.handle_redraw_event(message_position(true));
+        let line = separator(&mut editor).flatten().unwrap();
+        assert_eq!(line.text, "────");
+        assert_eq!(line.fragments().next().unwrap().style.as_deref(), Some(&separator_style));
+
+        editor.handle_redraw_event(message_position(false));
+        assert_eq!(separator(&mut editor), Some(None));
+    }
+
+    #[test]
+    fn moving_along_a_wrapped_line_is_marked_on_the_cursor() {
+        let mut editor = create_editor();
+        editor.handle_redraw_event(RedrawEvent::Resize { grid: 1, width: 4, height: 3 });
+        editor.handle_redraw_event(RedrawEvent::GridLine {
+            grid: 1,
+            row: 0,
+            column_start: 0,
+            cells: vec![cell("a", Some(0), Some(4))],
+            wrap: true,
+        });
+        // Redrawing the start of the row doesn't touch the flag of its last cell
+        editor.handle_redraw_event(RedrawEvent::GridLine {
+            grid: 1,
+            row: 0,
+            column_start: 0,
+            cells: vec![cell("b", Some(0), None)],
+            wrap: false,
+        });
+        editor.handle_redraw_event(grid_line(1, "ef  "));
+        editor.handle_redraw_event(grid_line(2, "gh  "));
+        let mut goto = |column, row| {
+            editor.handle_redraw_event(RedrawEvent::CursorGoto { grid: 1, column, row });
+            editor.cursor.followed_wrap
+        };
+
+        assert!(!goto(3, 0));
+        assert!(goto(0, 1));
+        assert!(goto(3, 0));
+        // The second row isn't wrapped, the third one is another line
+        assert!(!goto(0, 2));
+    }
+}
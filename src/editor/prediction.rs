@@ -0,0 +1,129 @@
+use std::time::{Duration, Instant};
+
+// Neovim answers a key within its 'timeoutlen', a prediction it ignored for longer is taken back
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The characters typed in insert mode that are drawn before Neovim draws them, as set by
+/// `g:neovide_predictive_echo`. They are kept apart from the grids, which only hold what Neovim
+/// drew, and are shown or taken back only between Neovim's batches, so that no flush shows half
+/// of an update.
+#[derive(Default)]
+pub struct EchoPrediction {
+    typed: Option<Typed>,
+    // Neovim sent events that are only drawn on its next flush
+    in_batch: bool,
+}
+
+struct Typed {
+    grid: u64,
+    // The cursor position when the first character was typed
+    position: (u64, u64),
+    text: String,
+    since: Instant,
+    // Drawn over Neovim's row, without the characters typed since when `changed`
+    shown: bool,
+    changed: bool,
+    // Neovim moved the cursor, changed the mode or redrew the row since the first character
+    answered: bool,
+    // Neovim drew the row itself, which replaces the prediction on screen
+    row_drawn: bool,
+}
+
+/// What to draw for the prediction
+#[derive(Debug, PartialEq)]
+pub enum EchoUpdate {
+    /// The row with the text inserted at the position
+    Show { grid: u64, position: (u64, u64), text: String },
+    /// Neovim's row again, the prediction being taken back
+    Hide { grid: u64, row: u64 },
+}
+
+impl EchoPrediction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Neovim sent an event that is drawn on its next flush
+    pub fn redraw_event(&mut self) {
+        self.in_batch = true;
+    }
+
+    /// The number of typed characters that Neovim hasn't answered yet
+    pub fn typed_count(&self) -> u64 {
+        self.typed.as_ref().map_or(0, |typed| typed.text.chars().count() as u64)
+    }
+
+    /// Adds the character after the ones typed before, or at the cursor position
+    pub fn predict(&mut self, grid: u64, position: (u64, u64), character: char) {
+        let typed = self.typed.get_or_insert_with(|| Typed {
+            grid,
+            position,
+            text: String::new(),
+            since: Instant::now(),
+            shown: false,
+            changed: false,
+            answered: false,
+            row_drawn: false,
+        });
+        typed.text.push(character);
+        typed.changed = true;
+    }
+
+    /// Neovim moved the cursor or changed the mode
+    pub fn answered(&mut self) {
+        if let Some(typed) = &mut self.typed {
+            typed.answered = true;
+        }
+    }
+
+    pub fn row_drawn(&mut self, grid: u64, row: u64) {
+        if let Some(typed) = self.typed.as_mut().filter(|typed| typed.grid == grid)
+            && typed.position.1 == row
+        {
+            typed.answered = true;
+            typed.row_drawn = true;
+        }
+    }
+
+    /// The grid was cleared, scrolled, resized or destroyed
+    pub fn grid_changed(&mut self, grid: u64) {
+        if let Some(typed) = self.typed.as_mut().filter(|typed| typed.grid == grid) {
+            typed.answered = true;
+        }
+    }
+
+    /// Shows the new characters right away, unless Neovim is in the middle of a batch
+    pub fn show_now(&mut self) -> Option<EchoUpdate> {
+        if self.in_batch { None } else { self.show() }
+    }
+
+    /// At Neovim's flush, the answered or timed out prediction is replaced by what Neovim drew,
+    /// and the characters typed during the batch are shown
+    pub fn flush(&mut self) -> Option<EchoUpdate> {
+        self.in_batch = false;
+        let typed = self.typed.as_ref()?;
+        if !typed.answered && typed.since.elapsed() < TIMEOUT {
+            return self.show();
+        }
+        let typed = self.typed.take()?;
+        (typed.shown && !typed.row_drawn)
+            .then_some(EchoUpdate::Hide { grid: typed.grid, row: typed.position.1 })
+    }
+
+    /// The grid and cursor position the shown characters were typed at, with their count
+    pub fn cursor(&self) -> Option<(u64, (u64, u64), u64)> {
+        let typed = self.typed.as_ref().filter(|typed| typed.shown)?;
+        Some((typed.grid, typed.position, self.typed_count()))
+    }
+
+    fn show(&mut self) -> Option<EchoUpdate> {
+        let typed = self.typed.as_mut().filter(|typed| typed.changed)?;
+        typed.shown = true;
+        typed.changed = false;
+        Some(EchoUpdate::Show {
+            grid: typed.grid,
+            position: typed.position,
+            text: typed.text.clone(),
+        })
+    }
+}
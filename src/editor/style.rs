@@ -1,3 +1,6 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde_json::{Value, json};
 use skia_safe::Color4f;
 
 #[derive(new, Debug, Clone, PartialEq)]
@@ -19,6 +22,19 @@ pub enum UnderlineStyle {
     UnderCurl,
 }
 
+impl UnderlineStyle {
+    /// The name of the attribute in `hl_attr_define`
+    fn attribute_name(self) -> &'static str {
+        match self {
+            Self::Underline => "underline",
+            Self::UnderDouble => "underdouble",
+            Self::UnderDash => "underdashed",
+            Self::UnderDot => "underdotted",
+            Self::UnderCurl => "undercurl",
+        }
+    }
+}
+
 #[derive(new, Debug, Clone, PartialEq)]
 pub struct Style {
     pub colors: Colors,
@@ -39,6 +55,13 @@ pub struct Style {
     pub blend: u8,
     #[new(default)]
     pub underline: Option<UnderlineStyle>,
+    /// Names of the highlight groups the style was combined from.
+    #[new(default)]
+    pub highlight_groups: Vec<String>,
+    /// Names of the builtin UI elements the style was drawn for, like DiffAdd, whatever group
+    /// they link to.
+    #[new(default)]
+    pub ui_elements: Vec<String>,
 }
 
 impl Style {
@@ -63,6 +86,38 @@ impl Style {
     }
 }
 
+fn color_json(color: Option<Color4f>) -> Value {
+    color.map_or(Value::Null, |color| {
+        let color = color.to_color();
+        Value::from(format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b()))
+    })
+}
+
+/// The styles defined with `hl_attr_define` as JSON, ordered by id, with the highlight groups
+/// they were combined from. Unset colors are null, they fall back to the default colors.
+pub fn highlight_table_json(styles: &HashMap<u64, Arc<Style>>) -> Value {
+    let mut ids = styles.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.into_iter()
+        .map(|id| {
+            let style = &styles[&id];
+            json!({
+                "id": id,
+                "groups": style.highlight_groups,
+                "foreground": color_json(style.colors.foreground),
+                "background": color_json(style.colors.background),
+                "special": color_json(style.colors.special),
+                "reverse": style.reverse,
+                "italic": style.italic,
+                "bold": style.bold,
+                "strikethrough": style.strikethrough,
+                "blend": style.blend,
+                "underline": style.underline.map(UnderlineStyle::attribute_name),
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,4 +180,40 @@ mod tests {
         style.colors.special = None;
         assert_eq!(style.special(&DEFAULT_COLORS), style.foreground(&DEFAULT_COLORS),);
     }
+
+    #[test]
+    fn highlight_table_has_the_attributes_of_each_style() {
+        let mut comment = Style::new(Colors::new(
+            Some(Color4f::new(1.0, 0.0, 0.0, 1.0)),
+            None,
+            Some(Color4f::new(0.0, 0.0, 1.0, 1.0)),
+        ));
+        comment.italic = true;
+        comment.blend = 20;
+        comment.underline = Some(UnderlineStyle::UnderCurl);
+        comment.highlight_groups = vec!["Comment".to_owned()];
+        let styles = HashMap::from([
+            (7, Arc::new(comment)),
+            (2, Arc::new(Style::new(Colors::new(None, None, None)))),
+        ]);
+
+        let table = highlight_table_json(&styles);
+        assert_eq!(table[0]["id"], 2);
+        assert_eq!(
+            table[1],
+            json!({
+                "id": 7,
+                "groups": ["Comment"],
+                "foreground": "#ff0000",
+                "background": null,
+                "special": "#0000ff",
+                "reverse": false,
+                "italic": true,
+                "bold": false,
+                "strikethrough": false,
+                "blend": 20,
+                "underline": "undercurl",
+            })
+        );
+    }
 }
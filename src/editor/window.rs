@@ -1,10 +1,14 @@
 use std::{collections::HashMap, ops::Range, sync::Arc};
 
-use log::warn;
+use log::debug;
 
 use crate::{
     bridge::GridLineCell,
-    editor::{AnchorInfo, DrawCommand, DrawCommandBatcher, grid::CharacterGrid, style::Style},
+    editor::{
+        AnchorInfo, DrawCommand, DrawCommandBatcher,
+        grid::{CharacterGrid, GridCell},
+        style::Style,
+    },
     renderer::{WindowDrawCommand, box_drawing},
     units::{GridRect, GridSize},
 };
@@ -68,6 +72,19 @@ impl Line {
     pub fn cells(&self) -> Option<&[String]> {
         self.cells.as_deref()
     }
+
+    /// A line of unstyled text, one cell per character
+    #[cfg(test)]
+    pub fn from_text_for_test(text: &str) -> Self {
+        let length = text.len() as u32;
+        let fragment = LineFragmentData {
+            text_range: 0..length,
+            style: None,
+            cells: 0..text.chars().count() as u32,
+            words: Vec::new(),
+        };
+        Line { text: text.to_owned(), fragments: vec![fragment], cells: None }
+    }
 }
 
 impl LineFragment<'_> {
@@ -88,6 +105,21 @@ impl<'a> Word<'a> {
         Self { text, cell: 0, cluster_sizes }
     }
 
+    /// The cells covered by the word, counted from the start of its fragment. The second cell of
+    /// a double width character is included.
+    pub fn cells(&self) -> Range<u32> {
+        self.cell..self.cell + self.cluster_sizes.len() as u32
+    }
+
+    /// The cells of the double width characters of the word, the second cell being left out
+    pub fn double_width_cells(&self) -> impl Iterator<Item = usize> + '_ {
+        self.cluster_sizes
+            .windows(2)
+            .enumerate()
+            .filter(|(_, sizes)| sizes[0] > 0 && sizes[1] == 0)
+            .map(|(cell, _)| cell)
+    }
+
     pub fn grapheme_clusters(&self) -> impl Iterator<Item = (usize, &'a str)> + Clone {
         self.cluster_sizes.iter().enumerate().filter(|(_, size)| **size > 0).scan(
             0,
@@ -107,6 +139,8 @@ pub struct Window {
 
     pub anchor_info: Option<AnchorInfo>,
     grid_position: (f64, f64),
+    // The cell repeated in the separator above a message window, while there's one
+    message_separator: Option<GridCell>,
 }
 
 impl Window {
@@ -124,6 +158,7 @@ impl Window {
             window_type,
             anchor_info,
             grid_position,
+            message_separator: None,
         };
         window.send_updated_position(draw_command_batcher);
         window
@@ -166,6 +201,14 @@ impl Window {
         (grid_cell.0, grid_cell.1, double_width)
     }
 
+    #[cfg(test)]
+    pub fn text(&self) -> Vec<String> {
+        (0..self.grid.height)
+            .filter_map(|row| self.grid.row(row))
+            .map(|cells| cells.iter().map(|(character, _)| character.as_str()).collect())
+            .collect()
+    }
+
     pub fn get_width(&self) -> u64 {
         self.grid.width as u64
     }
@@ -194,6 +237,10 @@ impl Window {
     pub fn resize(&mut self, batcher: &mut DrawCommandBatcher, new_size: (u64, u64)) {
         self.grid.resize((new_size.0 as usize, new_size.1 as usize));
         self.send_updated_position(batcher);
+        // The separator spans the new width
+        if self.message_separator.is_some() {
+            self.send_message_separator(batcher);
+        }
     }
 
     fn modify_grid(
@@ -219,6 +266,8 @@ impl Window {
                 return;
             }
 
+            // Cells past the end of the grid are dropped anyway
+            let times = times.min(self.grid.width.saturating_sub(*column_pos) as u64);
             for _ in 0..times.saturating_sub(1) {
                 if let Some(cell) = self.grid.get_cell_mut(*column_pos, row_index) {
                     *cell = (text.clone(), style.clone());
@@ -330,6 +379,11 @@ impl Window {
     // until current_start is greater than the grid width and sending the resulting
     // fragments as a batch.
     fn redraw_line(&self, batcher: &mut DrawCommandBatcher, row: usize) {
+        let line = self.build_line(row);
+        self.send_command(batcher, WindowDrawCommand::DrawLine { row, line });
+    }
+
+    fn build_line(&self, row: usize) -> Line {
         let mut current_start = 0;
         let mut line_fragments = Vec::new();
         let mut text = String::new();
@@ -344,9 +398,7 @@ impl Window {
             .then(|| self.row_cells(row))
             .flatten();
 
-        let line = Line { text, fragments: line_fragments, cells };
-
-        self.send_command(batcher, WindowDrawCommand::DrawLine { row, line });
+        Line { text, fragments: line_fragments, cells }
     }
 
     fn row_cells(&self, row: usize) -> Option<Vec<String>> {
@@ -359,22 +411,106 @@ impl Window {
         row: u64,
         column_start: u64,
         cells: Vec<GridLineCell>,
+        wrap: bool,
         defined_styles: &HashMap<u64, Arc<Style>>,
     ) {
         let mut previous_style = None;
         let row = row as usize;
-        if row < self.grid.height {
-            let mut column_pos = column_start as usize;
-            for cell in cells {
-                self.modify_grid(row, &mut column_pos, cell, defined_styles, &mut previous_style);
+        // While resizing, Neovim can still send lines for the previous size, they are clipped to
+        // the current grid and redrawn once Neovim catches up
+        if row >= self.grid.height {
+            debug!(
+                "Ignoring grid_line for row {row} of grid {}, which has {} rows",
+                self.grid_id, self.grid.height
+            );
+            return;
+        }
+        let mut column_pos = column_start as usize;
+        for cell in cells {
+            if column_pos >= self.grid.width {
+                debug!(
+                    "Clipping grid_line for row {row} of grid {} at its {} columns",
+                    self.grid_id, self.grid.width
+                );
+                break;
             }
+            self.modify_grid(row, &mut column_pos, cell, defined_styles, &mut previous_style);
+        }
+        // The flag belongs to the last cell of the row, lines that end before it leave it alone
+        if column_pos >= self.grid.width {
+            self.grid.set_wrap(row, wrap);
+        }
 
+        self.redraw_line(batcher, row);
+    }
+
+    /// Whether moving between the rows stays on one line of the buffer, wrapped over them
+    pub fn wraps_between(&self, from_row: u64, to_row: u64) -> bool {
+        let rows = from_row.min(to_row)..from_row.max(to_row);
+        !rows.is_empty() && rows.into_iter().all(|row| self.grid.wraps(row as usize))
+    }
+
+    pub fn redraw_row(&self, batcher: &mut DrawCommandBatcher, row: usize) {
+        if row < self.grid.height {
             self.redraw_line(batcher, row);
-        } else {
-            warn!("Draw command out of bounds");
         }
     }
 
+    /// Draws the row with the text inserted at the column like typing in insert mode does, moving
+    /// the rest of the row to the right, while the grid keeps what Neovim drew
+    pub fn draw_predicted_row(
+        &mut self,
+        batcher: &mut DrawCommandBatcher,
+        row: usize,
+        column: usize,
+        text: &str,
+    ) {
+        let Some(saved) = self.grid.row(row).filter(|cells| column < cells.len()) else {
+            return;
+        };
+        let saved = saved.to_vec();
+        let typed = text.chars().map(|character| (character.to_string(), None));
+        let predicted =
+            saved[..column].iter().cloned().chain(typed).chain(saved[column..].to_vec());
+        for (column, predicted_cell) in predicted.enumerate() {
+            if let Some(cell) = self.grid.get_cell_mut(column, row) {
+                *cell = predicted_cell;
+            }
+        }
+        self.redraw_line(batcher, row);
+        for (column, saved_cell) in saved.into_iter().enumerate() {
+            if let Some(cell) = self.grid.get_cell_mut(column, row) {
+                *cell = saved_cell;
+            }
+        }
+    }
+
+    pub fn set_message_separator(
+        &mut self,
+        batcher: &mut DrawCommandBatcher,
+        separator: Option<GridCell>,
+    ) {
+        self.message_separator = separator;
+        self.send_message_separator(batcher);
+    }
+
+    fn send_message_separator(&self, batcher: &mut DrawCommandBatcher) {
+        let line = self.message_separator.clone().map(|cell| {
+            let mut grid = CharacterGrid::new((self.grid.width, 1));
+            grid.set_all_characters(cell);
+            Window {
+                grid_id: self.grid_id,
+                grid,
+                window_type: WindowType::Editor,
+                anchor_info: None,
+                grid_position: self.grid_position,
+                message_separator: None,
+            }
+            .build_line(0)
+        });
+        self.send_command(batcher, WindowDrawCommand::MessageSeparator(line));
+    }
+
     pub fn draw_centered_text_line(
         &mut self,
         batcher: &mut DrawCommandBatcher,
@@ -518,9 +654,22 @@ mod tests {
             window_type: WindowType::Editor,
             anchor_info: None,
             grid_position: (0.0, 0.0),
+            message_separator: None,
         }
     }
 
+    #[test]
+    fn only_changed_lines_compare_unequal() {
+        let red = make_window([[("a", Some(colors::RED)), ("b", Some(colors::RED))]]);
+        let green = make_window([[("a", Some(colors::RED)), ("b", Some(colors::GREEN))]]);
+        let text = make_window([[("a", Some(colors::RED)), ("c", Some(colors::RED))]]);
+
+        let line = red.build_line(0);
+        assert_eq!(red.build_line(0), line);
+        assert_ne!(green.build_line(0), line);
+        assert_ne!(text.build_line(0), line);
+    }
+
     #[test]
     fn test_build_line_fragment_macro_basic() {
         let window = make_window([[
@@ -748,4 +897,17 @@ mod tests {
 
         assert_eq!(window.get_cursor_grid_cell(0, 0), (" ".to_string(), None, false));
     }
+
+    #[test]
+    fn get_cursor_grid_cell_returns_the_highlight_groups_of_the_cell() {
+        let mut window = make_window([[("a", None), ("b", None)]]);
+        let mut style = Style::new(Colors::new(None, None, None));
+        style.highlight_groups = vec!["String".to_string()];
+        *window.grid.get_cell_mut(1, 0).unwrap() = ("b".to_string(), Some(Arc::new(style)));
+
+        let (_, style, _) = window.get_cursor_grid_cell(1, 0);
+        assert_eq!(style.unwrap().highlight_groups, vec!["String"]);
+        let (_, style, _) = window.get_cursor_grid_cell(0, 0);
+        assert_eq!(style, None);
+    }
 }
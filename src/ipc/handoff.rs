@@ -22,6 +22,8 @@ use crate::{
 
 const CLIENT_IO_TIMEOUT: Duration = Duration::from_secs(2);
 const LISTENER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// How long an instance that is still starting up gets to bind its listener
+const STARTUP_GRACE_PERIOD: Duration = Duration::from_secs(5);
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HandoffRequest {
@@ -35,6 +37,8 @@ pub struct HandoffRequest {
     pub neovim_bin: Option<String>,
     #[serde(default)]
     pub neovim_args: Option<Vec<String>>,
+    #[serde(default)]
+    pub cursor: Option<(u64, u64)>,
 }
 
 impl HandoffRequest {
@@ -49,6 +53,7 @@ impl HandoffRequest {
             new_window: false,
             neovim_bin: None,
             neovim_args: None,
+            cursor: None,
         }
     }
 }
@@ -89,7 +94,41 @@ struct HandoffResponse {
     error: Option<String>,
 }
 
+/// Forwards the request to the running instance. When another instance was launched just before
+/// this one and hasn't bound its listener yet, this waits for it instead of starting a second one.
 pub fn try_handoff(request: &HandoffRequest) -> HandoffResult {
+    loop {
+        match send_request(request) {
+            HandoffResult::NoListener if is_starting(startup_lock_age()) => {
+                thread::sleep(LISTENER_POLL_INTERVAL);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Tells instances launched from now on that this one will be listening soon
+pub fn mark_starting() {
+    let result = fs::create_dir_all(neovide_std_datapath())
+        .and_then(|_| fs::write(startup_lock_path(), std::process::id().to_string()));
+    if let Err(error) = result {
+        log::warn!("failed to create instance startup lock: {error}");
+    }
+}
+
+fn startup_lock_age() -> Option<Duration> {
+    fs::metadata(startup_lock_path())
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+}
+
+/// Locks older than the grace period are left behind by instances that failed to start
+fn is_starting(lock_age: Option<Duration>) -> bool {
+    lock_age.is_some_and(|age| age < STARTUP_GRACE_PERIOD)
+}
+
+fn send_request(request: &HandoffRequest) -> HandoffResult {
     let endpoint = endpoint_path();
     let stream = match UnixStream::connect(&endpoint) {
         Ok(stream) => stream,
@@ -171,6 +210,7 @@ pub fn start_listener(proxy: EventLoopProxy<EventPayload>) -> Result<ListenerGua
     listener
         .set_nonblocking(true)
         .context("failed to configure instance IPC listener as nonblocking")?;
+    let _ = fs::remove_file(startup_lock_path());
 
     let shutdown = Arc::new(AtomicBool::new(false));
     let listener_proxy = proxy.clone();
@@ -230,6 +270,7 @@ fn handle_request(
                 new_window: request.new_window,
                 neovim_bin: request.neovim_bin,
                 neovim_args: request.neovim_args,
+                cursor: request.cursor,
             },
             target: EventTarget::Focused,
         };
@@ -252,6 +293,10 @@ fn endpoint_path() -> std::path::PathBuf {
     neovide_std_datapath().join(format!("neovide-{}.sock", release_channel()))
 }
 
+fn startup_lock_path() -> std::path::PathBuf {
+    neovide_std_datapath().join(format!("neovide-{}.starting", release_channel()))
+}
+
 fn write_message<T, W>(writer: &mut W, value: &T) -> Result<()>
 where
     T: Serialize,
@@ -284,9 +329,13 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{HandoffRequest, HandoffResponse, read_message, write_message};
+    use super::{
+        HandoffRequest, HandoffResponse, STARTUP_GRACE_PERIOD, is_starting, read_message,
+        write_message,
+    };
     use crate::version::BUILD_VERSION;
-    use std::io::Cursor;
+    use serde_json::json;
+    use std::{io::Cursor, time::Duration};
 
     #[test]
     fn handoff_request_new_sets_build_version() {
@@ -332,4 +381,55 @@ mod tests {
 
         assert_eq!(decoded, response);
     }
+
+    #[test]
+    fn forwarded_arguments_use_a_stable_message_format() {
+        let request = HandoffRequest {
+            files_to_open: vec!["./file2.txt".into(), "/tmp/file3.txt".into()],
+            caller_cwd: Some("/home/user".into()),
+            neovim_args: Some(vec!["--clean".into()]),
+            cursor: Some((42, 5)),
+            ..HandoffRequest::new()
+        };
+
+        let mut encoded = Vec::new();
+        write_message(&mut encoded, &request).unwrap();
+
+        assert_eq!(encoded.last(), Some(&b'\n'));
+        let message: serde_json::Value = serde_json::from_slice(&encoded).unwrap();
+        assert_eq!(
+            message,
+            json!({
+                "version": BUILD_VERSION,
+                "files_to_open": ["./file2.txt", "/tmp/file3.txt"],
+                "cwd": null,
+                "caller_cwd": "/home/user",
+                "tabs": true,
+                "new_window": false,
+                "neovim_bin": null,
+                "neovim_args": ["--clean"],
+                "cursor": [42, 5],
+            })
+        );
+    }
+
+    #[test]
+    fn messages_from_older_versions_are_accepted() {
+        let message = format!(
+            r#"{{"version":"{BUILD_VERSION}","files_to_open":["a.txt"],"cwd":null,"caller_cwd":null,"tabs":false,"new_window":false}}"#
+        );
+
+        let decoded: HandoffRequest = read_message(&mut Cursor::new(message)).unwrap();
+
+        assert_eq!(decoded.files_to_open, vec!["a.txt"]);
+        assert_eq!(decoded.neovim_args, None);
+        assert_eq!(decoded.cursor, None);
+    }
+
+    #[test]
+    fn waits_only_for_recently_started_instances() {
+        assert!(!is_starting(None));
+        assert!(is_starting(Some(Duration::from_millis(200))));
+        assert!(!is_starting(Some(STARTUP_GRACE_PERIOD)));
+    }
 }
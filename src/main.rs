@@ -22,7 +22,7 @@ mod dimensions;
 mod editor;
 mod error_handling;
 mod frame;
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 mod ipc;
 mod platform;
 mod profiling;
@@ -30,6 +30,7 @@ mod renderer;
 mod running_tracker;
 mod settings;
 mod units;
+mod url_scheme;
 mod utils;
 mod version;
 mod window;
@@ -80,7 +81,7 @@ use crate::{
     settings::{Config, Settings, load_last_window_settings},
 };
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 use crate::utils::resolved_cwd;
 
 pub use profiling::startup_profiler;
@@ -155,13 +156,17 @@ fn main() -> ExitCode {
         clipboard_handle,
     );
 
-    #[cfg(target_os = "macos")]
-    let _handoff_listener = match ipc::handoff::start_listener(event_loop.create_proxy()) {
-        Ok(listener) => Some(listener),
-        Err(error) => {
-            log::warn!("failed to start handoff listener: {error:#}");
-            None
+    #[cfg(unix)]
+    let _handoff_listener = if listens_for_handoff(&settings.get::<CmdLineSettings>()) {
+        match ipc::handoff::start_listener(event_loop.create_proxy()) {
+            Ok(listener) => Some(listener),
+            Err(error) => {
+                log::warn!("failed to start handoff listener: {error:#}");
+                None
+            }
         }
+    } else {
+        None
     };
 
     let result = application.run(event_loop);
@@ -183,8 +188,11 @@ fn preflight(settings: &Settings) -> Result<()> {
         }
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(unix)]
     match maybe_handoff(settings) {
+        HandoffOutcome::Continue if requests_reuse(&settings.get::<CmdLineSettings>()) => {
+            ipc::handoff::mark_starting()
+        }
         HandoffOutcome::Continue => {}
         HandoffOutcome::Exit => std::process::exit(0),
         HandoffOutcome::Error(error) => return Err(anyhow::anyhow!(error)),
@@ -296,24 +304,43 @@ fn setup(
     Ok(())
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 enum HandoffOutcome {
     Continue,
     Exit,
     Error(String),
 }
 
-#[cfg(target_os = "macos")]
+/// Whether the instance was launched to reuse a running one, URLs always go to the running instance,
+/// that's what they are registered for
+#[cfg(unix)]
+fn requests_reuse(cmdline_settings: &CmdLineSettings) -> bool {
+    cmdline_settings.reuse_instance || cmdline_settings.open_url.is_some()
+}
+
+/// Whether this instance takes the requests of the ones launched later. On macOS every instance
+/// does, since the ones started from the Dock or Finder can't be given `--reuse-instance`.
+#[cfg(unix)]
+fn listens_for_handoff(cmdline_settings: &CmdLineSettings) -> bool {
+    cfg!(target_os = "macos") || requests_reuse(cmdline_settings)
+}
+
+#[cfg(unix)]
 fn maybe_handoff(settings: &Settings) -> HandoffOutcome {
     let cmdline_settings = settings.get::<CmdLineSettings>();
-    if !cmdline_settings.reuse_instance
+    #[cfg(target_os = "macos")]
+    let new_window = cmdline_settings.new_window;
+    #[cfg(not(target_os = "macos"))]
+    let new_window = false;
+
+    if !requests_reuse(&cmdline_settings)
         || cmdline_settings.server.is_some()
-        || (cmdline_settings.files_to_open.is_empty() && !cmdline_settings.new_window)
+        || (cmdline_settings.files_to_open.is_empty() && !new_window)
     {
         return HandoffOutcome::Continue;
     }
 
-    let CmdLineSettings { files_to_open, tabs, new_window, neovim_bin, neovim_args, .. } =
+    let CmdLineSettings { files_to_open, tabs, neovim_bin, neovim_args, open_url, .. } =
         cmdline_settings;
 
     let request = ipc::handoff::HandoffRequest {
@@ -325,6 +352,7 @@ fn maybe_handoff(settings: &Settings) -> HandoffOutcome {
         new_window,
         neovim_bin,
         neovim_args: (!neovim_args.is_empty()).then_some(neovim_args),
+        cursor: open_url.and_then(|url| url.cursor()),
     };
 
     match ipc::handoff::try_handoff(&request) {
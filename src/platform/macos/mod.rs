@@ -35,6 +35,7 @@ use crate::bridge::{
 };
 use crate::renderer::fonts::font_options::FontOptions;
 use crate::settings::Settings;
+use crate::url_scheme::OpenUrl;
 use crate::utils::expand_tilde;
 use crate::window::macos::tab_navigation::KeyCombo;
 use crate::{cmd_line::CmdLineSettings, frame::Frame};
@@ -317,20 +318,27 @@ fn open_external_url(url: &str) {
     }
 }
 
-fn apply_menu_item_hotkey(item: &NSMenuItem, raw: &str, setting_name: &str) {
-    let Some(shortcut) = KeyCombo::parse(raw) else {
-        return;
-    };
-
-    let Some(key) = shortcut.to_key() else {
+fn parse_menu_item_hotkey(raw: &str, setting_name: &str) -> Option<KeyCombo> {
+    let shortcut = KeyCombo::parse(raw)?;
+    if shortcut.to_key().is_none() {
         log::warn!(
             "macOS menu shortcut '{raw}' for {setting_name} uses an unsupported named key; ignoring"
         );
-        return;
-    };
+        return None;
+    }
+    Some(shortcut)
+}
 
-    item.setKeyEquivalent(key.as_ref());
-    item.setKeyEquivalentModifierMask(shortcut.to_modifiers());
+fn apply_menu_item_hotkey(item: &NSMenuItem, shortcut: KeyCombo, window_settings: &WindowSettings) {
+    // Without a key equivalent a disabled builtin shortcut reaches Neovim
+    if window_settings.disabled_builtin_shortcuts.contains(&shortcut.to_vim_key()) {
+        item.setKeyEquivalent(ns_string!(""));
+        return;
+    }
+    if let Some(key) = shortcut.to_key() {
+        item.setKeyEquivalent(key.as_ref());
+        item.setKeyEquivalentModifierMask(shortcut.to_modifiers());
+    }
 }
 
 #[derive(Debug)]
@@ -1023,10 +1031,8 @@ impl MacosWindowFeature {
 
     /// Update background color, opacity, shadow and blur of a window.
     fn update_background(&self) {
-        let WindowSettings { show_border, opacity, normal_opacity, .. } =
-            self.settings.get::<WindowSettings>();
-        let opaque = opacity.min(normal_opacity) >= 1.0;
-        self.update_ns_background(opaque, show_border);
+        let window_settings = self.settings.get::<WindowSettings>();
+        self.update_ns_background(!window_settings.transparent(), window_settings.show_border);
     }
 
     pub fn set_document_state(&self, path: &str, modified: bool) {
@@ -1064,6 +1070,10 @@ impl MacosWindowFeature {
                 log::info!("normal_opacity changed to {normal_opacity}");
                 self.update_background();
             }
+            WindowSettingsChanged::BackgroundOpacity(background_opacity) => {
+                log::info!("background_opacity changed to {background_opacity}");
+                self.update_background();
+            }
             WindowSettingsChanged::WindowBlurred(window_blurred) => {
                 log::info!("window_blurred changed to {window_blurred}");
                 self.update_background();
@@ -1071,6 +1081,14 @@ impl MacosWindowFeature {
             WindowSettingsChanged::MacosSimpleFullscreen(enabled) => {
                 self.set_simple_fullscreen_mode(enabled);
             }
+            WindowSettingsChanged::DisabledBuiltinShortcuts(_) => {
+                let window_settings = self.settings.get::<WindowSettings>();
+                APP_MENU.with(|menu_cell| {
+                    if let Some(menu) = menu_cell.borrow().as_ref() {
+                        menu.update_hotkeys(&window_settings);
+                    }
+                });
+            }
             _ => {}
         }
     }
@@ -1541,6 +1559,8 @@ struct Menu {
     _tab_overview_observer: Retained<TabOverviewNotificationHandler>,
     _window_menu_observer: Retained<WindowMenuNotificationHandler>,
     window_menu_delegate: Retained<WindowMenuDelegate>,
+    // The items with a key equivalent, updated when the disabled builtin shortcuts change
+    hotkey_items: RefCell<Vec<(Retained<NSMenuItem>, KeyCombo)>>,
 }
 
 impl Menu {
@@ -1553,12 +1573,38 @@ impl Menu {
             _tab_overview_observer: TabOverviewNotificationHandler::register(mtm),
             _window_menu_observer: WindowMenuNotificationHandler::register(mtm),
             window_menu_delegate: WindowMenuDelegate::new(mtm),
+            hotkey_items: RefCell::new(Vec::new()),
         };
-        menu.add_menus(mtm, &settings.get::<CmdLineSettings>());
+        menu.add_menus(mtm, &settings.get::<CmdLineSettings>(), &settings.get::<WindowSettings>());
         menu
     }
 
-    fn add_app_menu(&self, mtm: MainThreadMarker, settings: &CmdLineSettings) -> Retained<NSMenu> {
+    fn add_hotkey(
+        &self,
+        item: &NSMenuItem,
+        raw: &str,
+        setting_name: &str,
+        window_settings: &WindowSettings,
+    ) {
+        let Some(shortcut) = parse_menu_item_hotkey(raw, setting_name) else {
+            return;
+        };
+        apply_menu_item_hotkey(item, shortcut, window_settings);
+        self.hotkey_items.borrow_mut().push((item.retain(), shortcut));
+    }
+
+    fn update_hotkeys(&self, window_settings: &WindowSettings) {
+        for (item, shortcut) in self.hotkey_items.borrow().iter() {
+            apply_menu_item_hotkey(item, *shortcut, window_settings);
+        }
+    }
+
+    fn add_app_menu(
+        &self,
+        mtm: MainThreadMarker,
+        settings: &CmdLineSettings,
+        window_settings: &WindowSettings,
+    ) -> Retained<NSMenu> {
         unsafe {
             let app_menu = NSMenu::new(mtm);
             let process_name = NSProcessInfo::processInfo().processName();
@@ -1579,16 +1625,22 @@ impl Menu {
             // application window operations
             let hide_item = NSMenuItem::new(mtm);
             hide_item.setTitle(&ns_string!("Hide ").stringByAppendingString(&process_name));
-            apply_menu_item_hotkey(&hide_item, &settings.system_hide_hotkey, "system_hide_hotkey");
+            self.add_hotkey(
+                &hide_item,
+                &settings.system_hide_hotkey,
+                "system_hide_hotkey",
+                window_settings,
+            );
             hide_item.setAction(Some(sel!(hide:)));
             app_menu.addItem(&hide_item);
 
             let hide_others_item = NSMenuItem::new(mtm);
             hide_others_item.setTitle(ns_string!("Hide Others"));
-            apply_menu_item_hotkey(
+            self.add_hotkey(
                 &hide_others_item,
                 &settings.system_hide_others_hotkey,
                 "system_hide_others_hotkey",
+                window_settings,
             );
             hide_others_item.setAction(Some(sel!(hideOtherApplications:)));
             app_menu.addItem(&hide_others_item);
@@ -1603,7 +1655,12 @@ impl Menu {
 
             let quit_item = NSMenuItem::new(mtm);
             quit_item.setTitle(&ns_string!("Quit ").stringByAppendingString(&process_name));
-            apply_menu_item_hotkey(&quit_item, &settings.system_quit_hotkey, "system_quit_hotkey");
+            self.add_hotkey(
+                &quit_item,
+                &settings.system_quit_hotkey,
+                "system_quit_hotkey",
+                window_settings,
+            );
             quit_item.setAction(Some(sel!(quit:)));
             quit_item.setTarget(Some(&self.quit_handler));
             app_menu.addItem(&quit_item);
@@ -1612,12 +1669,17 @@ impl Menu {
         }
     }
 
-    fn add_menus(&self, mtm: MainThreadMarker, settings: &CmdLineSettings) {
+    fn add_menus(
+        &self,
+        mtm: MainThreadMarker,
+        settings: &CmdLineSettings,
+        window_settings: &WindowSettings,
+    ) {
         let app = NSApplication::sharedApplication(mtm);
 
         let main_menu = NSMenu::new(mtm);
 
-        let app_menu = self.add_app_menu(mtm, settings);
+        let app_menu = self.add_app_menu(mtm, settings, window_settings);
         let app_menu_item = NSMenuItem::new(mtm);
         app_menu_item.setSubmenu(Some(&app_menu));
         if let Some(services_menu) = app_menu.itemWithTitle(ns_string!("Services")) {
@@ -1625,7 +1687,7 @@ impl Menu {
         }
         main_menu.addItem(&app_menu_item);
 
-        let win_menu = self.add_window_menu(mtm, settings);
+        let win_menu = self.add_window_menu(mtm, settings, window_settings);
         let win_menu_item = NSMenuItem::new(mtm);
         win_menu_item.setSubmenu(Some(&win_menu));
         main_menu.addItem(&win_menu_item);
@@ -1645,6 +1707,7 @@ impl Menu {
         &self,
         mtm: MainThreadMarker,
         settings: &CmdLineSettings,
+        window_settings: &WindowSettings,
     ) -> Retained<NSMenu> {
         unsafe {
             let menu = NSMenu::new(mtm);
@@ -1655,20 +1718,22 @@ impl Menu {
 
             let full_screen_item = NSMenuItem::new(mtm);
             full_screen_item.setTitle(ns_string!("Enter Full Screen"));
-            apply_menu_item_hotkey(
+            self.add_hotkey(
                 &full_screen_item,
                 &settings.system_fullscreen_hotkey,
                 "system_fullscreen_hotkey",
+                window_settings,
             );
             full_screen_item.setAction(Some(sel!(toggleFullScreen:)));
             menu.addItem(&full_screen_item);
 
             let create_new_window = NSMenuItem::new(mtm);
             create_new_window.setTitle(ns_string!("New Window"));
-            apply_menu_item_hotkey(
+            self.add_hotkey(
                 &create_new_window,
                 &settings.system_new_window_hotkey,
                 "system_new_window_hotkey",
+                window_settings,
             );
             create_new_window.setAction(Some(sel!(neovideCreateWindow:)));
             create_new_window.setTarget(Some(&self.new_window_handler));
@@ -1677,10 +1742,11 @@ impl Menu {
             if should_show_native_tab_bar() {
                 let show_all_tabs_item = NSMenuItem::new(mtm);
                 show_all_tabs_item.setTitle(ns_string!("Editors"));
-                apply_menu_item_hotkey(
+                self.add_hotkey(
                     &show_all_tabs_item,
                     &settings.system_show_all_tabs_hotkey,
                     "system_show_all_tabs_hotkey",
+                    window_settings,
                 );
                 show_all_tabs_item.setAction(Some(sel!(neovideShowAllTabs:)));
                 show_all_tabs_item.setTarget(Some(&self.tab_overview_handler));
@@ -1689,10 +1755,11 @@ impl Menu {
 
             let min_item = NSMenuItem::new(mtm);
             min_item.setTitle(ns_string!("Minimize"));
-            apply_menu_item_hotkey(
+            self.add_hotkey(
                 &min_item,
                 &settings.system_minimize_hotkey,
                 "system_minimize_hotkey",
+                window_settings,
             );
             min_item.setAction(Some(sel!(performMiniaturize:)));
             menu.addItem(&min_item);
@@ -1797,7 +1864,22 @@ pub fn is_tab_overview_active() -> bool {
 pub fn register_file_handler() {
     fn dispatch_file_drops(filenames: &NSArray<NSString>) {
         for filename in filenames.iter() {
-            send_or_queue_file_drop(filename.to_string(), None);
+            send_or_queue_file_drop(filename.to_string(), None, None);
+        }
+    }
+
+    fn dispatch_url_opens(urls: &NSArray<NSURL>) {
+        for url in urls.iter() {
+            let Some(url) = url.absoluteString() else {
+                continue;
+            };
+            match OpenUrl::parse(&url.to_string()) {
+                Ok(url) => {
+                    let cursor = url.cursor();
+                    send_or_queue_file_drop(url.file, None, cursor);
+                }
+                Err(error) => log::error!("{error}"),
+            }
         }
     }
 
@@ -1840,6 +1922,18 @@ pub fn register_file_handler() {
         MacosWindowFeature::activate_and_focus_existing_window();
     }
 
+    // See signature at
+    // https://developer.apple.com/documentation/appkit/nsapplicationdelegate/application(_:open:)?language=objc
+    unsafe extern "C-unwind" fn handle_open_urls(
+        _this: &mut AnyObject,
+        _sel: objc2::runtime::Sel,
+        _sender: &objc2::runtime::AnyObject,
+        urls: &NSArray<NSURL>,
+    ) {
+        dispatch_url_opens(urls);
+        MacosWindowFeature::activate_and_focus_existing_window();
+    }
+
     let mtm = MainThreadMarker::new().expect("File handler must be registered on main thread.");
 
     unsafe {
@@ -1857,6 +1951,11 @@ pub fn register_file_handler() {
             handle_open_files as unsafe extern "C-unwind" fn(_, _, _, _) -> _,
         );
 
+        my_class.add_method(
+            sel!(application:openURLs:),
+            handle_open_urls as unsafe extern "C-unwind" fn(_, _, _, _) -> _,
+        );
+
         my_class.add_method(
             sel!(applicationDockMenu:),
             handle_application_dock_menu as unsafe extern "C-unwind" fn(_, _, _) -> _,
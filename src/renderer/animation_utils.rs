@@ -1,4 +1,4 @@
-use glamour::{Point2, Unit};
+use glamour::{Box2, Point2, Unit};
 
 #[allow(dead_code)]
 pub fn ease_linear(t: f32) -> f32 {
@@ -74,6 +74,18 @@ pub fn ease_point<T: Unit<Scalar = f32>>(
     Point2::new(ease(ease_func, start.x, end.x, t), ease(ease_func, start.y, end.y, t))
 }
 
+pub fn ease_rect<T: Unit<Scalar = f32>>(
+    ease_func: fn(f32) -> f32,
+    start: Box2<T>,
+    end: Box2<T>,
+    t: f32,
+) -> Box2<T> {
+    Box2::new(
+        ease_point(ease_func, start.min, end.min, t),
+        ease_point(ease_func, start.max, end.max, t),
+    )
+}
+
 #[derive(Clone)]
 pub struct CriticallyDampedSpringAnimation {
     pub position: f32,
@@ -132,7 +144,7 @@ impl CriticallyDampedSpringAnimation {
 mod test {
     use super::*;
 
-    use crate::units::PixelPos;
+    use crate::units::{GridPos, GridRect, GridSize, PixelPos};
 
     #[test]
     fn test_lerp() {
@@ -257,4 +269,28 @@ mod test {
         assert_eq!(ease_point(ease_out_expo, start, end, 1.0), end);
         assert_eq!(ease_point(ease_out_expo, start, end, 1.1), expected);
     }
+
+    #[test]
+    fn test_ease_rect_moves_and_resizes() {
+        // A split moving from the left half of the grid to the right one, and shrinking
+        let start =
+            GridRect::from_origin_and_size(GridPos::new(0.0, 0.0), GridSize::new(40.0, 20.0));
+        let end =
+            GridRect::from_origin_and_size(GridPos::new(40.0, 10.0), GridSize::new(20.0, 10.0));
+        let halfway = ease_rect(ease_linear, start, end, 0.5);
+        assert_eq!(halfway.min, GridPos::new(20.0, 5.0));
+        assert_eq!(halfway.size(), GridSize::new(30.0, 15.0));
+        assert_eq!(ease_rect(ease_out_expo, start, end, 0.0), start);
+        assert_eq!(ease_rect(ease_out_expo, start, end, 1.0), end);
+    }
+
+    #[test]
+    fn test_ease_rect_grows_from_collapsed() {
+        let origin = GridPos::new(10.0, 5.0);
+        let start = GridRect::from_origin_and_size(origin, GridSize::ZERO);
+        let end = GridRect::from_origin_and_size(origin, GridSize::new(8.0, 4.0));
+        let quarter = ease_rect(ease_linear, start, end, 0.25);
+        assert_eq!(quarter.min, origin);
+        assert_eq!(quarter.size(), GridSize::new(2.0, 1.0));
+    }
 }
@@ -0,0 +1,343 @@
+use std::{collections::HashSet, path::Path};
+
+use skia_safe::{Canvas, Color4f, Paint, RRect, Rect};
+
+use crate::{
+    bridge::BufferInfo,
+    renderer::{
+        GridRenderer,
+        tab_bar::elide,
+        text_cells::{AmbiWidth, CellText, text_cells},
+    },
+    units::{GridScale, PixelPos, PixelRect},
+};
+
+const MAX_LABEL_COLUMNS: usize = 24;
+// Empty cells on each side of a chip label
+const CHIP_PADDING_COLUMNS: usize = 1;
+const MODIFIED_INDICATOR: &str = " ●";
+const NO_NAME: &str = "[No Name]";
+
+const CHIP_INSET: f32 = 2.0;
+const CURRENT_CHIP_ALPHA: f32 = 0.15;
+const INACTIVE_TEXT_ALPHA: f32 = 0.6;
+const BAR_TINT_ALPHA: f32 = 0.04;
+
+/// One buffer in the bar, laid out in grid cells
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chip {
+    pub handle: u64,
+    pub label: String,
+    pub current: bool,
+    pub modified: bool,
+    /// The first cell of the chip, counted from the start of the bar
+    pub column: usize,
+    pub columns: usize,
+}
+
+impl Chip {
+    fn text(&self) -> String {
+        if self.modified {
+            format!("{}{MODIFIED_INDICATOR}", self.label)
+        } else {
+            self.label.clone()
+        }
+    }
+}
+
+/// A bar of clickable buffer chips drawn above the grid from the externalized tabline
+pub struct BufferBar {
+    buffers: Vec<BufferInfo>,
+    current_buffer: Option<u64>,
+    modified: HashSet<u64>,
+    chips: Vec<Chip>,
+    ambiwidth: AmbiWidth,
+    // Where the bar was drawn last, in window pixels, and how many cells it was scrolled by
+    region: Option<PixelRect<f32>>,
+    scroll_columns: usize,
+}
+
+impl BufferBar {
+    pub fn new() -> Self {
+        Self {
+            buffers: Vec::new(),
+            current_buffer: None,
+            modified: HashSet::new(),
+            chips: Vec::new(),
+            ambiwidth: AmbiWidth::Single,
+            region: None,
+            scroll_columns: 0,
+        }
+    }
+
+    pub fn update(&mut self, current_buffer: Option<u64>, buffers: Vec<BufferInfo>) {
+        self.current_buffer = current_buffer;
+        self.buffers = buffers;
+        self.layout();
+    }
+
+    /// Sets the buffers with unsaved changes, the tabline doesn't report those
+    pub fn set_modified(&mut self, modified: Vec<u64>) {
+        self.modified = modified.into_iter().collect();
+        self.layout();
+    }
+
+    /// Lays the chips out again, ambiguous width characters may have changed size
+    pub fn set_ambiwidth(&mut self, ambiwidth: AmbiWidth) {
+        self.ambiwidth = ambiwidth;
+        self.layout();
+    }
+
+    fn layout(&mut self) {
+        self.chips =
+            layout_chips(&self.buffers, self.current_buffer, &self.modified, self.ambiwidth);
+    }
+
+    pub fn is_visible(&self, enabled: bool) -> bool {
+        enabled && !self.chips.is_empty()
+    }
+
+    /// The space reserved above the grid, one line high when the bar is shown
+    pub fn height(&self, enabled: bool, grid_scale: GridScale) -> u32 {
+        if self.is_visible(enabled) { grid_scale.height().ceil() as u32 } else { 0 }
+    }
+
+    /// Returns true when the position is on the bar, including the empty space after the chips
+    pub fn contains(&self, position: PixelPos<f32>) -> bool {
+        self.region.is_some_and(|region| {
+            (region.min.x..region.max.x).contains(&position.x)
+                && (region.min.y..region.max.y).contains(&position.y)
+        })
+    }
+
+    /// Returns the handle of the buffer under the position
+    pub fn buffer_at(&self, position: PixelPos<f32>, grid_scale: GridScale) -> Option<u64> {
+        if !self.contains(position) {
+            return None;
+        }
+        let region = self.region?;
+        let column = ((position.x - region.min.x) / grid_scale.width()) as usize;
+        chip_at(&self.chips, column + self.scroll_columns).map(|chip| chip.handle)
+    }
+
+    /// Draws the bar right above `below`, and returns the region the bar covers, or `below` when
+    /// it's hidden
+    pub fn draw(
+        &mut self,
+        enabled: bool,
+        canvas: &Canvas,
+        grid_renderer: &mut GridRenderer,
+        below: &PixelRect<f32>,
+    ) -> PixelRect<f32> {
+        let height = self.height(enabled, grid_renderer.grid_scale) as f32;
+        if height == 0.0 {
+            self.region = None;
+            return *below;
+        }
+
+        let region = PixelRect::new(
+            PixelPos::new(below.min.x, below.min.y - height),
+            PixelPos::new(below.max.x, below.min.y),
+        );
+        self.region = Some(region);
+
+        let cell_width = grid_renderer.grid_scale.width();
+        let bar_columns = (region.size().width / cell_width) as usize;
+        self.scroll_columns = scroll_columns(&self.chips, bar_columns);
+
+        let foreground =
+            grid_renderer.default_style.foreground(&grid_renderer.default_style.colors);
+        let background = Color4f::from(grid_renderer.get_default_background(1.0));
+        let with_alpha = |color: Color4f, alpha: f32| Color4f { a: alpha, ..color };
+
+        let bar_rect = Rect::from_xywh(region.min.x, region.min.y, region.size().width, height);
+        canvas.save();
+        canvas.clip_rect(bar_rect, None, Some(false));
+        canvas.draw_rect(bar_rect, &Paint::new(background, None));
+        canvas.draw_rect(bar_rect, &Paint::new(with_alpha(foreground, BAR_TINT_ALPHA), None));
+
+        let baseline_offset = grid_renderer.shaper.baseline_offset();
+        let style = grid_renderer.default_style.clone();
+        for chip in &self.chips {
+            let x = region.min.x + (chip.column as f32 - self.scroll_columns as f32) * cell_width;
+            if chip.current {
+                let chip_rect =
+                    Rect::from_xywh(x, region.min.y, chip.columns as f32 * cell_width, height)
+                        .with_inset((CHIP_INSET, CHIP_INSET));
+                let mut paint = Paint::new(with_alpha(foreground, CURRENT_CHIP_ALPHA), None);
+                paint.set_anti_alias(true);
+                canvas
+                    .draw_rrect(RRect::new_rect_xy(chip_rect, height / 4.0, height / 4.0), &paint);
+            }
+
+            let text = chip.text();
+            let cells = CellText::new(&text, self.ambiwidth);
+            let text_color =
+                if chip.current { foreground } else { with_alpha(foreground, INACTIVE_TEXT_ALPHA) };
+            let mut paint = Paint::new(text_color, None);
+            paint.set_anti_alias(false);
+
+            let origin =
+                (x + CHIP_PADDING_COLUMNS as f32 * cell_width, region.min.y + baseline_offset);
+            for blob in grid_renderer.shaper.shape_cached(cells.word(), (&style).into()).iter() {
+                canvas.draw_text_blob(blob, origin, &paint);
+            }
+        }
+        canvas.restore();
+
+        region
+    }
+}
+
+/// Buffers are shown by their file name, the full path rarely fits
+fn buffer_label(name: &str) -> String {
+    Path::new(name)
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| NO_NAME.to_owned())
+}
+
+fn layout_chips(
+    buffers: &[BufferInfo],
+    current_buffer: Option<u64>,
+    modified: &HashSet<u64>,
+    ambiwidth: AmbiWidth,
+) -> Vec<Chip> {
+    let mut column = 0;
+    buffers
+        .iter()
+        .map(|buffer| {
+            let label = elide(&buffer_label(&buffer.name), MAX_LABEL_COLUMNS, ambiwidth);
+            let modified = modified.contains(&buffer.handle);
+            let indicator_columns =
+                if modified { text_cells(MODIFIED_INDICATOR, ambiwidth) } else { 0 };
+            let columns =
+                text_cells(&label, ambiwidth) + indicator_columns + 2 * CHIP_PADDING_COLUMNS;
+            let chip = Chip {
+                handle: buffer.handle,
+                label,
+                current: current_buffer == Some(buffer.handle),
+                modified,
+                column,
+                columns,
+            };
+            column += columns;
+            chip
+        })
+        .collect()
+}
+
+fn chip_at(chips: &[Chip], column: usize) -> Option<&Chip> {
+    chips.iter().find(|chip| (chip.column..chip.column + chip.columns).contains(&column))
+}
+
+/// Scrolls the bar just far enough for the current buffer to be visible when the chips don't fit
+fn scroll_columns(chips: &[Chip], bar_columns: usize) -> usize {
+    chips
+        .iter()
+        .find(|chip| chip.current)
+        .map_or(0, |chip| (chip.column + chip.columns).saturating_sub(bar_columns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffers() -> Vec<BufferInfo> {
+        vec![
+            BufferInfo { handle: 1, name: "/home/user/src/main.rs".to_owned() },
+            BufferInfo { handle: 2, name: String::new() },
+            BufferInfo { handle: 5, name: "/tmp/notes.md".to_owned() },
+        ]
+    }
+
+    #[test]
+    fn chips_show_file_names_and_state() {
+        let chips = layout_chips(&buffers(), Some(5), &HashSet::from([1]), AmbiWidth::Single);
+
+        assert_eq!(
+            chips,
+            vec![
+                Chip {
+                    handle: 1,
+                    label: "main.rs".to_owned(),
+                    current: false,
+                    modified: true,
+                    column: 0,
+                    columns: 11,
+                },
+                Chip {
+                    handle: 2,
+                    label: "[No Name]".to_owned(),
+                    current: false,
+                    modified: false,
+                    column: 11,
+                    columns: 11,
+                },
+                Chip {
+                    handle: 5,
+                    label: "notes.md".to_owned(),
+                    current: true,
+                    modified: false,
+                    column: 22,
+                    columns: 10,
+                },
+            ]
+        );
+        assert_eq!(chips[0].text(), "main.rs ●");
+        assert_eq!(chips[2].text(), "notes.md");
+    }
+
+    #[test]
+    fn modified_flags_update_the_chips() {
+        let mut buffer_bar = BufferBar::new();
+        buffer_bar.update(Some(1), buffers());
+        assert!(buffer_bar.chips.iter().all(|chip| !chip.modified));
+
+        buffer_bar.set_modified(vec![2]);
+        let modified = buffer_bar.chips.iter().filter(|chip| chip.modified).collect::<Vec<_>>();
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].handle, 2);
+        assert_eq!(buffer_bar.chips[2].column, 22);
+    }
+
+    #[test]
+    fn double_ambiwidth_widens_the_modified_indicator() {
+        let mut buffer_bar = BufferBar::new();
+        buffer_bar.update(None, buffers());
+        buffer_bar.set_modified(vec![1]);
+        assert_eq!(buffer_bar.chips[0].columns, 11);
+
+        buffer_bar.set_ambiwidth(AmbiWidth::Double);
+        assert_eq!(buffer_bar.chips[0].columns, 12);
+        assert_eq!(buffer_bar.chips[1].column, 12);
+    }
+
+    #[test]
+    fn long_names_are_elided() {
+        let buffers = vec![BufferInfo { handle: 1, name: format!("/tmp/{}.rs", "a".repeat(40)) }];
+        let chips = layout_chips(&buffers, None, &HashSet::new(), AmbiWidth::Single);
+        assert_eq!(text_cells(&chips[0].label, AmbiWidth::Single), MAX_LABEL_COLUMNS);
+        assert!(chips[0].label.starts_with('…'));
+    }
+
+    #[test]
+    fn clicks_map_to_chips() {
+        let chips = layout_chips(&buffers(), None, &HashSet::new(), AmbiWidth::Single);
+        assert_eq!(chip_at(&chips, 0).map(|chip| chip.handle), Some(1));
+        assert_eq!(chip_at(&chips, 8).map(|chip| chip.handle), Some(1));
+        assert_eq!(chip_at(&chips, 9).map(|chip| chip.handle), Some(2));
+        assert_eq!(chip_at(&chips, 29).map(|chip| chip.handle), Some(5));
+        assert_eq!(chip_at(&chips, 30), None);
+    }
+
+    #[test]
+    fn the_current_buffer_is_scrolled_into_view() {
+        let chips = layout_chips(&buffers(), Some(5), &HashSet::new(), AmbiWidth::Single);
+        assert_eq!(scroll_columns(&chips, 80), 0);
+        assert_eq!(scroll_columns(&chips, 25), 5);
+
+        let chips = layout_chips(&buffers(), Some(1), &HashSet::new(), AmbiWidth::Single);
+        assert_eq!(scroll_columns(&chips, 5), 4);
+    }
+}
@@ -0,0 +1,105 @@
+use std::time::{Duration, Instant};
+
+use skia_safe::Canvas;
+
+use crate::{
+    renderer::{
+        GridRenderer,
+        overlay_label::{self, Corner},
+    },
+    units::PixelSize,
+};
+
+// The last part of the linger time is spent fading out
+const MAX_FADE_DURATION: Duration = Duration::from_millis(300);
+
+/// Keeps the last executed command on screen for a while after the cmdline is closed, for
+/// screencasts. Set through `g:neovide_cmdline_linger_ms`.
+#[derive(Default)]
+pub struct CmdlineLinger {
+    text: String,
+    left_at: Option<Instant>,
+}
+
+impl CmdlineLinger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new cmdline replaces the lingering one right away
+    pub fn entered(&mut self) {
+        self.left_at = None;
+    }
+
+    pub fn left(&mut self, text: String) {
+        self.text = text;
+        self.left_at = Some(Instant::now());
+    }
+
+    fn opacity(&self, linger: Duration, now: Instant) -> f32 {
+        let Some(left_at) = self.left_at else {
+            return 0.0;
+        };
+        let remaining = linger.saturating_sub(now.saturating_duration_since(left_at));
+        let fade = MAX_FADE_DURATION.min(linger);
+        if fade.is_zero() {
+            return 0.0;
+        }
+        (remaining.as_secs_f32() / fade.as_secs_f32()).min(1.0)
+    }
+
+    pub fn is_animating(&self, linger: Duration) -> bool {
+        self.opacity(linger, Instant::now()) > 0.0
+    }
+
+    pub fn draw(
+        &self,
+        canvas: &Canvas,
+        grid_renderer: &mut GridRenderer,
+        linger: Duration,
+        bounds: PixelSize<f32>,
+    ) {
+        let opacity = self.opacity(linger, Instant::now());
+        if opacity <= 0.0 {
+            return;
+        }
+        canvas.save_layer_alpha_f(None, opacity);
+        overlay_label::draw(canvas, grid_renderer, &self.text, Corner::BottomLeft, bounds);
+        canvas.restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINGER: Duration = Duration::from_millis(1000);
+
+    #[test]
+    fn the_timer_starts_on_hide_and_is_cleared_on_show() {
+        let mut linger = CmdlineLinger::new();
+        assert_eq!(linger.left_at, None);
+
+        linger.left(":write".to_owned());
+        assert!(linger.left_at.is_some());
+        assert!(linger.is_animating(LINGER));
+
+        linger.entered();
+        assert_eq!(linger.left_at, None);
+        assert!(!linger.is_animating(LINGER));
+    }
+
+    #[test]
+    fn the_command_fades_out_at_the_end() {
+        let mut linger = CmdlineLinger::new();
+        linger.left(":write".to_owned());
+        let left_at = linger.left_at.unwrap();
+
+        assert_eq!(linger.opacity(LINGER, left_at), 1.0);
+        assert_eq!(linger.opacity(LINGER, left_at + Duration::from_millis(700)), 1.0);
+        assert_eq!(linger.opacity(LINGER, left_at + Duration::from_millis(850)), 0.5);
+        assert_eq!(linger.opacity(LINGER, left_at + LINGER), 0.0);
+        // Nothing lingers when it's disabled
+        assert_eq!(linger.opacity(Duration::ZERO, left_at), 0.0);
+    }
+}
@@ -0,0 +1,349 @@
+use skia_safe::{Canvas, Color4f, Paint, PaintStyle, RRect, Rect};
+use winit::{
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{Key, NamedKey},
+};
+
+use crate::{
+    renderer::{GridRenderer, text_cells::CellText},
+    units::PixelSize,
+};
+
+const PROMPT: &str = "> ";
+const WIDTH_COLUMNS: usize = 60;
+const MAX_VISIBLE_MATCHES: usize = 12;
+// Empty cells on each side of the text, and the distance to the top of the window in rows
+const PADDING_COLUMNS: usize = 1;
+const TOP_MARGIN_ROWS: f32 = 2.0;
+const CORNER_RADIUS: f32 = 4.0;
+
+const PALETTE_TINT_ALPHA: f32 = 0.06;
+const SELECTED_ITEM_ALPHA: f32 = 0.15;
+const BORDER_ALPHA: f32 = 0.25;
+
+// Bonuses of the fuzzy scorer
+const MATCH_SCORE: i32 = 1;
+const CONSECUTIVE_BONUS: i32 = 5;
+const WORD_START_BONUS: i32 = 3;
+const FIRST_CHARACTER_BONUS: i32 = 10;
+
+/// Scores how well `query` matches `candidate`, case insensitively. The characters of the query
+/// have to appear in order in the candidate. Consecutive characters, and characters at the start
+/// of the candidate or of a word in it, score higher, and the skipped characters lower the score.
+/// Returns `None` when the candidate doesn't match.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate = candidate.chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous_match: Option<usize> = None;
+    for query_char in query.chars() {
+        let index = (next..candidate.len())
+            .find(|index| candidate[*index].to_lowercase().eq(query_char.to_lowercase()))?;
+
+        score += MATCH_SCORE;
+        if index == 0 {
+            score += FIRST_CHARACTER_BONUS;
+        } else if previous_match == Some(index - 1) {
+            score += CONSECUTIVE_BONUS;
+        } else if !candidate[index - 1].is_alphanumeric()
+            || (candidate[index - 1].is_lowercase() && candidate[index].is_uppercase())
+        {
+            score += WORD_START_BONUS;
+        }
+        score -= (index - next) as i32;
+
+        previous_match = Some(index);
+        next = index + 1;
+    }
+    Some(score)
+}
+
+/// The indices of the commands matching the query, best first. Shorter commands win ties, so that
+/// an exact match comes before the longer commands starting with it.
+fn matching_commands(commands: &[String], query: &str) -> Vec<usize> {
+    let mut matches = commands
+        .iter()
+        .enumerate()
+        .filter_map(|(index, command)| Some((fuzzy_score(query, command)?, index)))
+        .collect::<Vec<_>>();
+    matches.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .cmp(score_a)
+            .then(commands[*a].len().cmp(&commands[*b].len()))
+            .then(commands[*a].cmp(&commands[*b]))
+    });
+    matches.into_iter().map(|(_, index)| index).collect()
+}
+
+/// What the open palette does with a key, decoupled from winit so that it can be tested
+#[derive(Clone, Debug, PartialEq)]
+enum PaletteInput {
+    Text(String),
+    Backspace,
+    Previous,
+    Next,
+    Activate,
+    Dismiss,
+    OtherKey,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommandPaletteResponse {
+    /// The palette isn't interested in the event, it goes on to Neovim
+    Ignored,
+    /// The palette used the event, and might need to be redrawn
+    Consumed,
+    /// A command was picked, the palette closed and the command should be run
+    Dispatch(String),
+}
+
+/// A searchable list of the Ex commands, opened by `:NeovideCommandPalette`
+pub struct CommandPalette {
+    commands: Vec<String>,
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self { commands: Vec::new(), query: String::new(), matches: Vec::new(), selected: 0 }
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.commands.is_empty()
+    }
+
+    pub fn open(&mut self, commands: Vec<String>) {
+        self.commands = commands;
+        self.set_query(String::new());
+    }
+
+    pub fn close(&mut self) {
+        self.commands.clear();
+        self.matches.clear();
+        self.query.clear();
+    }
+
+    fn set_query(&mut self, query: String) {
+        self.matches = matching_commands(&self.commands, &query);
+        self.query = query;
+        self.selected = 0;
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) -> CommandPaletteResponse {
+        if !self.is_open() {
+            return CommandPaletteResponse::Ignored;
+        }
+
+        let input = match event {
+            WindowEvent::KeyboardInput { event, .. } => match key_input(event) {
+                Some(input) => input,
+                None => return CommandPaletteResponse::Consumed,
+            },
+            WindowEvent::Ime(_) => return CommandPaletteResponse::Consumed,
+            WindowEvent::Focused(false) => {
+                self.close();
+                return CommandPaletteResponse::Ignored;
+            }
+            _ => return CommandPaletteResponse::Ignored,
+        };
+        self.handle_input(input)
+    }
+
+    fn handle_input(&mut self, input: PaletteInput) -> CommandPaletteResponse {
+        match input {
+            PaletteInput::Text(text) => self.set_query(self.query.clone() + &text),
+            PaletteInput::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(query);
+            }
+            PaletteInput::Previous if !self.matches.is_empty() => {
+                self.selected = self.selected.checked_sub(1).unwrap_or(self.matches.len() - 1);
+            }
+            PaletteInput::Next if !self.matches.is_empty() => {
+                self.selected = (self.selected + 1) % self.matches.len();
+            }
+            PaletteInput::Activate => {
+                let command =
+                    self.matches.get(self.selected).map(|index| self.commands[*index].clone());
+                self.close();
+                return command
+                    .map_or(CommandPaletteResponse::Consumed, CommandPaletteResponse::Dispatch);
+            }
+            PaletteInput::Dismiss => self.close(),
+            PaletteInput::Previous | PaletteInput::Next | PaletteInput::OtherKey => {}
+        }
+        CommandPaletteResponse::Consumed
+    }
+
+    /// Draws the prompt and the best matches at the top of the window
+    pub fn draw(&self, canvas: &Canvas, grid_renderer: &mut GridRenderer, bounds: PixelSize<f32>) {
+        if !self.is_open() {
+            return;
+        }
+
+        let cell_width = grid_renderer.grid_scale.width();
+        let row_height = grid_renderer.grid_scale.height();
+        // Keep the selected match in view
+        let first_visible = (self.selected + 1).saturating_sub(MAX_VISIBLE_MATCHES);
+        let visible = self.matches.iter().skip(first_visible).take(MAX_VISIBLE_MATCHES);
+        let rows = 1 + visible.len();
+        let width = (WIDTH_COLUMNS as f32 * cell_width).min(bounds.width);
+        let palette_rect = Rect::from_xywh(
+            ((bounds.width - width) / 2.0).max(0.0),
+            TOP_MARGIN_ROWS * row_height,
+            width,
+            rows as f32 * row_height,
+        );
+
+        let foreground =
+            grid_renderer.default_style.foreground(&grid_renderer.default_style.colors);
+        let background = Color4f::from(grid_renderer.get_default_background(1.0));
+        let with_alpha = |color: Color4f, alpha: f32| Color4f { a: alpha, ..color };
+
+        let palette_rrect = RRect::new_rect_xy(palette_rect, CORNER_RADIUS, CORNER_RADIUS);
+        let mut paint = Paint::new(background, None);
+        paint.set_anti_alias(true);
+        canvas.save();
+        canvas.clip_rrect(palette_rrect, None, Some(true));
+        canvas.draw_rect(palette_rect, &paint);
+        canvas
+            .draw_rect(palette_rect, &Paint::new(with_alpha(foreground, PALETTE_TINT_ALPHA), None));
+
+        let baseline_offset = grid_renderer.shaper.baseline_offset();
+        let style = grid_renderer.default_style.clone();
+        let mut text_paint = Paint::new(foreground, None);
+        text_paint.set_anti_alias(false);
+        let ambiwidth = grid_renderer.ambiwidth;
+        let mut draw_row = |row: usize, text: &str| {
+            let cells = CellText::new(text, ambiwidth);
+            let origin = (
+                palette_rect.left + PADDING_COLUMNS as f32 * cell_width,
+                palette_rect.top + row as f32 * row_height + baseline_offset,
+            );
+            for blob in grid_renderer.shaper.shape_cached(cells.word(), (&style).into()).iter() {
+                canvas.draw_text_blob(blob, origin, &text_paint);
+            }
+        };
+
+        draw_row(0, &format!("{PROMPT}{}", self.query));
+        for (row, index) in visible.enumerate() {
+            let row = row + 1;
+            if first_visible + row - 1 == self.selected {
+                let item_rect = Rect::from_xywh(
+                    palette_rect.left,
+                    palette_rect.top + row as f32 * row_height,
+                    width,
+                    row_height,
+                );
+                canvas.draw_rect(
+                    item_rect,
+                    &Paint::new(with_alpha(foreground, SELECTED_ITEM_ALPHA), None),
+                );
+            }
+            draw_row(row, &self.commands[*index]);
+        }
+        canvas.restore();
+
+        let mut border_paint = Paint::new(with_alpha(foreground, BORDER_ALPHA), None);
+        border_paint.set_style(PaintStyle::Stroke);
+        border_paint.set_anti_alias(true);
+        canvas.draw_rrect(palette_rrect, &border_paint);
+    }
+}
+
+fn key_input(event: &KeyEvent) -> Option<PaletteInput> {
+    if event.state != ElementState::Pressed {
+        return None;
+    }
+    match &event.logical_key {
+        Key::Named(NamedKey::ArrowUp) => Some(PaletteInput::Previous),
+        Key::Named(NamedKey::ArrowDown | NamedKey::Tab) => Some(PaletteInput::Next),
+        Key::Named(NamedKey::Enter) => Some(PaletteInput::Activate),
+        Key::Named(NamedKey::Escape) => Some(PaletteInput::Dismiss),
+        Key::Named(NamedKey::Backspace) => Some(PaletteInput::Backspace),
+        _ => match &event.text {
+            Some(text) if !text.chars().any(char::is_control) => {
+                Some(PaletteInput::Text(text.to_string()))
+            }
+            _ => Some(PaletteInput::OtherKey),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn the_query_has_to_appear_in_order() {
+        assert!(fuzzy_score("wq", "wqall").is_some());
+        assert!(fuzzy_score("qw", "wqall").is_none());
+        assert!(fuzzy_score("xyz", "write").is_none());
+        assert_eq!(fuzzy_score("", "write"), Some(0));
+    }
+
+    #[test]
+    fn consecutive_and_word_start_matches_score_higher() {
+        // The start of the command and consecutive characters beat scattered ones
+        assert!(fuzzy_score("wri", "write") > fuzzy_score("wri", "wincmd_right"));
+        // Word starts count, after separators and in camel case
+        assert!(fuzzy_score("nf", "NeovideFocus") > fuzzy_score("nf", "Neoinfo"));
+        assert!(fuzzy_score("nf", "neovide_focus") > fuzzy_score("nf", "neovidefocus"));
+        // The case doesn't matter
+        assert_eq!(fuzzy_score("NEO", "neovide"), fuzzy_score("neo", "neovide"));
+    }
+
+    #[test]
+    fn matches_are_sorted_by_score_then_length() {
+        let names = commands(&["NeovideFocus", "write", "wall", "w", "windo"]);
+        let sorted = matching_commands(&names, "w")
+            .into_iter()
+            .map(|index| names[index].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(sorted, ["w", "wall", "windo", "write"]);
+    }
+
+    #[test]
+    fn typing_filters_and_enter_runs_the_selection() {
+        let mut palette = CommandPalette::new();
+        palette.open(commands(&["NeovideFocus", "NeovideInfo", "write"]));
+        assert_eq!(palette.matches.len(), 3);
+
+        palette.handle_input(PaletteInput::Text("neo".to_owned()));
+        palette.handle_input(PaletteInput::Text("in".to_owned()));
+        assert_eq!(palette.matches.len(), 1);
+        palette.handle_input(PaletteInput::Backspace);
+        assert_eq!(palette.matches.len(), 2);
+        // Both match equally well, the shorter one comes first
+        assert_eq!(palette.commands[palette.matches[0]], "NeovideInfo");
+
+        palette.handle_input(PaletteInput::Next);
+        palette.handle_input(PaletteInput::Next);
+        palette.handle_input(PaletteInput::Previous);
+        assert_eq!(palette.selected, 1);
+        assert_eq!(
+            palette.handle_input(PaletteInput::Activate),
+            CommandPaletteResponse::Dispatch("NeovideFocus".to_owned())
+        );
+        assert!(!palette.is_open());
+    }
+
+    #[test]
+    fn nothing_runs_without_a_match_or_after_escape() {
+        let mut palette = CommandPalette::new();
+        palette.open(commands(&["write"]));
+        palette.handle_input(PaletteInput::Text("zz".to_owned()));
+        assert_eq!(palette.handle_input(PaletteInput::Activate), CommandPaletteResponse::Consumed);
+
+        palette.open(commands(&["write"]));
+        palette.handle_input(PaletteInput::Dismiss);
+        assert!(!palette.is_open());
+    }
+}
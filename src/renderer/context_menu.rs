@@ -0,0 +1,473 @@
+use rmpv::Value;
+use skia_safe::{Canvas, Color4f, Paint, PaintStyle, RRect, Rect};
+use winit::{
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{Key, NamedKey},
+};
+
+use crate::{
+    error_msg,
+    renderer::{
+        GridRenderer,
+        text_cells::{CellText, text_cells},
+    },
+    settings::ParseFromValue,
+    units::{PixelPos, PixelRect, PixelSize},
+};
+
+// Empty cells on each side of an item label
+const ITEM_PADDING_COLUMNS: usize = 2;
+const CORNER_RADIUS: f32 = 4.0;
+
+const MENU_TINT_ALPHA: f32 = 0.06;
+const SELECTED_ITEM_ALPHA: f32 = 0.15;
+const BORDER_ALPHA: f32 = 0.25;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContextMenuItem {
+    pub label: String,
+    /// An Ex command, run like it was typed after `:`
+    pub command: String,
+}
+
+impl ContextMenuItem {
+    fn new(label: &str, command: &str) -> Self {
+        Self { label: label.to_owned(), command: command.to_owned() }
+    }
+}
+
+/// The items of the right click menu, set through `g:neovide_context_menu`. Without items, right
+/// clicks go to Neovim.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContextMenuItems(pub Vec<ContextMenuItem>);
+
+impl Default for ContextMenuItems {
+    fn default() -> Self {
+        // Running the command leaves visual mode, so `gv` selects the text again first
+        Self(vec![
+            ContextMenuItem::new("Cut", "normal! gv\"+x"),
+            ContextMenuItem::new("Copy", "normal! gv\"+y"),
+            ContextMenuItem::new("Paste", "normal! \"+P"),
+            ContextMenuItem::new("Select All", "normal! ggVG"),
+        ])
+    }
+}
+
+impl ParseFromValue for ContextMenuItems {
+    fn parse_from_value(&mut self, value: Value) {
+        let Some(items) = value.as_array() else {
+            error_msg!(
+                "Setting context_menu expected a list of {{label, command}} tables, but received {value:?}"
+            );
+            return;
+        };
+
+        let mut parsed = Vec::with_capacity(items.len());
+        for item in items {
+            let field = |name: &str| {
+                item.as_map()?
+                    .iter()
+                    .find(|(key, _)| key.as_str() == Some(name))
+                    .and_then(|(_, value)| value.as_str())
+                    .map(str::to_owned)
+            };
+            match (field("label"), field("command")) {
+                (Some(label), Some(command)) => parsed.push(ContextMenuItem { label, command }),
+                _ => {
+                    error_msg!(
+                        "Setting context_menu expected items with a label and a command, but received {item:?}"
+                    );
+                    return;
+                }
+            }
+        }
+        self.0 = parsed;
+    }
+}
+
+impl From<ContextMenuItems> for Value {
+    fn from(items: ContextMenuItems) -> Self {
+        Value::from(
+            items
+                .0
+                .into_iter()
+                .map(|item| {
+                    Value::Map(vec![
+                        (Value::from("label"), Value::from(item.label)),
+                        (Value::from("command"), Value::from(item.command)),
+                    ])
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// What the open menu does with an input, decoupled from winit so that it can be tested
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MenuInput {
+    PointerMoved(PixelPos<f32>),
+    Press(MouseButton),
+    Release,
+    Previous,
+    Next,
+    First,
+    Last,
+    Activate,
+    Dismiss,
+    OtherKey,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContextMenuResponse {
+    /// The menu isn't interested in the event, it goes on to Neovim
+    Ignored,
+    /// The menu used the event, and might need to be redrawn
+    Consumed,
+    /// An item was picked, the menu closed and the command should be run
+    Dispatch(String),
+}
+
+/// A menu opened by right clicking the editor, drawn on top of everything else
+pub struct ContextMenu {
+    items: Vec<ContextMenuItem>,
+    anchor: PixelPos<f32>,
+    pointer: PixelPos<f32>,
+    selected: Option<usize>,
+    // The button release that follows the press closing the menu shouldn't reach Neovim either
+    swallowed_release: Option<MouseButton>,
+    // Where the menu was drawn last, in window pixels, and the height of one item
+    region: Option<PixelRect<f32>>,
+    item_height: f32,
+}
+
+impl ContextMenu {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            anchor: PixelPos::default(),
+            pointer: PixelPos::default(),
+            selected: None,
+            swallowed_release: None,
+            region: None,
+            item_height: 0.0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.items.is_empty()
+    }
+
+    pub fn open(&mut self, anchor: PixelPos<f32>, items: ContextMenuItems) {
+        self.items = items.0;
+        self.anchor = anchor;
+        self.pointer = anchor;
+        self.selected = None;
+        self.region = None;
+    }
+
+    pub fn close(&mut self) {
+        self.items.clear();
+        self.selected = None;
+        self.region = None;
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) -> ContextMenuResponse {
+        if let WindowEvent::MouseInput { state: ElementState::Released, button, .. } = event
+            && self.swallowed_release.take_if(|swallowed| *swallowed == *button).is_some()
+        {
+            return ContextMenuResponse::Consumed;
+        }
+        if !self.is_open() {
+            return ContextMenuResponse::Ignored;
+        }
+
+        let input = match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                MenuInput::PointerMoved(PixelPos::new(position.x as f32, position.y as f32))
+            }
+            WindowEvent::MouseInput { state: ElementState::Pressed, button, .. } => {
+                MenuInput::Press(*button)
+            }
+            WindowEvent::MouseInput { state: ElementState::Released, .. } => MenuInput::Release,
+            WindowEvent::MouseWheel { .. } => MenuInput::Dismiss,
+            WindowEvent::KeyboardInput { event, .. } => match key_input(event) {
+                Some(input) => input,
+                None => return ContextMenuResponse::Consumed,
+            },
+            WindowEvent::Focused(false) | WindowEvent::Resized(_) => {
+                self.close();
+                return ContextMenuResponse::Ignored;
+            }
+            _ => return ContextMenuResponse::Ignored,
+        };
+        self.handle_input(input)
+    }
+
+    fn handle_input(&mut self, input: MenuInput) -> ContextMenuResponse {
+        match input {
+            MenuInput::PointerMoved(position) => {
+                self.pointer = position;
+                if let Some(index) = self.item_at(position) {
+                    self.selected = Some(index);
+                }
+            }
+            MenuInput::Press(button) => {
+                self.swallowed_release = Some(button);
+                return self.pick(self.item_at(self.pointer));
+            }
+            MenuInput::Release | MenuInput::OtherKey => {}
+            MenuInput::Previous => {
+                self.selected = Some(match self.selected {
+                    Some(0) | None => self.items.len() - 1,
+                    Some(index) => index - 1,
+                });
+            }
+            MenuInput::Next => {
+                self.selected = Some(match self.selected {
+                    Some(index) if index + 1 < self.items.len() => index + 1,
+                    _ => 0,
+                });
+            }
+            MenuInput::First => self.selected = Some(0),
+            MenuInput::Last => self.selected = Some(self.items.len() - 1),
+            MenuInput::Activate => return self.pick(self.selected),
+            MenuInput::Dismiss => self.close(),
+        }
+        ContextMenuResponse::Consumed
+    }
+
+    /// Closes the menu, running the command of the picked item if there is one
+    fn pick(&mut self, index: Option<usize>) -> ContextMenuResponse {
+        let command =
+            index.and_then(|index| self.items.get(index)).map(|item| item.command.clone());
+        self.close();
+        command.map_or(ContextMenuResponse::Consumed, ContextMenuResponse::Dispatch)
+    }
+
+    fn item_at(&self, position: PixelPos<f32>) -> Option<usize> {
+        let region = self.region?;
+        if !(region.min.x..region.max.x).contains(&position.x) {
+            return None;
+        }
+        item_index_at(position.y - region.min.y, self.item_height, self.items.len())
+    }
+
+    /// Draws the menu at the click position, moved as needed to stay inside `bounds`
+    pub fn draw(
+        &mut self,
+        canvas: &Canvas,
+        grid_renderer: &mut GridRenderer,
+        bounds: PixelSize<f32>,
+    ) {
+        if !self.is_open() {
+            return;
+        }
+
+        let cell_width = grid_renderer.grid_scale.width();
+        let item_height = grid_renderer.grid_scale.height();
+        let ambiwidth = grid_renderer.ambiwidth;
+        let label_columns =
+            self.items.iter().map(|item| text_cells(&item.label, ambiwidth)).max().unwrap_or(0);
+        let size = PixelSize::new(
+            (label_columns + 2 * ITEM_PADDING_COLUMNS) as f32 * cell_width,
+            self.items.len() as f32 * item_height,
+        );
+        let origin = menu_origin(self.anchor, size, bounds);
+        let region =
+            PixelRect::new(origin, PixelPos::new(origin.x + size.width, origin.y + size.height));
+        self.region = Some(region);
+        self.item_height = item_height;
+
+        let foreground =
+            grid_renderer.default_style.foreground(&grid_renderer.default_style.colors);
+        let background = Color4f::from(grid_renderer.get_default_background(1.0));
+        let with_alpha = |color: Color4f, alpha: f32| Color4f { a: alpha, ..color };
+
+        let menu_rect = Rect::from_xywh(origin.x, origin.y, size.width, size.height);
+        let menu_rrect = RRect::new_rect_xy(menu_rect, CORNER_RADIUS, CORNER_RADIUS);
+        let mut paint = Paint::new(background, None);
+        paint.set_anti_alias(true);
+        canvas.save();
+        canvas.clip_rrect(menu_rrect, None, Some(true));
+        canvas.draw_rect(menu_rect, &paint);
+        canvas.draw_rect(menu_rect, &Paint::new(with_alpha(foreground, MENU_TINT_ALPHA), None));
+
+        let baseline_offset = grid_renderer.shaper.baseline_offset();
+        let style = grid_renderer.default_style.clone();
+        let mut text_paint = Paint::new(foreground, None);
+        text_paint.set_anti_alias(false);
+        for (index, item) in self.items.iter().enumerate() {
+            let y = origin.y + index as f32 * item_height;
+            if self.selected == Some(index) {
+                let item_rect = Rect::from_xywh(origin.x, y, size.width, item_height);
+                canvas.draw_rect(
+                    item_rect,
+                    &Paint::new(with_alpha(foreground, SELECTED_ITEM_ALPHA), None),
+                );
+            }
+
+            let cells = CellText::new(&item.label, ambiwidth);
+            let text_origin =
+                (origin.x + ITEM_PADDING_COLUMNS as f32 * cell_width, y + baseline_offset);
+            for blob in grid_renderer.shaper.shape_cached(cells.word(), (&style).into()).iter() {
+                canvas.draw_text_blob(blob, text_origin, &text_paint);
+            }
+        }
+        canvas.restore();
+
+        let mut border_paint = Paint::new(with_alpha(foreground, BORDER_ALPHA), None);
+        border_paint.set_style(PaintStyle::Stroke);
+        border_paint.set_anti_alias(true);
+        canvas.draw_rrect(menu_rrect, &border_paint);
+    }
+}
+
+fn key_input(event: &KeyEvent) -> Option<MenuInput> {
+    if event.state != ElementState::Pressed {
+        return None;
+    }
+    match &event.logical_key {
+        Key::Named(NamedKey::ArrowUp) => Some(MenuInput::Previous),
+        Key::Named(NamedKey::ArrowDown | NamedKey::Tab) => Some(MenuInput::Next),
+        Key::Named(NamedKey::Home | NamedKey::PageUp) => Some(MenuInput::First),
+        Key::Named(NamedKey::End | NamedKey::PageDown) => Some(MenuInput::Last),
+        Key::Named(NamedKey::Enter | NamedKey::Space) => Some(MenuInput::Activate),
+        Key::Named(NamedKey::Escape) => Some(MenuInput::Dismiss),
+        _ => Some(MenuInput::OtherKey),
+    }
+}
+
+/// Maps a position `y` pixels below the top of the menu to the index of the item under it
+fn item_index_at(y: f32, item_height: f32, item_count: usize) -> Option<usize> {
+    if y < 0.0 || item_height <= 0.0 {
+        return None;
+    }
+    let index = (y / item_height) as usize;
+    (index < item_count).then_some(index)
+}
+
+/// Opens the menu down and to the right of the click, and flips it to the other side of the click
+/// when it would overflow the window
+fn menu_origin(
+    anchor: PixelPos<f32>,
+    size: PixelSize<f32>,
+    bounds: PixelSize<f32>,
+) -> PixelPos<f32> {
+    let place = |anchor: f32, size: f32, bound: f32| {
+        if anchor + size <= bound { anchor } else { (anchor - size).max(0.0) }
+    };
+    PixelPos::new(
+        place(anchor.x, size.width, bounds.width),
+        place(anchor.y, size.height, bounds.height),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_menu() -> ContextMenu {
+        let mut menu = ContextMenu::new();
+        menu.open(PixelPos::new(100.0, 50.0), ContextMenuItems::default());
+        // What drawing the menu with 10x20 pixel cells would lay out
+        menu.region = Some(PixelRect::new(PixelPos::new(100.0, 50.0), PixelPos::new(240.0, 130.0)));
+        menu.item_height = 20.0;
+        menu
+    }
+
+    #[test]
+    fn the_default_menu_has_the_clipboard_actions() {
+        assert_eq!(
+            ContextMenuItems::default(),
+            ContextMenuItems(vec![
+                ContextMenuItem::new("Cut", "normal! gv\"+x"),
+                ContextMenuItem::new("Copy", "normal! gv\"+y"),
+                ContextMenuItem::new("Paste", "normal! \"+P"),
+                ContextMenuItem::new("Select All", "normal! ggVG"),
+            ])
+        );
+    }
+
+    #[test]
+    fn settings_round_trip_through_values() {
+        let items = ContextMenuItems(vec![ContextMenuItem::new("Save", "write")]);
+        let mut parsed = ContextMenuItems::default();
+        parsed.parse_from_value(Value::from(items.clone()));
+        assert_eq!(parsed, items);
+
+        parsed.parse_from_value(Value::Array(Vec::new()));
+        assert!(parsed.0.is_empty());
+    }
+
+    #[test]
+    fn clicking_an_item_dispatches_its_command() {
+        let mut menu = open_menu();
+        menu.handle_input(MenuInput::PointerMoved(PixelPos::new(150.0, 75.0)));
+        assert_eq!(menu.selected, Some(1));
+
+        assert_eq!(
+            menu.handle_input(MenuInput::Press(MouseButton::Left)),
+            ContextMenuResponse::Dispatch("normal! gv\"+y".to_owned())
+        );
+        assert!(!menu.is_open());
+        assert_eq!(menu.swallowed_release, Some(MouseButton::Left));
+    }
+
+    #[test]
+    fn clicking_outside_dismisses_the_menu() {
+        let mut menu = open_menu();
+        menu.handle_input(MenuInput::PointerMoved(PixelPos::new(20.0, 75.0)));
+        assert_eq!(
+            menu.handle_input(MenuInput::Press(MouseButton::Left)),
+            ContextMenuResponse::Consumed
+        );
+        assert!(!menu.is_open());
+    }
+
+    #[test]
+    fn the_keyboard_moves_the_selection_and_activates_items() {
+        let mut menu = open_menu();
+        menu.handle_input(MenuInput::Previous);
+        assert_eq!(menu.selected, Some(3));
+        menu.handle_input(MenuInput::Next);
+        assert_eq!(menu.selected, Some(0));
+        menu.handle_input(MenuInput::Next);
+        menu.handle_input(MenuInput::Next);
+        assert_eq!(
+            menu.handle_input(MenuInput::Activate),
+            ContextMenuResponse::Dispatch("normal! \"+gP".to_owned())
+        );
+
+        let mut menu = open_menu();
+        assert_eq!(menu.handle_input(MenuInput::Activate), ContextMenuResponse::Consumed);
+        assert!(!menu.is_open());
+
+        let mut menu = open_menu();
+        menu.handle_input(MenuInput::Last);
+        menu.handle_input(MenuInput::Dismiss);
+        assert!(!menu.is_open());
+    }
+
+    #[test]
+    fn items_are_hit_by_row() {
+        assert_eq!(item_index_at(0.0, 20.0, 4), Some(0));
+        assert_eq!(item_index_at(39.0, 20.0, 4), Some(1));
+        assert_eq!(item_index_at(80.0, 20.0, 4), None);
+        assert_eq!(item_index_at(-1.0, 20.0, 4), None);
+    }
+
+    #[test]
+    fn the_menu_stays_inside_the_window() {
+        let size = PixelSize::new(100.0, 80.0);
+        let bounds = PixelSize::new(800.0, 600.0);
+        assert_eq!(menu_origin(PixelPos::new(10.0, 10.0), size, bounds), PixelPos::new(10.0, 10.0));
+        assert_eq!(
+            menu_origin(PixelPos::new(750.0, 590.0), size, bounds),
+            PixelPos::new(650.0, 510.0)
+        );
+        assert_eq!(
+            menu_origin(PixelPos::new(50.0, 40.0), size, PixelSize::new(120.0, 100.0)).x,
+            0.0
+        );
+    }
+}
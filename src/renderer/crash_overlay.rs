@@ -0,0 +1,224 @@
+use skia_safe::{Canvas, Color4f, Paint, PaintStyle, RRect, Rect};
+use winit::{
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{Key, NamedKey},
+};
+
+use crate::{
+    bridge::NeovimExit,
+    renderer::{
+        GridRenderer,
+        text_cells::{CellText, text_cells},
+    },
+    units::PixelSize,
+};
+
+const HINT: &str = "Press r to restart Neovim, q to close the window";
+const PADDING_COLUMNS: usize = 2;
+const CORNER_RADIUS: f32 = 4.0;
+const BORDER_ALPHA: f32 = 0.25;
+
+/// What the overlay does with a key, decoupled from winit so that it can be tested
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CrashInput {
+    Restart,
+    Quit,
+    OtherKey,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrashOverlayResponse {
+    /// The overlay is closed, the event is handled as usual
+    Ignored,
+    /// The overlay used the event, nothing else should see it
+    Consumed,
+    /// The overlay closed, and a new Neovim should be started for the window
+    Restart,
+    /// The overlay closed, and the window should be closed
+    Quit,
+}
+
+/// Tells that the Neovim process of the window crashed, and offers to restart it. It's shown
+/// instead of closing the window, so that the reason isn't lost.
+#[derive(Default)]
+pub struct CrashOverlay {
+    lines: Vec<String>,
+}
+
+impl CrashOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    pub fn show(&mut self, exit: &NeovimExit) {
+        let NeovimExit::Crashed { reason, stderr } = exit else {
+            return;
+        };
+        self.lines = vec![reason.clone()];
+        if !stderr.is_empty() {
+            self.lines.push(String::new());
+            self.lines.extend(stderr.iter().cloned());
+        }
+        self.lines.push(String::new());
+        self.lines.push(HINT.to_owned());
+    }
+
+    pub fn close(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) -> CrashOverlayResponse {
+        if !self.is_open() {
+            return CrashOverlayResponse::Ignored;
+        }
+
+        let input = match event {
+            WindowEvent::KeyboardInput { event, .. } => match key_input(event) {
+                Some(input) => input,
+                None => return CrashOverlayResponse::Consumed,
+            },
+            // There's no Neovim left to ask whether the window can close
+            WindowEvent::CloseRequested => CrashInput::Quit,
+            WindowEvent::Ime(_)
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. } => {
+                return CrashOverlayResponse::Consumed;
+            }
+            _ => return CrashOverlayResponse::Ignored,
+        };
+        self.handle_input(input)
+    }
+
+    fn handle_input(&mut self, input: CrashInput) -> CrashOverlayResponse {
+        match input {
+            CrashInput::Restart => {
+                self.close();
+                CrashOverlayResponse::Restart
+            }
+            CrashInput::Quit => {
+                self.close();
+                CrashOverlayResponse::Quit
+            }
+            CrashInput::OtherKey => CrashOverlayResponse::Consumed,
+        }
+    }
+
+    /// Draws the reason of the crash and the last lines Neovim wrote to stderr in the middle of
+    /// the window
+    pub fn draw(&self, canvas: &Canvas, grid_renderer: &mut GridRenderer, bounds: PixelSize<f32>) {
+        if !self.is_open() {
+            return;
+        }
+
+        let cell_width = grid_renderer.grid_scale.width();
+        let row_height = grid_renderer.grid_scale.height();
+        let ambiwidth = grid_renderer.ambiwidth;
+        let columns = self.lines.iter().map(|line| text_cells(line, ambiwidth)).max();
+        let width =
+            ((columns.unwrap_or(0) + 2 * PADDING_COLUMNS) as f32 * cell_width).min(bounds.width);
+        let height = (self.lines.len() + 2) as f32 * row_height;
+        let rect = Rect::from_xywh(
+            ((bounds.width - width) / 2.0).max(0.0),
+            ((bounds.height - height) / 2.0).max(0.0),
+            width,
+            height,
+        );
+
+        let foreground =
+            grid_renderer.default_style.foreground(&grid_renderer.default_style.colors);
+        let background = Color4f::from(grid_renderer.get_default_background(1.0));
+        let rrect = RRect::new_rect_xy(rect, CORNER_RADIUS, CORNER_RADIUS);
+        let mut paint = Paint::new(background, None);
+        paint.set_anti_alias(true);
+        canvas.save();
+        canvas.clip_rrect(rrect, None, Some(true));
+        canvas.draw_rect(rect, &paint);
+
+        let baseline_offset = grid_renderer.shaper.baseline_offset();
+        let style = grid_renderer.default_style.clone();
+        let mut text_paint = Paint::new(foreground, None);
+        text_paint.set_anti_alias(false);
+        for (row, line) in self.lines.iter().enumerate() {
+            let cells = CellText::new(line, ambiwidth);
+            let origin = (
+                rect.left + PADDING_COLUMNS as f32 * cell_width,
+                rect.top + (row + 1) as f32 * row_height + baseline_offset,
+            );
+            for blob in grid_renderer.shaper.shape_cached(cells.word(), (&style).into()).iter() {
+                canvas.draw_text_blob(blob, origin, &text_paint);
+            }
+        }
+        canvas.restore();
+
+        let mut border_paint = Paint::new(Color4f { a: BORDER_ALPHA, ..foreground }, None);
+        border_paint.set_style(PaintStyle::Stroke);
+        border_paint.set_anti_alias(true);
+        canvas.draw_rrect(rrect, &border_paint);
+    }
+}
+
+fn key_input(event: &KeyEvent) -> Option<CrashInput> {
+    if event.state != ElementState::Pressed {
+        return None;
+    }
+    match &event.logical_key {
+        Key::Named(NamedKey::Escape) => Some(CrashInput::Quit),
+        Key::Character(text) if text.eq_ignore_ascii_case("q") => Some(CrashInput::Quit),
+        Key::Character(text) if text.eq_ignore_ascii_case("r") => Some(CrashInput::Restart),
+        _ => Some(CrashInput::OtherKey),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crash() -> NeovimExit {
+        NeovimExit::Crashed {
+            reason: "Neovim was killed by signal 11".to_owned(),
+            stderr: vec!["Segmentation fault".to_owned()],
+        }
+    }
+
+    #[test]
+    fn only_crashes_open_the_overlay() {
+        let mut overlay = CrashOverlay::new();
+        overlay.show(&NeovimExit::Quit);
+        assert!(!overlay.is_open());
+
+        overlay.show(&crash());
+        assert!(overlay.is_open());
+        assert_eq!(overlay.lines[0], "Neovim was killed by signal 11");
+        assert!(overlay.lines.contains(&"Segmentation fault".to_owned()));
+        assert_eq!(overlay.lines.last().map(String::as_str), Some(HINT));
+    }
+
+    #[test]
+    fn restart_and_quit_close_the_overlay() {
+        let mut overlay = CrashOverlay::new();
+        overlay.show(&crash());
+        assert_eq!(overlay.handle_input(CrashInput::OtherKey), CrashOverlayResponse::Consumed);
+        assert!(overlay.is_open());
+        assert_eq!(overlay.handle_input(CrashInput::Restart), CrashOverlayResponse::Restart);
+        assert!(!overlay.is_open());
+
+        overlay.show(&crash());
+        assert_eq!(overlay.handle_input(CrashInput::Quit), CrashOverlayResponse::Quit);
+        assert!(!overlay.is_open());
+    }
+
+    #[test]
+    fn a_closed_overlay_ignores_events() {
+        let mut overlay = CrashOverlay::new();
+        assert_eq!(
+            overlay.handle_event(&WindowEvent::CloseRequested),
+            CrashOverlayResponse::Ignored
+        );
+        overlay.show(&crash());
+        assert_eq!(overlay.handle_event(&WindowEvent::CloseRequested), CrashOverlayResponse::Quit);
+    }
+}
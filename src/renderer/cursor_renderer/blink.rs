@@ -1,4 +1,7 @@
-use std::time::{Duration, Instant};
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
 
 use crate::{editor::Cursor, window::ShouldRender};
 
@@ -9,10 +12,15 @@ pub enum BlinkState {
     Off,
 }
 
+// All the cursors blink from the same clock, so that the ones of different windows stay in unison
+static BLINK_EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
 pub struct BlinkStatus {
     state: BlinkState,
     /// When the cursor should change to the next [`BlinkState`]
     transition_time: Instant,
+    /// The end of the `blinkwait` delay, which restarts whenever the cursor changes
+    wait_until: Instant,
     current_cursor: Option<Cursor>,
 }
 
@@ -25,11 +33,25 @@ fn is_static(cursor: &Cursor) -> bool {
         || cursor.blinkon.is_none()
 }
 
+/// The state of a blinking cursor on the shared clock, and how long it stays in it
+fn blink_phase(cursor: &Cursor, since_epoch: Duration) -> (BlinkState, Duration) {
+    let on = cursor.blinkon.unwrap_or(0);
+    let cycle = on + cursor.blinkoff.unwrap_or(0);
+    let position = (since_epoch.as_millis() % cycle as u128) as u64;
+    if position < on {
+        (BlinkState::On, Duration::from_millis(on - position))
+    } else {
+        (BlinkState::Off, Duration::from_millis(cycle - position))
+    }
+}
+
 impl BlinkStatus {
     pub fn new() -> BlinkStatus {
+        let now = Instant::now();
         BlinkStatus {
             state: BlinkState::Waiting,
-            transition_time: Instant::now(),
+            transition_time: now,
+            wait_until: now,
             current_cursor: None,
         }
     }
@@ -48,49 +70,52 @@ impl BlinkStatus {
     }
 
     pub fn update_status(&mut self, new_cursor: &Cursor) -> ShouldRender {
-        let now = Instant::now();
-        if self.current_cursor.is_none() || new_cursor != self.current_cursor.as_ref().unwrap() {
+        self.update_status_at(new_cursor, Instant::now())
+    }
+
+    fn update_status_at(&mut self, new_cursor: &Cursor, now: Instant) -> ShouldRender {
+        if self.current_cursor.as_ref() != Some(new_cursor) {
             self.current_cursor = Some(new_cursor.clone());
-            if new_cursor.blinkwait.is_some() && new_cursor.blinkwait != Some(0) {
-                self.state = BlinkState::Waiting;
-            } else {
-                self.state = BlinkState::On;
-            }
-            self.transition_time = now + self.get_delay();
+            self.wait_until = now + Duration::from_millis(new_cursor.blinkwait.unwrap_or(0));
         }
 
         let current_cursor = self.current_cursor.as_ref().unwrap();
 
+        // A cursor that stops blinking while it's off has to be drawn again right away
         if is_static(current_cursor) {
+            let was_off = self.state == BlinkState::Off;
             self.state = BlinkState::Waiting;
-            ShouldRender::Wait
+            return if was_off { ShouldRender::Immediately } else { ShouldRender::Wait };
+        }
+
+        // Once the wait is over, the cursor follows the phase of the shared clock
+        let (state, transition_time) = if now < self.wait_until {
+            (BlinkState::Waiting, self.wait_until)
         } else {
-            if self.transition_time <= now {
-                self.state = match self.state {
-                    BlinkState::Waiting => BlinkState::On,
-                    BlinkState::On => BlinkState::Off,
-                    BlinkState::Off => BlinkState::On,
-                };
-                self.transition_time += self.get_delay();
-                // In case we are lagging badly...
-                if self.transition_time <= now {
-                    self.transition_time = now + self.get_delay();
-                }
-                return ShouldRender::Immediately;
-            }
-            ShouldRender::Deadline(self.transition_time)
+            let (state, remaining) =
+                blink_phase(current_cursor, now.saturating_duration_since(*BLINK_EPOCH));
+            (state, now + remaining)
+        };
+        self.transition_time = transition_time;
+        if self.state != state {
+            self.state = state;
+            return ShouldRender::Immediately;
         }
+        ShouldRender::Deadline(self.transition_time)
     }
 
     /// Calculate the opacity the cursor should be drawn with when smooth cursor blink is enabled.
     /// `0.0` is fully transparent, `1.0` is fully opaque.
     pub fn opacity(&self) -> f32 {
-        let now = Instant::now();
+        self.opacity_at(Instant::now())
+    }
+
+    fn opacity_at(&self, now: Instant) -> f32 {
         if self.state == BlinkState::Waiting {
             return 1.0;
         }
         let total = self.get_delay().as_secs_f32();
-        let remaining = (self.transition_time - now).as_secs_f32();
+        let remaining = self.transition_time.saturating_duration_since(now).as_secs_f32();
         match self.state {
             BlinkState::Waiting => 1.0,
             BlinkState::On => (remaining / total).clamp(0.0, 1.0),
@@ -115,3 +140,70 @@ impl BlinkStatus {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blinking_cursor(blinkwait: u64) -> Cursor {
+        Cursor {
+            blinkwait: Some(blinkwait),
+            blinkon: Some(400),
+            blinkoff: Some(250),
+            ..Cursor::new()
+        }
+    }
+
+    #[test]
+    fn cursors_blink_in_the_same_phase() {
+        let now = *BLINK_EPOCH + Duration::from_millis(10_123);
+        let mut first = BlinkStatus::new();
+        let mut second = BlinkStatus::new();
+        // One cursor already blinks, the other one just got done waiting
+        first.update_status_at(&blinking_cursor(700), now - Duration::from_millis(5000));
+        second.update_status_at(&blinking_cursor(700), now - Duration::from_millis(700));
+
+        first.update_status_at(&blinking_cursor(700), now);
+        second.update_status_at(&blinking_cursor(700), now);
+        assert_eq!(first.state, second.state);
+        assert_eq!(first.transition_time, second.transition_time);
+        assert_eq!(first.opacity_at(now), second.opacity_at(now));
+    }
+
+    #[test]
+    fn changing_the_cursor_restarts_the_wait() {
+        let now = *BLINK_EPOCH + Duration::from_millis(10_000);
+        let mut status = BlinkStatus::new();
+        status.update_status_at(&blinking_cursor(700), now - Duration::from_millis(5000));
+        status.update_status_at(&blinking_cursor(700), now);
+        assert_ne!(status.state, BlinkState::Waiting);
+
+        status.update_status_at(&blinking_cursor(600), now);
+        assert_eq!(status.state, BlinkState::Waiting);
+        assert_eq!(status.opacity_at(now), 1.0);
+        assert_eq!(
+            status.update_status_at(&blinking_cursor(600), now + Duration::from_millis(600)),
+            ShouldRender::Immediately
+        );
+    }
+
+    #[test]
+    fn a_mode_without_blinkon_keeps_the_cursor_solid() {
+        // The cursor is in the off phase of its blink
+        let now = *BLINK_EPOCH + Duration::from_millis(10_850);
+        let mut status = BlinkStatus::new();
+        status.update_status_at(&blinking_cursor(0), now - Duration::from_millis(5000));
+        status.update_status_at(&blinking_cursor(0), now);
+        assert_eq!(status.state, BlinkState::Off);
+
+        let solid = Cursor { blinkon: Some(0), ..blinking_cursor(0) };
+        assert_eq!(status.update_status_at(&solid, now), ShouldRender::Immediately);
+        assert!(status.should_render());
+        assert_eq!(status.opacity_at(now), 1.0);
+        assert_eq!(
+            status.update_status_at(&solid, now + Duration::from_millis(1000)),
+            ShouldRender::Wait
+        );
+        assert!(status.should_render());
+    }
+}
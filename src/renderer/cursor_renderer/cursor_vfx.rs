@@ -1,13 +1,13 @@
 use log::error;
 use nvim_rs::Value;
-use skia_safe::{BlendMode, Canvas, Color, Paint, Rect, paint::Style};
+use skia_safe::{BlendMode, Canvas, Color, Paint, PathBuilder, Rect, paint::Style};
 
 use crate::{
     editor::Cursor,
     renderer::cursor_renderer::CursorSettings,
     renderer::{animation_utils::*, grid_renderer::GridRenderer},
     settings::*,
-    units::{GridSize, PixelPos, PixelSize, PixelVec},
+    units::{GridSize, PixelPos, PixelSize, PixelVec, to_skia_point},
 };
 
 pub trait CursorVfx {
@@ -49,6 +49,7 @@ pub enum TrailMode {
 pub enum VfxMode {
     Highlight(HighlightMode),
     Trail(TrailMode),
+    Smear,
     Disabled,
 }
 
@@ -65,6 +66,7 @@ impl ParseFromValue for VfxMode {
                 "railgun" => VfxMode::Trail(TrailMode::Railgun),
                 "torpedo" => VfxMode::Trail(TrailMode::Torpedo),
                 "pixiedust" => VfxMode::Trail(TrailMode::PixieDust),
+                "smear" => VfxMode::Smear,
                 "" => VfxMode::Disabled,
                 value => {
                     error!("Expected a VfxMode name, but received {value:?}");
@@ -109,6 +111,7 @@ impl From<VfxMode> for Value {
             VfxMode::Trail(TrailMode::Railgun) => Value::from("railgun"),
             VfxMode::Trail(TrailMode::Torpedo) => Value::from("torpedo"),
             VfxMode::Trail(TrailMode::PixieDust) => Value::from("pixiedust"),
+            VfxMode::Smear => Value::from("smear"),
             VfxMode::Disabled => Value::from(""),
         }
     }
@@ -135,6 +138,7 @@ pub fn new_cursor_vfxs(modes: &VfxModeList) -> Vec<Box<dyn CursorVfx>> {
                 Some(Box::new(PointHighlight::new(mode)) as Box<dyn CursorVfx>)
             }
             VfxMode::Trail(mode) => Some(Box::new(ParticleTrail::new(mode)) as Box<dyn CursorVfx>),
+            VfxMode::Smear => Some(Box::new(Smear::default()) as Box<dyn CursorVfx>),
             VfxMode::Disabled => None,
         })
         .collect()
@@ -144,11 +148,17 @@ pub struct PointHighlight {
     t: f32,
     center_position: PixelPos<f32>,
     mode: HighlightMode,
+    base_color: Color,
 }
 
 impl PointHighlight {
     pub fn new(mode: &HighlightMode) -> PointHighlight {
-        PointHighlight { t: 0.0, center_position: PixelPos::new(0.0, 0.0), mode: mode.clone() }
+        PointHighlight {
+            t: 0.0,
+            center_position: PixelPos::new(0.0, 0.0),
+            mode: mode.clone(),
+            base_color: skia_safe::colors::WHITE,
+        }
     }
 }
 
@@ -156,13 +166,14 @@ impl CursorVfx for PointHighlight {
     fn update(
         &mut self,
         settings: &CursorSettings,
-        _base_color: Color,
+        base_color: Color,
         current_cursor_destination: PixelPos<f32>,
         _cursor_dimensions: PixelSize<f32>,
         _immediate_movement: bool,
         dt: f32,
     ) -> bool {
         self.center_position = current_cursor_destination;
+        self.base_color = base_color;
         if settings.vfx_particle_highlight_lifetime > 0.0 {
             self.t = (self.t + dt * (1.0 / settings.vfx_particle_highlight_lifetime)).min(1.0);
         } else {
@@ -185,7 +196,7 @@ impl CursorVfx for PointHighlight {
         settings: &CursorSettings,
         canvas: &Canvas,
         grid_renderer: &mut GridRenderer,
-        cursor: &Cursor,
+        _cursor: &Cursor,
     ) {
         if (self.t - 1.0).abs() < f32::EPSILON {
             return;
@@ -194,10 +205,8 @@ impl CursorVfx for PointHighlight {
         let mut paint = Paint::new(skia_safe::colors::WHITE, None);
         paint.set_blend_mode(BlendMode::SrcOver);
 
-        let colors = &grid_renderer.default_style.colors;
-        let base_color: Color = cursor.background(colors, settings.cell_color_fallback).to_color();
         let alpha = ease(ease_in_quad, settings.vfx_opacity, 0.0, self.t) as u8;
-        let color = Color::from_argb(alpha, base_color.r(), base_color.g(), base_color.b());
+        let color = self.base_color.with_a(alpha);
 
         paint.set_color(color);
 
@@ -433,6 +442,134 @@ impl CursorVfx for ParticleTrail {
     }
 }
 
+// The trail is this part of the distance travelled, up to a few cells
+const SMEAR_STRETCH: f32 = 0.5;
+const SMEAR_MAX_STRETCH_CELLS: f32 = 4.0;
+// Moves shorter than this part of a cell don't smear
+const SMEAR_MIN_DISTANCE: f32 = 0.5;
+// The width of the end of the trail, relative to the cursor
+const SMEAR_TAIL_WIDTH: f32 = 0.5;
+
+/// The corners of the smear left by a cursor that moved from `from` to `to`, the centers of the
+/// cursor cell, once `progress` of the animation is done. The trail points back in the direction
+/// of travel, and shrinks into the cursor as the animation progresses.
+fn smear_quad(
+    from: PixelPos<f32>,
+    to: PixelPos<f32>,
+    cursor_dimensions: PixelSize<f32>,
+    progress: f32,
+) -> Option<[PixelPos<f32>; 4]> {
+    let travel = to - from;
+    let distance = travel.length();
+    let cell_size = cursor_dimensions.width.min(cursor_dimensions.height);
+    if distance < SMEAR_MIN_DISTANCE * cell_size || progress >= 1.0 {
+        return None;
+    }
+
+    let direction = travel / distance;
+    let stretch = (distance * SMEAR_STRETCH)
+        .min(SMEAR_MAX_STRETCH_CELLS * cursor_dimensions.height)
+        * (1.0 - ease(ease_out_quad, 0.0, 1.0, progress));
+    let tail = to - direction * stretch;
+
+    // Half of the extent of the cursor across the direction of travel
+    let normal = PixelVec::new(-direction.y, direction.x);
+    let half_width = (normal.x.abs() * cursor_dimensions.width
+        + normal.y.abs() * cursor_dimensions.height)
+        / 2.0;
+    let head_offset = normal * half_width;
+    let tail_offset = head_offset * SMEAR_TAIL_WIDTH;
+    Some([to - head_offset, to + head_offset, tail + tail_offset, tail - tail_offset])
+}
+
+/// Stretches a quad from the previous position of the cursor to the new one when it moves
+pub struct Smear {
+    from: PixelPos<f32>,
+    to: PixelPos<f32>,
+    cursor_dimensions: PixelSize<f32>,
+    progress: f32,
+    color: Color,
+    positioned: bool,
+}
+
+impl Default for Smear {
+    fn default() -> Smear {
+        Smear {
+            from: PixelPos::new(0.0, 0.0),
+            to: PixelPos::new(0.0, 0.0),
+            cursor_dimensions: PixelSize::new(0.0, 0.0),
+            progress: 1.0,
+            color: skia_safe::colors::WHITE,
+            positioned: false,
+        }
+    }
+}
+
+impl CursorVfx for Smear {
+    fn update(
+        &mut self,
+        settings: &CursorSettings,
+        base_color: Color,
+        current_cursor_destination: PixelPos<f32>,
+        cursor_dimensions: PixelSize<f32>,
+        immediate_movement: bool,
+        dt: f32,
+    ) -> bool {
+        self.color = base_color;
+        self.cursor_dimensions = cursor_dimensions;
+        if current_cursor_destination != self.to {
+            self.from = self.to;
+            self.to = current_cursor_destination;
+            // The first position of the cursor is not a move
+            let first_position = !self.positioned;
+            self.positioned = true;
+            self.progress = if immediate_movement || first_position { 1.0 } else { 0.0 };
+        }
+        if settings.animation_length > 0.0 {
+            self.progress = (self.progress + dt / settings.animation_length).min(1.0);
+        } else {
+            self.progress = 1.0;
+        }
+        smear_quad(self.from, self.to, self.cursor_dimensions, self.progress).is_some()
+    }
+
+    fn restart(&mut self, position: PixelPos<f32>) {
+        self.from = position;
+        self.to = position;
+        self.progress = 1.0;
+        self.positioned = true;
+    }
+
+    fn cursor_jumped(&mut self, _position: PixelPos<f32>) {}
+
+    fn render(
+        &self,
+        settings: &CursorSettings,
+        canvas: &Canvas,
+        _grid_renderer: &mut GridRenderer,
+        _cursor: &Cursor,
+    ) {
+        let Some(corners) = smear_quad(self.from, self.to, self.cursor_dimensions, self.progress)
+        else {
+            return;
+        };
+
+        let alpha = ease(ease_in_quad, settings.vfx_opacity, 0.0, self.progress) as u8;
+        let mut paint = Paint::new(self.color.with_a(alpha), None);
+        paint.set_anti_alias(settings.antialiasing);
+        paint.set_blend_mode(BlendMode::SrcOver);
+
+        let mut builder = PathBuilder::new();
+        builder
+            .move_to(to_skia_point(corners[0]))
+            .line_to(to_skia_point(corners[1]))
+            .line_to(to_skia_point(corners[2]))
+            .line_to(to_skia_point(corners[3]))
+            .close();
+        canvas.draw_path(&builder.detach(), &paint);
+    }
+}
+
 // Random number generator based on http://www.pcg-random.org/
 struct RngState {
     state: u64,
@@ -501,3 +638,48 @@ fn rotate_vec(v: PixelVec<f32>, rot: f32) -> PixelVec<f32> {
 
     PixelVec::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELL: PixelSize<f32> = PixelSize::new(10.0, 20.0);
+
+    #[test]
+    fn the_smear_trails_behind_the_direction_of_travel() {
+        let from = PixelPos::new(0.0, 0.0);
+        let to = PixelPos::new(100.0, 0.0);
+        let corners = smear_quad(from, to, CELL, 0.0).unwrap();
+
+        // The head covers the height of the cursor, the tail is half of the distance back and
+        // narrower
+        assert_eq!(corners[0], PixelPos::new(100.0, -10.0));
+        assert_eq!(corners[1], PixelPos::new(100.0, 10.0));
+        assert_eq!(corners[2], PixelPos::new(50.0, 5.0));
+        assert_eq!(corners[3], PixelPos::new(50.0, -5.0));
+
+        // Moving up stretches it downwards, across the width of the cursor
+        let corners = smear_quad(PixelPos::new(0.0, 100.0), from, CELL, 0.0).unwrap();
+        assert_eq!(corners[0], PixelPos::new(-5.0, 0.0));
+        assert_eq!(corners[2], PixelPos::new(2.5, 50.0));
+    }
+
+    #[test]
+    fn the_stretch_is_capped_and_shrinks_with_the_animation() {
+        let from = PixelPos::new(0.0, 0.0);
+        let to = PixelPos::new(0.0, 1000.0);
+        let corners = smear_quad(from, to, CELL, 0.0).unwrap();
+        assert_eq!(corners[2].y, 1000.0 - SMEAR_MAX_STRETCH_CELLS * CELL.height);
+
+        let corners = smear_quad(from, to, CELL, 0.5).unwrap();
+        assert!(corners[2].y > 1000.0 - SMEAR_MAX_STRETCH_CELLS * CELL.height);
+        assert_eq!(smear_quad(from, to, CELL, 1.0), None);
+    }
+
+    #[test]
+    fn short_moves_do_not_smear() {
+        let from = PixelPos::new(0.0, 0.0);
+        assert_eq!(smear_quad(from, PixelPos::new(2.0, 1.0), CELL, 0.0), None);
+        assert!(smear_quad(from, PixelPos::new(10.0, 0.0), CELL, 0.0).is_some());
+    }
+}
@@ -5,15 +5,23 @@ use std::{collections::HashMap, sync::Arc};
 
 use approx::AbsDiffEq;
 use itertools::Itertools;
-use skia_safe::{Canvas, Paint, Path, PathBuilder, op};
+use rmpv::Value;
+use skia_safe::{Canvas, Color, Color4f, Paint, Path, PathBuilder, op};
 use winit::event::WindowEvent;
 
 use crate::{
     bridge::EditorMode,
     editor::{Cursor, CursorShape, Word},
+    error_msg,
     profiling::{tracy_plot, tracy_zone},
-    renderer::{GridRenderer, RenderedWindow, animation_utils::*},
-    settings::{ParseFromValue, Settings},
+    renderer::{
+        GridRenderer, RenderedWindow, animation_utils::*, animations_disabled,
+        rendered_window::scale_cells,
+    },
+    settings::{
+        ParseFromValue, Settings,
+        power::{animation_scale, battery_saver_active},
+    },
     units::{
         GridPos, GridScale, GridSize, PixelPos, PixelRect, PixelSize, PixelVec, to_skia_point,
     },
@@ -44,6 +52,131 @@ static PLOT_NAMES_Y: [&CStr; 4] = [
     unsafe { CStr::from_ptr(b"Cursor bottom left y\0".as_ptr() as *const c_char) },
 ];
 
+/// A cursor shape used in every mode instead of the one from guicursor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CursorShapeOverride {
+    /// Respects guicursor
+    #[default]
+    None,
+    Block,
+    Beam,
+    Underline,
+}
+
+impl CursorShapeOverride {
+    /// Returns the shape and cell percentage to draw the cursor with
+    fn apply(
+        self,
+        shape: &CursorShape,
+        cell_percentage: Option<f32>,
+    ) -> (CursorShape, Option<f32>) {
+        let overridden = match self {
+            Self::None => return (shape.clone(), cell_percentage),
+            Self::Block => CursorShape::Block,
+            Self::Beam => CursorShape::Vertical,
+            Self::Underline => CursorShape::Horizontal,
+        };
+        // The percentage of guicursor only fits the shape it was set for, the others fall back to
+        // a narrow bar
+        let cell_percentage = cell_percentage.filter(|_| overridden == *shape);
+        (overridden, cell_percentage)
+    }
+}
+
+impl ParseFromValue for CursorShapeOverride {
+    fn parse_from_value(&mut self, value: Value) {
+        match value.as_str() {
+            Some("none") => *self = Self::None,
+            Some("block") => *self = Self::Block,
+            Some("beam") => *self = Self::Beam,
+            Some("underline") => *self = Self::Underline,
+            _ => {
+                error_msg!(
+                    "Setting cursor_shape_override expected one of `none`, `block`, `beam`, `underline`, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<CursorShapeOverride> for Value {
+    fn from(value: CursorShapeOverride) -> Self {
+        Value::from(match value {
+            CursorShapeOverride::None => "none",
+            CursorShapeOverride::Block => "block",
+            CursorShapeOverride::Beam => "beam",
+            CursorShapeOverride::Underline => "underline",
+        })
+    }
+}
+
+/// A cursor color used instead of the one from the `Cursor` highlight
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CursorColor {
+    /// Respects the highlight
+    #[default]
+    Auto,
+    Color(Color),
+}
+
+impl CursorColor {
+    /// Parses `#rgb`, `#rrggbb` or `#rrggbbaa`, the `#` being optional
+    pub(super) fn parse_hex(text: &str) -> Option<Color> {
+        let digits = text.trim().strip_prefix('#').unwrap_or(text.trim());
+        if !digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |index: usize, width: usize| {
+            let value = u8::from_str_radix(&digits[index * width..(index + 1) * width], 16).ok()?;
+            // A single digit is repeated, so that `f` is `ff`
+            Some(if width == 1 { value * 0x11 } else { value })
+        };
+        let (width, alpha) = match digits.len() {
+            3 => (1, 0xff),
+            6 => (2, 0xff),
+            8 => (2, channel(3, 2)?),
+            _ => return None,
+        };
+        Some(Color::from_argb(alpha, channel(0, width)?, channel(1, width)?, channel(2, width)?))
+    }
+
+    /// Returns the color to draw the cursor with, given the one resolved from the highlight
+    fn apply(self, resolved: Color4f) -> Color4f {
+        match self {
+            Self::Auto => resolved,
+            Self::Color(color) => color.into(),
+        }
+    }
+}
+
+impl ParseFromValue for CursorColor {
+    fn parse_from_value(&mut self, value: Value) {
+        let parsed = value.as_str().and_then(|text| match text {
+            "auto" => Some(Self::Auto),
+            text => Self::parse_hex(text).map(Self::Color),
+        });
+        match parsed {
+            Some(color) => *self = color,
+            None => {
+                error_msg!(
+                    "Setting cursor_color expected `auto` or a hex color like `#ff8800`, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<CursorColor> for Value {
+    fn from(value: CursorColor) -> Self {
+        Value::from(match value {
+            CursorColor::Auto => "auto".to_owned(),
+            CursorColor::Color(color) => {
+                format!("#{:02x}{:02x}{:02x}{:02x}", color.r(), color.g(), color.b(), color.a())
+            }
+        })
+    }
+}
+
 #[derive(SettingGroup)]
 #[setting_prefix = "cursor"]
 #[derive(Clone)]
@@ -57,6 +190,11 @@ pub struct CursorSettings {
     unfocused_outline_width: f32,
     smooth_blink: bool,
     cell_color_fallback: bool,
+    ligature_split: bool,
+    follow_wrapped_lines: bool,
+    fill_linespace: bool,
+    shape_override: CursorShapeOverride,
+    color: CursorColor,
 
     vfx_mode: cursor_vfx::VfxModeList,
     vfx_opacity: f32,
@@ -68,6 +206,20 @@ pub struct CursorSettings {
     vfx_particle_curl: f32,
 }
 
+impl CursorSettings {
+    /// The settings with `neovide_no_animations`, which jumps the cursor and turns off the
+    /// particles and the smooth blink
+    fn without_animations(self) -> Self {
+        Self {
+            animation_length: 0.0,
+            short_animation_length: 0.0,
+            smooth_blink: false,
+            vfx_mode: cursor_vfx::VfxModeList::default(),
+            ..self
+        }
+    }
+}
+
 impl Default for CursorSettings {
     fn default() -> Self {
         CursorSettings {
@@ -80,6 +232,11 @@ impl Default for CursorSettings {
             unfocused_outline_width: 1.0 / 8.0,
             smooth_blink: false,
             cell_color_fallback: false,
+            ligature_split: false,
+            follow_wrapped_lines: false,
+            fill_linespace: true,
+            shape_override: CursorShapeOverride::None,
+            color: CursorColor::Auto,
             vfx_mode: cursor_vfx::VfxModeList::default(),
             vfx_opacity: 200.0,
             vfx_particle_lifetime: 0.5,
@@ -92,6 +249,47 @@ impl Default for CursorSettings {
     }
 }
 
+/// Moves a corner of the cell so that the cursor covers `cell_percentage` of the cell width for
+/// the beam, and of the cell height for the underline
+fn shaped_corner(
+    cursor_shape: &CursorShape,
+    cell_percentage: f32,
+    (x, y): (f32, f32),
+) -> GridPos<f32> {
+    match cursor_shape {
+        CursorShape::Block => (x, y).into(),
+        // Transform the x position so that the right side is translated over to
+        // the BAR_WIDTH position
+        CursorShape::Vertical => ((x + 0.5) * cell_percentage - 0.5, y).into(),
+        // Do the same as above, but flip the y coordinate and then flip the result
+        // so that the horizontal bar is at the bottom of the character space
+        // instead of the top.
+        CursorShape::Horizontal => (x, -((-y + 0.5) * cell_percentage - 0.5)).into(),
+    }
+}
+
+/// The size of the area the cursor shape is fitted in. Blocks and underlines cover both cells of
+/// a wide character, while beams keep the thickness they have on a single cell.
+fn cursor_dimensions(
+    cell_size: PixelSize<f32>,
+    cursor_shape: &CursorShape,
+    double_width: bool,
+) -> PixelSize<f32> {
+    match cursor_shape {
+        CursorShape::Block | CursorShape::Horizontal if double_width => {
+            PixelSize::new(cell_size.width * 2.0, cell_size.height)
+        }
+        _ => cell_size,
+    }
+}
+
+/// The height of the area the cursor shape is fitted in. It covers the whole cell with
+/// `fill_linespace`, or only the glyphs without the 'linespace' padding, which is split evenly
+/// above and below them.
+fn cursor_height(cell_height: f32, linespace: f32, fill_linespace: bool) -> f32 {
+    if fill_linespace { cell_height } else { (cell_height - linespace.max(0.0)).max(1.0) }
+}
+
 #[derive(Clone)]
 pub struct Corner {
     current_position: PixelPos<f32>,
@@ -155,6 +353,12 @@ impl Corner {
         animating
     }
 
+    /// Moves the corner right to the column of its destination, only the rows are animated
+    fn skip_horizontal_animation(&mut self) {
+        self.animation_x.reset();
+        self.current_position.x = self.previous_destination.x;
+    }
+
     fn jump(
         &mut self,
         settings: &CursorSettings,
@@ -254,12 +458,16 @@ pub struct CursorRenderer {
     destination: PixelPos<f32>,
     blink_status: BlinkStatus,
     previous_cursor_position: Option<(u64, GridPos<u64>)>,
-    previous_cursor_shape: Option<CursorShape>,
+    previous_cursor_shape: Option<(CursorShape, f32)>,
+    // The shape and cell percentage from guicursor, before the override
+    guicursor_shape: (CursorShape, Option<f32>),
     cmdline_ctx: CmdlineCtx,
     cursor_vfxs: Vec<Box<dyn cursor_vfx::CursorVfx>>,
     previous_vfx_mode: cursor_vfx::VfxModeList,
     window_has_focus: bool,
     jumped: bool,
+    // The font scale of the window the cursor is in, see `g:neovide_floating_font_scale`
+    font_scale: f32,
 
     settings: Arc<Settings>,
 }
@@ -273,11 +481,13 @@ impl CursorRenderer {
             blink_status: BlinkStatus::new(),
             previous_cursor_position: None,
             previous_cursor_shape: None,
+            guicursor_shape: (CursorShape::Block, None),
             cmdline_ctx: CmdlineCtx::Inactive,
             cursor_vfxs: vec![],
             previous_vfx_mode: cursor_vfx::VfxModeList::default(),
             window_has_focus: true,
             jumped: false,
+            font_scale: 1.0,
 
             settings,
         };
@@ -291,8 +501,30 @@ impl CursorRenderer {
         }
     }
 
+    pub fn cursor(&self) -> &Cursor {
+        &self.cursor
+    }
+
+    /// The window, row and column of the cursor when the ligature under it should be split
+    pub fn ligature_split_cell(&self) -> Option<(u64, u64, u64)> {
+        let (column, row) = self.cursor.grid_position;
+        (self.settings.get::<CursorSettings>().ligature_split && self.cursor.enabled).then_some((
+            self.cursor.parent_window_id,
+            row,
+            column,
+        ))
+    }
+
     pub fn update_cursor(&mut self, new_cursor: Cursor) {
+        self.guicursor_shape = (new_cursor.shape.clone(), new_cursor.cell_percentage);
         self.cursor = new_cursor;
+        self.apply_shape_override(self.settings.get::<CursorSettings>().shape_override);
+    }
+
+    fn apply_shape_override(&mut self, shape_override: CursorShapeOverride) {
+        let (shape, cell_percentage) = &self.guicursor_shape;
+        (self.cursor.shape, self.cursor.cell_percentage) =
+            shape_override.apply(shape, *cell_percentage);
     }
 
     fn set_cursor_shape(&mut self, cursor_shape: &CursorShape, cell_percentage: f32) {
@@ -305,18 +537,7 @@ impl CursorRenderer {
                 let (x, y) = STANDARD_CORNERS[i];
 
                 Corner {
-                    relative_position: match cursor_shape {
-                        CursorShape::Block => (x, y).into(),
-                        // Transform the x position so that the right side is translated over to
-                        // the BAR_WIDTH position
-                        CursorShape::Vertical => ((x + 0.5) * cell_percentage - 0.5, y).into(),
-                        // Do the same as above, but flip the y coordinate and then flip the result
-                        // so that the horizontal bar is at the bottom of the character space
-                        // instead of the top.
-                        CursorShape::Horizontal => {
-                            (x, -((-y + 0.5) * cell_percentage - 0.5)).into()
-                        }
-                    },
+                    relative_position: shaped_corner(cursor_shape, cell_percentage, (x, y)),
                     #[cfg(feature = "profiling")]
                     id: i,
                     ..corner
@@ -335,6 +556,7 @@ impl CursorRenderer {
         let new_cursor_pos = if let Some(window) = windows.get(&self.cursor.parent_window_id) {
             let mut grid = cursor_grid_position_f + window.grid_current_position.to_vector();
             grid.y -= window.scroll_animation.position;
+            grid.y += window.content_offset();
 
             let top_border = window.viewport_margins.top as f32;
             let bottom_border = window.viewport_margins.bottom as f32;
@@ -348,10 +570,15 @@ impl CursorRenderer {
                     - bottom_border,
             );
 
-            self.destination = grid * grid_scale;
+            // The cells of scaled floats are smaller than the ones of the grid they're placed on
+            let origin = window.grid_current_position;
+            self.destination =
+                origin * grid_scale + (grid - origin) * window.content_grid_scale(grid_scale);
+            self.font_scale = window.font_scale();
             Some((window.id, cursor_grid_position))
         } else {
             self.destination = cursor_grid_position_f * grid_scale;
+            self.font_scale = 1.0;
             Some((0, cursor_grid_position))
         };
         if new_cursor_pos != self.previous_cursor_position {
@@ -367,9 +594,25 @@ impl CursorRenderer {
         self.blink_status.update_status(&self.cursor)
     }
 
+    fn cursor_settings(&self) -> CursorSettings {
+        let settings = self.settings.get::<CursorSettings>();
+        match animations_disabled(&self.settings) {
+            true => settings.without_animations(),
+            false => settings,
+        }
+    }
+
+    /// The color of the cursor itself, from the highlight unless `neovide_cursor_color` is set
+    fn background_color(&self, grid_renderer: &GridRenderer, settings: &CursorSettings) -> Color4f {
+        settings.color.apply(
+            self.cursor
+                .background(&grid_renderer.default_style.colors, settings.cell_color_fallback),
+        )
+    }
+
     pub fn draw(&mut self, grid_renderer: &mut GridRenderer, canvas: &Canvas) {
         tracy_zone!("cursor_draw");
-        let settings = self.settings.get::<CursorSettings>();
+        let settings = self.cursor_settings();
         let render = self.blink_status.should_render() || settings.smooth_blink;
         let opacity = match settings.smooth_blink {
             true => self.blink_status.opacity(),
@@ -387,8 +630,7 @@ impl CursorRenderer {
         }
         // Draw Background
         let background_color = self
-            .cursor
-            .background(&grid_renderer.default_style.colors, settings.cell_color_fallback)
+            .background_color(grid_renderer, &settings)
             .to_color()
             .with_a((opacity * alpha) as u8);
         paint.set_color(background_color);
@@ -396,7 +638,8 @@ impl CursorRenderer {
         let path = if self.window_has_focus || self.cursor.shape != CursorShape::Block {
             self.draw_rectangle(canvas, &paint)
         } else {
-            let outline_width = settings.unfocused_outline_width * grid_renderer.em_size;
+            let outline_width =
+                settings.unfocused_outline_width * grid_renderer.em_size * self.font_scale;
             self.draw_rectangular_outline(canvas, &paint, outline_width)
         };
 
@@ -420,18 +663,20 @@ impl CursorRenderer {
             canvas,
             PixelRect::from_origin_and_size(
                 self.destination,
-                GridSize::new(1, 1) * grid_renderer.grid_scale,
+                GridSize::new(1, 1) * scale_cells(grid_renderer.grid_scale, self.font_scale),
             ),
             foreground_color,
             PixelPos::default(),
         );
         if !box_char_drawn {
-            let pos = (self.destination.x, self.destination.y + baseline_offset);
+            // The character is shaped at the size of the grid, like the lines of scaled floats
+            canvas.translate((self.destination.x, self.destination.y));
+            canvas.scale((self.font_scale, self.font_scale));
             let blobs = &grid_renderer
                 .shaper
                 .shape_cached(Word::new(&character, &[character.len() as u8]), coarse_style);
             for blob in blobs.iter() {
-                canvas.draw_text_blob(blob, pos, &paint);
+                canvas.draw_text_blob(blob, (0.0, baseline_offset), &paint);
             }
         }
 
@@ -452,19 +697,32 @@ impl CursorRenderer {
         if !self.cursor.enabled {
             return false;
         }
-        let settings = self.settings.get::<CursorSettings>();
+        let mut settings = self.cursor_settings();
+        let scale = animation_scale(battery_saver_active(&self.settings));
+        settings.animation_length *= scale;
+        settings.short_animation_length *= scale;
+
+        self.apply_shape_override(settings.shape_override);
 
         if settings.vfx_mode != self.previous_vfx_mode {
             self.cursor_vfxs = cursor_vfx::new_cursor_vfxs(&settings.vfx_mode);
             self.previous_vfx_mode = settings.vfx_mode.clone();
         }
 
-        let mut cursor_width = grid_renderer.grid_scale.width();
-        if self.cursor.double_width && self.cursor.shape == CursorShape::Block {
-            cursor_width *= 2.0;
-        }
-
-        let cursor_dimensions = PixelSize::new(cursor_width, grid_renderer.grid_scale.height());
+        let cell_scale = scale_cells(grid_renderer.grid_scale, self.font_scale);
+        let cell_height = cell_scale.height();
+        let cursor_dimensions = cursor_dimensions(
+            PixelSize::new(
+                cell_scale.width(),
+                cursor_height(
+                    cell_height,
+                    grid_renderer.shaper.linespace() * self.font_scale,
+                    settings.fill_linespace,
+                ),
+            ),
+            &self.cursor.shape,
+            self.cursor.double_width,
+        );
 
         let in_insert_mode = matches!(current_mode, EditorMode::Insert);
 
@@ -472,14 +730,17 @@ impl CursorRenderer {
         let changed_to_from_cmdline = !matches!(self.cmdline_ctx, CmdlineCtx::Inactive)
             ^ !matches!(next_cmdline_ctx, CmdlineCtx::Inactive);
 
-        let center_destination = self.destination + cursor_dimensions.to_vector() * 0.5;
+        // The cursor stays centered on the cell when it leaves out the linespace
+        let center_destination =
+            self.destination + PixelVec::new(cursor_dimensions.width, cell_height) * 0.5;
 
-        if self.previous_cursor_shape.as_ref() != Some(&self.cursor.shape) {
-            self.previous_cursor_shape = Some(self.cursor.shape.clone());
-            self.set_cursor_shape(
-                &self.cursor.shape.clone(),
-                self.cursor.cell_percentage.unwrap_or(DEFAULT_CELL_PERCENTAGE),
-            );
+        // Modes can share a shape with different percentages, like ver25 and ver50
+        let cell_percentage =
+            self.cursor.cell_percentage.unwrap_or(DEFAULT_CELL_PERCENTAGE).clamp(0.0, 1.0);
+        let cursor_shape = (self.cursor.shape.clone(), cell_percentage);
+        if self.previous_cursor_shape.as_ref() != Some(&cursor_shape) {
+            self.previous_cursor_shape = Some(cursor_shape);
+            self.set_cursor_shape(&self.cursor.shape.clone(), cell_percentage);
 
             for vfx in self.cursor_vfxs.iter_mut() {
                 vfx.restart(center_destination);
@@ -523,6 +784,10 @@ impl CursorRenderer {
                     )
                 }
             }
+            // Along a wrapped line the cursor goes on to the next row like the text does,
+            // instead of sweeping back across the window
+            let follow_wrap =
+                self.jumped && self.cursor.followed_wrap && settings.follow_wrapped_lines;
             for corner in self.corners.iter_mut() {
                 let corner_animating = corner.update(
                     cursor_dimensions.into(),
@@ -530,15 +795,15 @@ impl CursorRenderer {
                     dt,
                     immediate_movement,
                 );
+                if follow_wrap {
+                    corner.skip_horizontal_animation();
+                }
 
                 animating |= corner_animating;
             }
 
             let mut vfx_animating = false;
-            let vfx_base_color = self
-                .cursor
-                .background(&grid_renderer.default_style.colors, settings.cell_color_fallback)
-                .to_color();
+            let vfx_base_color = self.background_color(grid_renderer, &settings).to_color();
 
             for vfx in self.cursor_vfxs.iter_mut() {
                 let ret = vfx.update(
@@ -620,4 +885,158 @@ impl CursorRenderer {
     pub fn get_destination(&self) -> PixelPos<f32> {
         self.destination
     }
+
+    /// Where the cursor is drawn right now, in the middle of its animation, around its corners
+    pub fn current_bounds(&self) -> PixelRect<f32> {
+        PixelRect::from_points(self.corners.iter().map(|corner| corner.current_position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::CursorMode;
+
+    fn shaped_size(cursor_shape: CursorShape, cell_percentage: f32) -> (f32, f32) {
+        let corners = STANDARD_CORNERS
+            .iter()
+            .map(|corner| shaped_corner(&cursor_shape, cell_percentage, *corner))
+            .collect::<Vec<_>>();
+        (corners[1].x - corners[0].x, corners[2].y - corners[1].y)
+    }
+
+    #[test]
+    fn beams_cover_their_percentage_of_the_cell_width() {
+        assert_eq!(shaped_size(CursorShape::Vertical, 0.5), (0.5, 1.0));
+        let corner = shaped_corner(&CursorShape::Vertical, 0.5, STANDARD_CORNERS[0]);
+        assert_eq!((corner.x, corner.y), (-0.5, -0.5));
+    }
+
+    #[test]
+    fn underlines_cover_their_percentage_of_the_cell_height() {
+        let (width, height) = shaped_size(CursorShape::Horizontal, 0.2);
+        assert_eq!(width, 1.0);
+        assert!((height - 0.2).abs() < 1e-6);
+        // The underline sits at the bottom of the cell
+        let corner = shaped_corner(&CursorShape::Horizontal, 0.2, STANDARD_CORNERS[2]);
+        assert_eq!((corner.x, corner.y), (0.5, 0.5));
+    }
+
+    #[test]
+    fn blocks_cover_the_whole_cell() {
+        assert_eq!(shaped_size(CursorShape::Block, 0.2), (1.0, 1.0));
+    }
+
+    #[test]
+    fn cursors_on_wide_characters_cover_both_cells() {
+        let cell = PixelSize::new(10.0, 20.0);
+        let wide = PixelSize::new(20.0, 20.0);
+        assert_eq!(cursor_dimensions(cell, &CursorShape::Block, true), wide);
+        assert_eq!(cursor_dimensions(cell, &CursorShape::Horizontal, true), wide);
+        assert_eq!(cursor_dimensions(cell, &CursorShape::Vertical, true), cell);
+        assert_eq!(cursor_dimensions(cell, &CursorShape::Block, false), cell);
+        assert_eq!(cursor_dimensions(cell, &CursorShape::Horizontal, false), cell);
+    }
+
+    #[test]
+    fn the_cursor_can_cover_the_linespace() {
+        // A 20 pixel font with a linespace of 4
+        assert_eq!(cursor_height(24.0, 4.0, true), 24.0);
+        assert_eq!(cursor_height(24.0, 4.0, false), 20.0);
+        // A negative linespace already cuts into the glyphs
+        assert_eq!(cursor_height(18.0, -2.0, false), 18.0);
+    }
+
+    #[test]
+    fn the_shape_override_replaces_the_insert_mode_beam() {
+        let mut cursor = Cursor::new();
+        let insert_mode = CursorMode {
+            shape: Some(CursorShape::Vertical),
+            cell_percentage: Some(0.25),
+            ..CursorMode::default()
+        };
+        cursor.change_mode(&insert_mode, &HashMap::new());
+
+        assert_eq!(
+            CursorShapeOverride::Block.apply(&cursor.shape, cursor.cell_percentage),
+            (CursorShape::Block, None)
+        );
+        assert_eq!(
+            CursorShapeOverride::None.apply(&cursor.shape, cursor.cell_percentage),
+            (CursorShape::Vertical, Some(0.25))
+        );
+        assert_eq!(
+            CursorShapeOverride::Beam.apply(&cursor.shape, cursor.cell_percentage),
+            (CursorShape::Vertical, Some(0.25))
+        );
+        assert_eq!(
+            CursorShapeOverride::Underline.apply(&CursorShape::Block, None),
+            (CursorShape::Horizontal, None)
+        );
+    }
+
+    #[test]
+    fn a_hex_cursor_color_overrides_the_highlight() {
+        let resolved = Color4f::new(0.1, 0.2, 0.3, 1.0);
+        let mut color = CursorColor::default();
+        assert_eq!(color.apply(resolved), resolved);
+
+        color.parse_from_value(Value::from("#ff8800"));
+        assert_eq!(color, CursorColor::Color(Color::from_rgb(0xff, 0x88, 0x00)));
+        assert_eq!(color.apply(resolved), Color4f::from(Color::from_rgb(0xff, 0x88, 0x00)));
+
+        color.parse_from_value(Value::from("auto"));
+        assert_eq!(color.apply(resolved), resolved);
+    }
+
+    #[test]
+    fn cursor_colors_are_parsed_robustly() {
+        let parse = CursorColor::parse_hex;
+        assert_eq!(parse(" F80 "), Some(Color::from_rgb(0xff, 0x88, 0x00)));
+        assert_eq!(parse("#ff880080"), Some(Color::from_argb(0x80, 0xff, 0x88, 0x00)));
+        assert_eq!(parse("#ff88"), None);
+        assert_eq!(parse("#gg8800"), None);
+        assert_eq!(parse("#ff880é"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn without_animations_the_cursor_jumps_right_to_its_destination() {
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 20.0));
+        let destination = PixelPos::new(100.0, 40.0);
+        let settings = CursorSettings { animation_length: 1.0, ..CursorSettings::default() };
+        let dt = 1.0 / 60.0;
+
+        let mut animated = Corner::new();
+        animated.jump(&settings, destination, grid_scale, 0);
+        assert!(animated.update(grid_scale, destination, dt, false));
+        assert_ne!(animated.current_position, animated.get_destination(destination, grid_scale));
+
+        let mut instant = Corner::new();
+        instant.jump(&settings.without_animations(), destination, grid_scale, 0);
+        assert!(!instant.update(grid_scale, destination, dt, false));
+        assert_eq!(instant.current_position, instant.get_destination(destination, grid_scale));
+    }
+
+    #[test]
+    fn a_move_across_a_wrap_animates_by_one_row() {
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 20.0));
+        let settings = CursorSettings::default();
+        let dt = 1.0 / 60.0;
+        // From the last column of a wrapped row to the first column of the next one
+        let start = PixelPos::new(795.0, 10.0);
+        let destination = PixelPos::new(5.0, 30.0);
+
+        let mut corner = Corner::new();
+        corner.jump(&settings, start, grid_scale, 0);
+        corner.update(grid_scale, start, dt, true);
+        corner.jump(&settings, destination, grid_scale, 0);
+        assert!(corner.update(grid_scale, destination, dt, false));
+        corner.skip_horizontal_animation();
+
+        let corner_destination = corner.get_destination(destination, grid_scale);
+        assert_eq!(corner.current_position.x, corner_destination.x);
+        let rows_left = (corner_destination.y - corner.current_position.y) / grid_scale.height();
+        assert!(rows_left > 0.0 && rows_left <= 1.0);
+    }
 }
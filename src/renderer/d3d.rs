@@ -49,7 +49,7 @@ use winit::{
     window::Window,
 };
 
-use super::{RendererSettings, SkiaRenderer, VSync, vsync::VSyncWinSwapChain};
+use super::{GraphicsInfo, RendererSettings, SkiaRenderer, VSync, vsync::VSyncWinSwapChain};
 #[cfg(feature = "gpu_profiling")]
 use crate::profiling::{GpuCtx, d3d::create_d3d_gpu_context};
 use crate::{
@@ -99,10 +99,11 @@ pub struct D3DSkiaRenderer {
     fence_event: HANDLE,
     frame_swapped: bool,
     frame_index: usize,
+    sync_interval: u32,
     _backend_context: BackendContext,
     #[cfg(feature = "gpu_profiling")]
     pub device: ID3D12Device,
-    _adapter: IDXGIAdapter1,
+    adapter: IDXGIAdapter1,
     _composition_device: IDCompositionDevice,
     _target: IDCompositionTarget,
     _visual: IDCompositionVisual,
@@ -242,7 +243,7 @@ impl D3DSkiaRenderer {
         };
 
         let mut ret = Self {
-            _adapter: adapter,
+            adapter,
             #[cfg(feature = "gpu_profiling")]
             device,
             command_queue,
@@ -258,6 +259,7 @@ impl D3DSkiaRenderer {
             fence_event,
             frame_swapped: true,
             frame_index,
+            sync_interval: 1,
             _composition_device: composition_device,
             _target: target,
             _visual: visual,
@@ -384,7 +386,7 @@ impl SkiaRenderer for D3DSkiaRenderer {
             self.gr_context.submit(Some(SyncCpu::No));
 
             tracy_gpu_zone!("present");
-            if self.swap_chain.Present(1, DXGI_PRESENT(0)).is_ok() {
+            if self.swap_chain.Present(self.sync_interval, DXGI_PRESENT(0)).is_ok() {
                 self.frame_swapped = true;
             }
         }
@@ -425,6 +427,23 @@ impl SkiaRenderer for D3DSkiaRenderer {
         self.setup_surfaces();
     }
 
+    fn set_vsync(&mut self, enabled: bool) {
+        self.sync_interval = if enabled { 1 } else { 0 };
+    }
+
+    fn graphics_info(&self) -> GraphicsInfo {
+        let gpu = unsafe { self.adapter.GetDesc1() }.ok().map(|desc| {
+            String::from_utf16_lossy(&desc.Description).trim_end_matches('\0').to_owned()
+        });
+        GraphicsInfo {
+            backend: "Direct3D 12",
+            gpu,
+            driver: None,
+            surface_format: "R8G8B8A8_UNORM".to_owned(),
+            linear_blending: false,
+        }
+    }
+
     fn create_vsync(&self, proxy: EventLoopProxy<EventPayload>) -> VSync {
         VSync::WindowsSwapChain(VSyncWinSwapChain::new(proxy, self.swap_chain_waitable))
     }
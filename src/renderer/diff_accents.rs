@@ -0,0 +1,191 @@
+use std::{ops::Range, sync::Arc};
+
+use skia_safe::{Canvas, Color4f, Paint, Rect, TileMode, gradient_shader};
+
+use crate::editor::{Colors, Style};
+
+// The accent is mixed from the diff background towards the text color by this amount
+const MIX: f32 = 0.5;
+const GRADIENT_ALPHA: f32 = 0.35;
+// The bar takes this part of a cell width
+const BAR_WIDTH: f32 = 0.15;
+
+/// The diff highlight group a cell was drawn with, found from the UI elements and the highlight
+/// groups of its style
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+    Add,
+    Change,
+    Delete,
+    Text,
+}
+
+impl DiffKind {
+    fn from_group(group: &str) -> Option<Self> {
+        match group {
+            "DiffAdd" => Some(Self::Add),
+            "DiffChange" => Some(Self::Change),
+            "DiffDelete" => Some(Self::Delete),
+            "DiffText" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    /// The kind of a style, DiffText wins over the DiffChange of the rest of its line. The UI
+    /// elements keep their name when a colorscheme links them to another group.
+    pub fn of(style: &Style) -> Option<Self> {
+        let names = style.ui_elements.iter().chain(&style.highlight_groups);
+        let kinds = names.filter_map(|name| Self::from_group(name));
+        kinds.max_by_key(|kind| *kind == Self::Text)
+    }
+}
+
+/// What is drawn over the background of a diff line
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffAccent {
+    /// A bar on the left edge of the cell at `column`
+    Bar { column: u32, color: Color4f },
+    /// A gradient fading out over the `cells` of an intra-line change
+    Gradient { cells: Range<u32>, color: Color4f },
+}
+
+fn accent_color(style: &Style, default_colors: &Colors) -> Color4f {
+    let background = style.background(default_colors);
+    let foreground = style.foreground(default_colors);
+    let mix = |from: f32, to: f32| from + (to - from) * MIX;
+    Color4f::new(
+        mix(background.r, foreground.r),
+        mix(background.g, foreground.g),
+        mix(background.b, foreground.b),
+        1.0,
+    )
+}
+
+/// The accents of a line from the cells and styles of its fragments. The bar goes to the first
+/// cell of the line with a diff highlight, and every DiffText fragment gets a gradient.
+pub fn line_accents<'a>(
+    fragments: impl IntoIterator<Item = (&'a Range<u32>, &'a Option<Arc<Style>>)>,
+    default_colors: &Colors,
+) -> Vec<DiffAccent> {
+    let mut accents = Vec::new();
+    let mut has_bar = false;
+    for (cells, style) in fragments {
+        let Some(style) = style else {
+            continue;
+        };
+        let Some(kind) = DiffKind::of(style) else {
+            continue;
+        };
+        let color = accent_color(style, default_colors);
+        if !has_bar {
+            has_bar = true;
+            accents.push(DiffAccent::Bar { column: cells.start, color });
+        }
+        if kind == DiffKind::Text {
+            accents.push(DiffAccent::Gradient { cells: cells.clone(), color });
+        }
+    }
+    accents
+}
+
+/// Draws the accents of a line whose cells are `cell_width` wide and `line_height` high, with
+/// their opacity multiplied by `alpha`
+pub fn draw_accents(
+    canvas: &Canvas,
+    accents: &[DiffAccent],
+    cell_width: f32,
+    line_height: f32,
+    alpha: f32,
+) {
+    for accent in accents {
+        let mut paint = Paint::default();
+        paint.set_anti_alias(false);
+        match accent {
+            DiffAccent::Bar { column, color } => {
+                let left = *column as f32 * cell_width;
+                let width = (cell_width * BAR_WIDTH).max(1.0).round();
+                paint.set_color4f(Color4f { a: color.a * alpha, ..*color }, None);
+                canvas.draw_rect(Rect::from_xywh(left, 0.0, width, line_height), &paint);
+            }
+            DiffAccent::Gradient { cells, color } => {
+                let left = cells.start as f32 * cell_width;
+                let right = cells.end as f32 * cell_width;
+                let colors =
+                    [Color4f { a: GRADIENT_ALPHA * alpha, ..*color }, Color4f { a: 0.0, ..*color }];
+                paint.set_shader(gradient_shader::linear(
+                    ((left, 0.0), (right, 0.0)),
+                    &colors[..],
+                    None,
+                    TileMode::Clamp,
+                    None,
+                    None,
+                ));
+                canvas.draw_rect(Rect::from_ltrb(left, 0.0, right, line_height), &paint);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colors() -> Colors {
+        Colors::new(
+            Some(Color4f::new(1.0, 1.0, 1.0, 1.0)),
+            Some(Color4f::new(0.0, 0.0, 0.0, 1.0)),
+            None,
+        )
+    }
+
+    fn style(groups: &[&str]) -> Option<Arc<Style>> {
+        let mut style = Style::new(Colors::new(None, Some(Color4f::new(0.0, 0.5, 0.0, 1.0)), None));
+        style.highlight_groups = groups.iter().map(|group| group.to_string()).collect();
+        Some(Arc::new(style))
+    }
+
+    #[test]
+    fn diff_add_cells_get_the_accent_bar() {
+        let (plain, added) = (style(&["Comment"]), style(&["DiffAdd"]));
+        let fragments = [(0..2, None), (2..4, plain), (4..10, added.clone()), (10..12, added)];
+        let accents =
+            line_accents(fragments.iter().map(|(cells, style)| (cells, style)), &colors());
+        assert_eq!(
+            accents,
+            vec![DiffAccent::Bar { column: 4, color: Color4f::new(0.5, 0.75, 0.5, 1.0) }]
+        );
+    }
+
+    #[test]
+    fn diff_text_gets_a_gradient_and_other_lines_nothing() {
+        assert_eq!(
+            DiffKind::of(&style(&["DiffChange", "DiffText"]).unwrap()),
+            Some(DiffKind::Text)
+        );
+        let fragments =
+            [(0..3, style(&["DiffChange"])), (3..6, style(&["DiffChange", "DiffText"]))];
+        let accents =
+            line_accents(fragments.iter().map(|(cells, style)| (cells, style)), &colors());
+        assert!(matches!(accents[..], [
+            DiffAccent::Bar { column: 0, .. },
+            DiffAccent::Gradient { ref cells, .. },
+        ] if *cells == (3..6)));
+
+        let fragments = [(0..6, style(&["Comment"])), (6..8, None)];
+        assert!(
+            line_accents(fragments.iter().map(|(cells, style)| (cells, style)), &colors())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn diff_elements_linked_to_other_groups_still_get_accents() {
+        let mut linked =
+            Style::new(Colors::new(None, Some(Color4f::new(0.0, 0.5, 0.0, 1.0)), None));
+        linked.highlight_groups = vec!["GitSignsAddLn".to_string()];
+        assert_eq!(DiffKind::of(&linked), None);
+
+        linked.ui_elements = vec!["DiffAdd".to_string()];
+        assert_eq!(DiffKind::of(&linked), Some(DiffKind::Add));
+    }
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashSet, num::NonZeroUsize, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    rc::Rc,
+};
 
 use itertools::Itertools;
 use log::{debug, error, info, trace, warn};
@@ -9,9 +13,9 @@ use skia_safe::{
 };
 use swash::{
     Metrics,
-    shape::ShapeContext,
+    shape::{Direction, ShapeContext},
     text::{
-        Script,
+        BidiClass, Codepoint, JoiningType, Script,
         cluster::{CharCluster, Parser, Status, Token},
     },
 };
@@ -28,10 +32,139 @@ use crate::{
 struct ShapeKey {
     pub text: String,
     pub style: CoarseStyle,
+    pub ligatures: bool,
+    pub fonts: Option<Vec<FontDescription>>,
+    pub context: RunContext,
+}
+
+/// What the shaper needs to know about the cells around a word, since each word is shaped on its
+/// own
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct RunContext {
+    /// The cells are mirrored by 'rightleft'
+    pub right_left: bool,
+    /// The first cell joins the cell to its left, like the letters of an Arabic word that
+    /// changes highlight in the middle
+    pub joins_left: bool,
+    /// The last cell joins the cell to its right
+    pub joins_right: bool,
 }
 
+// Turning these off makes the fonts draw every character with its own glyph
+const LIGATURE_FEATURES: [&str; 2] = ["calt", "liga"];
+
 const FONT_CACHE_SIZE: usize = 8 * 1024 * 1024;
 
+// Makes the letters next to it take their connected forms, without a glyph of its own
+const ZERO_WIDTH_JOINER: &str = "\u{200D}";
+
+/// The direction of the first character with a strong direction, Hebrew and Arabic letters are
+/// right-to-left. Text without one, like numbers and punctuation, is left-to-right.
+fn text_direction(text: &str) -> Direction {
+    text.chars()
+        .find_map(|character| match character.bidi_class() {
+            BidiClass::L => Some(Direction::LeftToRight),
+            BidiClass::R | BidiClass::AL => Some(Direction::RightToLeft),
+            _ => None,
+        })
+        .unwrap_or(Direction::LeftToRight)
+}
+
+/// The script of the first character that belongs to one, the shaper needs it to pick the rules
+/// of complex scripts like Arabic
+fn text_script(text: &str) -> Script {
+    text.chars()
+        .map(|character| character.script())
+        .find(|script| !matches!(script, Script::Common | Script::Inherited | Script::Unknown))
+        .unwrap_or(Script::Latin)
+}
+
+/// Whether the grapheme cluster starts with a letter of a cursive script like Arabic that connects
+/// to the letter after it in the text. Right-joining letters like ا, د, ر and و don't, they only
+/// connect to the letter before them.
+pub fn joins_following(cluster: &str) -> bool {
+    cluster.chars().next().is_some_and(|character| {
+        matches!(character.joining_type(), JoiningType::D | JoiningType::C | JoiningType::L)
+    })
+}
+
+/// Whether the grapheme cluster starts with a letter that connects to the letter before it in the
+/// text
+pub fn joins_preceding(cluster: &str) -> bool {
+    cluster.chars().next().is_some_and(|character| {
+        matches!(character.joining_type(), JoiningType::D | JoiningType::C | JoiningType::R)
+    })
+}
+
+/// Whether the characters are an emoji sequence joined by zero width joiners, like the family
+/// emoji, which fonts draw as a single glyph
+fn is_zwj_sequence(characters: impl IntoIterator<Item = char>) -> bool {
+    let (mut joined, mut pictographic) = (false, false);
+    for character in characters {
+        joined |= character == '\u{200D}';
+        pictographic |= character.is_extended_pictographic();
+    }
+    joined && pictographic
+}
+
+fn cluster_chars(cluster: &CharCluster) -> impl Iterator<Item = char> + '_ {
+    cluster.chars().iter().map(|character| character.ch)
+}
+
+/// The grapheme clusters of the word with their cell, in the order they are shaped. A
+/// 'rightleft' window mirrors the cells, so the right-to-left runs are put back in their logical
+/// order. The clusters keep their cell, so they are still drawn where Neovim put them.
+/// Words that join the cells around them get a zero width joiner on that side, so their letters
+/// are shaped as if the whole run was shaped at once.
+fn shaping_clusters<'a>(word: &Word<'a>, context: RunContext) -> Vec<(usize, &'a str)> {
+    let mut clusters = word.grapheme_clusters().collect::<Vec<_>>();
+    if let (true, Some(&(cell, _))) = (context.joins_left, clusters.first()) {
+        clusters.insert(0, (cell, ZERO_WIDTH_JOINER));
+    }
+    if let (true, Some(&(cell, _))) = (context.joins_right, clusters.last()) {
+        clusters.push((cell, ZERO_WIDTH_JOINER));
+    }
+    if context.right_left && text_direction(word.text) == Direction::RightToLeft {
+        clusters.reverse();
+    }
+    clusters
+}
+
+/// The characters of the grapheme clusters, with their cell in the user data so that the glyphs
+/// can be positioned according to Neovim's grid rules. All the characters of a cell, like a
+/// letter and its combining marks, end up in the same cluster.
+fn cell_tokens<'a>(clusters: &'a [(usize, &'a str)]) -> impl Iterator<Item = Token> + 'a {
+    clusters.iter().flat_map(|&(cell_index, cluster)| {
+        cluster.char_indices().map(move |(offset, character)| Token {
+            ch: character,
+            offset: offset as u32,
+            len: character.len_utf8() as u8,
+            info: character.into(),
+            data: cell_index as u32,
+        })
+    })
+}
+
+/// The number of cells of the grapheme clusters of the word made of several characters, like a
+/// letter with combining marks, by their first cell
+fn combined_clusters(word: &Word) -> HashMap<usize, u32> {
+    let clusters = word.grapheme_clusters().collect::<Vec<_>>();
+    let ends = clusters.iter().skip(1).map(|(cell, _)| *cell).chain([word.cells().len()]);
+    clusters
+        .iter()
+        .zip(ends)
+        .filter(|((_, cluster), _)| cluster.chars().nth(1).is_some())
+        .map(|(&(cell, _), end)| (cell, (end - cell) as u32))
+        .collect()
+}
+
+/// Where a combined cluster `advance` pixels wide starts in cells `cells_width` pixels wide. The
+/// width of a letter with combining marks depends on the font that has them, so they are centered
+/// instead of starting at the left edge like the other glyphs.
+fn centered_offset(cells_width: f32, advance: f32) -> f32 {
+    (cells_width - advance) / 2.0
+}
+
 pub struct CachingShaper {
     options: FontOptions,
     font_loader: FontLoader,
@@ -39,6 +172,11 @@ pub struct CachingShaper {
     shape_context: ShapeContext,
     scale_factor: f32,
     linespace: f32,
+    text_antialiasing: TextAntialiasing,
+    text_hinting: TextHinting,
+    fallback_fonts: Vec<FontDescription>,
+    // The fonts of 'guifontwide', for the double width characters
+    wide_fonts: Vec<FontDescription>,
     font_info: Option<(Metrics, f32)>,
 }
 
@@ -64,6 +202,37 @@ fn filter_failed_fonts(mut options: FontOptions, font_keys: &HashSet<FontKey>) -
     options
 }
 
+/// The fonts tried for a cluster, in order: the guifontwide ones for double width characters,
+/// the ones of the highlight group, the guifont, the configured fallbacks and the default font. The
+/// loaded fonts and the system are only searched when none of them has the glyphs.
+fn font_fallback_keys(
+    wide_fonts: &[FontDescription],
+    highlight_fonts: Option<&[FontDescription]>,
+    options: &FontOptions,
+    fallback_fonts: &[FontDescription],
+    style: CoarseStyle,
+) -> Vec<FontKey> {
+    let key = |font_desc: Option<FontDescription>| FontKey {
+        font_desc,
+        hinting: options.hinting.clone(),
+        edging: options.edging.clone(),
+    };
+    // The fonts that aren't the guifont take the style of the text unless they set one
+    let styled = |font_desc: &FontDescription| {
+        key(Some(FontDescription {
+            family: font_desc.family.clone(),
+            style: font_desc.style.clone().or_else(|| style.name().map(str::to_string)),
+        }))
+    };
+
+    let mut keys = wide_fonts.iter().map(styled).collect::<Vec<_>>();
+    keys.extend(highlight_fonts.into_iter().flatten().map(styled));
+    keys.extend(options.font_list(style).into_iter().map(|font_desc| key(Some(font_desc))));
+    keys.extend(fallback_fonts.iter().map(styled));
+    keys.push(key(None));
+    keys.into_iter().unique().collect()
+}
+
 impl CachingShaper {
     pub fn new(scale_factor: f32) -> CachingShaper {
         let options = FontOptions::default();
@@ -75,6 +244,10 @@ impl CachingShaper {
             shape_context: ShapeContext::new(),
             scale_factor,
             linespace: 0.0,
+            text_antialiasing: TextAntialiasing::default(),
+            text_hinting: TextHinting::default(),
+            fallback_fonts: Vec::new(),
+            wide_fonts: Vec::new(),
             font_info: None,
         };
         shaper.reset_font_loader();
@@ -106,21 +279,22 @@ impl CachingShaper {
         self.reset_font_loader();
     }
 
-    pub fn update_font(&mut self, guifont_setting: &str) {
+    /// Returns whether the font was updated, the previous one is kept when the setting is invalid
+    pub fn update_font(&mut self, guifont_setting: &str) -> bool {
         debug!("Updating font: {guifont_setting}");
 
         let options = match FontOptions::parse(guifont_setting) {
             Ok(opt) => opt,
             Err(msg) => {
                 error_msg!("Failed to parse guifont: {}", msg);
-                return;
+                return false;
             }
         };
 
-        self.update_font_options(options);
+        self.update_font_options(options)
     }
 
-    pub fn update_font_options(&mut self, options: FontOptions) {
+    pub fn update_font_options(&mut self, options: FontOptions) -> bool {
         debug!("Updating font options: {options:?}");
 
         let keys = options
@@ -135,7 +309,7 @@ impl CachingShaper {
             .collect::<Vec<_>>();
 
         if keys.is_empty() {
-            return;
+            return false;
         }
 
         let failed_fonts =
@@ -148,7 +322,7 @@ impl CachingShaper {
                 options,
                 failed_fonts.iter().join(",\n"),
             );
-            return;
+            return false;
         }
 
         if !failed_fonts.is_empty() {
@@ -164,6 +338,7 @@ impl CachingShaper {
         debug!("Font updated to: {options:?}");
         self.options = options;
         self.reset_font_loader();
+        true
     }
 
     pub fn update_linespace(&mut self, linespace: f32) {
@@ -186,12 +361,64 @@ impl CachingShaper {
         }
     }
 
+    pub fn linespace(&self) -> f32 {
+        self.linespace
+    }
+
+    pub fn update_text_antialiasing(&mut self, text_antialiasing: TextAntialiasing) {
+        if text_antialiasing != self.text_antialiasing {
+            debug!("Updating text antialiasing: {text_antialiasing:?}");
+            self.text_antialiasing = text_antialiasing;
+            self.reset_font_loader();
+        }
+    }
+
+    pub fn update_text_hinting(&mut self, text_hinting: TextHinting) {
+        if text_hinting != self.text_hinting {
+            debug!("Updating text hinting: {text_hinting:?}");
+            self.text_hinting = text_hinting;
+            self.reset_font_loader();
+        }
+    }
+
+    /// Sets the fonts of 'guifontwide', an empty option draws double width characters with the
+    /// guifont again. An invalid value keeps the previous fonts.
+    pub fn update_font_wide(&mut self, guifontwide: &str) {
+        debug!("Updating wide font: {guifontwide}");
+
+        let wide_fonts = if guifontwide.trim().is_empty() {
+            Vec::new()
+        } else {
+            match FontOptions::parse(guifontwide) {
+                Ok(options) => options.normal,
+                Err(msg) => {
+                    error_msg!("Failed to parse guifontwide: {}", msg);
+                    return;
+                }
+            }
+        };
+        if wide_fonts != self.wide_fonts {
+            self.wide_fonts = wide_fonts;
+            self.blob_cache.clear();
+        }
+    }
+
+    pub fn update_fallback_fonts(&mut self, fallback_fonts: &[FontDescription]) {
+        if fallback_fonts != self.fallback_fonts {
+            debug!("Updating fallback fonts: {fallback_fonts:?}");
+            self.fallback_fonts = fallback_fonts.to_vec();
+            self.blob_cache.clear();
+        }
+    }
+
     fn reset_font_loader(&mut self) {
         tracy_zone!("reset_font_loader");
         self.font_info = None;
         let font_size = self.current_size();
 
-        self.font_loader = FontLoader::new(font_size);
+        self.font_loader = FontLoader::new(font_size)
+            .with_text_antialiasing(self.text_antialiasing)
+            .with_text_hinting(self.text_hinting);
         let (_, font_width) = self.info();
         info!("Reset Font Loader: font_size: {font_size:.2}px, font_width: {font_width:.2}px");
 
@@ -265,51 +492,37 @@ impl CachingShaper {
 
     fn build_clusters(
         &mut self,
-        word: Word<'_>,
+        clusters: &[(usize, &str)],
+        double_width_cells: &HashSet<usize>,
+        script: Script,
         style: CoarseStyle,
+        fonts: Option<&[FontDescription]>,
     ) -> Vec<(Vec<CharCluster>, Rc<FontPair>)> {
         let mut cluster = CharCluster::new();
 
-        // Enumerate the characters storing the glyph index in the user data so that we can position
-        // glyphs according to Neovim's grid rules
-        let mut parser = Parser::new(
-            Script::Latin,
-            word.grapheme_clusters().flat_map(|(cell_index, cluster)| {
-                cluster.char_indices().map(move |(offset, character)| Token {
-                    ch: character,
-                    offset: offset as u32,
-                    len: character.len_utf8() as u8,
-                    info: character.into(),
-                    data: cell_index as u32,
-                })
-            }),
-        );
+        let mut parser = Parser::new(script, cell_tokens(clusters));
+
+        let narrow_font_keys =
+            font_fallback_keys(&[], fonts, &self.options, &self.fallback_fonts, style);
+        let wide_font_keys =
+            font_fallback_keys(&self.wide_fonts, fonts, &self.options, &self.fallback_fonts, style);
 
         let mut results = Vec::new();
         'cluster: while parser.next(&mut cluster) {
-            // TODO: Don't redo this work for every cluster. Save it some how
-            // Create font fallback list
-            let mut font_fallback_keys = Vec::new();
-
-            // Add parsed fonts from guifont or config file
-            font_fallback_keys.extend(
-                self.options
-                    .font_list(style)
-                    .iter()
-                    .map(|font_desc| FontKey {
-                        font_desc: Some(font_desc.clone()),
-                        hinting: self.options.hinting.clone(),
-                        edging: self.options.edging.clone(),
-                    })
-                    .unique(),
-            );
+            let cell = cluster.chars()[0].data as usize;
+            let font_fallback_keys = match double_width_cells.contains(&cell) {
+                true => &wide_font_keys,
+                false => &narrow_font_keys,
+            };
 
-            // Add default font
-            font_fallback_keys.push(FontKey {
-                font_desc: None,
-                hinting: self.options.hinting.clone(),
-                edging: self.options.edging.clone(),
-            });
+            // A font that has all the emoji of a sequence might still draw them one by one, so
+            // look for one that joins them first
+            if is_zwj_sequence(cluster_chars(&cluster))
+                && let Some(font_pair) = self.zwj_sequence_font(&cluster, font_fallback_keys, style)
+            {
+                results.push((cluster.to_owned(), font_pair));
+                continue 'cluster;
+            }
 
             // Use the cluster.map function to select a viable font from the fallback list and loaded fonts
 
@@ -388,6 +601,35 @@ impl CachingShaper {
         grouped_results
     }
 
+    /// The first font that draws the emoji sequence of the cluster as a single glyph, if any
+    fn zwj_sequence_font(
+        &mut self,
+        cluster: &CharCluster,
+        font_fallback_keys: &[FontKey],
+        style: CoarseStyle,
+    ) -> Option<Rc<FontPair>> {
+        let mut candidates = font_fallback_keys
+            .iter()
+            .filter_map(|fallback_key| self.font_loader.get_or_load(fallback_key))
+            .collect::<Vec<_>>();
+        candidates.extend(self.font_loader.loaded_fonts());
+        candidates.extend(self.font_loader.load_font_for_character(style, cluster.chars()[0].ch));
+
+        candidates.into_iter().find(|font_pair| {
+            let mut cluster = cluster.to_owned();
+            let charmap = font_pair.swash_font.as_ref().charmap();
+            if cluster.map(|ch| charmap.map(ch)) != Status::Complete {
+                return false;
+            }
+
+            let mut shaper = self.shape_context.builder(font_pair.swash_font.as_ref()).build();
+            shaper.add_cluster(&cluster);
+            let mut glyph_count = 0;
+            shaper.shape_with(|glyph_cluster| glyph_count += glyph_cluster.glyphs.len());
+            glyph_count == 1
+        })
+    }
+
     pub fn cleanup_font_cache(&self) {
         // Only purge if we are truly about to exhaust the cache.
         // See: https://github.com/neovide/neovide/issues/3299
@@ -404,21 +646,70 @@ impl CachingShaper {
         }
     }
 
-    pub fn shape(&mut self, word: Word<'_>, style: CoarseStyle) -> Vec<TextBlob> {
-        let current_size = self.current_size();
-        let glyph_width = self.font_base_dimensions().width;
-
+    pub fn shape(
+        &mut self,
+        word: Word<'_>,
+        style: CoarseStyle,
+        ligatures: bool,
+        fonts: Option<&[FontDescription]>,
+        context: RunContext,
+    ) -> Vec<TextBlob> {
         let mut resulting_blobs = Vec::new();
+        for (font_pair, glyph_data) in self.shape_glyphs(word, style, ligatures, fonts, context) {
+            let mut blob_builder = TextBlobBuilder::new();
+            let (glyphs, positions) =
+                blob_builder.alloc_run_pos(&font_pair.skia_font, glyph_data.len(), None);
+            for (i, (glyph_id, glyph_position)) in glyph_data.iter().enumerate() {
+                glyphs[i] = *glyph_id;
+                positions[i] = (*glyph_position).into();
+            }
+
+            let blob = blob_builder.make();
+            resulting_blobs.push(blob.expect("Could not create textblob"));
+        }
 
-        for (cluster_group, font_pair) in self.build_clusters(word, style) {
-            let features = self.get_font_features(
+        resulting_blobs
+    }
+
+    /// The glyphs of the word with their position, by the font they are drawn with
+    fn shape_glyphs(
+        &mut self,
+        word: Word<'_>,
+        style: CoarseStyle,
+        ligatures: bool,
+        fonts: Option<&[FontDescription]>,
+        context: RunContext,
+    ) -> Vec<(Rc<FontPair>, Vec<(u16, (f32, f32))>)> {
+        let current_size = self.current_size();
+        let glyph_width = self.font_base_dimensions().width;
+        let direction = text_direction(word.text);
+        let script = text_script(word.text);
+        let clusters = shaping_clusters(&word, context);
+        let combined_clusters = combined_clusters(&word);
+        let double_width_cells = word.double_width_cells().collect::<HashSet<_>>();
+
+        let mut resulting_glyphs = Vec::new();
+
+        for (cluster_group, font_pair) in
+            self.build_clusters(&clusters, &double_width_cells, script, style, fonts)
+        {
+            let mut features = self.get_font_features(
                 font_pair.as_ref().key.font_desc.as_ref().map(|desc| desc.family.as_str()),
             );
+            // Emoji sequences are ligatures too, but splitting them doesn't help editing
+            let emoji_sequence =
+                cluster_group.iter().any(|cluster| is_zwj_sequence(cluster_chars(cluster)));
+            if !ligatures && !emoji_sequence {
+                features.retain(|(name, _)| !LIGATURE_FEATURES.contains(&name.as_str()));
+                features.extend(LIGATURE_FEATURES.map(|name| (name.to_string(), 0)));
+            }
 
             let mut shaper = self
                 .shape_context
                 .builder(font_pair.swash_font.as_ref())
                 .features(features.iter().map(|(name, value)| (name.as_ref(), *value)))
+                .script(script)
+                .direction(direction)
                 .size(current_size)
                 .build();
 
@@ -433,6 +724,12 @@ impl CachingShaper {
             shaper.shape_with(|glyph_cluster| {
                 //Align to the grid at the start of each cluster
                 let mut x_offset = glyph_width * glyph_cluster.data as f32;
+                if let Some(cells) = combined_clusters.get(&(glyph_cluster.data as usize))
+                    && glyph_cluster.components.is_empty()
+                {
+                    let advance = glyph_cluster.glyphs.iter().map(|glyph| glyph.advance).sum();
+                    x_offset += centered_offset(glyph_width * *cells as f32, advance);
+                }
 
                 for glyph in glyph_cluster.glyphs {
                     let position = (x_offset + glyph.x, -glyph.y);
@@ -441,33 +738,36 @@ impl CachingShaper {
                 }
             });
 
-            if glyph_data.is_empty() {
-                continue;
-            }
-
-            let mut blob_builder = TextBlobBuilder::new();
-            let (glyphs, positions) =
-                blob_builder.alloc_run_pos(&font_pair.skia_font, glyph_data.len(), None);
-            for (i, (glyph_id, glyph_position)) in glyph_data.iter().enumerate() {
-                glyphs[i] = *glyph_id;
-                positions[i] = (*glyph_position).into();
+            if !glyph_data.is_empty() {
+                resulting_glyphs.push((font_pair, glyph_data));
             }
-
-            let blob = blob_builder.make();
-            resulting_blobs.push(blob.expect("Could not create textblob"));
         }
 
-        resulting_blobs
+        resulting_glyphs
     }
 
     pub fn shape_cached(&mut self, word: Word<'_>, style: CoarseStyle) -> &Vec<TextBlob> {
+        self.shape_cached_with(word, style, true, None, RunContext::default())
+    }
+
+    /// Shapes the word with the ligatures and contextual alternates of the font turned on or off,
+    /// and with `fonts` tried before the guifont
+    pub fn shape_cached_with(
+        &mut self,
+        word: Word<'_>,
+        style: CoarseStyle,
+        ligatures: bool,
+        fonts: Option<&[FontDescription]>,
+        context: RunContext,
+    ) -> &Vec<TextBlob> {
         tracy_zone!("shape_cached");
         let text = word.text;
-        let key = ShapeKey::new(text.to_string(), style);
+        let key =
+            ShapeKey::new(text.to_string(), style, ligatures, fonts.map(<[_]>::to_vec), context);
 
         if !self.blob_cache.contains(&key) {
             trace!("Shaping text: {text:?}");
-            let blobs = self.shape(word, style);
+            let blobs = self.shape(word, style, ligatures, fonts, context);
             self.blob_cache.put(key.clone(), blobs);
         }
 
@@ -563,6 +863,168 @@ mod tests {
         }
     }
 
+    fn cells(clusters: &[(usize, &str)]) -> Vec<usize> {
+        clusters.iter().map(|(cell, _)| *cell).collect()
+    }
+
+    #[test]
+    fn arabic_runs_are_shaped_right_to_left() {
+        let text = "سلام";
+        let cluster_sizes = text.chars().map(|ch| ch.len_utf8() as u8).collect::<Vec<_>>();
+        let word = Word::new(text, &cluster_sizes);
+        assert_eq!(text_direction(text), Direction::RightToLeft);
+        assert_eq!(text_script(text), Script::Arabic);
+
+        // The cells of a 'rightleft' window are mirrored, so the logical order is reversed
+        assert_eq!(cells(&shaping_clusters(&word, RunContext::default())), [0, 1, 2, 3]);
+        let right_left = RunContext { right_left: true, ..Default::default() };
+        let clusters = shaping_clusters(&word, right_left);
+        assert_eq!(cells(&clusters), [3, 2, 1, 0]);
+        assert_eq!(clusters.iter().map(|(_, text)| *text).collect::<String>(), "مالس");
+    }
+
+    #[test]
+    fn latin_runs_stay_left_to_right() {
+        let text = "1. hello";
+        let word = Word::new(text, &[1; 8]);
+        assert_eq!(text_direction(text), Direction::LeftToRight);
+        assert_eq!(text_script(text), Script::Latin);
+        let right_left = RunContext { right_left: true, ..Default::default() };
+        assert_eq!(cells(&shaping_clusters(&word, right_left)), [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(text_direction("123"), Direction::LeftToRight);
+    }
+
+    #[test]
+    fn arabic_words_are_shaped_as_one_connected_run() {
+        // "بيت", its letters take their initial, medial and final forms when shaped together
+        let text = "بيت";
+        let word = Word::new(text, &[2, 2, 2]);
+        assert!(word.grapheme_clusters().all(|(_, cluster)| joins_following(cluster)));
+        assert_eq!(text_script(text), Script::Arabic);
+        let clusters = shaping_clusters(&word, RunContext::default());
+        assert_eq!(clusters.iter().map(|(_, text)| *text).collect::<String>(), text);
+        assert!(!joins_following("a") && !joins_preceding("a"));
+        assert!(!joins_following("1") && !joins_preceding("1"));
+        // Right-joining letters only connect to the letter before them
+        assert!(joins_preceding("د") && !joins_following("د"));
+    }
+
+    /// The glyph ids of the letters shaped on their own in the context
+    fn glyph_ids(shaper: &mut CachingShaper, text: &str, context: RunContext) -> HashSet<u16> {
+        let cluster_sizes = text.chars().map(|ch| ch.len_utf8() as u8).collect::<Vec<_>>();
+        let word = Word::new(text, &cluster_sizes);
+        shaper
+            .shape_glyphs(word, CoarseStyle::default(), true, None, context)
+            .into_iter()
+            .flat_map(|(_, glyphs)| glyphs.into_iter().map(|(id, _)| id))
+            .collect()
+    }
+
+    #[test]
+    #[ignore = "needs a system font with Arabic glyphs, which the bundled fonts don't have"]
+    fn split_words_are_shaped_like_the_whole_word() {
+        let mut shaper = CachingShaper::new(1.0);
+        let joined = |left: &str, right: &str| joins_following(left) && joins_preceding(right);
+        let isolated = glyph_ids(&mut shaper, "ب", RunContext::default());
+
+        // "بب" split between its letters, the first one takes its initial form like in the whole
+        // word
+        let whole = glyph_ids(&mut shaper, "بب", RunContext::default());
+        assert!(joined("ب", "ب"));
+        let start = glyph_ids(
+            &mut shaper,
+            "ب",
+            RunContext { joins_right: joined("ب", "ب"), ..Default::default() },
+        );
+        assert!(whole.iter().any(|glyph| start.contains(glyph) && !isolated.contains(glyph)));
+
+        // "دب" split between its letters, د doesn't connect to the letter after it, so ب keeps
+        // the form it has in the whole word
+        let whole = glyph_ids(&mut shaper, "دب", RunContext::default());
+        assert!(!joined("د", "ب"));
+        let end = glyph_ids(
+            &mut shaper,
+            "ب",
+            RunContext { joins_left: joined("د", "ب"), ..Default::default() },
+        );
+        assert!(end.is_subset(&whole));
+    }
+
+    #[test]
+    fn words_split_by_a_highlight_keep_their_connected_forms() {
+        // "بي" and "ت" of "بيت" are highlighted differently, so they are shaped separately
+        let start = Word::new("بي", &[2, 2]);
+        let context = RunContext { joins_right: true, ..Default::default() };
+        let clusters = shaping_clusters(&start, context);
+        assert_eq!(cells(&clusters), [0, 1, 1]);
+        assert_eq!(clusters.last().unwrap().1, ZERO_WIDTH_JOINER);
+
+        let end = Word::new("ت", &[2]);
+        let context = RunContext { joins_left: true, ..Default::default() };
+        let clusters = shaping_clusters(&end, context);
+        assert_eq!(cells(&clusters), [0, 0]);
+        assert_eq!(clusters[0].1, ZERO_WIDTH_JOINER);
+
+        // The joiner on the left of a mirrored word comes last in the logical order
+        let context = RunContext { right_left: true, joins_left: true, ..Default::default() };
+        let clusters = shaping_clusters(&end, context);
+        assert_eq!(clusters.last().unwrap().1, ZERO_WIDTH_JOINER);
+    }
+
+    #[test]
+    fn a_letter_with_a_combining_mark_is_one_cluster_in_its_cell() {
+        // "é" written as "e" and U+0301, followed by "x"
+        let word = Word::new("e\u{301}x", &[3, 1]);
+        let clusters = shaping_clusters(&word, RunContext::default());
+        assert_eq!(clusters, [(0, "e\u{301}"), (1, "x")]);
+
+        let mut parser = Parser::new(Script::Latin, cell_tokens(&clusters));
+        let mut cluster = CharCluster::new();
+        let mut parsed = vec![];
+        while parser.next(&mut cluster) {
+            parsed.push(cluster.chars().iter().map(|ch| (ch.ch, ch.data)).collect::<Vec<_>>());
+        }
+        assert_eq!(parsed, [vec![('e', 0), ('\u{301}', 0)], vec![('x', 1)]]);
+
+        assert_eq!(combined_clusters(&word), HashMap::from([(0, 1)]));
+        assert_eq!(centered_offset(10.0, 8.0), 1.0);
+        assert_eq!(centered_offset(10.0, 10.0), 0.0);
+    }
+
+    #[test]
+    fn combined_clusters_are_centered_in_all_their_cells() {
+        // A wide character with a variation selector, and a plain one
+        let word = Word::new("一\u{FE0F}二", &[6, 0, 3, 0]);
+        assert_eq!(combined_clusters(&word), HashMap::from([(0, 2)]));
+    }
+
+    #[test]
+    fn a_zwj_family_emoji_is_parsed_as_one_sequence() {
+        // The family emoji, a man, a woman and a girl joined by zero width joiners
+        let text = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert!(is_zwj_sequence(text.chars()));
+
+        // It takes the two cells Neovim gives it, and the parser keeps it in a single cluster, so
+        // a font that has the sequence shapes it as a single glyph
+        let cluster_sizes = [text.len() as u8, 0];
+        let word = Word::new(text, &cluster_sizes);
+        let clusters = shaping_clusters(&word, RunContext::default());
+        let mut parser = Parser::new(Script::Latin, cell_tokens(&clusters));
+        let mut cluster = CharCluster::new();
+        assert!(parser.next(&mut cluster));
+        assert_eq!(cluster_chars(&cluster).collect::<String>(), text);
+        assert!(is_zwj_sequence(cluster_chars(&cluster)));
+        assert!(!parser.next(&mut cluster));
+        assert_eq!(combined_clusters(&word), HashMap::from([(0, 2)]));
+    }
+
+    #[test]
+    fn joined_letters_are_not_emoji_sequences() {
+        assert!(!is_zwj_sequence("\u{628}\u{200D}".chars()));
+        assert!(!is_zwj_sequence("\u{1F468}".chars()));
+        assert!(is_zwj_sequence("\u{1F3F3}\u{FE0F}\u{200D}\u{1F308}".chars()));
+    }
+
     #[test]
     fn filters_failed_normal_fonts_and_preserves_order() {
         let options = FontOptions {
@@ -662,4 +1124,108 @@ mod tests {
             vec!["Courier New", "monospace"]
         );
     }
+
+    #[test]
+    fn configured_fallbacks_are_tried_after_the_guifont_and_before_the_default_font() {
+        let options = FontOptions::parse("Fira Code,Iosevka:h12").unwrap();
+        let fallback_fonts = [
+            FontDescription { family: "Symbols Nerd Font".to_string(), style: None },
+            FontDescription { family: "Noto Sans CJK JP".to_string(), style: None },
+        ];
+        let families = |style| {
+            font_fallback_keys(&[], None, &options, &fallback_fonts, style)
+                .into_iter()
+                .map(|key| key.font_desc.map(|font_desc| (font_desc.family, font_desc.style)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            families(CoarseStyle::default()),
+            [
+                Some(("Fira Code".to_string(), None)),
+                Some(("Iosevka".to_string(), None)),
+                Some(("Symbols Nerd Font".to_string(), None)),
+                Some(("Noto Sans CJK JP".to_string(), None)),
+                None,
+            ]
+        );
+        // The fallbacks follow the style of the text
+        let bold = CoarseStyle::permutations().find(|style| style.name() == Some("Bold")).unwrap();
+        let bold = families(bold);
+        assert_eq!(bold[2], Some(("Symbols Nerd Font".to_string(), Some("Bold".to_string()))));
+    }
+
+    /// The family name of the bundled Last Resort font, which has a glyph for every character
+    const LAST_RESORT_FAMILY: &str = "Last Resort";
+
+    /// The family of the font each run of the text is drawn with, `None` for the bundled font
+    fn resolved_families(shaper: &mut CachingShaper, text: &str) -> Vec<Option<String>> {
+        let cluster_sizes = text.chars().map(|ch| ch.len_utf8() as u8).collect::<Vec<_>>();
+        word_families(shaper, Word::new(text, &cluster_sizes))
+    }
+
+    fn word_families(shaper: &mut CachingShaper, word: Word<'_>) -> Vec<Option<String>> {
+        let text = word.text;
+        shaper
+            .shape_glyphs(word, CoarseStyle::default(), true, None, RunContext::default())
+            .into_iter()
+            .map(|(font_pair, glyphs)| {
+                assert!(glyphs.iter().all(|(id, _)| *id != 0), "{text} has a missing glyph");
+                font_pair.key.font_desc.as_ref().map(|font_desc| font_desc.family.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn glyphs_are_resolved_from_the_configured_fallbacks() {
+        let mut shaper = CachingShaper::new(1.0);
+        let missing = FontDescription { family: "Missing Fallback".to_string(), style: None };
+        // A fallback that isn't installed is skipped, the bundled font draws the text
+        shaper.update_fallback_fonts(std::slice::from_ref(&missing));
+        assert_eq!(resolved_families(&mut shaper, "a"), [None]);
+
+        // Last Resort is bundled, so the chain doesn't depend on the fonts of the system
+        shaper.update_fallback_fonts(&[
+            missing,
+            FontDescription { family: LAST_RESORT_FAMILY.to_string(), style: None },
+        ]);
+        assert_eq!(resolved_families(&mut shaper, "a"), [Some(LAST_RESORT_FAMILY.to_string())]);
+    }
+
+    #[test]
+    fn double_width_characters_try_the_wide_font_first() {
+        let options = FontOptions::parse("Fira Code").unwrap();
+        let wide_fonts = FontOptions::parse("Noto Sans Mono CJK JP").unwrap().normal;
+        let families = |wide_fonts| {
+            font_fallback_keys(wide_fonts, None, &options, &[], CoarseStyle::default())
+                .into_iter()
+                .map(|key| key.font_desc.map(|font_desc| font_desc.family))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            families(&wide_fonts),
+            [Some("Noto Sans Mono CJK JP".to_string()), Some("Fira Code".to_string()), None]
+        );
+        assert_eq!(families(&[]), [Some("Fira Code".to_string()), None]);
+
+        // Only the first cell of a double width character is shaped
+        let word = Word::new("a日b", &[1, 3, 0, 1]);
+        assert_eq!(word.double_width_cells().collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn double_width_cells_are_shaped_with_the_guifontwide_font() {
+        let mut shaper = CachingShaper::new(1.0);
+        let wide = LAST_RESORT_FAMILY.to_string();
+        shaper.update_font_wide(&wide);
+
+        // The middle "a" takes two cells, like a CJK character would, the others stay in the
+        // bundled font
+        let word = Word::new("aab", &[1, 1, 0, 1]);
+        assert_eq!(word_families(&mut shaper, word), [None, Some(wide), None]);
+
+        shaper.update_font_wide("");
+        let word = Word::new("aab", &[1, 1, 0, 1]);
+        assert_eq!(word_families(&mut shaper, word), [None]);
+    }
 }
@@ -7,12 +7,16 @@ use std::{
 
 use log::trace;
 use lru::LruCache;
-use skia_safe::{Data, Font, FontHinting as SkiaHinting, FontMgr, font::Edging as SkiaEdging};
+use skia_safe::{
+    Data, Font, FontHinting as SkiaHinting, FontMgr, Typeface, font::Edging as SkiaEdging,
+};
 
 use crate::{
     profiling::tracy_zone,
     renderer::fonts::{
-        font_options::{CoarseStyle, FontDescription, FontEdging, FontHinting},
+        font_options::{
+            CoarseStyle, FontDescription, FontEdging, FontHinting, TextAntialiasing, TextHinting,
+        },
         swash_font::SwashFont,
     },
 };
@@ -27,11 +31,16 @@ pub struct FontPair {
 }
 
 impl FontPair {
-    fn new(key: FontKey, mut skia_font: Font) -> Option<FontPair> {
+    fn new(
+        key: FontKey,
+        mut skia_font: Font,
+        text_antialiasing: TextAntialiasing,
+        text_hinting: TextHinting,
+    ) -> Option<FontPair> {
         skia_font.set_subpixel(true);
         skia_font.set_baseline_snap(true);
-        skia_font.set_hinting(font_hinting(&key.hinting));
-        skia_font.set_edging(font_edging(&key.edging));
+        skia_font.set_hinting(font_hinting(&key.hinting, text_hinting));
+        skia_font.set_edging(font_edging(&key.edging, text_antialiasing));
 
         let typeface = skia_font.typeface();
         let (font_data, index) = typeface.to_font_data()?;
@@ -64,6 +73,8 @@ pub struct FontLoader {
     cache: LruCache<FontKey, Rc<FontPair>>,
     failed_fonts: HashSet<FontKey>,
     font_size: f32,
+    text_antialiasing: TextAntialiasing,
+    text_hinting: TextHinting,
     last_resort: Option<Rc<FontPair>>,
 }
 
@@ -84,21 +95,46 @@ impl FontLoader {
             cache: LruCache::new(NonZeroUsize::new(20).unwrap()),
             failed_fonts: HashSet::new(),
             font_size,
+            text_antialiasing: TextAntialiasing::default(),
+            text_hinting: TextHinting::default(),
             last_resort: None,
         }
     }
 
+    pub fn with_text_antialiasing(mut self, text_antialiasing: TextAntialiasing) -> Self {
+        self.text_antialiasing = text_antialiasing;
+        self
+    }
+
+    pub fn with_text_hinting(mut self, text_hinting: TextHinting) -> Self {
+        self.text_hinting = text_hinting;
+        self
+    }
+
     fn load(&mut self, font_key: FontKey) -> Option<FontPair> {
         tracy_zone!("load_font");
         trace!("Loading font {font_key:?}");
         if let Some(desc) = &font_key.font_desc {
             let (family, style) = desc.as_family_and_font_style();
-            let typeface = self.font_mgr.match_family_style(family, style)?;
-            FontPair::new(font_key, Font::from_typeface(typeface, self.font_size))
+            let typeface = self
+                .font_mgr
+                .match_family_style(family, style)
+                .or_else(|| bundled_typeface(&self.font_mgr, family))?;
+            FontPair::new(
+                font_key,
+                Font::from_typeface(typeface, self.font_size),
+                self.text_antialiasing,
+                self.text_hinting,
+            )
         } else {
             let data = Data::new_copy(DEFAULT_FONT);
             let typeface = self.font_mgr.new_from_data(&data, 0)?;
-            FontPair::new(font_key, Font::from_typeface(typeface, self.font_size))
+            FontPair::new(
+                font_key,
+                Font::from_typeface(typeface, self.font_size),
+                self.text_antialiasing,
+                self.text_hinting,
+            )
         }
     }
 
@@ -147,6 +183,8 @@ impl FontLoader {
         let font_pair = Rc::new(FontPair::new(
             font_key.clone(),
             Font::from_typeface(typeface, self.font_size),
+            self.text_antialiasing,
+            self.text_hinting,
         )?);
 
         self.cache.put(font_key, font_pair.clone());
@@ -162,8 +200,12 @@ impl FontLoader {
             let data = Data::new_copy(LAST_RESORT_FONT);
 
             let typeface = self.font_mgr.new_from_data(&data, 0)?;
-            let font_pair =
-                Rc::new(FontPair::new(font_key, Font::from_typeface(typeface, self.font_size))?);
+            let font_pair = Rc::new(FontPair::new(
+                font_key,
+                Font::from_typeface(typeface, self.font_size),
+                self.text_antialiasing,
+                self.text_hinting,
+            )?);
 
             self.last_resort = Some(font_pair.clone());
             Some(font_pair)
@@ -183,20 +225,35 @@ impl FontLoader {
     }
 }
 
-fn font_hinting(hinting: &FontHinting) -> SkiaHinting {
-    match hinting {
-        FontHinting::Full => SkiaHinting::Full,
-        FontHinting::Slight => SkiaHinting::Slight,
-        FontHinting::Normal => SkiaHinting::Normal,
-        FontHinting::None => SkiaHinting::None,
+/// The bundled fonts can be picked by their family name too, even when they aren't installed
+fn bundled_typeface(font_mgr: &FontMgr, family: &str) -> Option<Typeface> {
+    [DEFAULT_FONT, LAST_RESORT_FONT]
+        .into_iter()
+        .filter_map(|font| font_mgr.new_from_data(&Data::new_copy(font), 0))
+        .find(|typeface| typeface.family_name() == family)
+}
+
+fn font_hinting(hinting: &FontHinting, text_hinting: TextHinting) -> SkiaHinting {
+    match (text_hinting, hinting) {
+        (TextHinting::None, _) => SkiaHinting::None,
+        (TextHinting::Slight, _) => SkiaHinting::Slight,
+        (TextHinting::Normal, _) => SkiaHinting::Normal,
+        (TextHinting::Full, _) => SkiaHinting::Full,
+        (TextHinting::Auto, FontHinting::None) => SkiaHinting::None,
+        (TextHinting::Auto, FontHinting::Slight) => SkiaHinting::Slight,
+        (TextHinting::Auto, FontHinting::Normal) => SkiaHinting::Normal,
+        (TextHinting::Auto, FontHinting::Full) => SkiaHinting::Full,
     }
 }
 
-fn font_edging(edging: &FontEdging) -> SkiaEdging {
-    match edging {
-        FontEdging::AntiAlias => SkiaEdging::AntiAlias,
-        FontEdging::Alias => SkiaEdging::Alias,
-        FontEdging::SubpixelAntiAlias => SkiaEdging::SubpixelAntiAlias,
+fn font_edging(edging: &FontEdging, text_antialiasing: TextAntialiasing) -> SkiaEdging {
+    match (text_antialiasing, edging) {
+        (TextAntialiasing::Subpixel, _) => SkiaEdging::SubpixelAntiAlias,
+        (TextAntialiasing::Grayscale, _) => SkiaEdging::AntiAlias,
+        (TextAntialiasing::None, _) => SkiaEdging::Alias,
+        (TextAntialiasing::Auto, FontEdging::AntiAlias) => SkiaEdging::AntiAlias,
+        (TextAntialiasing::Auto, FontEdging::Alias) => SkiaEdging::Alias,
+        (TextAntialiasing::Auto, FontEdging::SubpixelAntiAlias) => SkiaEdging::SubpixelAntiAlias,
     }
 }
 
@@ -222,4 +279,59 @@ mod tests {
         assert!(loader.get_or_load(&missing_font).is_none());
         assert_eq!(loader.failed_fonts.len(), 1);
     }
+
+    #[test]
+    fn bundled_fonts_are_loaded_by_family_name() {
+        let mut loader = FontLoader::new(14.0);
+        for family in ["FiraCode Nerd Font Mono", "Last Resort"] {
+            let font_key = FontKey {
+                font_desc: Some(FontDescription { family: family.to_string(), style: None }),
+                hinting: FontHinting::default(),
+                edging: FontEdging::default(),
+            };
+            let font_pair = loader.get_or_load(&font_key).expect("the bundled font is loaded");
+            assert_eq!(font_pair.skia_font.typeface().family_name(), family);
+        }
+    }
+
+    #[test]
+    fn text_antialiasing_overrides_the_guifont_edging() {
+        for edging in [FontEdging::AntiAlias, FontEdging::SubpixelAntiAlias, FontEdging::Alias] {
+            assert_eq!(
+                font_edging(&edging, TextAntialiasing::Subpixel),
+                SkiaEdging::SubpixelAntiAlias
+            );
+            assert_eq!(font_edging(&edging, TextAntialiasing::Grayscale), SkiaEdging::AntiAlias);
+            assert_eq!(font_edging(&edging, TextAntialiasing::None), SkiaEdging::Alias);
+        }
+
+        assert_eq!(
+            font_edging(&FontEdging::AntiAlias, TextAntialiasing::Auto),
+            SkiaEdging::AntiAlias
+        );
+        assert_eq!(font_edging(&FontEdging::Alias, TextAntialiasing::Auto), SkiaEdging::Alias);
+        assert_eq!(
+            font_edging(&FontEdging::SubpixelAntiAlias, TextAntialiasing::Auto),
+            SkiaEdging::SubpixelAntiAlias
+        );
+    }
+
+    #[test]
+    fn font_hinting_overrides_the_guifont_hinting() {
+        let levels = [
+            (TextHinting::None, SkiaHinting::None),
+            (TextHinting::Slight, SkiaHinting::Slight),
+            (TextHinting::Normal, SkiaHinting::Normal),
+            (TextHinting::Full, SkiaHinting::Full),
+        ];
+        for hinting in [FontHinting::None, FontHinting::Slight, FontHinting::Full] {
+            for (text_hinting, expected) in levels {
+                assert_eq!(font_hinting(&hinting, text_hinting), expected);
+            }
+        }
+
+        assert_eq!(font_hinting(&FontHinting::default(), TextHinting::Auto), SkiaHinting::Full);
+        assert_eq!(font_hinting(&FontHinting::Slight, TextHinting::Auto), SkiaHinting::Slight);
+        assert_eq!(font_hinting(&FontHinting::None, TextHinting::Auto), SkiaHinting::None);
+    }
 }
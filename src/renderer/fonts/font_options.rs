@@ -309,6 +309,84 @@ impl FontEdging {
     }
 }
 
+/// Overrides the edging of the guifont for all the text, set through `g:neovide_text_antialiasing`
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Default)]
+pub enum TextAntialiasing {
+    /// Uses the `#e-` option of the guifont
+    #[default]
+    Auto,
+    Subpixel,
+    Grayscale,
+    None,
+}
+
+impl ParseFromValue for TextAntialiasing {
+    fn parse_from_value(&mut self, value: Value) {
+        match value.as_str() {
+            Some("auto") => *self = Self::Auto,
+            Some("subpixel") => *self = Self::Subpixel,
+            Some("grayscale") => *self = Self::Grayscale,
+            Some("none") => *self = Self::None,
+            _ => {
+                error_msg!(
+                    "Setting text_antialiasing expected one of `auto`, `subpixel`, `grayscale`, `none`, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<TextAntialiasing> for Value {
+    fn from(value: TextAntialiasing) -> Self {
+        Value::from(match value {
+            TextAntialiasing::Auto => "auto",
+            TextAntialiasing::Subpixel => "subpixel",
+            TextAntialiasing::Grayscale => "grayscale",
+            TextAntialiasing::None => "none",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TextHinting {
+    /// Uses the `#h-` option of the guifont
+    #[default]
+    Auto,
+    None,
+    Slight,
+    Normal,
+    Full,
+}
+
+impl ParseFromValue for TextHinting {
+    fn parse_from_value(&mut self, value: Value) {
+        match value.as_str() {
+            Some("auto") => *self = Self::Auto,
+            Some("none") => *self = Self::None,
+            Some("slight") => *self = Self::Slight,
+            Some("normal") => *self = Self::Normal,
+            Some("full") => *self = Self::Full,
+            _ => {
+                error_msg!(
+                    "Setting font_hinting expected one of `auto`, `none`, `slight`, `normal`, `full`, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<TextHinting> for Value {
+    fn from(value: TextHinting) -> Self {
+        Value::from(match value {
+            TextHinting::Auto => "auto",
+            TextHinting::None => "none",
+            TextHinting::Slight => "slight",
+            TextHinting::Normal => "normal",
+            TextHinting::Full => "full",
+        })
+    }
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Default)]
 pub enum FontHinting {
     #[default]
@@ -331,6 +409,112 @@ impl FontHinting {
     }
 }
 
+/// A guifont for the text of a highlight group, and the fonts parsed from it
+#[derive(Clone, Debug, PartialEq)]
+pub struct HighlightFont {
+    guifont: String,
+    fonts: Vec<FontDescription>,
+}
+
+/// Fonts for the text of some highlight groups, set through `g:neovide_highlight_fonts`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HighlightFonts(HashMap<String /* group */, HighlightFont>);
+
+impl HighlightFonts {
+    /// Returns the fonts of the last group with an override, Neovim puts the groups that were
+    /// combined on top at the end
+    pub fn fonts_for(&self, groups: &[String]) -> Option<&[FontDescription]> {
+        groups.iter().rev().find_map(|group| self.0.get(group)).map(|font| font.fonts.as_slice())
+    }
+}
+
+impl ParseFromValue for HighlightFonts {
+    fn parse_from_value(&mut self, value: Value) {
+        let Some(entries) = value.as_map() else {
+            error_msg!(
+                "Setting highlight_fonts expected a table of highlight groups to guifonts, but received {value:?}"
+            );
+            return;
+        };
+
+        let mut parsed = HashMap::new();
+        for (group, guifont) in entries {
+            let (Some(group), Some(guifont)) = (group.as_str(), guifont.as_str()) else {
+                error_msg!(
+                    "Setting highlight_fonts expected a guifont string for each group, but received {group:?} = {guifont:?}"
+                );
+                return;
+            };
+            match FontOptions::parse(guifont) {
+                Ok(options) if !options.normal.is_empty() => {
+                    let font = HighlightFont { guifont: guifont.to_owned(), fonts: options.normal };
+                    parsed.insert(group.to_owned(), font);
+                }
+                _ => {
+                    error_msg!(
+                        "Setting highlight_fonts has an invalid guifont for {group}: {guifont}"
+                    );
+                    return;
+                }
+            }
+        }
+        self.0 = parsed;
+    }
+}
+
+impl From<HighlightFonts> for Value {
+    fn from(value: HighlightFonts) -> Self {
+        Value::Map(
+            value
+                .0
+                .into_iter()
+                .map(|(group, font)| (Value::from(group), Value::from(font.guifont)))
+                .collect(),
+        )
+    }
+}
+
+/// Fonts tried for the glyphs missing from the guifont, before the default font and the ones the
+/// system picks. Set through `g:neovide_fallback_fonts`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FallbackFonts(Vec<FontDescription>);
+
+impl FallbackFonts {
+    pub fn fonts(&self) -> &[FontDescription] {
+        &self.0
+    }
+}
+
+impl ParseFromValue for FallbackFonts {
+    fn parse_from_value(&mut self, value: Value) {
+        let families = value.as_array().and_then(|families| {
+            families
+                .iter()
+                .map(|family| family.as_str().map(str::trim).filter(|family| !family.is_empty()))
+                .collect::<Option<Vec<_>>>()
+        });
+        match families {
+            Some(families) => {
+                self.0 = families
+                    .into_iter()
+                    .map(|family| FontDescription { family: family.to_owned(), style: None })
+                    .collect();
+            }
+            None => {
+                error_msg!(
+                    "Setting fallback_fonts expected a list of font families, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<FallbackFonts> for Value {
+    fn from(value: FallbackFonts) -> Self {
+        Value::Array(value.0.into_iter().map(|font| Value::from(font.family)).collect())
+    }
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Default)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum PixelGeometry {
@@ -715,4 +899,38 @@ mod tests {
         assert_eq!(style.weight(), Weight::from(100));
         assert_eq!(style.slant(), Slant::Upright);
     }
+
+    #[test]
+    fn highlight_fonts_are_picked_from_the_last_matching_group() {
+        let mut highlight_fonts = HighlightFonts::default();
+        highlight_fonts.parse_from_value(Value::Map(vec![
+            (Value::from("Comment"), Value::from("Iosevka Etoile:i")),
+            (Value::from("String"), Value::from("Fira Code")),
+        ]));
+
+        let groups = ["Comment".to_string(), "CursorLine".to_string()];
+        assert_eq!(
+            highlight_fonts.fonts_for(&groups),
+            Some(
+                &[FontDescription {
+                    family: "Iosevka Etoile".to_string(),
+                    style: Some("Italic".to_string())
+                }][..]
+            )
+        );
+        let groups = ["Comment".to_string(), "String".to_string()];
+        assert_eq!(highlight_fonts.fonts_for(&groups).unwrap()[0].family, "Fira Code");
+        assert_eq!(highlight_fonts.fonts_for(&["Normal".to_string()]), None);
+    }
+
+    #[test]
+    fn fallback_fonts_are_parsed_in_order() {
+        let mut fallback_fonts = FallbackFonts::default();
+        fallback_fonts.parse_from_value(Value::Array(vec![
+            Value::from("Symbols Nerd Font"),
+            Value::from(" Noto Sans CJK JP "),
+        ]));
+        let families = fallback_fonts.fonts().iter().map(|font| font.family.as_str());
+        assert_eq!(families.collect::<Vec<_>>(), ["Symbols Nerd Font", "Noto Sans CJK JP"]);
+    }
 }
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use skia_safe::{Canvas, Color4f, Paint};
+
+use crate::{
+    renderer::rendered_window::RenderedWindow,
+    units::{GridPos, GridRect, GridScale, GridSize, PixelRect, to_skia_rect},
+};
+
+const GHOST_OPACITY: f32 = 0.3;
+
+/// The cursor positions the splits had when the cursor left them, drawn as dim cursors. Set
+/// through `g:neovide_ghost_cursors`.
+#[derive(Default)]
+pub struct GhostCursors {
+    // The row in the text area and the column, by grid
+    positions: HashMap<u64, GridPos<u64>>,
+}
+
+impl GhostCursors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the position for the grid, or forgets it when the cursor is back in the window
+    pub fn set(&mut self, grid_id: u64, position: Option<GridPos<u64>>) {
+        match position {
+            Some(position) => self.positions.insert(grid_id, position),
+            None => self.positions.remove(&grid_id),
+        };
+    }
+
+    pub fn get(&self, grid_id: u64) -> Option<GridPos<u64>> {
+        self.positions.get(&grid_id).copied()
+    }
+
+    pub fn window_closed(&mut self, grid_id: u64) {
+        self.positions.remove(&grid_id);
+    }
+
+    /// Returns where the ghosts are drawn, following the windows as they move and scroll. The
+    /// window of the real cursor has none.
+    pub fn rects(
+        &self,
+        windows: &HashMap<u64, RenderedWindow>,
+        cursor_grid_id: u64,
+        grid_scale: GridScale,
+    ) -> Vec<PixelRect<f32>> {
+        self.positions
+            .iter()
+            .filter(|(grid_id, _)| **grid_id != cursor_grid_id)
+            .filter_map(|(grid_id, position)| {
+                let window = windows.get(grid_id).filter(|window| !window.hidden)?;
+                let mut origin =
+                    position.try_cast::<f32>()? + window.grid_current_position.to_vector();
+                origin.y += window.viewport_margins.top as f32 - window.scroll_animation.position
+                    + window.content_offset();
+                Some(GridRect::from_origin_and_size(origin, GridSize::new(1.0, 1.0)) * grid_scale)
+            })
+            .collect()
+    }
+}
+
+pub fn draw(canvas: &Canvas, rects: &[PixelRect<f32>], color: Color4f) {
+    let mut paint = Paint::new(Color4f { a: color.a * GHOST_OPACITY, ..color }, None);
+    paint.set_anti_alias(false);
+    for rect in rects {
+        canvas.draw_rect(to_skia_rect(rect), &paint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::PixelSize;
+
+    #[test]
+    fn positions_are_kept_per_window_until_cleared_or_closed() {
+        let mut ghosts = GhostCursors::new();
+        ghosts.set(2, Some(GridPos::new(4, 1)));
+        ghosts.set(3, Some(GridPos::new(0, 7)));
+        ghosts.set(2, Some(GridPos::new(5, 2)));
+        assert_eq!(ghosts.get(2), Some(GridPos::new(5, 2)));
+        assert_eq!(ghosts.get(3), Some(GridPos::new(0, 7)));
+        assert_eq!(ghosts.get(4), None);
+
+        // The cursor entered the window
+        ghosts.set(2, None);
+        assert_eq!(ghosts.get(2), None);
+
+        ghosts.window_closed(3);
+        assert_eq!(ghosts.get(3), None);
+    }
+
+    #[test]
+    fn ghosts_follow_their_window_and_skip_the_cursor_window() {
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 20.0));
+        let mut windows = HashMap::new();
+        for (grid_id, column) in [(2, 0.0), (3, 40.0)] {
+            let mut window = RenderedWindow::new(grid_id);
+            window.grid_current_position = GridPos::new(column, 1.0);
+            windows.insert(grid_id, window);
+        }
+
+        let mut ghosts = GhostCursors::new();
+        ghosts.set(2, Some(GridPos::new(4, 1)));
+        ghosts.set(3, Some(GridPos::new(2, 3)));
+        // Gone windows are skipped too
+        ghosts.set(5, Some(GridPos::new(0, 0)));
+
+        assert_eq!(
+            ghosts.rects(&windows, 2, grid_scale),
+            [PixelRect::new((420.0, 80.0).into(), (430.0, 100.0).into())]
+        );
+    }
+}
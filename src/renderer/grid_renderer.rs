@@ -1,16 +1,25 @@
 use std::{ops::Range, sync::Arc};
 
-use log::trace;
+use log::{trace, warn};
 use skia_safe::{
-    BlendMode, Canvas, Color, Color4f, HSV, Paint, PathBuilder, colors, dash_path_effect,
+    BlendMode, Canvas, Color, Color4f, FontMgr, FontStyle, HSV, Paint, PathBuilder, colors,
+    dash_path_effect,
+    textlayout::{
+        FontCollection, ParagraphBuilder, ParagraphStyle, TextDecoration, TextHeightBehavior,
+        TextStyle,
+    },
 };
 
 use crate::{
-    editor::{Colors, LineFragment, Style, UnderlineStyle},
+    editor::{Colors, LineFragment, Style, UnderlineStyle, Word},
     profiling::tracy_zone,
     renderer::{
         CachingShaper, RendererSettings,
         box_drawing::{self},
+        diff_accents,
+        fonts::caching_shaper::{RunContext, joins_following, joins_preceding},
+        list_markers::{self, ListMarkers},
+        text_cells::AmbiWidth,
     },
     settings::*,
     units::{
@@ -19,7 +28,139 @@ use crate::{
     window::WindowSettings,
 };
 
-use super::{box_drawing::BoxDrawingSettings, fonts::font_options::FontOptions};
+use super::{
+    box_drawing::BoxDrawingSettings,
+    fonts::font_options::{
+        FallbackFonts, FontOptions, PixelGeometry, TextAntialiasing, TextHinting,
+    },
+};
+
+/// Subpixel antialiasing only looks right when Skia knows the subpixel layout of the monitor, and
+/// it can't be blended over a transparent background
+fn subpixel_antialiasing_warning(
+    text_antialiasing: TextAntialiasing,
+    pixel_geometry: &PixelGeometry,
+    opacity: f32,
+) -> Option<&'static str> {
+    if text_antialiasing != TextAntialiasing::Subpixel {
+        None
+    } else if *pixel_geometry == PixelGeometry::Unknown {
+        Some("Subpixel text antialiasing needs g:neovide_pixel_geometry to be set")
+    } else if opacity < 1.0 {
+        Some("Subpixel text antialiasing can show color fringes with a transparent background")
+    } else {
+        None
+    }
+}
+
+/// Translucent glyphs can't use subpixel antialiasing, it needs the final background color
+fn text_antialiasing_for_opacity(
+    text_antialiasing: TextAntialiasing,
+    text_opacity: f32,
+) -> TextAntialiasing {
+    match text_antialiasing {
+        TextAntialiasing::Subpixel if text_opacity < 1.0 => TextAntialiasing::Grayscale,
+        text_antialiasing => text_antialiasing,
+    }
+}
+
+/// The multipliers of `neovide_text_opacity` and `neovide_background_opacity`, applied to the
+/// alpha of the glyphs and of the cell backgrounds respectively
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComponentOpacity {
+    text: f32,
+    background: f32,
+}
+
+impl ComponentOpacity {
+    pub fn from_settings(settings: &WindowSettings) -> Self {
+        Self {
+            text: settings.text_opacity.clamp(0.0, 1.0),
+            background: settings.background_opacity.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn text_color(self, color: Color4f) -> Color4f {
+        Color4f { a: color.a * self.text, ..color }
+    }
+
+    pub fn background_alpha(self, alpha: f32) -> f32 {
+        alpha * self.background
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DecorationKind {
+    Underline(UnderlineStyle),
+    Strikethrough,
+}
+
+/// A line under or through a run of cells
+#[derive(Debug, Clone, PartialEq)]
+struct Decoration {
+    kind: DecorationKind,
+    color: Color4f,
+    cells: Range<u32>,
+}
+
+/// The decorations of the fragments of a line, with adjacent fragments that have the same one
+/// sharing it. The fragments of a ligature can have different highlights, and a line drawn per
+/// fragment would restart the curls and dashes at each of them.
+fn decoration_runs<'a>(
+    fragments: impl IntoIterator<Item = (Range<u32>, &'a Style)>,
+    default_colors: &Colors,
+) -> Vec<Decoration> {
+    let mut decorations: Vec<Decoration> = Vec::new();
+    for (cells, style) in fragments {
+        let color = style.special(default_colors);
+        let kinds = [
+            style.underline.map(DecorationKind::Underline),
+            style.strikethrough.then_some(DecorationKind::Strikethrough),
+        ];
+        for kind in kinds.into_iter().flatten() {
+            let continued = decorations.iter_mut().rev().find(|decoration| {
+                decoration.kind == kind
+                    && decoration.color == color
+                    && decoration.cells.end == cells.start
+            });
+            match continued {
+                Some(decoration) => decoration.cells.end = cells.end,
+                None => decorations.push(Decoration { kind, color, cells: cells.clone() }),
+            }
+        }
+    }
+    decorations
+}
+
+/// Returns true when the cursor column is on one of the cells of the word, so that a ligature
+/// spanning the cursor gets split whichever of its cells the cursor is on
+fn splits_ligatures(word: &Word, fragment_start: u32, cursor_column: Option<u32>) -> bool {
+    cursor_column
+        .and_then(|column| column.checked_sub(fragment_start))
+        .is_some_and(|column| word.cells().contains(&column))
+}
+
+/// Whether the letters at the end of `left` connect to the ones at the start of `right`. The
+/// fragments are next to each other but have different highlights, so they are shaped separately.
+/// The cells of a 'rightleft' window are mirrored, so the text goes from `right` to `left` there.
+pub fn fragments_join(left: &LineFragment, right: &LineFragment, right_left: bool) -> bool {
+    let left_width = left.cells.len() as u32;
+    let last_cluster = left
+        .words()
+        .last()
+        .filter(|word| word.cells().end == left_width)
+        .and_then(|word| word.grapheme_clusters().last());
+    let first_cluster = right
+        .words()
+        .next()
+        .filter(|word| word.cell == 0)
+        .and_then(|word| word.grapheme_clusters().next());
+    let (Some((_, last)), Some((_, first))) = (last_cluster, first_cluster) else {
+        return false;
+    };
+    let (preceding, following) = if right_left { (first, last) } else { (last, first) };
+    joins_following(preceding) && joins_preceding(following)
+}
 
 pub struct GridRenderer {
     pub shaper: CachingShaper,
@@ -28,7 +169,11 @@ pub struct GridRenderer {
     pub grid_scale: GridScale,
     pub box_char_renderer: box_drawing::Renderer,
     pub is_ready: bool,
+    // For text that Neovim didn't put on the grid
+    pub ambiwidth: AmbiWidth,
 
+    // The system fonts, for text that isn't aligned to the grid
+    proportional_fonts: FontCollection,
     settings: Arc<Settings>,
 }
 
@@ -43,6 +188,13 @@ pub struct BackgroundInfo {
 impl GridRenderer {
     pub fn new(scale_factor: f64, settings: Arc<Settings>) -> Self {
         let mut shaper = CachingShaper::new(scale_factor as f32);
+        let renderer_settings = settings.get::<RendererSettings>();
+        shaper.update_text_antialiasing(text_antialiasing_for_opacity(
+            renderer_settings.text_antialiasing,
+            settings.get::<WindowSettings>().text_opacity,
+        ));
+        shaper.update_text_hinting(renderer_settings.font_hinting);
+        shaper.update_fallback_fonts(renderer_settings.fallback_fonts.fonts());
         let default_style = Arc::new(Style::new(Colors::new(
             Some(colors::WHITE),
             Some(colors::BLACK),
@@ -52,6 +204,8 @@ impl GridRenderer {
         let font_dimensions = shaper.font_base_dimensions();
         let grid_scale = GridScale::new(font_dimensions);
         let cell_size = GridSize::new(1, 1) * grid_scale;
+        let mut proportional_fonts = FontCollection::new();
+        proportional_fonts.set_default_font_manager(Some(FontMgr::new()), None);
 
         GridRenderer {
             shaper,
@@ -64,7 +218,9 @@ impl GridRenderer {
                 BoxDrawingSettings::default(),
             ),
             is_ready: false,
+            ambiwidth: AmbiWidth::default(),
 
+            proportional_fonts,
             settings,
         }
     }
@@ -78,9 +234,12 @@ impl GridRenderer {
         self.update_font_dimensions();
     }
 
-    pub fn update_font(&mut self, guifont_setting: &str) {
-        self.shaper.update_font(guifont_setting);
+    /// Rebuilds the fonts, their fallbacks and the cell size. Returns whether the font changed, an
+    /// invalid `guifont` keeps the previous one.
+    pub fn update_font(&mut self, guifont_setting: &str) -> bool {
+        let updated = self.shaper.update_font(guifont_setting);
         self.update_font_dimensions();
+        updated
     }
 
     pub fn update_font_options(&mut self, options: FontOptions) {
@@ -88,6 +247,35 @@ impl GridRenderer {
         self.update_font_dimensions();
     }
 
+    pub fn update_text_antialiasing(&mut self, text_antialiasing: TextAntialiasing) {
+        let pixel_geometry = self.settings.get::<RendererSettings>().pixel_geometry;
+        let WindowSettings { opacity, text_opacity, .. } = self.settings.get::<WindowSettings>();
+        if let Some(warning) =
+            subpixel_antialiasing_warning(text_antialiasing, &pixel_geometry, opacity)
+        {
+            warn!("{warning}");
+        }
+        self.shaper.update_text_antialiasing(text_antialiasing_for_opacity(
+            text_antialiasing,
+            text_opacity,
+        ));
+        self.update_font_dimensions();
+    }
+
+    /// Applies the antialiasing again, after the text opacity changed
+    pub fn refresh_text_antialiasing(&mut self) {
+        self.update_text_antialiasing(self.settings.get::<RendererSettings>().text_antialiasing);
+    }
+
+    pub fn update_font_hinting(&mut self, text_hinting: TextHinting) {
+        self.shaper.update_text_hinting(text_hinting);
+        self.update_font_dimensions();
+    }
+
+    pub fn update_fallback_fonts(&mut self, fallback_fonts: &FallbackFonts) {
+        self.shaper.update_fallback_fonts(fallback_fonts.fonts());
+    }
+
     pub fn update_linespace(&mut self, linespace_setting: f32) {
         self.shaper.update_linespace(linespace_setting);
         self.update_font_dimensions();
@@ -133,7 +321,8 @@ impl GridRenderer {
         paint.set_color(style_background);
 
         let is_default_background = style_background == self.get_default_background_color();
-        let normal_opacity = self.settings.get::<WindowSettings>().normal_opacity;
+        let window_settings = self.settings.get::<WindowSettings>();
+        let normal_opacity = window_settings.normal_opacity;
 
         let alpha = if normal_opacity < 1.0 && is_default_background {
             normal_opacity
@@ -143,7 +332,8 @@ impl GridRenderer {
             opacity
         };
 
-        paint.set_alpha_f(alpha);
+        paint
+            .set_alpha_f(ComponentOpacity::from_settings(&window_settings).background_alpha(alpha));
         paint.color4f()
     }
 
@@ -160,7 +350,9 @@ impl GridRenderer {
         if style.is_none() && !debug {
             return BackgroundInfo {
                 custom_color: false,
-                transparent: self.default_style.blend > 0 || opacity < 1.0,
+                transparent: self.default_style.blend > 0
+                    || opacity < 1.0
+                    || self.settings.get::<WindowSettings>().background_opacity < 1.0,
             };
         }
         let region = self.compute_text_region(cells);
@@ -180,7 +372,8 @@ impl GridRenderer {
         }
 
         let is_default_background = style_background == self.get_default_background_color();
-        let normal_opacity = self.settings.get::<WindowSettings>().normal_opacity;
+        let window_settings = self.settings.get::<WindowSettings>();
+        let normal_opacity = window_settings.normal_opacity;
 
         let alpha = if normal_opacity < 1.0 && is_default_background {
             normal_opacity
@@ -189,6 +382,7 @@ impl GridRenderer {
         } else {
             opacity
         };
+        let alpha = ComponentOpacity::from_settings(&window_settings).background_alpha(alpha);
         paint.set_alpha_f(alpha);
 
         let custom_color = paint.color4f() != self.default_style.colors.background.unwrap();
@@ -199,14 +393,46 @@ impl GridRenderer {
         BackgroundInfo { custom_color, transparent: alpha < 1.0 }
     }
 
-    /// Draws some foreground text.
+    /// Draws the accents of the diff highlights of a line over its background, returns true when
+    /// any was drawn
+    pub fn draw_diff_accents<'a>(
+        &self,
+        canvas: &Canvas,
+        fragments: impl IntoIterator<Item = LineFragment<'a>>,
+        opacity: f32,
+    ) -> bool {
+        if !self.settings.get::<RendererSettings>().diff_accent {
+            return false;
+        }
+        let accents = diff_accents::line_accents(
+            fragments.into_iter().map(|fragment| (fragment.cells, fragment.style)),
+            &self.default_style.colors,
+        );
+        let alpha = ComponentOpacity::from_settings(&self.settings.get::<WindowSettings>())
+            .background_alpha(opacity);
+        diff_accents::draw_accents(
+            canvas,
+            &accents,
+            self.grid_scale.width(),
+            self.grid_scale.height(),
+            alpha,
+        );
+        !accents.is_empty()
+    }
+
+    /// Draws some foreground text, `context` describes the cells around the fragment, and
+    /// `listchars` the markers of its window.
     /// Returns true if any text was actually drawn.
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_foreground(
         &mut self,
         text_canvas: &Canvas,
         boxchar_canvas: &Canvas,
         fragment: &LineFragment,
         window_position: PixelPos<f32>,
+        cursor_column: Option<u32>,
+        context: RunContext,
+        listchars: &ListMarkers,
     ) -> (bool, bool) {
         tracy_zone!("draw_foreground");
 
@@ -216,29 +442,31 @@ impl GridRenderer {
 
         let style = style.as_ref().unwrap_or(&self.default_style);
         let mut text_drawn = false;
-
-        if let Some(underline_style) = style.underline {
-            let stroke_size = self.shaper.stroke_size();
-            // Measure the underline offset from the baseline position snapped to a whole pixel
-            let baseline_position = self.shaper.baseline_offset().round();
-            // The underline should be at least 1 pixel below the baseline
-            let underline_position =
-                baseline_position - self.shaper.underline_offset().min(-1.).round();
-            let p1 = PixelPos::new(region.min.x, underline_position);
-            let p2 = PixelPos::new(region.max.x, underline_position);
-
-            self.draw_underline(text_canvas, style, underline_style, stroke_size, p1, p2);
-            text_drawn = true;
-        }
-
-        if self.box_char_renderer.draw_glyph(
+        let component_opacity =
+            ComponentOpacity::from_settings(&self.settings.get::<WindowSettings>());
+        let foreground = component_opacity.text_color(style.foreground(&self.default_style.colors));
+
+        let renderer_settings = self.settings.get::<RendererSettings>();
+        let markers =
+            if renderer_settings.crisp_listchars { listchars.markers_of(text) } else { None };
+
+        if let Some(markers) = markers {
+            list_markers::draw_markers(
+                text_canvas,
+                &markers,
+                region,
+                GridSize::new(1, 1) * self.grid_scale,
+                foreground,
+            );
+            return (true, false);
+        } else if self.box_char_renderer.draw_glyph(
             text,
             boxchar_canvas,
             region,
-            style.foreground(&self.default_style.colors).to_color(),
+            foreground.to_color(),
             window_position,
         ) {
-            return (text_drawn, true);
+            return (false, true);
         } else if !text.is_empty() {
             text_canvas.save();
 
@@ -252,12 +480,13 @@ impl GridRenderer {
             paint.set_anti_alias(false);
             paint.set_blend_mode(BlendMode::SrcOver);
 
-            if self.settings.get::<RendererSettings>().debug_renderer {
+            let highlight_fonts = renderer_settings.highlight_fonts;
+            if renderer_settings.debug_renderer {
                 let random_hsv: HSV = (rand::random::<f32>() * 360.0, 1.0, 1.0).into();
                 let random_color = random_hsv.to_color(255);
                 paint.set_color(random_color);
             } else {
-                paint.set_color(style.foreground(&self.default_style.colors).to_color());
+                paint.set_color(foreground.to_color());
             }
             for word in fragment.words() {
                 let adjustment = PixelVec::new(
@@ -265,7 +494,21 @@ impl GridRenderer {
                     self.shaper.baseline_offset(),
                 );
 
-                for blob in self.shaper.shape_cached(word, style.into()).iter() {
+                let ligatures = !splits_ligatures(&word, cells.start, cursor_column);
+                // Only the words at the edges of the fragment touch the cells around it
+                let context = RunContext {
+                    joins_left: context.joins_left && word.cell == 0,
+                    joins_right: context.joins_right && word.cells().end == cells.len() as u32,
+                    ..context
+                };
+                let blobs = self.shaper.shape_cached_with(
+                    word,
+                    style.into(),
+                    ligatures,
+                    highlight_fonts.fonts_for(&style.highlight_groups),
+                    context,
+                );
+                for blob in blobs.iter() {
                     tracy_zone!("draw_text_blob");
                     text_canvas.draw_text_blob(
                         blob,
@@ -276,25 +519,153 @@ impl GridRenderer {
                 }
             }
 
-            if style.strikethrough {
-                let line_position = region.center().y;
-                paint.set_color(style.special(&self.default_style.colors).to_color());
-                text_canvas.draw_line(
-                    (region.min.x, line_position),
-                    (region.max.x, line_position),
-                    &paint,
-                );
-                text_drawn = true;
-            }
             text_canvas.restore();
         }
         (text_drawn, false)
     }
 
+    /// Draws the underlines of the fragments of a line, before the text so that they don't cover
+    /// its descenders. Returns true if any was drawn.
+    pub fn draw_underlines<'a>(
+        &self,
+        text_canvas: &Canvas,
+        fragments: impl IntoIterator<Item = &'a LineFragment<'a>>,
+    ) -> bool {
+        self.draw_decorations(text_canvas, fragments, |kind| {
+            matches!(kind, DecorationKind::Underline(_))
+        })
+    }
+
+    /// Draws the strikethroughs of the fragments of a line, after the text so that they go through
+    /// it. Returns true if any was drawn.
+    pub fn draw_strikethroughs<'a>(
+        &self,
+        text_canvas: &Canvas,
+        fragments: impl IntoIterator<Item = &'a LineFragment<'a>>,
+    ) -> bool {
+        self.draw_decorations(text_canvas, fragments, |kind| kind == DecorationKind::Strikethrough)
+    }
+
+    /// Draws the decorations of the given kinds once for each run of cells sharing one
+    fn draw_decorations<'a>(
+        &self,
+        text_canvas: &Canvas,
+        fragments: impl IntoIterator<Item = &'a LineFragment<'a>>,
+        drawn: impl Fn(DecorationKind) -> bool,
+    ) -> bool {
+        tracy_zone!("draw_decorations");
+        let decorations = decoration_runs(
+            fragments.into_iter().map(|fragment| {
+                (
+                    fragment.cells.clone(),
+                    fragment.style.as_deref().unwrap_or(self.default_style.as_ref()),
+                )
+            }),
+            &self.default_style.colors,
+        )
+        .into_iter()
+        .filter(|decoration| drawn(decoration.kind))
+        .collect::<Vec<_>>();
+        for decoration in &decorations {
+            let region = self.compute_text_region(&decoration.cells);
+            match decoration.kind {
+                DecorationKind::Underline(underline_style) => {
+                    let stroke_size = self.shaper.stroke_size();
+                    // Measure the underline offset from the baseline position snapped to a whole
+                    // pixel
+                    let baseline_position = self.shaper.baseline_offset().round();
+                    // The underline should be at least 1 pixel below the baseline
+                    let underline_position =
+                        baseline_position - self.shaper.underline_offset().min(-1.).round();
+                    let p1 = PixelPos::new(region.min.x, underline_position);
+                    let p2 = PixelPos::new(region.max.x, underline_position);
+
+                    self.draw_underline(
+                        text_canvas,
+                        decoration.color,
+                        underline_style,
+                        stroke_size,
+                        p1,
+                        p2,
+                    );
+                }
+                DecorationKind::Strikethrough => {
+                    let line_position = region.center().y;
+                    let mut paint = Paint::new(decoration.color, None);
+                    paint.set_anti_alias(false);
+                    paint.set_blend_mode(BlendMode::SrcOver);
+                    text_canvas.draw_line(
+                        (region.min.x, line_position),
+                        (region.max.x, line_position),
+                        &paint,
+                    );
+                }
+            }
+        }
+        !decorations.is_empty()
+    }
+
+    /// Draws some foreground text with the proportional system font, starting at the first cell of
+    /// the fragment instead of putting each character in its own cell.
+    /// Returns true if any text was actually drawn.
+    pub fn draw_proportional_foreground(
+        &mut self,
+        text_canvas: &Canvas,
+        fragment: &LineFragment,
+    ) -> bool {
+        tracy_zone!("draw_proportional_foreground");
+
+        let LineFragment { text, cells, style, .. } = fragment;
+        if text.trim().is_empty() {
+            return false;
+        }
+
+        let region = self.compute_text_region(cells);
+        let style = style.as_ref().unwrap_or(&self.default_style);
+
+        let mut text_style = TextStyle::new();
+        text_style.set_font_families(&["sans-serif"]);
+        text_style.set_font_size(self.shaper.current_size());
+        text_style.set_font_style(match (style.bold, style.italic) {
+            (false, false) => FontStyle::normal(),
+            (true, false) => FontStyle::bold(),
+            (false, true) => FontStyle::italic(),
+            (true, true) => FontStyle::bold_italic(),
+        });
+        text_style
+            .set_foreground_paint(&Paint::new(style.foreground(&self.default_style.colors), None));
+        let mut decoration = TextDecoration::NO_DECORATION;
+        decoration.set(TextDecoration::UNDERLINE, style.underline.is_some());
+        decoration.set(TextDecoration::LINE_THROUGH, style.strikethrough);
+        text_style.set_decoration_type(decoration);
+        text_style.set_decoration_color(style.special(&self.default_style.colors).to_color());
+
+        let mut paragraph_style = ParagraphStyle::new();
+        paragraph_style.set_text_style(&text_style);
+        paragraph_style.set_max_lines(1);
+        paragraph_style.set_text_height_behavior(TextHeightBehavior::DisableAll);
+
+        let mut paragraph_builder =
+            ParagraphBuilder::new(&paragraph_style, &self.proportional_fonts);
+        paragraph_builder.add_text(text.trim_end());
+        let mut paragraph = paragraph_builder.build();
+        paragraph.layout(f32::INFINITY);
+
+        text_canvas.save();
+        let wider_cells = cells.start.saturating_sub(1)..cells.end + 1;
+        let clip_region = self.compute_text_region(&wider_cells);
+        text_canvas.clip_rect(to_skia_rect(&clip_region), None, Some(false));
+        // Line the proportional text up with the baseline of the grid text around it
+        let y = region.min.y + self.shaper.baseline_offset() - paragraph.alphabetic_baseline();
+        paragraph.paint(text_canvas, (region.min.x, y));
+        text_canvas.restore();
+        true
+    }
+
     fn draw_underline(
         &self,
         canvas: &Canvas,
-        style: &Arc<Style>,
+        color: Color4f,
         underline_style: UnderlineStyle,
         stroke_size: f32,
         p1: PixelPos<f32>,
@@ -315,9 +686,7 @@ impl GridRenderer {
         let p1 = (p1.x, p1.y + offset);
         let p2 = (p2.x, p2.y + offset);
 
-        underline_paint
-            .set_color(style.special(&self.default_style.colors).to_color())
-            .set_stroke_width(stroke_width);
+        underline_paint.set_color(color.to_color()).set_stroke_width(stroke_width);
 
         match underline_style {
             UnderlineStyle::Underline => {
@@ -372,3 +741,92 @@ impl GridRenderer {
         canvas.restore();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_and_background_opacity_apply_to_their_own_draws() {
+        let settings =
+            WindowSettings { text_opacity: 1.0, background_opacity: 0.25, ..Default::default() };
+        let opacity = ComponentOpacity::from_settings(&settings);
+        let white = Color4f::new(1.0, 1.0, 1.0, 1.0);
+        assert_eq!(opacity.text_color(white), white);
+        assert_eq!(opacity.background_alpha(0.8), 0.2);
+
+        let settings =
+            WindowSettings { text_opacity: 0.5, background_opacity: 1.0, ..Default::default() };
+        let opacity = ComponentOpacity::from_settings(&settings);
+        assert_eq!(opacity.text_color(white).a, 0.5);
+        assert_eq!(opacity.background_alpha(0.8), 0.8);
+    }
+
+    #[test]
+    fn translucent_text_drops_subpixel_antialiasing() {
+        assert_eq!(
+            text_antialiasing_for_opacity(TextAntialiasing::Subpixel, 0.9),
+            TextAntialiasing::Grayscale
+        );
+        assert_eq!(
+            text_antialiasing_for_opacity(TextAntialiasing::Subpixel, 1.0),
+            TextAntialiasing::Subpixel
+        );
+        assert_eq!(
+            text_antialiasing_for_opacity(TextAntialiasing::None, 0.5),
+            TextAntialiasing::None
+        );
+    }
+
+    #[test]
+    fn only_the_word_under_the_cursor_splits_its_ligatures() {
+        // "a => b" in a fragment starting at column 10
+        let mut word = Word::new("=>", &[1, 1]);
+        word.cell = 2;
+        assert!(!splits_ligatures(&word, 10, None));
+        assert!(!splits_ligatures(&word, 10, Some(11)));
+        assert!(splits_ligatures(&word, 10, Some(12)));
+        assert!(splits_ligatures(&word, 10, Some(13)));
+        assert!(!splits_ligatures(&word, 10, Some(14)));
+        assert!(!splits_ligatures(&word, 10, Some(2)));
+    }
+
+    #[test]
+    fn the_second_cell_of_a_wide_character_is_part_of_the_word() {
+        let word = Word::new("一=", &[3, 0, 1]);
+        assert!(splits_ligatures(&word, 0, Some(1)));
+        assert!(splits_ligatures(&word, 0, Some(2)));
+        assert!(!splits_ligatures(&word, 0, Some(3)));
+    }
+
+    fn underlined(foreground: Color4f) -> Style {
+        let red = Color4f::new(1.0, 0.0, 0.0, 1.0);
+        let mut style = Style::new(Colors::new(Some(foreground), None, Some(red)));
+        style.underline = Some(UnderlineStyle::UnderCurl);
+        style
+    }
+
+    #[test]
+    fn an_underline_is_drawn_once_across_a_run_of_highlights() {
+        let default_colors = Colors::new(
+            Some(Color4f::new(1.0, 1.0, 1.0, 1.0)),
+            Some(Color4f::new(0.0, 0.0, 0.0, 1.0)),
+            None,
+        );
+        // The two cells of a "->" ligature with different highlights
+        let arrow = underlined(Color4f::new(0.0, 1.0, 0.0, 1.0));
+        let head = underlined(Color4f::new(0.0, 0.0, 1.0, 1.0));
+        let plain = Style::new(Colors::new(None, None, None));
+
+        let decorations = decoration_runs(
+            [(3..4, &arrow), (4..5, &head), (5..6, &plain), (6..7, &head)],
+            &default_colors,
+        );
+        let underline = |cells| Decoration {
+            kind: DecorationKind::Underline(UnderlineStyle::UnderCurl),
+            color: Color4f::new(1.0, 0.0, 0.0, 1.0),
+            cells,
+        };
+        assert_eq!(decorations, [underline(3..5), underline(6..7)]);
+    }
+}
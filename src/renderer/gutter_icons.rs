@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use skia_safe::{Canvas, Color4f, Paint, PathBuilder, Rect, paint::Style};
+
+/// The severity of a diagnostic, in the order of `vim.diagnostic.severity`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warn,
+    Info,
+    Hint,
+}
+
+impl DiagnosticSeverity {
+    pub fn from_index(index: u64) -> Option<Self> {
+        match index {
+            1 => Some(Self::Error),
+            2 => Some(Self::Warn),
+            3 => Some(Self::Info),
+            4 => Some(Self::Hint),
+            _ => None,
+        }
+    }
+
+    // Used when the colorscheme doesn't define the DiagnosticSign* groups
+    fn default_color(self) -> Color4f {
+        match self {
+            Self::Error => Color4f::new(0.94, 0.32, 0.32, 1.0),
+            Self::Warn => Color4f::new(0.95, 0.73, 0.25, 1.0),
+            Self::Info => Color4f::new(0.35, 0.65, 0.95, 1.0),
+            Self::Hint => Color4f::new(0.55, 0.8, 0.6, 1.0),
+        }
+    }
+}
+
+/// A diagnostic sign in the gutter of a window, `row` is counted from the top of the grid,
+/// winbar included
+#[derive(Clone, Debug, PartialEq)]
+pub struct GutterSign {
+    pub row: u64,
+    pub severity: DiagnosticSeverity,
+    pub color: Option<Color4f>,
+}
+
+/// Maps the signs to the rows of the scrollable region below the `top_margin`, which hold
+/// `height` rows. Signs outside of it are dropped, and only the most severe sign of a row is kept.
+pub fn gutter_rows(
+    signs: &[GutterSign],
+    top_margin: u64,
+    height: u64,
+) -> HashMap<isize, GutterSign> {
+    let mut rows = HashMap::<isize, GutterSign>::new();
+    for sign in signs {
+        let Some(row) = sign.row.checked_sub(top_margin).filter(|row| *row < height) else {
+            continue;
+        };
+        let row = row as isize;
+        if rows.get(&row).is_none_or(|current| sign.severity < current.severity) {
+            rows.insert(row, sign.clone());
+        }
+    }
+    rows
+}
+
+/// Draws the icon of the sign centered in `cell_rect`, the two cells of the sign column
+pub fn draw_icon(canvas: &Canvas, sign: &GutterSign, cell_rect: Rect) {
+    let size = cell_rect.height().min(cell_rect.width()) * 0.7;
+    let icon = Rect::from_xywh(
+        cell_rect.center_x() - size / 2.0,
+        cell_rect.center_y() - size / 2.0,
+        size,
+        size,
+    );
+    let color = sign.color.unwrap_or_else(|| sign.severity.default_color());
+    let mut paint = Paint::new(color, None);
+    paint.set_anti_alias(true);
+    paint.set_style(Style::Stroke);
+    paint.set_stroke_width((size * 0.12).max(1.0));
+
+    let (x, y, w, h) = (icon.left, icon.top, icon.width(), icon.height());
+    let mut builder = PathBuilder::new();
+    match sign.severity {
+        // A circle with a cross
+        DiagnosticSeverity::Error => {
+            canvas.draw_oval(icon, &paint);
+            builder
+                .move_to((x + w * 0.35, y + h * 0.35))
+                .line_to((x + w * 0.65, y + h * 0.65))
+                .move_to((x + w * 0.65, y + h * 0.35))
+                .line_to((x + w * 0.35, y + h * 0.65));
+        }
+        // A triangle with an exclamation mark
+        DiagnosticSeverity::Warn => {
+            builder
+                .move_to((x + w * 0.5, y))
+                .line_to((x + w, y + h))
+                .line_to((x, y + h))
+                .close()
+                .move_to((x + w * 0.5, y + h * 0.4))
+                .line_to((x + w * 0.5, y + h * 0.65))
+                .move_to((x + w * 0.5, y + h * 0.8))
+                .line_to((x + w * 0.5, y + h * 0.84));
+        }
+        // A circle with an i
+        DiagnosticSeverity::Info => {
+            canvas.draw_oval(icon, &paint);
+            builder
+                .move_to((x + w * 0.5, y + h * 0.45))
+                .line_to((x + w * 0.5, y + h * 0.75))
+                .move_to((x + w * 0.5, y + h * 0.25))
+                .line_to((x + w * 0.5, y + h * 0.29));
+        }
+        // A diamond with a dot
+        DiagnosticSeverity::Hint => {
+            builder
+                .move_to((x + w * 0.5, y))
+                .line_to((x + w, y + h * 0.5))
+                .line_to((x + w * 0.5, y + h))
+                .line_to((x, y + h * 0.5))
+                .close()
+                .move_to((x + w * 0.5, y + h * 0.48))
+                .line_to((x + w * 0.5, y + h * 0.52));
+        }
+    }
+    canvas.draw_path(&builder.detach(), &paint);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(row: u64, severity: DiagnosticSeverity) -> GutterSign {
+        GutterSign { row, severity, color: None }
+    }
+
+    #[test]
+    fn sign_rows_are_mapped_below_the_winbar() {
+        let signs = [sign(1, DiagnosticSeverity::Warn), sign(4, DiagnosticSeverity::Hint)];
+        let rows = gutter_rows(&signs, 1, 10);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[&0].severity, DiagnosticSeverity::Warn);
+        assert_eq!(rows[&3].severity, DiagnosticSeverity::Hint);
+    }
+
+    #[test]
+    fn signs_outside_of_the_scrollable_region_are_dropped() {
+        let signs = [sign(0, DiagnosticSeverity::Error), sign(11, DiagnosticSeverity::Error)];
+        assert!(gutter_rows(&signs, 1, 10).is_empty());
+    }
+
+    #[test]
+    fn the_most_severe_sign_of_a_row_wins() {
+        let signs = [
+            sign(2, DiagnosticSeverity::Info),
+            sign(2, DiagnosticSeverity::Error),
+            sign(2, DiagnosticSeverity::Warn),
+        ];
+        let rows = gutter_rows(&signs, 0, 5);
+        assert_eq!(rows[&2].severity, DiagnosticSeverity::Error);
+    }
+}
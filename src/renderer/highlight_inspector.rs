@@ -0,0 +1,58 @@
+use skia_safe::Canvas;
+
+use crate::{
+    editor::Cursor,
+    renderer::{
+        GridRenderer,
+        overlay_label::{self, Corner},
+    },
+    units::PixelSize,
+};
+
+const NO_GROUP: &str = "No highlight group";
+const GROUP_SEPARATOR: &str = " › ";
+
+/// The highlight groups of the cell under the cursor, in the order Neovim combined them
+pub fn cursor_highlight_groups(cursor: &Cursor) -> &[String] {
+    cursor.grid_cell.1.as_ref().map_or(&[], |style| style.highlight_groups.as_slice())
+}
+
+fn label(groups: &[String]) -> String {
+    if groups.is_empty() { NO_GROUP.to_owned() } else { groups.join(GROUP_SEPARATOR) }
+}
+
+/// Draws the highlight groups under the cursor in the bottom right corner of the window
+pub fn draw(
+    canvas: &Canvas,
+    grid_renderer: &mut GridRenderer,
+    cursor: &Cursor,
+    bounds: PixelSize<f32>,
+) {
+    let text = label(cursor_highlight_groups(cursor));
+    overlay_label::draw(canvas, grid_renderer, &text, Corner::BottomRight, bounds);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::editor::{Colors, Style};
+
+    #[test]
+    fn the_groups_come_from_the_style_under_the_cursor() {
+        let mut cursor = Cursor::new();
+        assert!(cursor_highlight_groups(&cursor).is_empty());
+
+        let mut style = Style::new(Colors::new(None, None, None));
+        style.highlight_groups = vec!["Comment".to_owned(), "CursorLine".to_owned()];
+        cursor.grid_cell = ("x".to_owned(), Some(Arc::new(style)));
+        assert_eq!(cursor_highlight_groups(&cursor), ["Comment", "CursorLine"]);
+        assert_eq!(label(cursor_highlight_groups(&cursor)), "Comment › CursorLine");
+    }
+
+    #[test]
+    fn cells_without_a_group_say_so() {
+        assert_eq!(label(&[]), NO_GROUP);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::version::BUILD_VERSION;
+
+/// What the active skia backend reports about the graphics device and the window surface
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GraphicsInfo {
+    pub backend: &'static str,
+    pub gpu: Option<String>,
+    pub driver: Option<String>,
+    pub surface_format: String,
+    /// Whether the colors are blended in linear space, which needs an sRGB surface
+    pub linear_blending: bool,
+}
+
+/// The diagnostic information printed by `:NeovideInfo`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RendererInfo {
+    pub version: &'static str,
+    pub graphics: GraphicsInfo,
+    pub scale_factor: f64,
+    pub vsync: bool,
+    pub srgb: bool,
+}
+
+impl RendererInfo {
+    pub fn new(graphics: GraphicsInfo, scale_factor: f64, vsync: bool, srgb: bool) -> Self {
+        Self { version: BUILD_VERSION, graphics, scale_factor, vsync, srgb }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let unknown = || "unknown".to_owned();
+        let enabled = |value: bool| if value { "enabled" } else { "disabled" };
+        vec![
+            format!("Neovide {}", self.version),
+            format!("Renderer: {}", self.graphics.backend),
+            format!("GPU: {}", self.graphics.gpu.clone().unwrap_or_else(unknown)),
+            format!("Driver: {}", self.graphics.driver.clone().unwrap_or_else(unknown)),
+            format!("Surface format: {}", self.graphics.surface_format),
+            format!("Scale factor: {}", self.scale_factor),
+            format!("VSync: {}", enabled(self.vsync)),
+            format!("sRGB: {}", enabled(self.srgb)),
+            format!("Linear blending: {}", enabled(self.graphics.linear_blending)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_graphics_info() -> GraphicsInfo {
+        GraphicsInfo {
+            backend: "OpenGL",
+            gpu: Some("Mock GPU".to_owned()),
+            driver: None,
+            surface_format: "RGBA8".to_owned(),
+            linear_blending: false,
+        }
+    }
+
+    #[test]
+    fn info_is_populated_from_the_renderer_state() {
+        let info = RendererInfo::new(mock_graphics_info(), 1.5, true, false);
+        assert_eq!(info.version, BUILD_VERSION);
+        assert_eq!(info.graphics.backend, "OpenGL");
+        assert_eq!(info.scale_factor, 1.5);
+        assert!(info.vsync);
+        assert!(!info.srgb);
+        assert!(!info.graphics.linear_blending);
+    }
+
+    #[test]
+    fn info_lines_report_unknown_values() {
+        let graphics = GraphicsInfo { linear_blending: true, ..mock_graphics_info() };
+        let lines = RendererInfo::new(graphics, 2.0, false, true).lines();
+        assert_eq!(
+            lines[1..],
+            [
+                "Renderer: OpenGL",
+                "GPU: Mock GPU",
+                "Driver: unknown",
+                "Surface format: RGBA8",
+                "Scale factor: 2",
+                "VSync: disabled",
+                "sRGB: enabled",
+                "Linear blending: enabled",
+            ]
+        );
+    }
+
+    #[test]
+    fn info_lines_report_srgb_without_linear_blending() {
+        let lines = RendererInfo::new(mock_graphics_info(), 1.0, true, true).lines();
+        assert_eq!(lines[6..], ["VSync: enabled", "sRGB: enabled", "Linear blending: disabled"]);
+
+        let lines = RendererInfo::new(mock_graphics_info(), 1.0, true, false).lines();
+        assert_eq!(lines[7..], ["sRGB: disabled", "Linear blending: disabled"]);
+    }
+}
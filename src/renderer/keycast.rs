@@ -0,0 +1,175 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use skia_safe::Canvas;
+
+use crate::{
+    renderer::{
+        GridRenderer,
+        overlay_label::{self, Corner},
+    },
+    units::PixelSize,
+    window::ShouldRender,
+};
+
+// Only the most recent keys fit next to each other in the corner
+const MAX_KEYS: usize = 8;
+// The keys stay up while typing, and disappear once nothing was typed for this long
+const DISPLAY_DURATION: Duration = Duration::from_millis(2000);
+const FADE_DURATION: Duration = Duration::from_millis(300);
+
+#[derive(Debug, PartialEq, Eq)]
+struct TypedKey {
+    key: String,
+    count: usize,
+}
+
+/// The last keys sent to Neovim, shown in the corner of the window for screencasts. Set through
+/// `g:neovide_keycast`.
+#[derive(Default)]
+pub struct Keycast {
+    keys: VecDeque<TypedKey>,
+    last_key_at: Option<Instant>,
+}
+
+impl Keycast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key in Neovim notation, like `<C-w>`. A key that is repeated is shown once, with
+    /// the number of repeats.
+    pub fn push(&mut self, key: String, now: Instant) {
+        self.expire(now);
+        self.last_key_at = Some(now);
+        if let Some(last) = self.keys.back_mut()
+            && last.key == key
+        {
+            last.count += 1;
+            return;
+        }
+        if self.keys.len() == MAX_KEYS {
+            self.keys.pop_front();
+        }
+        self.keys.push_back(TypedKey { key, count: 1 });
+    }
+
+    fn expire(&mut self, now: Instant) {
+        if self.opacity(now) <= 0.0 {
+            self.keys.clear();
+            self.last_key_at = None;
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        let keys = self
+            .keys
+            .iter()
+            .map(|typed| match typed.count {
+                1 => typed.key.clone(),
+                count => format!("{}×{count}", typed.key),
+            })
+            .collect::<Vec<_>>();
+        (!keys.is_empty()).then(|| keys.join(" "))
+    }
+
+    fn opacity(&self, now: Instant) -> f32 {
+        let Some(last_key_at) = self.last_key_at else {
+            return 0.0;
+        };
+        let remaining = DISPLAY_DURATION.saturating_sub(now.saturating_duration_since(last_key_at));
+        (remaining.as_secs_f32() / FADE_DURATION.as_secs_f32()).min(1.0)
+    }
+
+    /// Only the fade out is animated, the keys stay still until then
+    pub fn is_animating(&self) -> bool {
+        let opacity = self.opacity(Instant::now());
+        opacity > 0.0 && opacity < 1.0
+    }
+
+    /// Renders again when the fade out starts
+    pub fn prepare_frame(&self, now: Instant) -> ShouldRender {
+        match self.last_key_at {
+            Some(last_key_at) if self.opacity(now) >= 1.0 => {
+                ShouldRender::Deadline(last_key_at + DISPLAY_DURATION - FADE_DURATION)
+            }
+            _ => ShouldRender::Wait,
+        }
+    }
+
+    pub fn draw(&self, canvas: &Canvas, grid_renderer: &mut GridRenderer, bounds: PixelSize<f32>) {
+        let opacity = self.opacity(Instant::now());
+        let Some(label) = self.label().filter(|_| opacity > 0.0) else {
+            return;
+        };
+        canvas.save_layer_alpha_f(None, opacity);
+        overlay_label::draw(canvas, grid_renderer, &label, Corner::BottomRight, bounds);
+        canvas.restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_shown_in_order_and_repeats_are_coalesced() {
+        let mut keycast = Keycast::new();
+        let now = Instant::now();
+        assert_eq!(keycast.label(), None);
+
+        keycast.push("d".to_owned(), now);
+        keycast.push("<C-w>".to_owned(), now);
+        keycast.push("j".to_owned(), now);
+        keycast.push("j".to_owned(), now);
+        keycast.push("j".to_owned(), now);
+        keycast.push("<Esc>".to_owned(), now);
+        assert_eq!(keycast.label().as_deref(), Some("d <C-w> j×3 <Esc>"));
+    }
+
+    #[test]
+    fn only_the_last_keys_are_kept() {
+        let mut keycast = Keycast::new();
+        let now = Instant::now();
+        for key in 0..MAX_KEYS + 2 {
+            keycast.push(key.to_string(), now);
+        }
+        assert_eq!(keycast.keys.len(), MAX_KEYS);
+        assert_eq!(keycast.label().as_deref(), Some("2 3 4 5 6 7 8 9"));
+    }
+
+    #[test]
+    fn keys_fade_out_and_expire_after_a_pause() {
+        let mut keycast = Keycast::new();
+        let typed_at = Instant::now();
+        keycast.push("i".to_owned(), typed_at);
+
+        assert_eq!(keycast.opacity(typed_at), 1.0);
+        assert_eq!(keycast.opacity(typed_at + Duration::from_millis(1700)), 1.0);
+        assert_eq!(keycast.opacity(typed_at + Duration::from_millis(1850)), 0.5);
+        assert_eq!(keycast.opacity(typed_at + DISPLAY_DURATION), 0.0);
+
+        // Typing again before the keys expire keeps them up, after that the list starts over
+        keycast.push("i".to_owned(), typed_at + Duration::from_millis(1000));
+        assert_eq!(keycast.label().as_deref(), Some("i×2"));
+        keycast.push("i".to_owned(), typed_at + Duration::from_millis(5000));
+        assert_eq!(keycast.label().as_deref(), Some("i"));
+    }
+
+    #[test]
+    fn only_the_fade_out_is_rendered_continuously() {
+        let mut keycast = Keycast::new();
+        let typed_at = Instant::now();
+        assert_eq!(keycast.prepare_frame(typed_at), ShouldRender::Wait);
+
+        keycast.push("i".to_owned(), typed_at);
+        let fade_start = typed_at + DISPLAY_DURATION - FADE_DURATION;
+        assert_eq!(keycast.prepare_frame(typed_at), ShouldRender::Deadline(fade_start));
+        assert_eq!(
+            keycast.prepare_frame(fade_start + Duration::from_millis(100)),
+            ShouldRender::Wait
+        );
+    }
+}
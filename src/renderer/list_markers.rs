@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use skia_safe::{
+    Canvas, Color4f, Paint, PathBuilder,
+    paint::{Cap, Style},
+};
+
+use crate::units::{PixelRect, PixelSize};
+
+const ALPHA: f32 = 0.6;
+
+/// What a marker of 'listchars' stands for, which decides the shape drawn for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListMarker {
+    Eol,
+    Space,
+}
+
+impl ListMarker {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "eol" => Some(Self::Eol),
+            "space" | "trail" | "lead" | "multispace" | "leadmultispace" => Some(Self::Space),
+            _ => None,
+        }
+    }
+}
+
+/// The markers of 'listchars' drawn by Neovide instead of the font. Only the characters that
+/// aren't ASCII are taken, since ASCII markers like the default `trail:-` also appear in the text.
+#[derive(Clone, Debug, Default)]
+pub struct ListMarkers {
+    listchars: String,
+    markers: HashMap<char, ListMarker>,
+}
+
+impl ListMarkers {
+    /// Parses the markers again when 'listchars' changed, returns true when it did
+    pub fn update(&mut self, listchars: &str) -> bool {
+        if listchars == self.listchars {
+            return false;
+        }
+        self.listchars = listchars.to_owned();
+        self.markers = listchars
+            .split(',')
+            .filter_map(|item| item.split_once(':'))
+            .filter_map(|(key, value)| Some((ListMarker::from_key(key)?, value)))
+            .flat_map(|(marker, value)| value.chars().map(move |ch| (ch, marker)))
+            .filter(|(ch, _)| !ch.is_ascii())
+            .collect();
+        true
+    }
+
+    pub fn marker(&self, ch: char) -> Option<ListMarker> {
+        self.markers.get(&ch).copied()
+    }
+
+    /// The marker of each cell of `text`, or `None` unless every character is a marker
+    pub fn markers_of(&self, text: &str) -> Option<Vec<ListMarker>> {
+        if text.is_empty() {
+            return None;
+        }
+        text.chars().map(|ch| self.marker(ch)).collect()
+    }
+}
+
+/// Draws one marker per cell from the left of `region`, dimmed from `color`
+pub fn draw_markers(
+    canvas: &Canvas,
+    markers: &[ListMarker],
+    region: PixelRect<f32>,
+    cell_size: PixelSize<f32>,
+    color: Color4f,
+) {
+    let mut paint = Paint::new(Color4f { a: color.a * ALPHA, ..color }, None);
+    paint.set_anti_alias(true);
+    let stroke_width = (cell_size.height / 16.0).max(1.0).round();
+    for (index, marker) in markers.iter().enumerate() {
+        let left = region.min.x + index as f32 * cell_size.width;
+        let center_x = left + cell_size.width / 2.0;
+        let center_y = region.min.y + cell_size.height / 2.0;
+        match marker {
+            ListMarker::Space => {
+                paint.set_style(Style::Fill);
+                let radius = (cell_size.width / 10.0).max(stroke_width / 2.0);
+                canvas.draw_circle((center_x, center_y), radius, &paint);
+            }
+            // A ¬, the bar across the middle of the cell with a tick down on the right
+            ListMarker::Eol => {
+                paint.set_style(Style::Stroke);
+                paint.set_stroke_width(stroke_width);
+                paint.set_stroke_cap(Cap::Round);
+                let right = left + cell_size.width * 0.8;
+                let mut builder = PathBuilder::new();
+                builder
+                    .move_to((left + cell_size.width * 0.2, center_y))
+                    .line_to((right, center_y))
+                    .line_to((right, center_y + cell_size.height * 0.15));
+                canvas.draw_path(&builder.detach(), &paint);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers(listchars: &str) -> ListMarkers {
+        let mut markers = ListMarkers::default();
+        markers.update(listchars);
+        markers
+    }
+
+    #[test]
+    fn the_eol_marker_is_detected() {
+        let markers = markers("tab:» ,eol:¬,trail:·,nbsp:␣");
+        assert_eq!(markers.marker('¬'), Some(ListMarker::Eol));
+        assert_eq!(markers.marker('·'), Some(ListMarker::Space));
+        // Tabs and non breaking spaces keep their glyphs
+        assert_eq!(markers.marker('»'), None);
+        assert_eq!(markers.marker('␣'), None);
+        assert_eq!(
+            markers.markers_of("··¬"),
+            Some(vec![ListMarker::Space, ListMarker::Space, ListMarker::Eol])
+        );
+        assert_eq!(markers.markers_of("a¬"), None);
+    }
+
+    #[test]
+    fn ascii_markers_are_left_to_the_font() {
+        let markers = markers("tab:> ,trail:-,eol:$");
+        assert_eq!(markers.marker('$'), None);
+        assert_eq!(markers.marker('-'), None);
+        assert_eq!(markers.markers_of("$"), None);
+    }
+}
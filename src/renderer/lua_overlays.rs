@@ -0,0 +1,126 @@
+use crate::renderer::{GridRenderer, text_cells::CellText};
+use skia_safe::{Canvas, Color4f, Paint, PaintStyle, Rect};
+
+pub const DEFAULT_LINE_WIDTH: f32 = 1.0;
+// Keeps a plugin that never clears its items from slowing every frame down
+const MAX_ITEMS: usize = 1000;
+
+/// Something drawn on top of the grids by a plugin, in logical pixels from the top left corner of
+/// the window. Without a color, the default foreground is used.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OverlayItem {
+    Rect { x: f32, y: f32, width: f32, height: f32, color: Option<Color4f> },
+    Text { x: f32, y: f32, text: String, color: Option<Color4f> },
+    Line { from: (f32, f32), to: (f32, f32), width: f32, color: Option<Color4f> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OverlayCommand {
+    /// Adds the item, or replaces the one with the same id
+    Set { id: String, item: OverlayItem },
+    /// Removes the item with the id, or all of them
+    Clear(Option<String>),
+}
+
+/// The items registered through `require("neovide").draw_*`, drawn every frame in the order they
+/// were first registered
+#[derive(Default)]
+pub struct LuaOverlays {
+    items: Vec<(String, OverlayItem)>,
+}
+
+impl LuaOverlays {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle_command(&mut self, command: OverlayCommand) {
+        match command {
+            OverlayCommand::Set { id, item } => {
+                match self.items.iter_mut().find(|(existing, _)| *existing == id) {
+                    Some((_, existing)) => *existing = item,
+                    None if self.items.len() >= MAX_ITEMS => {
+                        log::warn!("Ignoring overlay item {id:?}, there are already {MAX_ITEMS}");
+                    }
+                    None => self.items.push((id, item)),
+                }
+            }
+            OverlayCommand::Clear(Some(id)) => self.items.retain(|(existing, _)| *existing != id),
+            OverlayCommand::Clear(None) => self.items.clear(),
+        }
+    }
+
+    pub fn draw(&self, canvas: &Canvas, grid_renderer: &mut GridRenderer) {
+        let foreground =
+            grid_renderer.default_style.foreground(&grid_renderer.default_style.colors);
+        for (_, item) in &self.items {
+            match item {
+                OverlayItem::Rect { x, y, width, height, color } => {
+                    let mut paint = Paint::new(color.unwrap_or(foreground), None);
+                    paint.set_anti_alias(true);
+                    canvas.draw_rect(Rect::from_xywh(*x, *y, *width, *height), &paint);
+                }
+                OverlayItem::Line { from, to, width, color } => {
+                    let mut paint = Paint::new(color.unwrap_or(foreground), None);
+                    paint.set_anti_alias(true);
+                    paint.set_style(PaintStyle::Stroke);
+                    paint.set_stroke_width(*width);
+                    canvas.draw_line(*from, *to, &paint);
+                }
+                OverlayItem::Text { x, y, text, color } => {
+                    let cells = CellText::new(text, grid_renderer.ambiwidth);
+                    let mut paint = Paint::new(color.unwrap_or(foreground), None);
+                    paint.set_anti_alias(false);
+                    let origin = (*x, *y + grid_renderer.shaper.baseline_offset());
+                    let style = grid_renderer.default_style.clone();
+                    for blob in
+                        grid_renderer.shaper.shape_cached(cells.word(), (&style).into()).iter()
+                    {
+                        canvas.draw_text_blob(blob, origin, &paint);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32) -> OverlayItem {
+        OverlayItem::Rect { x, y: 0.0, width: 10.0, height: 10.0, color: None }
+    }
+
+    fn set(id: &str, item: OverlayItem) -> OverlayCommand {
+        OverlayCommand::Set { id: id.to_owned(), item }
+    }
+
+    #[test]
+    fn items_are_updated_in_place_and_cleared_by_id() {
+        let mut overlays = LuaOverlays::new();
+        overlays.handle_command(set("a", rect(0.0)));
+        overlays.handle_command(set("b", rect(1.0)));
+        overlays.handle_command(set("a", rect(2.0)));
+        assert_eq!(overlays.items, [("a".to_owned(), rect(2.0)), ("b".to_owned(), rect(1.0))]);
+
+        overlays.handle_command(OverlayCommand::Clear(Some("a".to_owned())));
+        assert_eq!(overlays.items, [("b".to_owned(), rect(1.0))]);
+
+        overlays.handle_command(set("c", rect(3.0)));
+        overlays.handle_command(OverlayCommand::Clear(None));
+        assert!(overlays.items.is_empty());
+    }
+
+    #[test]
+    fn new_items_are_ignored_past_the_limit() {
+        let mut overlays = LuaOverlays::new();
+        for index in 0..=MAX_ITEMS {
+            overlays.handle_command(set(&index.to_string(), rect(0.0)));
+        }
+        assert_eq!(overlays.items.len(), MAX_ITEMS);
+        // Existing items can still be updated
+        overlays.handle_command(set("0", rect(1.0)));
+        assert_eq!(overlays.items[0], ("0".to_owned(), rect(1.0)));
+    }
+}
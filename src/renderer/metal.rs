@@ -21,7 +21,7 @@ use winit::{event_loop::EventLoopProxy, window::Window};
 use crate::{
     platform::macos::get_ns_window,
     profiling::tracy_gpu_zone,
-    renderer::{RendererSettings, SkiaRenderer, VSync},
+    renderer::{GraphicsInfo, RendererSettings, SkiaRenderer, VSync},
     window::EventPayload,
 };
 
@@ -76,7 +76,7 @@ impl MetalDrawableSurface {
 
 pub struct MetalSkiaRenderer {
     window: Rc<Window>,
-    _device: Retained<ProtocolObject<dyn MTLDevice>>,
+    device: Retained<ProtocolObject<dyn MTLDevice>>,
     command_queue: Retained<ProtocolObject<dyn MTLCommandQueue>>,
     metal_layer: Retained<CAMetalLayer>,
     _backend: BackendContext,
@@ -129,7 +129,7 @@ impl MetalSkiaRenderer {
 
         MetalSkiaRenderer {
             window,
-            _device: device,
+            device,
             metal_layer,
             command_queue,
             _backend: backend,
@@ -199,6 +199,20 @@ impl SkiaRenderer for MetalSkiaRenderer {
         self.window.request_redraw();
     }
 
+    fn set_vsync(&mut self, enabled: bool) {
+        self.metal_layer.setDisplaySyncEnabled(enabled);
+    }
+
+    fn graphics_info(&self) -> GraphicsInfo {
+        GraphicsInfo {
+            backend: "Metal",
+            gpu: Some(self.device.name().to_string()),
+            driver: None,
+            surface_format: "BGRA8Unorm".to_owned(),
+            linear_blending: false,
+        }
+    }
+
     fn create_vsync(&self, _proxy: EventLoopProxy<EventPayload>) -> VSync {
         VSync::MacosMetal()
     }
@@ -1,13 +1,33 @@
 pub mod animation_utils;
 pub mod box_drawing;
+pub mod buffer_bar;
+mod cmdline_linger;
+pub mod command_palette;
+pub mod context_menu;
+pub mod crash_overlay;
 pub mod cursor_renderer;
+mod diff_accents;
 pub mod fonts;
+mod ghost_cursors;
 pub mod grid_renderer;
+pub mod gutter_icons;
+mod highlight_inspector;
+mod info;
+mod keycast;
+mod list_markers;
+pub mod lua_overlays;
+mod number_fade;
 pub mod opengl;
+mod overlay_label;
 pub mod profiler;
 pub mod progress_bar;
 mod rendered_layer;
 pub mod rendered_window;
+mod splash;
+pub mod tab_bar;
+pub mod tab_indicators;
+pub mod text_cells;
+mod theme_transition;
 mod vsync;
 
 #[cfg(target_os = "windows")]
@@ -21,11 +41,28 @@ use std::{
     collections::{HashMap, hash_map::Entry},
     rc::Rc,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
+use buffer_bar::BufferBar;
+use cmdline_linger::CmdlineLinger;
+use command_palette::CommandPalette;
+use context_menu::ContextMenu;
+use crash_overlay::CrashOverlay;
+use ghost_cursors::GhostCursors;
 use itertools::Itertools;
+use keycast::Keycast;
+use lua_overlays::{LuaOverlays, OverlayCommand};
 use progress_bar::{ProgressBar, ProgressBarSettings};
-use skia_safe::{Canvas, Color4f, Paint};
+use rmpv::Value;
+use skia_safe::{
+    BlendMode, Canvas, ClipOp, Color, Color4f, ColorSpace, ColorType, FilterMode, ISize, Paint,
+    Rect, SamplingOptions, Surface,
+};
+use splash::Splash;
+use tab_bar::TabBar;
+use text_cells::AmbiWidth;
+use theme_transition::ThemeTransition;
 
 use winit::{
     event::WindowEvent,
@@ -35,16 +72,23 @@ use winit::{
 
 use crate::{
     WindowSettings,
-    bridge::{EditorMode, StartupMessage},
+    bridge::{BufferInfo, EditorMode, StartupMessage, TabInfo},
     cmd_line::CmdLineSettings,
     editor::{Cursor, Style, WindowType},
+    error_msg,
     profiling::{tracy_create_gpu_context, tracy_named_frame, tracy_zone},
     renderer::{
-        fonts::font_options::PixelGeometry,
+        fonts::font_options::{
+            FallbackFonts, HighlightFonts, PixelGeometry, TextAntialiasing, TextHinting,
+        },
         rendered_layer::{FloatingLayer, group_windows},
     },
-    settings::*,
-    units::{GridPos, GridRect, GridScale, GridSize, PixelPos, PixelRect, to_skia_rect},
+    settings::{
+        motion::MotionState,
+        power::{animation_scale, battery_saver_active},
+        *,
+    },
+    units::{GridPos, GridRect, GridScale, GridSize, PixelPos, PixelRect, PixelSize, to_skia_rect},
     window::{EventPayload, ShouldRender},
 };
 
@@ -60,9 +104,11 @@ use skia_safe::graphics::{
 #[cfg(feature = "gpu_profiling")]
 use crate::profiling::GpuCtx;
 
-use cursor_renderer::CursorRenderer;
+use cursor_renderer::{CursorColor, CursorRenderer};
 pub use fonts::caching_shaper::CachingShaper;
+use grid_renderer::ComponentOpacity;
 pub use grid_renderer::GridRenderer;
+pub use info::{GraphicsInfo, RendererInfo};
 pub use rendered_window::{RenderedWindow, WindowDrawCommand, WindowDrawDetails};
 
 pub use vsync::VSync;
@@ -70,6 +116,7 @@ pub use vsync::VSync;
 use self::fonts::font_options::FontOptions;
 
 const MESSAGE_SELECTION_ALPHA: f32 = 0.35;
+const MIN_RENDER_SCALE: f32 = 0.25;
 const STARTUP_MESSAGE_LIMIT: usize = 4;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -98,6 +145,10 @@ pub struct RendererSettings {
     position_animation_length: f32,
     scroll_animation_length: f32,
     scroll_animation_far_lines: u32,
+    scroll_animation_min_lines: u32,
+    scroll_buffer_lines: u32,
+    scroll_overscan_lines: u32,
+    render_scale: f32,
     floating_blur: bool,
     floating_blur_amount_x: f32,
     floating_blur_amount_y: f32,
@@ -111,8 +162,31 @@ pub struct RendererSettings {
     underline_stroke_scale: f32,
     text_gamma: f32,
     text_contrast: f32,
+    text_antialiasing: TextAntialiasing,
+    font_hinting: TextHinting,
+    highlight_fonts: HighlightFonts,
+    fallback_fonts: FallbackFonts,
+    highlight_inspector: bool,
     experimental_layer_grouping: bool,
     pixel_geometry: PixelGeometry,
+    number_fade: bool,
+    gui_gutter_icons: bool,
+    show_tabs_indicator: bool,
+    crisp_listchars: bool,
+    diff_accent: bool,
+    typewriter_scroll: bool,
+    floating_snap: FloatingSnap,
+    floating_font_scale: f32,
+    max_floating_windows: u32,
+    animate_splits: bool,
+    cmdline_linger_ms: u32,
+    ghost_cursors: bool,
+    theme_transition_ms: u32,
+    no_animations: NoAnimations,
+    show_splash: bool,
+    proportional_winbar: bool,
+    renderer: RendererBackend,
+    background_color: BackgroundColor,
 }
 
 impl Default for RendererSettings {
@@ -121,6 +195,10 @@ impl Default for RendererSettings {
             position_animation_length: 0.15,
             scroll_animation_length: 0.3,
             scroll_animation_far_lines: 1,
+            scroll_animation_min_lines: 0,
+            scroll_buffer_lines: 0,
+            scroll_overscan_lines: 0,
+            render_scale: 1.0,
             floating_blur: true,
             floating_blur_amount_x: 2.0,
             floating_blur_amount_y: 2.0,
@@ -134,12 +212,65 @@ impl Default for RendererSettings {
             underline_stroke_scale: 1.,
             text_gamma: 0.0,
             text_contrast: 0.5,
+            text_antialiasing: TextAntialiasing::default(),
+            font_hinting: TextHinting::default(),
+            highlight_fonts: HighlightFonts::default(),
+            fallback_fonts: FallbackFonts::default(),
+            highlight_inspector: false,
             experimental_layer_grouping: false,
             pixel_geometry: PixelGeometry::default(),
+            number_fade: false,
+            gui_gutter_icons: false,
+            show_tabs_indicator: false,
+            crisp_listchars: false,
+            diff_accent: false,
+            typewriter_scroll: false,
+            floating_snap: FloatingSnap::None,
+            floating_font_scale: 1.0,
+            max_floating_windows: 200,
+            animate_splits: false,
+            cmdline_linger_ms: 0,
+            ghost_cursors: false,
+            theme_transition_ms: 0,
+            no_animations: NoAnimations::Off,
+            show_splash: true,
+            proportional_winbar: false,
+            renderer: RendererBackend::Auto,
+            background_color: BackgroundColor::Auto,
         }
     }
 }
 
+impl RendererSettings {
+    fn with_animation_scale(mut self, scale: f32) -> Self {
+        self.position_animation_length *= scale;
+        self.scroll_animation_length *= scale;
+        self
+    }
+}
+
+/// Set through `neovide_no_animations`, which turns every animation off whatever their own
+/// settings are
+pub fn animations_disabled(settings: &Settings) -> bool {
+    settings
+        .get::<RendererSettings>()
+        .no_animations
+        .disables_animations(settings.get::<MotionState>().reduce_motion)
+}
+
+/// Whether the reduce motion setting of the OS needs to be watched
+pub fn follows_reduced_motion(settings: &Settings) -> bool {
+    settings.get::<RendererSettings>().no_animations == NoAnimations::Auto
+}
+
+fn current_animation_scale(settings: &Settings) -> f32 {
+    if animations_disabled(settings) {
+        0.0
+    } else {
+        animation_scale(battery_saver_active(settings))
+    }
+}
+
 // Since draw commmands are inserted into a heap, we need to implement Ord such that
 // the commands that should be processed first (such as window draw commands or close
 // window) are sorted as larger than the ones that should be handled later
@@ -149,15 +280,40 @@ impl Default for RendererSettings {
 pub enum DrawCommand {
     UpdateCursor(Cursor),
     FontChanged(String),
+    FontWideChanged(String),
     LineSpaceChanged(f32),
+    AmbiWidthChanged(AmbiWidth),
     DefaultStyleChanged(Style),
     ModeChanged(EditorMode),
-    StartupMessage { message: StartupMessage, replace_last: bool, append: bool },
+    TablineUpdate {
+        current_tab: u64,
+        tabs: Vec<TabInfo>,
+        current_buffer: Option<u64>,
+        buffers: Vec<BufferInfo>,
+    },
+    ModifiedBuffers(Vec<u64>),
+    ShowTabline(u64),
+    CmdlineEntered,
+    CmdlineLeft(String),
+    LuaOverlay(OverlayCommand),
+    /// The cursor position the cursor left in the window, in its text area
+    GhostCursor {
+        grid_id: u64,
+        position: Option<(u64, u64)>,
+    },
+    StartupMessage {
+        message: StartupMessage,
+        replace_last: bool,
+        append: bool,
+    },
     ClearStartupMessages,
     StartupPrompt,
     ReplayStartupMessages,
     UIReady,
-    Window { grid_id: u64, command: WindowDrawCommand },
+    Window {
+        grid_id: u64,
+        command: WindowDrawCommand,
+    },
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -172,6 +328,17 @@ pub struct Renderer {
     current_mode: EditorMode,
 
     pub progress_bar: ProgressBar,
+    pub tab_bar: TabBar,
+    pub buffer_bar: BufferBar,
+    pub context_menu: ContextMenu,
+    pub command_palette: CommandPalette,
+    pub crash_overlay: CrashOverlay,
+    cmdline_linger: CmdlineLinger,
+    pub keycast: Keycast,
+    lua_overlays: LuaOverlays,
+    ghost_cursors: GhostCursors,
+    theme_transition: ThemeTransition,
+    pub sticky_modifiers: Option<String>,
 
     pub rendered_windows: HashMap<u64, RenderedWindow>,
     pub window_regions: Vec<WindowDrawDetails>,
@@ -184,6 +351,9 @@ pub struct Renderer {
     message_selection: Option<MessageSelection>,
     startup_messages: Vec<StartupMessage>,
     startup_message_ui_restored: bool,
+    scaled_surface: Option<Surface>,
+    floating_limit_warned: bool,
+    splash: Splash,
 }
 
 /// Results of processing the draw commands from the command channel.
@@ -215,6 +385,11 @@ impl Renderer {
         let profiler = profiler::Profiler::new(12.0, settings.clone());
 
         let progress_bar = ProgressBar::new();
+        let tab_bar = TabBar::new();
+        let buffer_bar = BufferBar::new();
+        let context_menu = ContextMenu::new();
+        let command_palette = CommandPalette::new();
+        let crash_overlay = CrashOverlay::new();
 
         Renderer {
             rendered_windows,
@@ -224,12 +399,26 @@ impl Renderer {
             window_regions,
             profiler,
             progress_bar,
+            tab_bar,
+            buffer_bar,
+            context_menu,
+            command_palette,
+            crash_overlay,
+            cmdline_linger: CmdlineLinger::new(),
+            keycast: Keycast::new(),
+            lua_overlays: LuaOverlays::new(),
+            ghost_cursors: GhostCursors::new(),
+            theme_transition: ThemeTransition::new(),
+            sticky_modifiers: None,
             os_scale_factor,
             user_scale_factor,
             settings,
             message_selection: None,
             startup_messages: Vec::new(),
             startup_message_ui_restored: false,
+            scaled_surface: None,
+            floating_limit_warned: false,
+            splash: Splash::new(),
         }
     }
 
@@ -237,6 +426,17 @@ impl Renderer {
         self.cursor_renderer.handle_event(event);
     }
 
+    /// Scrolls the window past the end of its buffer, up to `g:neovide_scroll_overscan_lines`
+    pub fn overscan(&mut self, window_id: u64, rows: f32) {
+        let max_lines = self.settings.get::<RendererSettings>().scroll_overscan_lines;
+        if max_lines == 0 {
+            return;
+        }
+        if let Some(window) = self.rendered_windows.get_mut(&window_id) {
+            window.overscan(rows, max_lines);
+        }
+    }
+
     pub fn font_names(&self) -> Vec<String> {
         self.grid_renderer.font_names()
     }
@@ -246,7 +446,9 @@ impl Renderer {
     }
 
     pub fn prepare_frame(&mut self) -> ShouldRender {
-        self.cursor_renderer.prepare_frame()
+        let mut should_render = self.cursor_renderer.prepare_frame();
+        should_render.update(self.keycast.prepare_frame(Instant::now()));
+        should_render
     }
 
     pub fn draw_frame(
@@ -256,19 +458,103 @@ impl Renderer {
         dt: f32,
     ) {
         tracy_zone!("renderer_draw_frame");
+        let render_scale = clamp_render_scale(self.settings.get::<RendererSettings>().render_scale);
+        if render_scale >= 1.0 {
+            self.scaled_surface = None;
+            self.draw_scene(root_canvas, content_region, 1.0, dt);
+            return;
+        }
+
+        // Render everything to a smaller offscreen surface, and upscale that to the window. All the
+        // layout is still done in window pixels, the canvas scale takes care of the conversion.
+        let window_size = root_canvas.base_layer_size();
+        let scaled_size = scaled_surface_size(window_size, render_scale);
+        let mut scaled_surface = match self.scaled_surface.take() {
+            Some(surface) if surface.image_info().dimensions() == scaled_size => surface,
+            _ => {
+                let image_info = root_canvas.image_info().with_dimensions(scaled_size);
+                let Some(surface) = root_canvas.new_surface(&image_info, None) else {
+                    log::warn!("Could not create a surface for neovide_render_scale");
+                    self.draw_scene(root_canvas, content_region, 1.0, dt);
+                    return;
+                };
+                surface
+            }
+        };
+
+        self.draw_scene(scaled_surface.canvas(), content_region, render_scale, dt);
+
+        let image = scaled_surface.image_snapshot();
+        let mut paint = Paint::default();
+        paint.set_blend_mode(BlendMode::Src);
+        root_canvas.save();
+        root_canvas.reset_matrix();
+        root_canvas.clear(Color::TRANSPARENT);
+        root_canvas.draw_image_rect_with_sampling_options(
+            &image,
+            None,
+            Rect::from_isize(window_size),
+            SamplingOptions::from(FilterMode::Linear),
+            &paint,
+        );
+        root_canvas.restore();
+        self.scaled_surface = Some(scaled_surface);
+    }
+
+    fn draw_scene(
+        &mut self,
+        root_canvas: &Canvas,
+        content_region: Option<&PixelRect<f32>>,
+        render_scale: f32,
+        dt: f32,
+    ) {
         let window_settings = self.settings.get::<WindowSettings>();
         let opacity = if window_settings.normal_opacity < 1.0 {
             window_settings.normal_opacity
         } else {
             window_settings.opacity
         };
+        let opacity = ComponentOpacity::from_settings(&window_settings).background_alpha(opacity);
         let default_background = self.grid_renderer.get_default_background(opacity);
         let grid_scale = self.grid_renderer.grid_scale;
+        let background_color = self.settings.get::<RendererSettings>().background_color;
 
         let layer_grouping = self.settings.get::<RendererSettings>().experimental_layer_grouping;
+        let number_fade = self.settings.get::<RendererSettings>().number_fade;
+        let typewriter_scroll = self.settings.get::<RendererSettings>().typewriter_scroll;
+        let max_floating_windows = self.settings.get::<RendererSettings>().max_floating_windows;
+        let cursor = self.cursor_renderer.cursor();
+        let ghost_cursor_rects = if self.settings.get::<RendererSettings>().ghost_cursors {
+            self.ghost_cursors.rects(&self.rendered_windows, cursor.parent_window_id, grid_scale)
+        } else {
+            Vec::new()
+        };
+        for window in self.rendered_windows.values_mut() {
+            let cursor_row =
+                (window.id == cursor.parent_window_id).then_some(cursor.grid_position.1);
+            window.set_number_fade_cursor_row(cursor_row.filter(|_| number_fade));
+            window.set_typewriter_cursor_row(typewriter_scroll, cursor_row);
+        }
         root_canvas.clear(default_background);
         root_canvas.save();
         root_canvas.reset_matrix();
+        root_canvas.scale((render_scale, render_scale));
+
+        // The padding and the space left after the last full cell are outside of the root grid
+        if background_color != BackgroundColor::Auto
+            && let Some(root_window) = self.rendered_windows.get(&1)
+        {
+            let grid_rect = to_skia_rect(&root_window.pixel_region(grid_scale));
+            let mut paint = Paint::new(
+                Color4f::from(background_color.padding_fill(default_background, opacity)),
+                None,
+            );
+            paint.set_blend_mode(BlendMode::Src);
+            root_canvas.save();
+            root_canvas.clip_rect(grid_rect, ClipOp::Difference, Some(false));
+            root_canvas.draw_paint(&paint);
+            root_canvas.restore();
+        }
 
         if let Some(content_region) = content_region {
             root_canvas.clip_rect(to_skia_rect(content_region), None, Some(false));
@@ -278,18 +564,18 @@ impl Renderer {
         }
 
         let (root_windows, floating_layers) = {
-            let (mut root_windows, mut floating_windows): (
-                Vec<&mut RenderedWindow>,
-                Vec<&mut RenderedWindow>,
-            ) = self
-                .rendered_windows
-                .values_mut()
-                .filter(|window| !window.hidden)
-                .partition(|window| window.anchor_info.is_none());
-
-            root_windows
-                .sort_by(|window_a, window_b| window_a.id.partial_cmp(&window_b.id).unwrap());
-            floating_windows.sort_by(floating_sort);
+            let (root_windows, mut floating_windows) =
+                partition_windows(&mut self.rendered_windows);
+            let floating_count = floating_windows.len();
+            if limit_floating_windows(&mut floating_windows, max_floating_windows)
+                && !self.floating_limit_warned
+            {
+                log::warn!(
+                    "{floating_count} floating windows are open, only the topmost \
+                     {max_floating_windows} are drawn, see g:neovide_max_floating_windows"
+                );
+                self.floating_limit_warned = true;
+            }
 
             let mut floating_layers = vec![];
 
@@ -351,18 +637,26 @@ impl Renderer {
         let max_root_x = max_window_max_x(&root_windows, grid_scale);
         let root_window_regions = root_windows
             .into_iter()
-            .map(|window| {
+            .filter_map(|window| {
                 let region = window.pixel_region(grid_scale);
                 let rightmost_root_window = is_rightmost_window_edge(region.max.x, max_root_x);
-                window.draw(
+                let details = window.draw(
                     root_canvas,
                     default_background,
                     grid_scale,
                     content_region.copied(),
                     rightmost_root_window,
-                )
+                );
+                // A closing split is only drawn, the mouse can't reach its grid anymore
+                (!window.is_closing()).then_some(details)
             })
             .collect_vec();
+        // Floating windows cover the ghosts like the text of the splits
+        ghost_cursors::draw(
+            root_canvas,
+            &ghost_cursor_rects,
+            self.grid_renderer.default_style.foreground(&self.grid_renderer.default_style.colors),
+        );
 
         let floating_window_regions = floating_layers
             .into_iter()
@@ -389,7 +683,60 @@ impl Renderer {
         root_canvas.restore();
 
         let progress_bar_settings = self.settings.get::<ProgressBarSettings>();
+        root_canvas.save();
+        root_canvas.scale((render_scale, render_scale));
         self.progress_bar.draw(&progress_bar_settings, root_canvas, &self.grid_renderer, grid_size);
+        if let Some(content_region) = content_region {
+            let buffer_bar_region = self.buffer_bar.draw(
+                window_settings.buffer_bar,
+                root_canvas,
+                &mut self.grid_renderer,
+                content_region,
+            );
+            self.tab_bar.draw(
+                window_settings.tabs,
+                root_canvas,
+                &mut self.grid_renderer,
+                &buffer_bar_region,
+            );
+        }
+        let window_size = root_canvas.base_layer_size();
+        let bounds = PixelSize::new(
+            window_size.width as f32 / render_scale,
+            window_size.height as f32 / render_scale,
+        );
+        self.lua_overlays.draw(root_canvas, &mut self.grid_renderer);
+        if self.settings.get::<RendererSettings>().show_splash {
+            self.splash.draw(root_canvas, &mut self.grid_renderer, bounds);
+        }
+        if self.settings.get::<RendererSettings>().highlight_inspector {
+            highlight_inspector::draw(
+                root_canvas,
+                &mut self.grid_renderer,
+                self.cursor_renderer.cursor(),
+                bounds,
+            );
+        }
+        self.cmdline_linger.draw(
+            root_canvas,
+            &mut self.grid_renderer,
+            Duration::from_millis(self.settings.get::<RendererSettings>().cmdline_linger_ms.into()),
+            bounds,
+        );
+        self.keycast.draw(root_canvas, &mut self.grid_renderer, bounds);
+        if let Some(sticky_modifiers) = &self.sticky_modifiers {
+            overlay_label::draw(
+                root_canvas,
+                &mut self.grid_renderer,
+                sticky_modifiers,
+                overlay_label::Corner::BottomLeft,
+                bounds,
+            );
+        }
+        self.context_menu.draw(root_canvas, &mut self.grid_renderer, bounds);
+        self.command_palette.draw(root_canvas, &mut self.grid_renderer, bounds);
+        self.crash_overlay.draw(root_canvas, &mut self.grid_renderer, bounds);
+        root_canvas.restore();
 
         #[cfg(feature = "profiling")]
         plot_skia_cache();
@@ -474,11 +821,15 @@ impl Renderer {
             root_windows.into_iter().chain(floating_windows)
         };
 
-        let settings = self.settings.get::<RendererSettings>();
+        let settings = self
+            .settings
+            .get::<RendererSettings>()
+            .with_animation_scale(current_animation_scale(&self.settings));
         // Clippy recommends short-circuiting with any which is not what we want
         #[allow(clippy::unnecessary_fold)]
         let mut animating =
             windows.fold(false, |acc, window| acc | window.animate(&settings, grid_rect, dt));
+        self.rendered_windows.retain(|_, window| !window.is_closed());
 
         let windows = &self.rendered_windows;
         let grid_scale = self.grid_renderer.grid_scale;
@@ -490,6 +841,22 @@ impl Renderer {
         self.progress_bar.animate(&progress_bar_settings, dt);
         animating |= self.progress_bar.is_animating();
 
+        self.splash.animate(dt);
+        animating |= self.splash.is_animating();
+
+        if let Some(style) = self.theme_transition.animate(dt) {
+            self.grid_renderer.default_style = style;
+            for window in self.rendered_windows.values_mut() {
+                window.invalidate_lines();
+            }
+        }
+        animating |= self.theme_transition.is_animating();
+
+        animating |= self
+            .cmdline_linger
+            .is_animating(Duration::from_millis(settings.cmdline_linger_ms.into()));
+        animating |= self.keycast.is_animating();
+
         animating
     }
 
@@ -525,7 +892,7 @@ impl Renderer {
         };
 
         for draw_command in batch {
-            self.handle_draw_command(draw_command, &mut result);
+            self.handle_draw_command(draw_command, &settings, &mut result);
             tracy_named_frame!("neovim draw batch processed");
         }
 
@@ -538,6 +905,7 @@ impl Renderer {
         }
 
         self.flush(&settings);
+        self.splash.flushed();
 
         result
     }
@@ -564,26 +932,48 @@ impl Renderer {
 
     pub fn prepare_lines(&mut self, force: bool) {
         let opacity = self.settings.get::<WindowSettings>().opacity;
-        self.rendered_windows
-            .iter_mut()
-            .for_each(|(_, w)| w.prepare_lines(&mut self.grid_renderer, opacity, force));
+        let proportional_winbar = self.settings.get::<RendererSettings>().proportional_winbar;
+        let ligature_split_cell = self.cursor_renderer.ligature_split_cell();
+        self.rendered_windows.iter_mut().for_each(|(_, w)| {
+            w.set_ligature_split_cursor(
+                ligature_split_cell
+                    .filter(|(window_id, ..)| *window_id == w.id)
+                    .map(|(_, row, column)| (row, column)),
+            );
+            w.prepare_lines(&mut self.grid_renderer, opacity, proportional_winbar, force)
+        });
     }
 
-    fn handle_draw_command(&mut self, draw_command: DrawCommand, result: &mut DrawCommandResult) {
+    fn handle_draw_command(
+        &mut self,
+        draw_command: DrawCommand,
+        settings: &RendererSettings,
+        result: &mut DrawCommandResult,
+    ) {
         match draw_command {
             DrawCommand::Window { grid_id, command: WindowDrawCommand::Close } => {
-                self.rendered_windows.remove(&grid_id);
+                self.ghost_cursors.window_closed(grid_id);
+                // Closed splits collapse before they are removed
+                if !self
+                    .rendered_windows
+                    .get_mut(&grid_id)
+                    .is_some_and(|window| window.start_closing())
+                {
+                    self.rendered_windows.remove(&grid_id);
+                }
             }
             DrawCommand::Window { grid_id, command } => {
                 match self.rendered_windows.entry(grid_id) {
                     Entry::Occupied(mut occupied_entry) => {
                         let rendered_window = occupied_entry.get_mut();
+                        rendered_window.animate_splits = settings.animate_splits;
                         rendered_window.handle_window_draw_command(command);
                     }
                     Entry::Vacant(vacant_entry) => match command {
                         WindowDrawCommand::Position { .. }
                         | WindowDrawCommand::ViewportMargins { .. } => {
                             let mut new_window = RenderedWindow::new(grid_id);
+                            new_window.animate_splits = settings.animate_splits;
                             new_window.handle_window_draw_command(command);
                             vacant_entry.insert(new_window);
                         }
@@ -599,23 +989,65 @@ impl Renderer {
                     },
                 }
             }
+            DrawCommand::GhostCursor { grid_id, position } => {
+                self.ghost_cursors.set(grid_id, position.map(GridPos::from));
+            }
             DrawCommand::UpdateCursor(new_cursor) => {
                 self.cursor_renderer.update_cursor(new_cursor);
             }
             DrawCommand::FontChanged(new_font) => {
-                self.grid_renderer.update_font(&new_font);
-                result.font_changed = true;
+                // The grid is only resized to the new cell size when the font could be loaded
+                result.font_changed |= self.grid_renderer.update_font(&new_font);
+            }
+            DrawCommand::FontWideChanged(new_font) => {
+                self.grid_renderer.shaper.update_font_wide(&new_font);
             }
             DrawCommand::LineSpaceChanged(new_linespace) => {
                 self.grid_renderer.update_linespace(new_linespace);
                 result.font_changed = true;
             }
+            DrawCommand::AmbiWidthChanged(ambiwidth) => {
+                self.grid_renderer.ambiwidth = ambiwidth;
+                self.buffer_bar.set_ambiwidth(ambiwidth);
+            }
             DrawCommand::DefaultStyleChanged(new_style) => {
-                self.grid_renderer.default_style = Arc::new(new_style);
+                let settings = self.settings.get::<RendererSettings>();
+                let duration = match animations_disabled(&self.settings) {
+                    true => Duration::ZERO,
+                    false => Duration::from_millis(settings.theme_transition_ms.into()),
+                };
+                self.grid_renderer.default_style = self.theme_transition.change(
+                    &self.grid_renderer.default_style,
+                    new_style,
+                    duration,
+                );
+                // Lines without a style of their own use the default colors
+                for window in self.rendered_windows.values_mut() {
+                    window.invalidate_lines();
+                }
             }
             DrawCommand::ModeChanged(new_mode) => {
                 self.current_mode = new_mode;
             }
+            DrawCommand::TablineUpdate { current_tab, tabs, current_buffer, buffers } => {
+                self.tab_bar.update(current_tab, tabs);
+                self.buffer_bar.update(current_buffer, buffers);
+            }
+            DrawCommand::ModifiedBuffers(buffers) => {
+                self.buffer_bar.set_modified(buffers);
+            }
+            DrawCommand::CmdlineEntered => {
+                self.cmdline_linger.entered();
+            }
+            DrawCommand::CmdlineLeft(text) => {
+                self.cmdline_linger.left(text);
+            }
+            DrawCommand::LuaOverlay(command) => {
+                self.lua_overlays.handle_command(command);
+            }
+            DrawCommand::ShowTabline(show_tabline) => {
+                self.tab_bar.set_show_tabline(show_tabline);
+            }
             DrawCommand::StartupMessage { message, replace_last, append } => {
                 self.push_startup_message(message, replace_last, append);
             }
@@ -690,12 +1122,28 @@ impl Renderer {
         self.message_selection = None;
         self.startup_messages.clear();
         self.startup_message_ui_restored = false;
+        self.splash = Splash::new();
+        self.cmdline_linger = CmdlineLinger::new();
+        self.keycast = Keycast::new();
+        self.lua_overlays = LuaOverlays::new();
+    }
+
+    /// The cells of the grid row under the cursor, and the column of the cursor in it
+    pub fn with_cursor_line_cells<T>(&self, f: impl FnOnce(&[String], u64) -> T) -> Option<T> {
+        let cursor = self.cursor_renderer.cursor();
+        let (column, row) = cursor.grid_position;
+        let window = self.rendered_windows.get(&cursor.parent_window_id)?;
+        window.with_line_cells(row as u32, |cells| f(cells, column))
     }
 
     pub fn get_cursor_destination(&self) -> PixelPos<f32> {
         self.cursor_renderer.get_destination()
     }
 
+    pub fn get_cursor_bounds(&self) -> PixelRect<f32> {
+        self.cursor_renderer.current_bounds()
+    }
+
     pub fn get_grid_size(&self) -> GridSize<u32> {
         if let Some(main_grid) = self.rendered_windows.get(&1) {
             main_grid.grid_size
@@ -716,6 +1164,33 @@ pub fn max_window_max_x(windows: &[&mut RenderedWindow], grid_scale: GridScale)
     })
 }
 
+type WindowsByDrawOrder<'w> = (Vec<&'w mut RenderedWindow>, Vec<&'w mut RenderedWindow>);
+
+/// Splits the visible windows into the root windows and the floats drawn above them, each in the
+/// order they are drawn
+fn partition_windows(windows: &mut HashMap<u64, RenderedWindow>) -> WindowsByDrawOrder<'_> {
+    let (mut root_windows, mut floating_windows): (Vec<_>, Vec<_>) = windows
+        .values_mut()
+        .filter(|window| !window.hidden)
+        .partition(|window| window.anchor_info.is_none());
+
+    root_windows.sort_by(|window_a, window_b| window_a.id.partial_cmp(&window_b.id).unwrap());
+    floating_windows.sort_by(floating_sort);
+    (root_windows, floating_windows)
+}
+
+/// Drops all but the topmost `limit` windows of the floats sorted by `floating_sort`, a limit of
+/// 0 keeps them all. The dropped windows are still tracked, they are just not drawn. Returns if
+/// any window was dropped.
+fn limit_floating_windows(floating_windows: &mut Vec<&mut RenderedWindow>, limit: u32) -> bool {
+    let limit = limit as usize;
+    if limit == 0 || floating_windows.len() <= limit {
+        return false;
+    }
+    floating_windows.drain(..floating_windows.len() - limit);
+    true
+}
+
 /// Defines how floating windows are sorted.
 fn floating_sort(window_a: &&mut RenderedWindow, window_b: &&mut RenderedWindow) -> Ordering {
     let orda = &window_a.anchor_info.as_ref().unwrap().sort_order;
@@ -723,6 +1198,210 @@ fn floating_sort(window_a: &&mut RenderedWindow, window_b: &&mut RenderedWindow)
     orda.cmp(ordb)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RendererBackend {
+    Auto,
+    OpenGL,
+    Vulkan,
+    Metal,
+    Direct3D,
+}
+
+impl ParseFromValue for RendererBackend {
+    fn parse_from_value(&mut self, value: Value) {
+        match value.as_str() {
+            Some("auto") => *self = Self::Auto,
+            Some("opengl") => *self = Self::OpenGL,
+            Some("vulkan") => *self = Self::Vulkan,
+            Some("metal") => *self = Self::Metal,
+            Some("direct3d") => *self = Self::Direct3D,
+            _ => {
+                error_msg!(
+                    "Setting renderer expected one of `auto`, `opengl`, `vulkan`, `metal`, `direct3d`, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<RendererBackend> for Value {
+    fn from(value: RendererBackend) -> Self {
+        Value::from(match value {
+            RendererBackend::Auto => "auto",
+            RendererBackend::OpenGL => "opengl",
+            RendererBackend::Vulkan => "vulkan",
+            RendererBackend::Metal => "metal",
+            RendererBackend::Direct3D => "direct3d",
+        })
+    }
+}
+
+/// How the origin of floating windows is rounded, set through `g:neovide_floating_snap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatingSnap {
+    None,
+    Pixel,
+    Cell,
+}
+
+impl ParseFromValue for FloatingSnap {
+    fn parse_from_value(&mut self, value: Value) {
+        match value.as_str() {
+            Some("none") => *self = Self::None,
+            Some("pixel") => *self = Self::Pixel,
+            Some("cell") => *self = Self::Cell,
+            _ => {
+                error_msg!(
+                    "Setting floating_snap expected one of `none`, `pixel`, `cell`, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<FloatingSnap> for Value {
+    fn from(value: FloatingSnap) -> Self {
+        Value::from(match value {
+            FloatingSnap::None => "none",
+            FloatingSnap::Pixel => "pixel",
+            FloatingSnap::Cell => "cell",
+        })
+    }
+}
+
+/// The color of the window outside the grid, set through `g:neovide_background_color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackgroundColor {
+    /// The default background of the grid
+    #[default]
+    Auto,
+    Color(Color),
+}
+
+impl BackgroundColor {
+    /// Returns the color to fill the padding with, the opacity of the window applying to the
+    /// configured color as it does to the default background
+    fn padding_fill(self, default_background: Color, opacity: f32) -> Color {
+        match self {
+            Self::Auto => default_background,
+            Self::Color(color) => {
+                let color = Color4f::from(color);
+                Color4f { a: color.a * opacity, ..color }.to_color()
+            }
+        }
+    }
+}
+
+impl ParseFromValue for BackgroundColor {
+    fn parse_from_value(&mut self, value: Value) {
+        let parsed = value.as_str().and_then(|text| match text {
+            "auto" => Some(Self::Auto),
+            text => CursorColor::parse_hex(text).map(Self::Color),
+        });
+        match parsed {
+            Some(color) => *self = color,
+            None => {
+                error_msg!(
+                    "Setting background_color expected `auto` or a hex color like `#1e1e2e`, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<BackgroundColor> for Value {
+    fn from(value: BackgroundColor) -> Self {
+        Value::from(match value {
+            BackgroundColor::Auto => "auto".to_owned(),
+            BackgroundColor::Color(color) => {
+                format!("#{:02x}{:02x}{:02x}{:02x}", color.r(), color.g(), color.b(), color.a())
+            }
+        })
+    }
+}
+
+/// Set through `g:neovide_no_animations`, either a boolean or `auto`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoAnimations {
+    #[default]
+    Off,
+    On,
+    /// Follows the reduce motion accessibility setting of the OS
+    Auto,
+}
+
+impl NoAnimations {
+    fn disables_animations(self, os_reduces_motion: bool) -> bool {
+        match self {
+            Self::Off => false,
+            Self::On => true,
+            Self::Auto => os_reduces_motion,
+        }
+    }
+}
+
+impl ParseFromValue for NoAnimations {
+    fn parse_from_value(&mut self, value: Value) {
+        let parsed = match &value {
+            Value::Boolean(true) => Some(Self::On),
+            Value::Boolean(false) => Some(Self::Off),
+            value if value.as_str() == Some("auto") => Some(Self::Auto),
+            _ => None,
+        };
+        match parsed {
+            Some(no_animations) => *self = no_animations,
+            None => {
+                error_msg!(
+                    "Setting no_animations expected a boolean or `auto`, but received {value:?}"
+                );
+            }
+        }
+    }
+}
+
+impl From<NoAnimations> for Value {
+    fn from(value: NoAnimations) -> Self {
+        match value {
+            NoAnimations::Off => Value::from(false),
+            NoAnimations::On => Value::from(true),
+            NoAnimations::Auto => Value::from("auto"),
+        }
+    }
+}
+
+/// The backends supported on this platform, the preferred one first
+#[cfg(target_os = "windows")]
+const AVAILABLE_BACKENDS: &[RendererBackend] =
+    &[RendererBackend::Direct3D, RendererBackend::OpenGL];
+#[cfg(target_os = "macos")]
+const AVAILABLE_BACKENDS: &[RendererBackend] = &[RendererBackend::Metal, RendererBackend::OpenGL];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const AVAILABLE_BACKENDS: &[RendererBackend] = &[RendererBackend::OpenGL];
+
+fn select_backend(requested: RendererBackend, available: &[RendererBackend]) -> RendererBackend {
+    let preferred = available[0];
+    match requested {
+        RendererBackend::Auto => preferred,
+        requested if available.contains(&requested) => requested,
+        requested => {
+            log::warn!(
+                "The {requested:?} renderer is not available on this platform, using {preferred:?} instead"
+            );
+            preferred
+        }
+    }
+}
+
+/// The backend the windows are created with, `--opengl` takes precedence over `neovide_renderer`
+pub fn selected_backend(settings: &Settings) -> RendererBackend {
+    let requested = if settings.get::<CmdLineSettings>().opengl {
+        RendererBackend::OpenGL
+    } else {
+        settings.get::<RendererSettings>().renderer
+    };
+    select_backend(requested, AVAILABLE_BACKENDS)
+}
+
 #[derive(Clone)]
 pub enum WindowConfigType {
     OpenGL(glutin::config::Config),
@@ -744,8 +1423,7 @@ pub fn build_window_config(
     event_loop: &ActiveEventLoop,
     settings: &Settings,
 ) -> WindowConfig {
-    let cmd_line_settings = settings.get::<CmdLineSettings>();
-    if cmd_line_settings.opengl {
+    if selected_backend(settings) == RendererBackend::OpenGL {
         opengl::build_window(window_attributes, event_loop)
     } else {
         let window = event_loop.create_window(window_attributes).unwrap();
@@ -760,8 +1438,7 @@ pub fn build_window_config(
     event_loop: &ActiveEventLoop,
     settings: &Settings,
 ) -> WindowConfig {
-    let cmd_line_settings = settings.get::<CmdLineSettings>();
-    if cmd_line_settings.opengl {
+    if selected_backend(settings) == RendererBackend::OpenGL {
         opengl::build_window(window_attributes, event_loop)
     } else {
         let window = event_loop.create_window(window_attributes).unwrap();
@@ -774,9 +1451,38 @@ pub fn build_window_config(
 pub fn build_window_config(
     window_attributes: WindowAttributes,
     event_loop: &ActiveEventLoop,
-    _settings: &Settings,
+    settings: &Settings,
 ) -> WindowConfig {
-    opengl::build_window(window_attributes, event_loop)
+    // Another requested backend was already replaced by OpenGL with a warning
+    match selected_backend(settings) {
+        RendererBackend::OpenGL => opengl::build_window(window_attributes, event_loop),
+        backend => unreachable!("the {backend:?} renderer is not available on this platform"),
+    }
+}
+
+pub fn clamp_render_scale(render_scale: f32) -> f32 {
+    if render_scale.is_finite() { render_scale.clamp(MIN_RENDER_SCALE, 1.0) } else { 1.0 }
+}
+
+/// The size of the offscreen surface used when `neovide_render_scale` is below 1
+pub fn scaled_surface_size(window_size: ISize, render_scale: f32) -> ISize {
+    let render_scale = clamp_render_scale(render_scale);
+    ISize::new(
+        ((window_size.width as f32 * render_scale).round() as i32).max(1),
+        ((window_size.height as f32 * render_scale).round() as i32).max(1),
+    )
+}
+
+/// Returns the color type and color space used for the window surface, depending on whether it got
+/// the sRGB requested with `--srgb`. When the backbuffer is sRGB encoded the GPU does the conversion
+/// on write, so the grid can be composited in linear space, which avoids the dark fringes around
+/// light text on dark backgrounds.
+pub fn surface_color_format(srgb: bool) -> (ColorType, ColorSpace) {
+    if srgb {
+        (ColorType::SRGBA8888, ColorSpace::new_srgb_linear())
+    } else {
+        (ColorType::RGBA8888, ColorSpace::new_srgb())
+    }
 }
 
 pub trait SkiaRenderer {
@@ -785,6 +1491,9 @@ pub trait SkiaRenderer {
     fn swap_buffers(&mut self);
     fn canvas(&mut self) -> &Canvas;
     fn resize(&mut self);
+    /// Switches the present mode of the surface, without recreating the window
+    fn set_vsync(&mut self, enabled: bool);
+    fn graphics_info(&self) -> GraphicsInfo;
     fn create_vsync(&self, proxy: EventLoopProxy<EventPayload>) -> VSync;
     #[cfg(feature = "gpu_profiling")]
     fn tracy_create_gpu_context(&self, name: &str) -> Box<dyn GpuCtx>;
@@ -820,8 +1529,13 @@ pub fn create_skia_renderer(
 mod tests {
     use std::sync::Arc;
 
+    use skia_safe::{AlphaType, ImageInfo, surfaces};
+
     use super::*;
-    use crate::bridge::MessageKind;
+    use crate::{
+        bridge::{MessageKind, WindowAnchor},
+        editor::{AnchorInfo, Colors, SortOrder},
+    };
 
     fn create_renderer() -> Renderer {
         let settings = Arc::new(Settings::new());
@@ -845,6 +1559,25 @@ mod tests {
         assert_eq!(renderer.grid_renderer.shaper.current_size(), initial_size * 1.5);
     }
 
+    #[test]
+    fn the_cells_follow_the_guifont_only_when_it_loads() {
+        let mut renderer = create_renderer();
+        let initial_scale = renderer.grid_renderer.grid_scale;
+
+        let result = renderer.handle_draw_commands(vec![DrawCommand::FontChanged(
+            "No Such Font Anywhere:h30".into(),
+        )]);
+        assert!(!result.font_changed);
+        assert_eq!(renderer.grid_renderer.grid_scale.height(), initial_scale.height());
+
+        // Any installed font, at twice the default size
+        let family = renderer.font_names().into_iter().next().expect("no fonts are installed");
+        let result =
+            renderer.handle_draw_commands(vec![DrawCommand::FontChanged(format!("{family}:h28"))]);
+        assert!(result.font_changed);
+        assert!(renderer.grid_renderer.grid_scale.height() > initial_scale.height());
+    }
+
     #[test]
     fn scale_can_be_resynced_from_settings_after_renderer_creation() {
         let settings = Arc::new(Settings::new());
@@ -863,6 +1596,18 @@ mod tests {
         assert_eq!(renderer.grid_renderer.shaper.current_size(), initial_size * 1.5);
     }
 
+    #[test]
+    fn the_splash_fades_out_on_the_first_flush() {
+        let mut renderer = create_renderer();
+        assert!(!renderer.splash.is_animating());
+
+        renderer.handle_draw_commands(vec![DrawCommand::UIReady]);
+        assert!(renderer.splash.is_animating());
+
+        renderer.clear();
+        assert!(!renderer.splash.is_animating());
+    }
+
     #[test]
     fn startup_messages_are_kept_until_ui_ready() {
         let mut renderer = create_renderer();
@@ -1067,4 +1812,330 @@ mod tests {
             ]
         );
     }
+
+    // Draws half transparent white over black and returns the resulting sRGB encoded red channel
+    fn blended_edge_pixel(srgb: bool) -> u8 {
+        let (color_type, color_space) = surface_color_format(srgb);
+        let info = ImageInfo::new((1, 1), color_type, AlphaType::Premul, color_space);
+        let mut surface = surfaces::raster(&info, None, None).unwrap();
+
+        let canvas = surface.canvas();
+        canvas.clear(Color::BLACK);
+        let mut paint = Paint::default();
+        paint.set_color(Color::WHITE);
+        paint.set_alpha_f(0.5);
+        canvas.draw_rect(Rect::from_wh(1.0, 1.0), &paint);
+
+        let dst_info =
+            ImageInfo::new((1, 1), ColorType::RGBA8888, AlphaType::Premul, ColorSpace::new_srgb());
+        let mut pixels = [0u8; 4];
+        assert!(surface.read_pixels(&dst_info, &mut pixels, 4, (0, 0)));
+        pixels[0]
+    }
+
+    #[test]
+    fn blending_without_srgb_composites_in_gamma_space() {
+        let edge = blended_edge_pixel(false);
+        assert!((127..=128).contains(&edge), "unexpected edge value {edge}");
+    }
+
+    #[test]
+    fn linear_blending_avoids_dark_fringes() {
+        let edge = blended_edge_pixel(true);
+        // 50% coverage in linear light is ~188 once encoded as sRGB
+        assert!((186..=190).contains(&edge), "unexpected edge value {edge}");
+        assert!(edge > blended_edge_pixel(false));
+    }
+
+    #[test]
+    fn scaled_surface_size_at_three_quarters() {
+        let size = scaled_surface_size(ISize::new(3840, 2160), 0.75);
+        assert_eq!(size, ISize::new(2880, 1620));
+    }
+
+    #[test]
+    fn scaled_surface_size_rounds_odd_sizes() {
+        let size = scaled_surface_size(ISize::new(1001, 777), 0.75);
+        assert_eq!(size, ISize::new(751, 583));
+    }
+
+    #[test]
+    fn scaled_surface_size_is_never_empty() {
+        let size = scaled_surface_size(ISize::new(1, 1), 0.75);
+        assert_eq!(size, ISize::new(1, 1));
+    }
+
+    #[test]
+    fn render_scale_is_clamped() {
+        assert_eq!(clamp_render_scale(0.75), 0.75);
+        assert_eq!(clamp_render_scale(2.0), 1.0);
+        assert_eq!(clamp_render_scale(0.0), MIN_RENDER_SCALE);
+        assert_eq!(clamp_render_scale(f32::NAN), 1.0);
+    }
+
+    #[test]
+    fn render_scale_keeps_the_grid_in_window_pixels() {
+        let mut renderer = create_renderer();
+        let grid_scale = renderer.grid_renderer.grid_scale;
+        let mut settings = renderer.settings.get::<RendererSettings>();
+        settings.render_scale = 0.75;
+        renderer.settings.set(&settings);
+
+        let info = ImageInfo::new_n32_premul((400, 300), None);
+        let mut surface = surfaces::raster(&info, None, None).unwrap();
+        renderer.draw_frame(surface.canvas(), None, 0.0);
+
+        assert_eq!(renderer.grid_renderer.grid_scale, grid_scale);
+        let scaled_surface = renderer.scaled_surface.as_ref().unwrap();
+        assert_eq!(scaled_surface.image_info().dimensions(), ISize::new(300, 225));
+    }
+
+    #[test]
+    fn the_background_color_fills_the_padding() {
+        let mut renderer = create_renderer();
+        let mut settings = renderer.settings.get::<RendererSettings>();
+        settings.show_splash = false;
+        settings.background_color = BackgroundColor::Color(Color::from_rgb(0xff, 0x00, 0x00));
+        renderer.settings.set(&settings);
+        renderer.handle_draw_commands(vec![window_command(
+            1,
+            WindowDrawCommand::Position {
+                grid_position: (0.0, 0.0),
+                grid_size: (2, 1),
+                anchor_info: None,
+                window_type: WindowType::Editor,
+            },
+        )]);
+
+        let info = ImageInfo::new_n32_premul((400, 300), None);
+        let mut surface = surfaces::raster(&info, None, None).unwrap();
+        renderer.draw_frame(surface.canvas(), None, 0.0);
+
+        let dst_info =
+            ImageInfo::new((1, 1), ColorType::RGBA8888, AlphaType::Premul, ColorSpace::new_srgb());
+        let mut pixel = [0u8; 4];
+        assert!(surface.read_pixels(&dst_info, &mut pixel, 4, (399, 299)));
+        assert_eq!(pixel, [0xff, 0x00, 0x00, 0xff]);
+    }
+
+    #[test]
+    fn cell_backgrounds_are_drawn_with_the_background_opacity() {
+        let mut renderer = create_renderer();
+        renderer.settings.set(&WindowSettings {
+            text_opacity: 0.25,
+            background_opacity: 0.5,
+            ..Default::default()
+        });
+        let red = Color4f::new(1.0, 0.0, 0.0, 1.0);
+        let style = Some(Arc::new(Style::new(Colors::new(None, Some(red), None))));
+
+        let info = ImageInfo::new_n32_premul((100, 100), None);
+        let mut surface = surfaces::raster(&info, None, None).unwrap();
+        let background =
+            renderer.grid_renderer.draw_background(surface.canvas(), &(0..2), &style, 1.0);
+        assert!(background.transparent);
+
+        let dst_info =
+            ImageInfo::new((1, 1), ColorType::RGBA8888, AlphaType::Premul, ColorSpace::new_srgb());
+        let mut pixel = [0u8; 4];
+        assert!(surface.read_pixels(&dst_info, &mut pixel, 4, (0, 0)));
+        // Half of the red, without the text opacity
+        assert!(pixel[3].abs_diff(0x80) <= 1);
+        assert_eq!(pixel[0], pixel[3]);
+        assert!(renderer.settings.get::<WindowSettings>().transparent());
+    }
+
+    #[test]
+    fn the_background_color_respects_the_opacity() {
+        let default_background = Color::from_argb(0x80, 0x10, 0x10, 0x10);
+        assert_eq!(BackgroundColor::Auto.padding_fill(default_background, 0.5), default_background);
+
+        let configured = BackgroundColor::Color(Color::from_rgb(0xff, 0x00, 0x00));
+        assert_eq!(
+            configured.padding_fill(default_background, 0.5),
+            Color::from_argb(0x80, 0xff, 0x00, 0x00)
+        );
+    }
+
+    #[test]
+    fn battery_saver_shortens_window_animations() {
+        let settings = RendererSettings::default().with_animation_scale(animation_scale(true));
+        assert_eq!(settings.position_animation_length, 0.075);
+        assert_eq!(settings.scroll_animation_length, 0.15);
+
+        let settings = RendererSettings::default().with_animation_scale(animation_scale(false));
+        assert_eq!(settings.scroll_animation_length, 0.3);
+    }
+
+    #[test]
+    fn the_os_reduce_motion_setting_is_only_followed_with_auto() {
+        for os_reduces_motion in [false, true] {
+            assert!(!NoAnimations::Off.disables_animations(os_reduces_motion));
+            assert!(NoAnimations::On.disables_animations(os_reduces_motion));
+            assert_eq!(
+                NoAnimations::Auto.disables_animations(os_reduces_motion),
+                os_reduces_motion
+            );
+        }
+
+        let settings = Settings::new();
+        settings.register::<RendererSettings>();
+        let mut renderer_settings = settings.get::<RendererSettings>();
+        renderer_settings.no_animations = NoAnimations::Auto;
+        settings.set(&renderer_settings);
+        assert!(!animations_disabled(&settings));
+        settings.set(&MotionState { reduce_motion: true });
+        assert!(animations_disabled(&settings));
+    }
+
+    fn window_command(grid_id: u64, command: WindowDrawCommand) -> DrawCommand {
+        DrawCommand::Window { grid_id, command }
+    }
+
+    fn open_window(grid_id: u64) -> DrawCommand {
+        window_command(
+            grid_id,
+            WindowDrawCommand::Position {
+                grid_position: (0.0, 0.0),
+                grid_size: (80, 10),
+                anchor_info: None,
+                window_type: WindowType::Editor,
+            },
+        )
+    }
+
+    fn scroll_position(renderer: &Renderer, grid_id: u64) -> f32 {
+        renderer.rendered_windows[&grid_id].scroll_animation.position
+    }
+
+    #[test]
+    fn the_cursor_bounds_follow_the_cursor_cell() {
+        let mut renderer = create_renderer();
+        renderer.settings.register::<cursor_renderer::CursorSettings>();
+        renderer.settings.register::<ProgressBarSettings>();
+        renderer.handle_draw_commands(vec![
+            open_window(1),
+            window_command(
+                2,
+                WindowDrawCommand::Position {
+                    grid_position: (10.0, 1.0),
+                    grid_size: (40, 8),
+                    anchor_info: None,
+                    window_type: WindowType::Editor,
+                },
+            ),
+            DrawCommand::UpdateCursor(Cursor {
+                grid_position: (3, 2),
+                parent_window_id: 2,
+                ..Cursor::new()
+            }),
+        ]);
+
+        // Long enough for the cursor animation to reach the cell
+        renderer.animate_frame(&GridRect::new((0.0, 0.0).into(), (80.0, 10.0).into()), 10.0);
+
+        let grid_scale = renderer.grid_renderer.grid_scale;
+        let bounds = renderer.get_cursor_bounds();
+        assert_eq!(bounds.min, GridPos::new(13.0, 3.0) * grid_scale);
+        assert_eq!(bounds.size(), GridSize::new(1.0, 1.0) * grid_scale);
+    }
+
+    #[test]
+    fn grids_scroll_independently() {
+        let mut renderer = create_renderer();
+        renderer.handle_draw_commands(vec![open_window(2), open_window(3)]);
+
+        renderer.handle_draw_commands(vec![
+            window_command(2, WindowDrawCommand::Viewport { scroll_delta: 3.0 }),
+            window_command(3, WindowDrawCommand::Viewport { scroll_delta: -2.0 }),
+        ]);
+        assert_eq!(scroll_position(&renderer, 2), -3.0);
+        assert_eq!(scroll_position(&renderer, 3), 2.0);
+
+        // Viewports of the same grid in one batch add up
+        renderer.handle_draw_commands(vec![
+            window_command(3, WindowDrawCommand::Viewport { scroll_delta: 1.0 }),
+            window_command(3, WindowDrawCommand::Viewport { scroll_delta: 1.0 }),
+        ]);
+        assert_eq!(scroll_position(&renderer, 2), -3.0);
+        assert_eq!(scroll_position(&renderer, 3), 0.0);
+
+        // A closed grid doesn't leave its scroll state behind
+        renderer.handle_draw_commands(vec![window_command(2, WindowDrawCommand::Close)]);
+        assert!(!renderer.rendered_windows.contains_key(&2));
+        renderer.handle_draw_commands(vec![open_window(2)]);
+        assert_eq!(scroll_position(&renderer, 2), 0.0);
+    }
+
+    fn open_float(grid_id: u64, z_index: u64) -> DrawCommand {
+        let mut sort_order = SortOrder::default();
+        sort_order.z_index = z_index;
+        window_command(
+            grid_id,
+            WindowDrawCommand::Position {
+                grid_position: (2.0, 2.0),
+                grid_size: (20, 5),
+                anchor_info: Some(AnchorInfo {
+                    anchor_grid_id: 1,
+                    anchor_type: WindowAnchor::NorthWest,
+                    anchor_left: 2.0,
+                    anchor_top: 2.0,
+                    sort_order,
+                }),
+                window_type: WindowType::Editor,
+            },
+        )
+    }
+
+    #[test]
+    fn only_the_topmost_floats_are_drawn_above_the_limit() {
+        let mut renderer = create_renderer();
+        renderer.handle_draw_commands(vec![open_window(1)]);
+        let z_indices = [40, 10, 80, 20, 70, 30, 60, 50];
+        renderer.handle_draw_commands(
+            z_indices.iter().enumerate().map(|(i, z)| open_float(i as u64 + 2, *z)).collect(),
+        );
+
+        let (_, mut floating) = partition_windows(&mut renderer.rendered_windows);
+        assert!(limit_floating_windows(&mut floating, 5));
+        let z_indices = floating
+            .iter()
+            .map(|window| window.anchor_info.as_ref().unwrap().sort_order.z_index)
+            .collect::<Vec<_>>();
+        assert_eq!(z_indices, [40, 50, 60, 70, 80]);
+
+        // The hidden floats are still tracked, so they can be closed
+        assert_eq!(renderer.rendered_windows.len(), 9);
+        renderer.handle_draw_commands(vec![window_command(3, WindowDrawCommand::Close)]);
+        assert!(!renderer.rendered_windows.contains_key(&3));
+    }
+
+    #[test]
+    fn floats_below_the_limit_are_all_drawn() {
+        let mut renderer = create_renderer();
+        renderer.handle_draw_commands(vec![open_float(2, 50), open_float(3, 60)]);
+        let (_, mut floating) = partition_windows(&mut renderer.rendered_windows);
+        assert!(!limit_floating_windows(&mut floating, 5));
+        assert!(!limit_floating_windows(&mut floating, 0));
+        assert_eq!(floating.len(), 2);
+    }
+
+    #[test]
+    fn auto_backend_picks_the_preferred_one() {
+        let available = [RendererBackend::Metal, RendererBackend::OpenGL];
+        assert_eq!(select_backend(RendererBackend::Auto, &available), RendererBackend::Metal);
+    }
+
+    #[test]
+    fn available_backend_is_forced() {
+        let available = [RendererBackend::Direct3D, RendererBackend::OpenGL];
+        assert_eq!(select_backend(RendererBackend::OpenGL, &available), RendererBackend::OpenGL);
+    }
+
+    #[test]
+    fn unavailable_backend_falls_back_to_the_preferred_one() {
+        let available = [RendererBackend::OpenGL];
+        assert_eq!(select_backend(RendererBackend::Vulkan, &available), RendererBackend::OpenGL);
+        assert_eq!(select_backend(RendererBackend::Metal, &available), RendererBackend::OpenGL);
+    }
 }
@@ -0,0 +1,24 @@
+// Relative numbers this far from the cursor, and farther, are drawn at the minimum alpha
+const FADE_DISTANCE: u64 = 12;
+const MIN_ALPHA: f32 = 0.35;
+
+/// The alpha of a line number `distance` lines away from the cursor. The number of the cursor
+/// line keeps full brightness, the others fade out linearly.
+pub fn number_fade_alpha(distance: u64) -> f32 {
+    let t = distance.min(FADE_DISTANCE) as f32 / FADE_DISTANCE as f32;
+    1.0 - (1.0 - MIN_ALPHA) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_fade_with_the_distance_to_the_cursor() {
+        assert_eq!(number_fade_alpha(0), 1.0);
+        assert!(number_fade_alpha(1) < 1.0);
+        assert!(number_fade_alpha(2) < number_fade_alpha(1));
+        assert_eq!(number_fade_alpha(FADE_DISTANCE), MIN_ALPHA);
+        assert_eq!(number_fade_alpha(FADE_DISTANCE + 20), MIN_ALPHA);
+    }
+}
@@ -19,7 +19,7 @@ use glutin::{
 use glutin_winit::DisplayBuilder;
 use raw_window_handle::HasWindowHandle;
 use skia_safe::{
-    ColorSpace, ColorType, SurfaceProps, SurfacePropsFlags,
+    SurfaceProps, SurfacePropsFlags,
     canvas::Canvas,
     gpu::{
         DirectContext, SurfaceOrigin, backend_render_targets::make_gl, gl::FramebufferInfo,
@@ -38,7 +38,10 @@ pub use super::vsync::VSyncWinDwm;
 #[cfg(target_os = "macos")]
 pub use super::vsync::VSyncMacosDisplayLink;
 
-use super::{RendererSettings, SkiaRenderer, VSync, WindowConfig, WindowConfigType};
+use super::{
+    GraphicsInfo, RendererSettings, SkiaRenderer, VSync, WindowConfig, WindowConfigType,
+    surface_color_format,
+};
 
 use crate::{profiling::tracy_gpu_zone, settings::Settings, window::EventPayload};
 
@@ -55,6 +58,7 @@ pub struct OpenGLSkiaRenderer {
     window_surface: Surface<WindowSurface>,
     config: Config,
     window: Option<Rc<Window>>,
+    linear_blending: bool,
 
     settings: Arc<Settings>,
 }
@@ -66,6 +70,12 @@ fn clamp_render_buffer_size(size: &PhysicalSize<u32>) -> PhysicalSize<u32> {
     )
 }
 
+fn gl_string(name: GLenum) -> Option<String> {
+    let value = unsafe { gl::GetString(name) };
+    (!value.is_null())
+        .then(|| unsafe { CStr::from_ptr(value.cast()) }.to_string_lossy().into_owned())
+}
+
 fn get_proc_address(surface: &Surface<WindowSurface>, addr: &CStr) -> *const c_void {
     GlDisplay::get_proc_address(&surface.display(), addr)
 }
@@ -103,15 +113,7 @@ impl OpenGLSkiaRenderer {
             .unwrap();
 
         // NOTE: We don't care if these fails, the driver can override the SwapInterval in any case, so it needs to work in all cases
-        // The OpenGL VSync is always disabled on Wayland and Windows, since they have their own
-        // implementation
-        let _ = if vsync && env::var("WAYLAND_DISPLAY").is_err() && OS != "windows" && OS != "macos"
-        {
-            window_surface
-                .set_swap_interval(&context, SwapInterval::Wait(NonZeroU32::new(1).unwrap()))
-        } else {
-            window_surface.set_swap_interval(&context, SwapInterval::DontWait)
-        };
+        let _ = window_surface.set_swap_interval(&context, current_swap_interval(vsync));
 
         gl::load_with(|s| get_proc_address(&window_surface, CString::new(s).unwrap().as_c_str()));
 
@@ -125,13 +127,27 @@ impl OpenGLSkiaRenderer {
 
         let mut gr_context = skia_safe::gpu::direct_contexts::make_gl(interface, None)
             .expect("Could not create direct context");
+
+        // Only blend in linear space when the driver actually gave us an sRGB capable framebuffer,
+        // otherwise the linear values would be presented as is and look washed out
+        let linear_blending = srgb && config.srgb_capable();
+        if srgb && !linear_blending {
+            log::warn!("sRGB was requested, but the OpenGL config is not sRGB capable");
+        }
+
         let fb_info = {
             let mut fboid: GLint = 0;
             unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut fboid) };
 
+            let format = if linear_blending {
+                skia_safe::gpu::gl::Format::SRGB8_ALPHA8
+            } else {
+                skia_safe::gpu::gl::Format::RGBA8
+            };
+
             FramebufferInfo {
                 fboid: fboid.try_into().expect("Could not create frame buffer id"),
-                format: skia_safe::gpu::gl::Format::RGBA8.into(),
+                format: format.into(),
                 ..Default::default()
             }
         };
@@ -142,6 +158,7 @@ impl OpenGLSkiaRenderer {
             &window_surface,
             &mut gr_context,
             &fb_info,
+            linear_blending,
             &settings,
         );
 
@@ -150,6 +167,7 @@ impl OpenGLSkiaRenderer {
             context,
             window: Some(window),
             config,
+            linear_blending,
             gr_context,
             fb_info,
             skia_surface,
@@ -191,10 +209,26 @@ impl SkiaRenderer for OpenGLSkiaRenderer {
             &self.window_surface,
             &mut self.gr_context,
             &self.fb_info,
+            self.linear_blending,
             &self.settings,
         );
     }
 
+    fn set_vsync(&mut self, enabled: bool) {
+        let _ =
+            self.window_surface.set_swap_interval(&self.context, current_swap_interval(enabled));
+    }
+
+    fn graphics_info(&self) -> GraphicsInfo {
+        GraphicsInfo {
+            backend: "OpenGL",
+            gpu: gl_string(gl::RENDERER),
+            driver: gl_string(gl::VERSION),
+            surface_format: if self.linear_blending { "SRGB8_ALPHA8" } else { "RGBA8" }.to_owned(),
+            linear_blending: self.linear_blending,
+        }
+    }
+
     #[allow(unused_variables)]
     fn create_vsync(&self, proxy: EventLoopProxy<EventPayload>) -> VSync {
         #[cfg(target_os = "linux")]
@@ -243,6 +277,20 @@ impl Drop for OpenGLSkiaRenderer {
     }
 }
 
+fn current_swap_interval(vsync: bool) -> SwapInterval {
+    swap_interval(vsync, env::var("WAYLAND_DISPLAY").is_ok(), OS)
+}
+
+// The OpenGL VSync is always disabled on Wayland, Windows and macOS, since they have their own
+// implementation
+fn swap_interval(vsync: bool, wayland: bool, os: &str) -> SwapInterval {
+    if vsync && !wayland && os != "windows" && os != "macos" {
+        SwapInterval::Wait(NonZeroU32::new(1).unwrap())
+    } else {
+        SwapInterval::DontWait
+    }
+}
+
 fn gen_config(mut config_iterator: Box<dyn Iterator<Item = Config> + '_>) -> Config {
     config_iterator.next().unwrap()
 }
@@ -262,6 +310,7 @@ pub fn build_window(
     WindowConfig { window: window.into(), config }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn create_surface(
     pixel_format: &Config,
     size: &PhysicalSize<u32>,
@@ -269,6 +318,7 @@ fn create_surface(
     window_surface: &Surface<WindowSurface>,
     gr_context: &mut DirectContext,
     fb_info: &FramebufferInfo,
+    srgb: bool,
     settings: &Settings,
 ) -> skia_safe::Surface {
     let size = clamp_render_buffer_size(size);
@@ -292,16 +342,42 @@ fn create_surface(
         render_settings.text_gamma,
     );
 
-    // NOTE: It would be much better to render using a linear gamma format, and SRGB backbuffer
-    // format But currently the Skia glyph atlas uses a 32-bit linear format texture, so some color
-    // precision is lost, and the font colors will be slightly off.
+    // NOTE: Linear blending requires an sRGB backbuffer. The Skia glyph atlas still uses a 32-bit
+    // linear format texture, so some color precision is lost, and the font colors can be slightly
+    // off compared to the gamma space blending.
+    let (color_type, color_space) = surface_color_format(srgb);
     wrap_backend_render_target(
         gr_context,
         &backend_render_target,
         SurfaceOrigin::BottomLeft,
-        ColorType::RGBA8888,
-        ColorSpace::new_srgb(),
+        color_type,
+        color_space,
         Some(surface_props).as_ref(),
     )
     .expect("Could not create skia backend render target")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_interval_waits_on_x11_when_vsync_is_enabled() {
+        assert_eq!(
+            swap_interval(true, false, "linux"),
+            SwapInterval::Wait(NonZeroU32::new(1).unwrap())
+        );
+    }
+
+    #[test]
+    fn swap_interval_does_not_wait_when_vsync_is_disabled() {
+        assert_eq!(swap_interval(false, false, "linux"), SwapInterval::DontWait);
+    }
+
+    #[test]
+    fn swap_interval_defers_to_the_platform_vsync() {
+        assert_eq!(swap_interval(true, true, "linux"), SwapInterval::DontWait);
+        assert_eq!(swap_interval(true, false, "windows"), SwapInterval::DontWait);
+        assert_eq!(swap_interval(true, false, "macos"), SwapInterval::DontWait);
+    }
+}
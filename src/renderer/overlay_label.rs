@@ -0,0 +1,54 @@
+use crate::{
+    renderer::{GridRenderer, text_cells::CellText},
+    units::PixelSize,
+};
+use skia_safe::{Canvas, Color4f, Paint, RRect, Rect};
+
+// Empty cells around the text, and the distance to the corner of the window in pixels
+const PADDING_COLUMNS: usize = 1;
+const MARGIN: f32 = 8.0;
+const BACKGROUND_ALPHA: f32 = 0.9;
+
+pub enum Corner {
+    BottomLeft,
+    BottomRight,
+}
+
+/// Draws a line of text on a rounded background in a bottom corner of the window, in the default
+/// colors of the grid
+pub fn draw(
+    canvas: &Canvas,
+    grid_renderer: &mut GridRenderer,
+    text: &str,
+    corner: Corner,
+    bounds: PixelSize<f32>,
+) {
+    let cells = CellText::new(text, grid_renderer.ambiwidth);
+
+    let cell_width = grid_renderer.grid_scale.width();
+    let height = grid_renderer.grid_scale.height();
+    let width = (cells.cells() + 2 * PADDING_COLUMNS) as f32 * cell_width;
+    let left = match corner {
+        Corner::BottomLeft => MARGIN,
+        Corner::BottomRight => bounds.width - width - MARGIN,
+    };
+    let rect = Rect::from_xywh(left, bounds.height - height - MARGIN, width, height);
+
+    let foreground = grid_renderer.default_style.foreground(&grid_renderer.default_style.colors);
+    let background =
+        Color4f { a: BACKGROUND_ALPHA, ..Color4f::from(grid_renderer.get_default_background(1.0)) };
+    let mut paint = Paint::new(background, None);
+    paint.set_anti_alias(true);
+    canvas.draw_rrect(RRect::new_rect_xy(rect, height / 4.0, height / 4.0), &paint);
+
+    let mut paint = Paint::new(foreground, None);
+    paint.set_anti_alias(false);
+    let origin = (
+        rect.left + PADDING_COLUMNS as f32 * cell_width,
+        rect.top + grid_renderer.shaper.baseline_offset(),
+    );
+    let style = grid_renderer.default_style.clone();
+    for blob in grid_renderer.shaper.shape_cached(cells.word(), (&style).into()).iter() {
+        canvas.draw_text_blob(blob, origin, &paint);
+    }
+}
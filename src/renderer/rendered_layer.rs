@@ -39,6 +39,12 @@ impl FloatingLayer<'_> {
                 }
                 draw_bound_rect = Rect::join2(draw_bound_rect, bounds);
             }
+            if let Some(separator) = window.message_separator_rect(region, grid_scale) {
+                if let Some(unioned) = draw_clip.op(&Path::rect(separator, None), PathOp::Union) {
+                    draw_clip = unioned;
+                }
+                draw_bound_rect = Rect::join2(draw_bound_rect, separator);
+            }
         }
 
         (draw_clip, draw_bound_rect)
@@ -103,13 +109,20 @@ impl FloatingLayer<'_> {
 
         (0..self.windows.len()).for_each(|i| {
             let window = &mut self.windows[i];
-            window.draw_background_surface(root_canvas, pixel_regions[i], grid_scale);
-            window.draw_foreground_surface(root_canvas, pixel_regions[i], grid_scale);
+            root_canvas.save();
+            let content_region = window.scale_canvas(root_canvas, pixel_regions[i]);
+            window.draw_background_surface(root_canvas, content_region, grid_scale);
+            window.draw_foreground_surface(root_canvas, content_region, grid_scale);
+            root_canvas.restore();
+            window.draw_message_separator(root_canvas, pixel_regions[i], grid_scale);
             ret.push(WindowDrawDetails {
                 id: window.id,
                 region: regions[i],
                 grid_size: window.grid_size,
                 window_type: window.window_type,
+                content_offset: window.content_offset(),
+                font_scale: window.font_scale(),
+                split_window: window.split_window(),
             });
         });
 
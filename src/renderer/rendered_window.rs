@@ -1,7 +1,8 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, ops::Range, rc::Rc};
 
 use skia_safe::{
-    Canvas, Color, Color4f, Matrix, Paint, Path, PathBuilder, Picture, PictureRecorder, Rect,
+    Canvas, ClipOp, Color, Color4f, Matrix, Paint, Path, PathBuilder, Picture, PictureRecorder,
+    Rect,
 };
 
 use crate::{
@@ -9,9 +10,21 @@ use crate::{
     cmd_line::CmdLineSettings,
     editor::{AnchorInfo, Line, LineFragment, SortOrder, WindowType},
     profiling::{tracy_plot, tracy_zone},
-    renderer::{GridRenderer, RendererSettings, animation_utils::*},
+    renderer::{
+        FloatingSnap, GridRenderer, RendererSettings,
+        animation_utils::*,
+        fonts::caching_shaper::RunContext,
+        grid_renderer::fragments_join,
+        gutter_icons::{self, GutterSign},
+        list_markers::ListMarkers,
+        number_fade::number_fade_alpha,
+        tab_indicators::{self, TabIndicator},
+    },
     settings::Settings,
-    units::{GridPos, GridRect, GridScale, GridSize, PixelPos, PixelRect, PixelVec, to_skia_rect},
+    units::{
+        GridPos, GridRect, GridScale, GridSize, PixelPos, PixelRect, PixelSize, PixelVec,
+        to_skia_rect,
+    },
     utils::RingBuffer,
 };
 
@@ -25,10 +38,85 @@ pub const NO_MULTIGRID_GRID_ID: u64 = 0;
 // briefly lags a resize.
 const MAX_TRAILING_FILL_CELLS: f32 = 1.0;
 
+/// Returns how many lines the scrollback buffer needs for a scrollable region of `inner_size`
+/// lines. By default a full screen of off-screen lines is kept around for the scroll animation,
+/// `scroll_buffer_lines` bounds that to save memory on huge windows. `0` means unbounded.
+fn scrollback_capacity(inner_size: usize, scroll_buffer_lines: u32) -> usize {
+    let offscreen_lines = match scroll_buffer_lines {
+        0 => inner_size,
+        limit => inner_size.min(limit as usize),
+    };
+    inner_size + offscreen_lines
+}
+
+/// Returns the rows a window scrolls at a flush. The Viewport events are used when there are some,
+/// since they know about the whole buffer. Grids that never get them, like the message grid, fall
+/// back to the rows moved by their Scroll events instead. A grid with a viewport ignores its
+/// Scroll events, otherwise the same scroll would be animated twice.
+fn flush_scroll_delta(
+    viewport_delta: Option<isize>,
+    grid_scroll_rows: isize,
+    has_viewport: bool,
+) -> isize {
+    match viewport_delta {
+        Some(delta) => delta,
+        None if !has_viewport => grid_scroll_rows,
+        None => 0,
+    }
+}
+
+/// Returns how many rows the text of a window moves down to center the cursor for typewriter
+/// scrolling. In the middle of the buffer Neovim keeps the cursor centered with 'scrolloff', so the
+/// text only moves at the start and the end of the buffer, where Neovim can't scroll any further.
+fn typewriter_offset(
+    cursor_row: u64,
+    text_rows: u64,
+    at_buffer_start: bool,
+    at_buffer_end: bool,
+) -> f32 {
+    let center = text_rows.saturating_sub(1) / 2;
+    let offset = center as i64 - cursor_row as i64;
+    if (offset > 0 && at_buffer_start) || (offset < 0 && at_buffer_end) {
+        offset as f32
+    } else {
+        0.0
+    }
+}
+
+/// Clamps how far the text is moved up past the end of the buffer, it's never moved down
+fn overscan_offset(offset: f32, max_lines: u32) -> f32 {
+    offset.clamp(-(max_lines as f32), 0.0)
+}
+
+/// The size of the cells drawn at the font scale
+pub fn scale_cells(grid_scale: GridScale, font_scale: f32) -> GridScale {
+    GridScale::new(PixelSize::new(
+        grid_scale.width() * font_scale,
+        grid_scale.height() * font_scale,
+    ))
+}
+
+/// Rounds the pixel origin of a floating window, so that it doesn't land between pixels and look
+/// blurry
+fn snap_origin(origin: PixelPos<f32>, grid_scale: GridScale, snap: FloatingSnap) -> PixelPos<f32> {
+    match snap {
+        FloatingSnap::None => origin,
+        FloatingSnap::Pixel => origin.round(),
+        FloatingSnap::Cell => (origin / grid_scale).round() * grid_scale,
+    }
+}
+
+/// Returns the rows of the winbar, which Neovim puts in the top viewport margin of the window.
+/// Floating windows draw their top border there too, so their margin is never treated as a winbar.
+fn winbar_rows(top_margin: u64, is_floating: bool) -> Range<isize> {
+    if is_floating { 0..0 } else { 0..top_margin as isize }
+}
+
 #[derive(Debug)]
 pub struct ViewportMargins {
     pub top: u64,
     pub bottom: u64,
+    pub left: u64,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -61,12 +149,34 @@ pub enum WindowDrawCommand {
     ViewportMargins {
         top: u64,
         bottom: u64,
-        #[allow(unused)]
         left: u64,
         #[allow(unused)]
         right: u64,
     },
+    /// Whether the first and the last line of the buffer are in view, and whether the last line
+    /// is at the top, where Neovim can't scroll any further
+    ViewportEdges {
+        at_buffer_start: bool,
+        at_buffer_end: bool,
+        at_scroll_end: bool,
+    },
     SortOrder(SortOrder),
+    GutterSigns(Vec<GutterSign>),
+    TabIndicators(Vec<TabIndicator>),
+    /// The 'winblend' of a split, Neovim only blends floating windows itself
+    Blend(u8),
+    /// Whether the window has 'rightleft' set, which mirrors its cells
+    RightLeft(bool),
+    /// The cells of the line numbers after the left margin, for `g:neovide_number_fade`
+    NumberColumn(Option<Range<u64>>),
+    /// The 'listchars' of the window, for `g:neovide_crisp_listchars`
+    ListChars(String),
+    /// The line drawn above the scrolled message grid, if there's one
+    MessageSeparator(Option<Line>),
+    /// The Neovim window shown by a split, only splits are positioned with `win_pos`
+    Split {
+        window: u64,
+    },
 }
 
 struct RenderedLine {
@@ -79,6 +189,20 @@ struct RenderedLine {
     is_valid: bool,
 }
 
+impl RenderedLine {
+    fn new(line: Line) -> Self {
+        RenderedLine {
+            line,
+            background_picture: None,
+            foreground_picture: None,
+            boxchar_picture: None,
+            trailing_background: None,
+            has_transparency: false,
+            is_valid: false,
+        }
+    }
+}
+
 struct TrailingFillRect {
     rect: Rect,
     color: Color4f,
@@ -90,18 +214,56 @@ pub struct RenderedWindow {
     pub hidden: bool,
     pub anchor_info: Option<AnchorInfo>,
     pub window_type: WindowType,
+    /// The Neovim window of the split, floats and the root grid don't have one
+    split_window: Option<u64>,
 
     pub grid_size: GridSize<u32>,
 
     scrollback_lines: RingBuffer<Option<Rc<RefCell<RenderedLine>>>>,
     actual_lines: RingBuffer<Option<Rc<RefCell<RenderedLine>>>>,
     scroll_delta: isize,
+    // The rows scrolled by the Scroll events since the last flush, and whether a Viewport event
+    // arrived in that time or ever, see `flush_scroll_delta`
+    grid_scroll_rows: isize,
+    viewport_scrolled: bool,
+    has_viewport: bool,
+    scroll_buffer_lines: u32,
     pub viewport_margins: ViewportMargins,
+    // The row of the cursor when it's in this window and the line numbers should fade
+    number_fade_cursor_row: Option<u64>,
+    number_column: Option<Range<u64>>,
+    // The line under the cursor, and the column of the cursor, when its ligatures are split
+    ligature_split: Option<(Rc<RefCell<RenderedLine>>, u32)>,
+    gutter_signs: Vec<GutterSign>,
+    gui_gutter_icons: bool,
+    tab_indicators: Vec<TabIndicator>,
+    show_tabs_indicator: bool,
+    at_buffer_start: bool,
+    at_buffer_end: bool,
+    at_scroll_end: bool,
+    // The rows the text is moved down to center the cursor, the animation moves towards it
+    typewriter_offset: f32,
+    typewriter_animation: CriticallyDampedSpringAnimation,
+    // The rows the text is moved up past the end of the buffer, springing back to zero
+    overscan_animation: CriticallyDampedSpringAnimation,
+    floating_snap: FloatingSnap,
+    // The text of floats is drawn at `g:neovide_floating_font_scale`, see `content_grid_scale`
+    font_scale: f32,
+    blend: u8,
+    right_left: bool,
+    list_markers: ListMarkers,
+    message_separator: Option<Rc<RefCell<RenderedLine>>>,
 
     grid_start_position: GridPos<f32>,
     pub grid_current_position: GridPos<f32>,
     grid_destination: GridPos<f32>,
     position_t: f32,
+    // The size of splits is animated along with their position when `animate_splits` is set
+    pub animate_splits: bool,
+    grid_start_size: GridSize<f32>,
+    grid_current_size: GridSize<f32>,
+    // The split was closed, and is removed once it has collapsed
+    closing: bool,
 
     pub scroll_animation: CriticallyDampedSpringAnimation,
 }
@@ -112,9 +274,20 @@ pub struct WindowDrawDetails {
     pub region: PixelRect<f32>,
     pub grid_size: GridSize<u32>,
     pub window_type: WindowType,
+    /// The rows the text is drawn below its grid position, for typewriter scrolling
+    pub content_offset: f32,
+    /// The cells of floats are scaled by `g:neovide_floating_font_scale`
+    pub font_scale: f32,
+    /// The Neovim window, when this is a split
+    pub split_window: Option<u64>,
 }
 
 impl WindowDrawDetails {
+    /// The size of the cells inside the window, to map the mouse to them
+    pub fn content_grid_scale(&self, grid_scale: GridScale) -> GridScale {
+        scale_cells(grid_scale, self.font_scale)
+    }
+
     pub fn event_grid_id(&self, settings: &Settings) -> u64 {
         if settings.get::<CmdLineSettings>().no_multi_grid { NO_MULTIGRID_GRID_ID } else { self.id }
     }
@@ -130,18 +303,46 @@ impl RenderedWindow {
             hidden: false,
             anchor_info: None,
             window_type: WindowType::Editor,
+            split_window: None,
 
             grid_size,
 
             actual_lines: RingBuffer::new(grid_size.height as usize, None),
             scrollback_lines: RingBuffer::new(2 * grid_size.height as usize, None),
             scroll_delta: 0,
-            viewport_margins: ViewportMargins { top: 0, bottom: 0 },
+            grid_scroll_rows: 0,
+            viewport_scrolled: false,
+            has_viewport: false,
+            scroll_buffer_lines: 0,
+            viewport_margins: ViewportMargins { top: 0, bottom: 0, left: 0 },
+            number_fade_cursor_row: None,
+            number_column: None,
+            ligature_split: None,
+            gutter_signs: Vec::new(),
+            gui_gutter_icons: false,
+            tab_indicators: Vec::new(),
+            show_tabs_indicator: false,
+            at_buffer_start: false,
+            at_buffer_end: false,
+            at_scroll_end: false,
+            typewriter_offset: 0.0,
+            typewriter_animation: CriticallyDampedSpringAnimation::new(),
+            overscan_animation: CriticallyDampedSpringAnimation::new(),
+            floating_snap: FloatingSnap::None,
+            font_scale: 1.0,
+            blend: 0,
+            right_left: false,
+            list_markers: ListMarkers::default(),
+            message_separator: None,
 
             grid_start_position: grid_position,
             grid_current_position: grid_position,
             grid_destination: grid_position,
             position_t: 2.0, // 2.0 is out of the 0.0 to 1.0 range and stops animation.
+            animate_splits: false,
+            grid_start_size: GridSize::ZERO,
+            grid_current_size: GridSize::ZERO,
+            closing: false,
 
             scroll_animation: CriticallyDampedSpringAnimation::new(),
         }
@@ -152,7 +353,80 @@ impl RenderedWindow {
         // characters.
         let fract = (self.grid_destination * grid_scale).fract();
         let pos = (self.grid_current_position * grid_scale - fract).round() + fract.to_vector();
-        PixelRect::<f32>::from_origin_and_size(pos.into(), self.grid_size() * grid_scale)
+        // Only the drawn origin is snapped, the animation keeps moving from the exact position
+        let pos = match self.anchor_info {
+            Some(_) => snap_origin(pos, grid_scale, self.floating_snap),
+            None => pos,
+        };
+        let size = if self.animates_splits() {
+            self.grid_current_size * grid_scale
+        } else {
+            self.grid_size() * self.content_grid_scale(grid_scale)
+        };
+        PixelRect::<f32>::from_origin_and_size(pos.into(), size)
+    }
+
+    /// The size of the cells inside the window, which are smaller or larger than the ones of the
+    /// grid for floats with `g:neovide_floating_font_scale`. Floats are still placed on the grid.
+    pub fn content_grid_scale(&self, grid_scale: GridScale) -> GridScale {
+        scale_cells(grid_scale, self.font_scale)
+    }
+
+    pub fn font_scale(&self) -> f32 {
+        self.font_scale
+    }
+
+    /// A split that was turned into a float keeps its window, but isn't a split anymore
+    pub fn split_window(&self) -> Option<u64> {
+        self.split_window.filter(|_| self.anchor_info.is_none())
+    }
+
+    /// Scales the canvas around the origin of the window for its font scale, and returns the
+    /// region to draw the lines in, with the cells of the grid
+    pub fn scale_canvas(&self, canvas: &Canvas, pixel_region: PixelRect<f32>) -> PixelRect<f32> {
+        if self.font_scale == 1.0 {
+            return pixel_region;
+        }
+        let origin = pixel_region.min;
+        canvas.translate((origin.x, origin.y));
+        canvas.scale((self.font_scale, self.font_scale));
+        canvas.translate((-origin.x, -origin.y));
+        let size = pixel_region.size();
+        PixelRect::from_origin_and_size(
+            origin,
+            PixelSize::new(size.width / self.font_scale, size.height / self.font_scale),
+        )
+    }
+
+    // Only the splits move around when the layout changes, not the floats or the outer grid
+    fn animates_splits(&self) -> bool {
+        self.animate_splits && self.anchor_info.is_none() && self.id != BASE_GRID_ID
+    }
+
+    fn target_size(&self) -> GridSize<f32> {
+        if self.closing { GridSize::ZERO } else { self.grid_size().try_cast().unwrap() }
+    }
+
+    /// Starts collapsing a closed split. Returns false when it's not animated, and should be
+    /// removed right away.
+    pub fn start_closing(&mut self) -> bool {
+        if !self.animates_splits() || !self.valid || self.hidden {
+            return false;
+        }
+        self.closing = true;
+        self.grid_start_position = self.grid_current_position;
+        self.grid_start_size = self.grid_current_size;
+        self.position_t = 0.0;
+        true
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.closing
+    }
+
+    /// A closed split that has finished collapsing
+    pub fn is_closed(&self) -> bool {
+        self.closing && self.position_t > 1.0
     }
 
     fn grid_size(&self) -> GridSize<u32> {
@@ -224,15 +498,33 @@ impl RenderedWindow {
         }
 
         let prev_position = self.grid_current_position;
-        self.grid_current_position = ease_point(
-            ease_out_expo,
-            self.grid_start_position,
-            self.get_target_position(grid_rect),
-            self.position_t,
-        );
+        let prev_size = self.grid_current_size;
+        let target_position = self.get_target_position(grid_rect);
+        let target_size = self.target_size();
+        if self.animates_splits() && self.position_t <= 1.0 {
+            let rect = ease_rect(
+                ease_out_expo,
+                GridRect::from_origin_and_size(self.grid_start_position, self.grid_start_size),
+                GridRect::from_origin_and_size(target_position, target_size),
+                self.position_t,
+            );
+            self.grid_current_position = rect.min;
+            self.grid_current_size = rect.size();
+        } else {
+            self.grid_current_position = ease_point(
+                ease_out_expo,
+                self.grid_start_position,
+                target_position,
+                self.position_t,
+            );
+            self.grid_current_size = target_size;
+        }
         animating |= self.grid_current_position != prev_position;
+        animating |= self.grid_current_size != prev_size;
 
         let scrolling = self.scroll_animation.update(dt, settings.scroll_animation_length);
+        animating |= self.typewriter_animation.update(dt, settings.scroll_animation_length);
+        animating |= self.overscan_animation.update(dt, settings.scroll_animation_length);
 
         animating |= scrolling;
 
@@ -251,12 +543,15 @@ impl RenderedWindow {
     ) {
         let inner_region = self.inner_region(pixel_region, grid_scale);
 
+        let blend_paint = (self.background_alpha() < 1.0)
+            .then(|| Paint::default().set_alpha_f(self.background_alpha()).to_owned());
+
         canvas.save();
         canvas.clip_rect(to_skia_rect(&pixel_region), None, false);
         for (matrix, line) in self.iter_border_lines_with_transform(pixel_region, grid_scale) {
             let line = line.borrow();
             if let Some(background_picture) = &line.background_picture {
-                canvas.draw_picture(background_picture, Some(&matrix), None);
+                canvas.draw_picture(background_picture, Some(&matrix), blend_paint.as_ref());
             }
         }
         canvas.restore();
@@ -264,10 +559,11 @@ impl RenderedWindow {
         canvas.save();
         canvas.clip_rect(inner_region, None, false);
         let mut pics = 0;
-        for (matrix, line) in self.iter_scrollable_lines_with_transform(pixel_region, grid_scale) {
+        for (_, matrix, line) in self.iter_scrollable_lines_with_transform(pixel_region, grid_scale)
+        {
             let line = line.borrow();
             if let Some(background_picture) = &line.background_picture {
-                canvas.draw_picture(background_picture, Some(&matrix), None);
+                canvas.draw_picture(background_picture, Some(&matrix), blend_paint.as_ref());
                 pics += 1;
             }
         }
@@ -292,10 +588,55 @@ impl RenderedWindow {
         }
         canvas.save();
         canvas.clip_rect(self.inner_region(pixel_region, grid_scale), None, false);
-        for (matrix, line) in self.iter_scrollable_lines_with_transform(pixel_region, grid_scale) {
+        let number_fade = self.number_fade_column();
+        let gutter_rows = self.gutter_rows();
+        let tab_rows = self.tab_indicator_rows();
+        let scroll_offset_lines = self.scroll_animation.position.floor() as isize;
+        for (i, matrix, line) in self.iter_scrollable_lines_with_transform(pixel_region, grid_scale)
+        {
             let line = line.borrow();
-            if let Some(foreground_picture) = &line.foreground_picture {
-                canvas.draw_picture(foreground_picture, Some(&matrix), None);
+            let Some(foreground_picture) = &line.foreground_picture else {
+                continue;
+            };
+            // The icon replaces the text of the sign, so the sign column is left out of the line
+            let gutter_sign = gutter_rows.get(&(i + scroll_offset_lines)).map(|sign| {
+                let sign_rect = Rect::from_xywh(
+                    matrix.translate_x() + self.viewport_margins.left as f32 * grid_scale.width(),
+                    matrix.translate_y(),
+                    2.0 * grid_scale.width(),
+                    grid_scale.height(),
+                );
+                canvas.save();
+                canvas.clip_rect(sign_rect, ClipOp::Difference, false);
+                (sign, sign_rect)
+            });
+            match &number_fade {
+                Some((columns, cursor_row)) => {
+                    let alpha = number_fade_alpha(i.abs_diff(*cursor_row) as u64);
+                    let column_rect = Rect::from_xywh(
+                        matrix.translate_x() + columns.start as f32 * grid_scale.width(),
+                        matrix.translate_y(),
+                        columns.len() as f32 * grid_scale.width(),
+                        grid_scale.height(),
+                    );
+                    draw_with_faded_column(canvas, foreground_picture, &matrix, column_rect, alpha);
+                }
+                None => {
+                    canvas.draw_picture(foreground_picture, Some(&matrix), None);
+                }
+            }
+            if let Some((sign, sign_rect)) = gutter_sign {
+                canvas.restore();
+                gutter_icons::draw_icon(canvas, sign, sign_rect);
+            }
+            if let Some(tabs) = tab_rows.get(&(i + scroll_offset_lines)) {
+                tab_indicators::draw_arrows(
+                    canvas,
+                    tabs,
+                    (matrix.translate_x(), matrix.translate_y()),
+                    grid_scale.width(),
+                    grid_scale.height(),
+                );
             }
         }
         canvas.restore();
@@ -310,7 +651,7 @@ impl RenderedWindow {
         }
         canvas.save();
         canvas.clip_rect(self.inner_region(pixel_region, grid_scale), None, false);
-        for (mut matrix, line) in
+        for (_, mut matrix, line) in
             self.iter_scrollable_lines_with_transform(pixel_region, grid_scale)
         {
             let line = line.borrow();
@@ -323,6 +664,117 @@ impl RenderedWindow {
         canvas.restore();
     }
 
+    pub fn set_number_fade_cursor_row(&mut self, cursor_row: Option<u64>) {
+        self.number_fade_cursor_row = cursor_row;
+    }
+
+    /// Moves the split ligatures to the line and column of the cursor, `None` when the cursor is
+    /// in another window. Only the lines that change are invalidated.
+    pub fn set_ligature_split_cursor(&mut self, cursor: Option<(u64, u64)>) {
+        let ligature_split = cursor.and_then(|(row, column)| {
+            self.line_for_row(row as u32).map(|line| (line, column as u32))
+        });
+        let unchanged = match (&self.ligature_split, &ligature_split) {
+            (Some((old_line, old_column)), Some((new_line, new_column))) => {
+                Rc::ptr_eq(old_line, new_line) && old_column == new_column
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return;
+        }
+
+        for (line, _) in self.ligature_split.iter().chain(ligature_split.iter()) {
+            line.borrow_mut().is_valid = false;
+        }
+        self.ligature_split = ligature_split;
+    }
+
+    /// Moves the text to center the row of the cursor, `None` when the cursor is in another
+    /// window, which keeps the text where it is. Floating windows are never moved.
+    pub fn set_typewriter_cursor_row(&mut self, enabled: bool, cursor_row: Option<u64>) {
+        let is_split = self.anchor_info.is_none() && self.window_type == WindowType::Editor;
+        let offset = match cursor_row {
+            _ if !enabled || !is_split => 0.0,
+            Some(cursor_row) => {
+                let text_rows = (self.grid_size.height as u64)
+                    .saturating_sub(self.viewport_margins.top)
+                    .saturating_sub(self.viewport_margins.bottom);
+                typewriter_offset(
+                    cursor_row.saturating_sub(self.viewport_margins.top),
+                    text_rows,
+                    self.at_buffer_start,
+                    self.at_buffer_end,
+                )
+            }
+            None => return,
+        };
+        // Continue from where the text is drawn now
+        self.typewriter_animation.position += self.typewriter_offset - offset;
+        self.typewriter_offset = offset;
+    }
+
+    /// Returns the rows the text is currently drawn below its grid position
+    pub fn content_offset(&self) -> f32 {
+        self.typewriter_offset
+            + self.typewriter_animation.position
+            + self.overscan_animation.position
+    }
+
+    /// Moves the text up past the end of the buffer by the rows scrolled down with the mouse wheel
+    /// once Neovim can't scroll any further, or back down when scrolling up. The text springs back
+    /// to its place on its own.
+    pub fn overscan(&mut self, rows: f32, max_lines: u32) {
+        let is_split = self.anchor_info.is_none() && self.window_type == WindowType::Editor;
+        if !is_split || !self.at_scroll_end {
+            return;
+        }
+        self.overscan_animation.position =
+            overscan_offset(self.overscan_animation.position + rows, max_lines);
+    }
+
+    /// Returns the cells of the number column, and the row of the cursor counted from the top of
+    /// the scrollable region, when the line numbers should fade
+    fn number_fade_column(&self) -> Option<(Range<usize>, isize)> {
+        let cursor_row = self.number_fade_cursor_row? as isize - self.viewport_margins.top as isize;
+        let columns = self.number_column.as_ref()?;
+        let left = self.viewport_margins.left;
+        Some(((left + columns.start) as usize..(left + columns.end) as usize, cursor_row))
+    }
+
+    /// Returns the diagnostic signs drawn as icons, by row of the scrollable region
+    fn gutter_rows(&self) -> HashMap<isize, GutterSign> {
+        if !self.gui_gutter_icons {
+            return HashMap::new();
+        }
+        let inner_size = (self.grid_size.height as u64)
+            .saturating_sub(self.viewport_margins.top)
+            .saturating_sub(self.viewport_margins.bottom);
+        gutter_icons::gutter_rows(&self.gutter_signs, self.viewport_margins.top, inner_size)
+    }
+
+    /// Returns the tabs drawn with an arrow, by row of the scrollable region
+    fn tab_indicator_rows(&self) -> HashMap<isize, Vec<TabIndicator>> {
+        if !self.show_tabs_indicator {
+            return HashMap::new();
+        }
+        let inner_size = (self.grid_size.height as u64)
+            .saturating_sub(self.viewport_margins.top)
+            .saturating_sub(self.viewport_margins.bottom);
+        tab_indicators::indicator_rows(&self.tab_indicators, self.viewport_margins.top, inner_size)
+    }
+
+    /// Returns how opaque the background of the window is, floating windows are already blended
+    /// by Neovim
+    fn background_alpha(&self) -> f32 {
+        if self.anchor_info.is_some() { 1.0 } else { (100 - self.blend) as f32 / 100.0 }
+    }
+
+    fn blended_background(&self, background: Color) -> Color {
+        background.with_a((background.a() as f32 * self.background_alpha()).round() as u8)
+    }
+
     pub fn has_transparency(&self) -> bool {
         let scroll_offset_lines = self.scroll_animation.position.floor() as isize;
         if self.scrollback_lines.is_empty() {
@@ -359,15 +811,19 @@ impl RenderedWindow {
                 region: pixel_region_box,
                 grid_size: self.grid_size,
                 window_type: self.window_type,
+                content_offset: self.content_offset(),
+                font_scale: self.font_scale,
+                split_window: self.split_window(),
             };
         }
 
         root_canvas.save();
         root_canvas.clip_rect(pixel_region, None, Some(false));
-        root_canvas.clear(default_background);
+        root_canvas.clear(self.blended_background(default_background));
 
-        self.draw_background_surface(root_canvas, draw_region_box, grid_scale);
-        self.draw_foreground_surface(root_canvas, draw_region_box, grid_scale);
+        let content_region = self.scale_canvas(root_canvas, draw_region_box);
+        self.draw_background_surface(root_canvas, content_region, grid_scale);
+        self.draw_foreground_surface(root_canvas, content_region, grid_scale);
 
         root_canvas.restore();
 
@@ -376,6 +832,9 @@ impl RenderedWindow {
             region: draw_region_box,
             grid_size: self.grid_size,
             window_type: self.window_type,
+            content_offset: self.content_offset(),
+            font_scale: self.font_scale,
+            split_window: self.split_window(),
         }
     }
 
@@ -422,13 +881,50 @@ impl RenderedWindow {
         canvas.clip_rect(to_skia_rect(&pixel_region), None, false);
 
         for fill in self.trailing_fill_rects(pixel_region, grid_scale) {
-            paint.set_color4f(fill.color, None);
+            let color = Color4f { a: fill.color.a * self.background_alpha(), ..fill.color };
+            paint.set_color4f(color, None);
             canvas.draw_rect(fill.rect, &paint);
         }
 
         canvas.restore();
     }
 
+    /// Where the separator above the scrolled message grid goes, if there's one
+    pub fn message_separator_rect(
+        &self,
+        pixel_region: PixelRect<f32>,
+        grid_scale: GridScale,
+    ) -> Option<Rect> {
+        self.message_separator.as_ref()?;
+        Some(Rect::from_xywh(
+            pixel_region.min.x,
+            pixel_region.min.y - grid_scale.height(),
+            pixel_region.size().width,
+            grid_scale.height(),
+        ))
+    }
+
+    pub fn draw_message_separator(
+        &self,
+        canvas: &Canvas,
+        pixel_region: PixelRect<f32>,
+        grid_scale: GridScale,
+    ) {
+        let Some(line) = &self.message_separator else {
+            return;
+        };
+        let line = line.borrow();
+        let mut matrix = Matrix::new_identity();
+        matrix.set_translate((pixel_region.min.x, pixel_region.min.y - grid_scale.height()));
+        for picture in [&line.background_picture, &line.foreground_picture].into_iter().flatten() {
+            canvas.draw_picture(picture, Some(&matrix), None);
+        }
+        if let Some((boxchar_picture, position)) = &line.boxchar_picture {
+            matrix.set_translate_x(pixel_region.min.x - position.x);
+            canvas.draw_picture(boxchar_picture, Some(&matrix), None);
+        }
+    }
+
     pub fn trailing_fill_path_and_bounds(
         &self,
         pixel_region: PixelRect<f32>,
@@ -483,7 +979,8 @@ impl RenderedWindow {
         //
         // See https://github.com/neovide/neovide/pull/3387
         let scroll_offset_lines = self.scroll_animation.position.floor();
-        let scroll_offset = scroll_offset_lines - self.scroll_animation.position;
+        let scroll_offset =
+            scroll_offset_lines - self.scroll_animation.position + self.content_offset();
         let scroll_offset_pixels = (scroll_offset * grid_scale.height()).round();
         for (i, line) in self.iter_scrollable_lines() {
             let line = line.borrow();
@@ -560,6 +1057,12 @@ impl RenderedWindow {
         self.scrollback_lines[scroll_offset + inner_row].as_ref().cloned()
     }
 
+    pub fn with_line_cells<T>(&self, row: u32, f: impl FnOnce(&[String]) -> T) -> Option<T> {
+        let line = self.line_for_row(row)?;
+        let line = line.borrow();
+        line.line.cells().map(f)
+    }
+
     pub fn line_text_range(&self, row: u32, start_col: u32, end_col: u32) -> Option<String> {
         let line = self.line_for_row(row)?;
         let line = line.borrow();
@@ -633,12 +1136,15 @@ impl RenderedWindow {
             WindowDrawCommand::Position { grid_position, grid_size, anchor_info, window_type } => {
                 tracy_zone!("position_cmd", 0);
 
-                self.valid = true;
-
                 let new_grid_size: GridSize<u32> =
                     GridSize::<u64>::from(grid_size).try_cast().unwrap();
                 let grid_position: GridPos<f32> =
                     GridPos::<f64>::from(grid_position).try_cast().unwrap();
+                let was_valid = self.valid && !self.closing;
+                let resized = new_grid_size != self.grid_size;
+                let moved = self.grid_destination != grid_position;
+                self.valid = true;
+                self.closing = false;
 
                 if self.grid_destination != grid_position {
                     if self.grid_start_position.x.abs() > f32::EPSILON
@@ -659,9 +1165,10 @@ impl RenderedWindow {
                 self.actual_lines.resize(height, None);
                 self.grid_size = new_grid_size;
 
-                self.scrollback_lines.resize(2 * height, None);
+                self.scrollback_lines
+                    .resize(scrollback_capacity(height, self.scroll_buffer_lines), None);
                 self.scrollback_lines.clone_from_iter(&self.actual_lines);
-                self.scroll_delta = 0;
+                self.reset_scroll_delta();
 
                 if height != self.actual_lines.len() {
                     self.scroll_animation.reset();
@@ -669,6 +1176,19 @@ impl RenderedWindow {
 
                 self.anchor_info = anchor_info;
                 self.window_type = window_type;
+
+                if self.animates_splits() {
+                    if !was_valid {
+                        // A new split grows from nothing at its position
+                        self.grid_start_position = grid_position;
+                        self.grid_start_size = GridSize::ZERO;
+                        self.position_t = 0.0;
+                    } else if resized || moved {
+                        self.grid_start_position = self.grid_current_position;
+                        self.grid_start_size = self.grid_current_size;
+                        self.position_t = 0.0;
+                    }
+                }
             }
             WindowDrawCommand::DrawLine { row, line } => {
                 tracy_zone!("draw_line_cmd", 0);
@@ -681,17 +1201,26 @@ impl RenderedWindow {
                     );
                     return;
                 }
+                // The lines are in a ring buffer, a row past the end would replace another one
+                if row >= self.actual_lines.len() {
+                    log::debug!(
+                        "Ignoring DrawLine for grid {} row {} past its {} rows",
+                        self.id,
+                        row,
+                        self.actual_lines.len()
+                    );
+                    return;
+                }
 
-                let line = RenderedLine {
-                    line,
-                    background_picture: None,
-                    foreground_picture: None,
-                    boxchar_picture: None,
-                    trailing_background: None,
-                    has_transparency: false,
-                    is_valid: false,
-                };
+                // Neovim often redraws lines without changing them, which keeps the pictures
+                if self.actual_lines[row]
+                    .as_ref()
+                    .is_some_and(|previous| previous.borrow().line == line)
+                {
+                    return;
+                }
 
+                let line = RenderedLine::new(line);
                 self.actual_lines[row] = Some(Rc::new(RefCell::new(line)));
             }
             WindowDrawCommand::Scroll { top, bottom, left, right, rows, cols } => {
@@ -700,11 +1229,12 @@ impl RenderedWindow {
                     self.full_window_vertical_scroll_rows(top, bottom, left, right, rows, cols)
                 {
                     self.actual_lines.rotate(rows as isize);
+                    self.grid_scroll_rows += rows as isize;
                 }
             }
             WindowDrawCommand::Clear => {
                 tracy_zone!("clear_cmd", 0);
-                self.scroll_delta = 0;
+                self.reset_scroll_delta();
                 self.scrollback_lines.iter_mut().for_each(|line| *line = None);
                 self.scroll_animation.reset();
             }
@@ -725,16 +1255,64 @@ impl RenderedWindow {
             }
             WindowDrawCommand::Viewport { scroll_delta } => {
                 log::trace!("Handling Viewport {}", self.id);
-                self.scroll_delta = scroll_delta.round() as isize;
+                // Several viewport events of the grid can arrive before the next flush
+                self.scroll_delta += scroll_delta.round() as isize;
+                self.viewport_scrolled = true;
+                self.has_viewport = true;
+            }
+            WindowDrawCommand::ViewportMargins { top, bottom, left, .. } => {
+                // Rows moving in or out of the winbar might need to be drawn differently
+                if top != self.viewport_margins.top {
+                    for line in self
+                        .actual_lines
+                        .iter_range_mut(0..top.max(self.viewport_margins.top) as isize)
+                        .flatten()
+                    {
+                        line.borrow_mut().is_valid = false;
+                    }
+                }
+                self.viewport_margins = ViewportMargins { top, bottom, left }
             }
-            WindowDrawCommand::ViewportMargins { top, bottom, .. } => {
-                self.viewport_margins = ViewportMargins { top, bottom }
+            WindowDrawCommand::ViewportEdges { at_buffer_start, at_buffer_end, at_scroll_end } => {
+                self.at_buffer_start = at_buffer_start;
+                self.at_buffer_end = at_buffer_end;
+                self.at_scroll_end = at_scroll_end;
             }
             WindowDrawCommand::SortOrder(sort_order) => {
                 if let Some(anchor_info) = self.anchor_info.as_mut() {
                     anchor_info.sort_order = sort_order;
                 }
             }
+            WindowDrawCommand::GutterSigns(signs) => {
+                self.gutter_signs = signs;
+            }
+            WindowDrawCommand::TabIndicators(tabs) => {
+                self.tab_indicators = tabs;
+            }
+            WindowDrawCommand::Blend(blend) => {
+                self.blend = blend.min(100);
+            }
+            WindowDrawCommand::RightLeft(right_left) => {
+                if right_left != self.right_left {
+                    self.invalidate_lines();
+                }
+                self.right_left = right_left;
+            }
+            WindowDrawCommand::NumberColumn(columns) => {
+                self.number_column = columns;
+            }
+            WindowDrawCommand::ListChars(listchars) => {
+                if self.list_markers.update(&listchars) {
+                    self.invalidate_lines();
+                }
+            }
+            WindowDrawCommand::MessageSeparator(line) => {
+                self.message_separator =
+                    line.map(|line| Rc::new(RefCell::new(RenderedLine::new(line))));
+            }
+            WindowDrawCommand::Split { window } => {
+                self.split_window = Some(window);
+            }
             _ => {}
         };
     }
@@ -743,25 +1321,45 @@ impl RenderedWindow {
         if !self.valid {
             return;
         }
-        // If the borders are changed, reset the scrollback to only fit the inner view
+        self.scroll_buffer_lines = renderer_settings.scroll_buffer_lines;
+        self.gui_gutter_icons = renderer_settings.gui_gutter_icons;
+        self.show_tabs_indicator = renderer_settings.show_tabs_indicator;
+        self.floating_snap = renderer_settings.floating_snap;
+        // Only the floats of buffers are scaled, not the messages or the cmdline
+        self.font_scale = match (&self.anchor_info, self.window_type) {
+            (Some(_), WindowType::Editor) if renderer_settings.floating_font_scale > 0.0 => {
+                renderer_settings.floating_font_scale
+            }
+            _ => 1.0,
+        };
+        // If the borders or the scroll buffer limit are changed, reset the scrollback to only fit
+        // the inner view
         let inner_range = self.viewport_margins.top as isize
             ..(self.actual_lines.len() - self.viewport_margins.bottom as usize) as isize;
         let inner_size = inner_range.len();
         let inner_view = self.actual_lines.iter_range(inner_range);
-        if inner_size != self.scrollback_lines.len() / 2 {
-            self.scrollback_lines.resize(2 * inner_size, None);
+        let capacity = scrollback_capacity(inner_size, self.scroll_buffer_lines);
+        if capacity != self.scrollback_lines.len() {
+            self.scrollback_lines.resize(capacity, None);
             self.scrollback_lines.clone_from_iter(inner_view);
-            self.scroll_delta = 0;
+            self.reset_scroll_delta();
             self.scroll_animation.reset();
             return;
         }
 
-        let scroll_delta = self.scroll_delta;
+        let scroll_delta = flush_scroll_delta(
+            self.viewport_scrolled.then_some(self.scroll_delta),
+            self.grid_scroll_rows,
+            self.has_viewport,
+        );
         self.scrollback_lines.rotate(scroll_delta);
 
         self.scrollback_lines.clone_from_iter(inner_view);
 
-        if scroll_delta != 0 {
+        // Short scrolls, like moving the cursor line by line, jump to the new position, without
+        // cancelling a scroll that is still animating
+        let min_lines = renderer_settings.scroll_animation_min_lines as usize;
+        if scroll_delta != 0 && scroll_delta.unsigned_abs() >= min_lines {
             let mut scroll_offset = self.scroll_animation.position;
 
             let max_delta =
@@ -773,7 +1371,8 @@ impl RenderedWindow {
             if scroll_delta.unsigned_abs() > max_delta {
                 let far_lines = renderer_settings
                     .scroll_animation_far_lines
-                    .min(self.actual_lines.len() as u32) as isize;
+                    .min(self.actual_lines.len() as u32)
+                    .min(max_delta as u32) as isize;
 
                 scroll_offset = -(far_lines * scroll_delta.signum()) as f32;
                 let empty_lines = if scroll_delta > 0 {
@@ -793,7 +1392,22 @@ impl RenderedWindow {
             self.scroll_animation.position = scroll_offset;
             log::trace!("Current scroll {scroll_offset}");
         }
+        self.reset_scroll_delta();
+    }
+
+    /// Draws all the lines again at the next frame, for changes that comparing the lines
+    /// doesn't cover, like the default colors
+    pub fn invalidate_lines(&mut self) {
+        let lines = self.actual_lines.iter_mut().chain(self.scrollback_lines.iter_mut());
+        for line in lines.flatten() {
+            line.borrow_mut().is_valid = false;
+        }
+    }
+
+    fn reset_scroll_delta(&mut self) {
         self.scroll_delta = 0;
+        self.grid_scroll_rows = 0;
+        self.viewport_scrolled = false;
     }
 
     fn iter_border_lines(&self) -> impl Iterator<Item = (isize, &Rc<RefCell<RenderedLine>>)> {
@@ -827,9 +1441,10 @@ impl RenderedWindow {
         &self,
         pixel_region: PixelRect<f32>,
         grid_scale: GridScale,
-    ) -> impl Iterator<Item = (Matrix, &Rc<RefCell<RenderedLine>>)> {
+    ) -> impl Iterator<Item = (isize, Matrix, &Rc<RefCell<RenderedLine>>)> {
         let scroll_offset_lines = self.scroll_animation.position.floor();
-        let scroll_offset = scroll_offset_lines - self.scroll_animation.position;
+        let scroll_offset =
+            scroll_offset_lines - self.scroll_animation.position + self.content_offset();
         let scroll_offset_pixels = (scroll_offset * grid_scale.height()).round();
 
         self.iter_scrollable_lines().map(move |(i, line)| {
@@ -840,7 +1455,7 @@ impl RenderedWindow {
                     + (scroll_offset_pixels
                         + ((i + self.viewport_margins.top as isize) as f32 * grid_scale.height())),
             ));
-            (matrix, line)
+            (i, matrix, line)
         })
     }
 
@@ -873,7 +1488,13 @@ impl RenderedWindow {
         to_skia_rect(&adjusted_region)
     }
 
-    pub fn prepare_lines(&mut self, grid_renderer: &mut GridRenderer, opacity: f32, force: bool) {
+    pub fn prepare_lines(
+        &mut self,
+        grid_renderer: &mut GridRenderer,
+        opacity: f32,
+        proportional_winbar: bool,
+        force: bool,
+    ) {
         let scroll_offset_lines = self.scroll_animation.position.floor() as isize;
         let height = self.grid_size.height as isize;
         if height == 0 {
@@ -881,7 +1502,14 @@ impl RenderedWindow {
         }
         let grid_scale = grid_renderer.grid_scale;
 
-        let mut prepare_line = |line: &Rc<RefCell<RenderedLine>>| {
+        let ligature_split = &self.ligature_split;
+        let right_left = self.right_left;
+        let list_markers = &self.list_markers;
+        let mut prepare_line = |line: &Rc<RefCell<RenderedLine>>, proportional: bool| {
+            let cursor_column = ligature_split
+                .as_ref()
+                .filter(|(split_line, _)| Rc::ptr_eq(split_line, line))
+                .map(|(_, column)| *column);
             let mut line = line.borrow_mut();
             let position = self.grid_destination * grid_renderer.grid_scale;
             let boxchar_moved = match line.boxchar_picture {
@@ -909,6 +1537,8 @@ impl RenderedWindow {
                 custom_background |= background_info.custom_color;
                 has_transparency |= background_info.transparent;
             }
+            custom_background |=
+                grid_renderer.draw_diff_accents(canvas, line.line.fragments(), opacity);
             let background_picture =
                 custom_background.then_some(recorder.finish_recording_as_picture(None).unwrap());
 
@@ -918,16 +1548,43 @@ impl RenderedWindow {
                 boxchar_recorder.begin_recording(grid_rect.with_offset((position.x, 0.0)), false);
             let mut text_drawn = false;
             let mut boxchar_drawn = false;
-            for line_fragment in line.line.fragments() {
+            let fragments = line.line.fragments().collect::<Vec<_>>();
+            if !proportional {
+                text_drawn |= grid_renderer.draw_underlines(text_canvas, &fragments);
+            }
+            // Box drawing characters don't get struck through, the line would merge with them
+            let mut struck_fragments = Vec::new();
+            for (i, line_fragment) in fragments.iter().enumerate() {
+                if proportional {
+                    text_drawn |=
+                        grid_renderer.draw_proportional_foreground(text_canvas, line_fragment);
+                    continue;
+                }
+                let context = RunContext {
+                    right_left,
+                    joins_left: i.checked_sub(1).is_some_and(|previous| {
+                        fragments_join(&fragments[previous], line_fragment, right_left)
+                    }),
+                    joins_right: fragments
+                        .get(i + 1)
+                        .is_some_and(|next| fragments_join(line_fragment, next, right_left)),
+                };
                 let (frag_text_drawn, frag_box_drawn) = grid_renderer.draw_foreground(
                     text_canvas,
                     boxchar_canvas,
-                    &line_fragment,
+                    line_fragment,
                     position,
+                    cursor_column,
+                    context,
+                    list_markers,
                 );
                 text_drawn |= frag_text_drawn;
                 boxchar_drawn |= frag_box_drawn;
+                if !frag_box_drawn {
+                    struck_fragments.push(line_fragment);
+                }
             }
+            text_drawn |= grid_renderer.draw_strikethroughs(text_canvas, struck_fragments);
             let foreground_picture =
                 text_drawn.then_some(recorder.finish_recording_as_picture(None).unwrap());
             let boxchar_picture = boxchar_drawn
@@ -954,14 +1611,17 @@ impl RenderedWindow {
                 .iter_range_mut(scroll_offset_lines..scroll_offset_lines + height + 1)
                 .flatten()
             {
-                prepare_line(line)
+                prepare_line(line, false)
             }
         }
 
-        for line in
-            self.actual_lines.iter_range_mut(0..self.viewport_margins.top as isize).flatten()
+        let winbar_rows = winbar_rows(self.viewport_margins.top, self.anchor_info.is_some());
+        for (row, line) in
+            self.actual_lines.iter_range_mut(0..self.viewport_margins.top as isize).enumerate()
         {
-            prepare_line(line)
+            if let Some(line) = line {
+                prepare_line(line, proportional_winbar && winbar_rows.contains(&(row as isize)))
+            }
         }
         let actual_line_count = self.actual_lines.len() as isize;
         for line in self
@@ -971,7 +1631,392 @@ impl RenderedWindow {
             )
             .flatten()
         {
-            prepare_line(line)
+            prepare_line(line, false)
         }
+        if let Some(line) = &self.message_separator {
+            prepare_line(line, false)
+        }
+    }
+}
+
+/// Draws a line with the cells in `column_rect` faded to `alpha`
+fn draw_with_faded_column(
+    canvas: &Canvas,
+    picture: &Picture,
+    matrix: &Matrix,
+    column_rect: Rect,
+    alpha: f32,
+) {
+    canvas.save();
+    canvas.clip_rect(column_rect, ClipOp::Difference, false);
+    canvas.draw_picture(picture, Some(matrix), None);
+    canvas.restore();
+
+    let mut paint = Paint::default();
+    paint.set_alpha_f(alpha);
+    canvas.save();
+    canvas.clip_rect(column_rect, None, false);
+    canvas.draw_picture(picture, Some(matrix), Some(&paint));
+    canvas.restore();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_window(height: u64) -> RenderedWindow {
+        let mut window = RenderedWindow::new(BASE_GRID_ID);
+        window.handle_window_draw_command(WindowDrawCommand::Position {
+            grid_position: (0.0, 0.0),
+            grid_size: (80, height),
+            anchor_info: None,
+            window_type: WindowType::Editor,
+        });
+        window
+    }
+
+    #[test]
+    fn each_window_keeps_its_own_listchars() {
+        let mut window = create_window(10);
+        let other_window = create_window(10);
+        window.handle_window_draw_command(WindowDrawCommand::ListChars("eol:¬".to_owned()));
+
+        assert!(window.list_markers.markers_of("¬").is_some());
+        assert!(other_window.list_markers.markers_of("¬").is_none());
+    }
+
+    #[test]
+    fn scrollback_keeps_a_full_screen_by_default() {
+        let mut window = create_window(10);
+        window.flush(&RendererSettings::default());
+
+        assert_eq!(window.scrollback_lines.len(), 20);
+    }
+
+    #[test]
+    fn scroll_buffer_lines_truncates_retained_region() {
+        let mut window = create_window(10);
+        let settings = RendererSettings { scroll_buffer_lines: 3, ..Default::default() };
+        window.flush(&settings);
+
+        assert_eq!(window.scrollback_lines.len(), 13);
+
+        // Scrolling further than the retained region only animates what is retained
+        window.handle_window_draw_command(WindowDrawCommand::Viewport { scroll_delta: 2.0 });
+        window.flush(&settings);
+        assert_eq!(window.scroll_animation.position, -2.0);
+
+        window.handle_window_draw_command(WindowDrawCommand::Viewport { scroll_delta: 5.0 });
+        window.flush(&settings);
+        assert_eq!(window.scroll_animation.position, -1.0);
+    }
+
+    #[test]
+    fn unchanged_lines_keep_their_pictures() {
+        let mut window = create_window(10);
+        let draw_line = |window: &mut RenderedWindow, text: &str| {
+            window.handle_window_draw_command(WindowDrawCommand::DrawLine {
+                row: 0,
+                line: Line::from_text_for_test(text),
+            });
+        };
+        let is_valid = |window: &RenderedWindow| {
+            window.actual_lines[0].as_ref().is_some_and(|line| line.borrow().is_valid)
+        };
+
+        draw_line(&mut window, "hello");
+        assert!(!is_valid(&window));
+        // As if the line had been drawn in a frame
+        window.actual_lines[0].as_ref().unwrap().borrow_mut().is_valid = true;
+
+        draw_line(&mut window, "hello");
+        assert!(is_valid(&window), "an unchanged line should be a cache hit");
+
+        draw_line(&mut window, "world");
+        assert!(!is_valid(&window));
+
+        window.actual_lines[0].as_ref().unwrap().borrow_mut().is_valid = true;
+        window.invalidate_lines();
+        assert!(!is_valid(&window));
+    }
+
+    #[test]
+    fn scrolls_shorter_than_the_min_lines_are_instant() {
+        let mut window = create_window(10);
+        let settings = RendererSettings { scroll_animation_min_lines: 3, ..Default::default() };
+        window.flush(&settings);
+
+        window.handle_window_draw_command(WindowDrawCommand::Viewport { scroll_delta: 1.0 });
+        window.flush(&settings);
+        assert_eq!(window.scroll_animation.position, 0.0);
+
+        window.handle_window_draw_command(WindowDrawCommand::Viewport { scroll_delta: 10.0 });
+        window.flush(&settings);
+        assert_eq!(window.scroll_animation.position, -10.0);
+
+        // The running animation continues
+        window.handle_window_draw_command(WindowDrawCommand::Viewport { scroll_delta: 1.0 });
+        window.flush(&settings);
+        assert_eq!(window.scroll_animation.position, -10.0);
+    }
+
+    #[test]
+    fn scroll_events_only_animate_grids_without_a_viewport() {
+        assert_eq!(flush_scroll_delta(Some(3), 0, true), 3);
+        // Both arrive when a window with a viewport scrolls, only the viewport counts
+        assert_eq!(flush_scroll_delta(Some(3), 3, true), 3);
+        assert_eq!(flush_scroll_delta(None, 2, true), 0);
+        assert_eq!(flush_scroll_delta(None, 2, false), 2);
+        assert_eq!(flush_scroll_delta(None, 0, false), 0);
+    }
+
+    #[test]
+    fn scroll_events_drive_the_animation_until_a_viewport_arrives() {
+        let settings = RendererSettings::default();
+        let mut window = create_window(10);
+        window.flush(&settings);
+        let scroll =
+            WindowDrawCommand::Scroll { top: 0, bottom: 10, left: 0, right: 80, rows: 2, cols: 0 };
+
+        window.handle_window_draw_command(scroll.clone());
+        window.flush(&settings);
+        assert_eq!(window.scroll_animation.position, -2.0);
+
+        window.scroll_animation.reset();
+        window.handle_window_draw_command(scroll.clone());
+        window.handle_window_draw_command(WindowDrawCommand::Viewport { scroll_delta: 2.0 });
+        window.flush(&settings);
+        assert_eq!(window.scroll_animation.position, -2.0);
+
+        window.scroll_animation.reset();
+        window.handle_window_draw_command(scroll);
+        window.flush(&settings);
+        assert_eq!(window.scroll_animation.position, 0.0);
+    }
+
+    #[test]
+    fn typewriter_scrolling_centers_the_cursor_at_the_ends_of_the_buffer() {
+        // In the middle of the buffer Neovim does the centering
+        assert_eq!(typewriter_offset(2, 11, false, false), 0.0);
+        assert_eq!(typewriter_offset(8, 11, false, false), 0.0);
+
+        // The first lines move down, the last lines move up
+        assert_eq!(typewriter_offset(2, 11, true, false), 3.0);
+        assert_eq!(typewriter_offset(8, 11, false, true), -3.0);
+        assert_eq!(typewriter_offset(5, 11, true, true), 0.0);
+
+        // But not the other way around
+        assert_eq!(typewriter_offset(8, 11, true, false), 0.0);
+        assert_eq!(typewriter_offset(2, 11, false, true), 0.0);
+
+        // The center of an even number of rows is the upper one
+        assert_eq!(typewriter_offset(0, 10, true, false), 4.0);
+    }
+
+    #[test]
+    fn typewriter_offset_is_animated_and_kept_for_other_windows() {
+        let mut window = create_window(11);
+        window.handle_window_draw_command(WindowDrawCommand::ViewportEdges {
+            at_buffer_start: true,
+            at_buffer_end: false,
+            at_scroll_end: false,
+        });
+        window.set_typewriter_cursor_row(true, Some(1));
+        assert_eq!(window.typewriter_offset, 4.0);
+        assert_eq!(window.content_offset(), 0.0);
+
+        window.set_typewriter_cursor_row(true, None);
+        assert_eq!(window.typewriter_offset, 4.0);
+
+        window.set_typewriter_cursor_row(false, Some(1));
+        assert_eq!(window.typewriter_offset, 0.0);
+    }
+
+    #[test]
+    fn the_overscan_offset_is_clamped() {
+        assert_eq!(overscan_offset(-2.0, 5), -2.0);
+        assert_eq!(overscan_offset(-7.5, 5), -5.0);
+        // The text is never moved down
+        assert_eq!(overscan_offset(1.0, 5), 0.0);
+        assert_eq!(overscan_offset(-1.0, 0), 0.0);
+    }
+
+    #[test]
+    fn overscan_only_moves_the_text_once_neovim_cant_scroll() {
+        let mut window = create_window(11);
+        window.overscan(-3.0, 5);
+        assert_eq!(window.content_offset(), 0.0);
+
+        window.handle_window_draw_command(WindowDrawCommand::ViewportEdges {
+            at_buffer_start: false,
+            at_buffer_end: true,
+            at_scroll_end: true,
+        });
+        window.overscan(-3.0, 5);
+        window.overscan(-3.0, 5);
+        assert_eq!(window.content_offset(), -5.0);
+        window.overscan(1.0, 5);
+        assert_eq!(window.content_offset(), -4.0);
+
+        // And springs back
+        let settings = RendererSettings::default();
+        window.animate(&settings, &GridRect::ZERO, 1.0);
+        assert_eq!(window.content_offset(), 0.0);
+    }
+
+    #[test]
+    fn floats_at_half_cells_snap_to_whole_pixels() {
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 15.0));
+        let mut window = RenderedWindow::new(2);
+        window.handle_window_draw_command(WindowDrawCommand::Position {
+            grid_position: (3.0, 2.5),
+            grid_size: (20, 5),
+            anchor_info: Some(AnchorInfo {
+                anchor_grid_id: 1,
+                anchor_type: WindowAnchor::NorthWest,
+                anchor_left: 3.0,
+                anchor_top: 2.5,
+                sort_order: SortOrder::default(),
+            }),
+            window_type: WindowType::Editor,
+        });
+        let grid_rect = GridRect::new(GridPos::new(0.0, 0.0), GridPos::new(80.0, 24.0));
+        let region_origin = |window: &mut RenderedWindow, floating_snap| {
+            let settings = RendererSettings { floating_snap, ..Default::default() };
+            window.flush(&settings);
+            window.animate(&settings, &grid_rect, 0.1);
+            window.pixel_region(grid_scale).min
+        };
+
+        assert_eq!(region_origin(&mut window, FloatingSnap::None), PixelPos::new(30.0, 37.5));
+        assert_eq!(region_origin(&mut window, FloatingSnap::Pixel), PixelPos::new(30.0, 38.0));
+        assert_eq!(region_origin(&mut window, FloatingSnap::Cell), PixelPos::new(30.0, 45.0));
+        // The position the animations start from is not snapped
+        assert_eq!(window.grid_current_position, GridPos::new(3.0, 2.5));
+    }
+
+    #[test]
+    fn scaled_floats_are_sized_with_smaller_cells() {
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 20.0));
+        let mut window = RenderedWindow::new(2);
+        window.handle_window_draw_command(WindowDrawCommand::Position {
+            grid_position: (4.0, 2.0),
+            grid_size: (30, 5),
+            anchor_info: Some(AnchorInfo {
+                anchor_grid_id: 1,
+                anchor_type: WindowAnchor::NorthWest,
+                anchor_left: 4.0,
+                anchor_top: 2.0,
+                sort_order: SortOrder::default(),
+            }),
+            window_type: WindowType::Editor,
+        });
+        let grid_rect = GridRect::new(GridPos::new(0.0, 0.0), GridPos::new(80.0, 24.0));
+        let settings = RendererSettings { floating_font_scale: 0.8, ..Default::default() };
+        window.flush(&settings);
+        window.animate(&settings, &grid_rect, 0.1);
+
+        let content_grid_scale = window.content_grid_scale(grid_scale);
+        assert_eq!((content_grid_scale.width(), content_grid_scale.height()), (8.0, 16.0));
+        // The float stays anchored to the grid, only its size shrinks
+        let region = window.pixel_region(grid_scale);
+        assert_eq!(region.min, PixelPos::new(40.0, 40.0));
+        assert_eq!(region.size(), PixelSize::new(240.0, 80.0));
+
+        // Without the float the scale doesn't apply
+        window.handle_window_draw_command(WindowDrawCommand::Position {
+            grid_position: (4.0, 2.0),
+            grid_size: (30, 5),
+            anchor_info: None,
+            window_type: WindowType::Editor,
+        });
+        window.flush(&settings);
+        assert_eq!(window.pixel_region(grid_scale).size(), PixelSize::new(300.0, 100.0));
+    }
+
+    #[test]
+    fn animated_splits_grow_in_and_collapse_when_closed() {
+        let grid_rect = GridRect::new(GridPos::new(0.0, 0.0), GridPos::new(80.0, 24.0));
+        let settings = RendererSettings::default();
+        let mut window = RenderedWindow::new(2);
+        window.animate_splits = true;
+        window.handle_window_draw_command(WindowDrawCommand::Position {
+            grid_position: (40.0, 0.0),
+            grid_size: (40, 24),
+            anchor_info: None,
+            window_type: WindowType::Editor,
+        });
+
+        window.animate(&settings, &grid_rect, settings.position_animation_length / 2.0);
+        assert_eq!(window.grid_current_position, GridPos::new(40.0, 0.0));
+        assert!(window.grid_current_size.width > 0.0 && window.grid_current_size.width < 40.0);
+        window.animate(&settings, &grid_rect, settings.position_animation_length);
+        window.animate(&settings, &grid_rect, 0.0);
+        assert_eq!(window.grid_current_size, GridSize::new(40.0, 24.0));
+
+        assert!(window.start_closing());
+        assert!(!window.is_closed());
+        window.animate(&settings, &grid_rect, settings.position_animation_length);
+        window.animate(&settings, &grid_rect, 0.0);
+        assert_eq!(window.grid_current_size, GridSize::ZERO);
+        assert!(window.is_closed());
+    }
+
+    #[test]
+    fn splits_are_removed_right_away_without_the_animation() {
+        let mut window = create_window(10);
+        window.animate_splits = true;
+        // The outer grid is not a split
+        assert!(!window.start_closing());
+
+        let mut window = RenderedWindow::new(2);
+        window.handle_window_draw_command(WindowDrawCommand::Position {
+            grid_position: (0.0, 0.0),
+            grid_size: (40, 24),
+            anchor_info: None,
+            window_type: WindowType::Editor,
+        });
+        assert!(!window.start_closing());
+    }
+
+    #[test]
+    fn winblend_lowers_the_background_alpha_of_its_grid_only() {
+        let background = Color::from_argb(255, 30, 30, 30);
+        let mut blended = create_window(10);
+        let opaque = create_window(10);
+        blended.handle_window_draw_command(WindowDrawCommand::Blend(40));
+
+        assert_eq!(blended.blended_background(background), background.with_a(153));
+        assert_eq!(opaque.blended_background(background), background);
+
+        // Combines with the transparency of the window
+        assert_eq!(blended.blended_background(background.with_a(128)).a(), 77);
+
+        blended.handle_window_draw_command(WindowDrawCommand::Blend(150));
+        assert_eq!(blended.blended_background(background).a(), 0);
+    }
+
+    #[test]
+    fn winbar_rows_come_from_the_top_margin() {
+        assert_eq!(winbar_rows(0, false), 0..0);
+        assert_eq!(winbar_rows(2, false), 0..2);
+        assert_eq!(winbar_rows(1, true), 0..0);
+
+        let mut window = create_window(10);
+        window.handle_window_draw_command(WindowDrawCommand::ViewportMargins {
+            top: 1,
+            bottom: 0,
+            left: 0,
+            right: 0,
+        });
+        assert_eq!(winbar_rows(window.viewport_margins.top, window.anchor_info.is_some()), 0..1);
+    }
+
+    #[test]
+    fn scroll_buffer_lines_larger_than_the_window_is_capped() {
+        assert_eq!(scrollback_capacity(10, 100), 20);
+        assert_eq!(scrollback_capacity(10, 0), 20);
+        assert_eq!(scrollback_capacity(10, 4), 14);
+        assert_eq!(scrollback_capacity(0, 4), 0);
     }
 }
@@ -0,0 +1,142 @@
+use crate::{
+    renderer::{GridRenderer, text_cells::CellText},
+    units::PixelSize,
+};
+use skia_safe::{Canvas, Color4f, Data, FilterMode, Image, Paint, Rect, SamplingOptions};
+
+static LOGO: &[u8] = include_bytes!("../../assets/neovide-256x256.png");
+
+const TEXT: &str = "Starting Neovim…";
+const FADE_DURATION: f32 = 0.25;
+// The logo takes this part of the smallest side of the window, up to its own size
+const LOGO_SCALE: f32 = 0.3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SplashState {
+    /// Neovim hasn't flushed anything yet
+    Waiting,
+    /// The first frame is drawn below the fading splash
+    FadingOut {
+        opacity: f32,
+    },
+    Hidden,
+}
+
+/// Covers the window from its creation until Neovim flushes the first frame
+pub struct Splash {
+    state: SplashState,
+    logo: Option<Image>,
+}
+
+impl Splash {
+    pub fn new() -> Self {
+        Self { state: SplashState::Waiting, logo: None }
+    }
+
+    pub fn is_animating(&self) -> bool {
+        matches!(self.state, SplashState::FadingOut { .. })
+    }
+
+    /// Neovim flushed, the splash starts to fade out if it was still up
+    pub fn flushed(&mut self) {
+        if self.state == SplashState::Waiting {
+            self.state = SplashState::FadingOut { opacity: 1.0 };
+        }
+    }
+
+    pub fn animate(&mut self, dt: f32) {
+        if let SplashState::FadingOut { opacity } = self.state {
+            let opacity = opacity - dt / FADE_DURATION;
+            self.state = if opacity > 0.0 {
+                SplashState::FadingOut { opacity }
+            } else {
+                SplashState::Hidden
+            };
+        }
+    }
+
+    fn opacity(&self) -> f32 {
+        match self.state {
+            SplashState::Waiting => 1.0,
+            SplashState::FadingOut { opacity } => opacity,
+            SplashState::Hidden => 0.0,
+        }
+    }
+
+    /// Draws the logo and the text in the middle of the window, on the default background
+    pub fn draw(
+        &mut self,
+        canvas: &Canvas,
+        grid_renderer: &mut GridRenderer,
+        bounds: PixelSize<f32>,
+    ) {
+        let opacity = self.opacity();
+        if opacity <= 0.0 {
+            return;
+        }
+
+        let background = Color4f::from(grid_renderer.get_default_background(1.0));
+        let foreground =
+            grid_renderer.default_style.foreground(&grid_renderer.default_style.colors);
+        canvas.draw_rect(
+            Rect::from_wh(bounds.width, bounds.height),
+            &Paint::new(Color4f { a: background.a * opacity, ..background }, None),
+        );
+
+        let logo = self
+            .logo
+            .get_or_insert_with(|| Image::from_encoded(Data::new_copy(LOGO)).expect("valid logo"));
+        let row_height = grid_renderer.grid_scale.height();
+        let size = (bounds.width.min(bounds.height) * LOGO_SCALE).min(logo.width() as f32);
+        let top = (bounds.height - size - 2.0 * row_height) / 2.0;
+        let logo_rect = Rect::from_xywh((bounds.width - size) / 2.0, top, size, size);
+        let mut paint = Paint::default();
+        paint.set_alpha_f(opacity);
+        canvas.draw_image_rect_with_sampling_options(
+            &*logo,
+            None,
+            logo_rect,
+            SamplingOptions::from(FilterMode::Linear),
+            &paint,
+        );
+
+        let cells = CellText::new(TEXT, grid_renderer.ambiwidth);
+        let text_width = cells.cells() as f32 * grid_renderer.grid_scale.width();
+        let origin = (
+            (bounds.width - text_width) / 2.0,
+            logo_rect.bottom + row_height + grid_renderer.shaper.baseline_offset(),
+        );
+        let mut text_paint = Paint::new(Color4f { a: foreground.a * opacity, ..foreground }, None);
+        text_paint.set_anti_alias(false);
+        let style = grid_renderer.default_style.clone();
+        for blob in grid_renderer.shaper.shape_cached(cells.word(), (&style).into()).iter() {
+            canvas.draw_text_blob(blob, origin, &text_paint);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_splash_fades_out_after_the_first_flush() {
+        let mut splash = Splash::new();
+        splash.animate(1.0);
+        assert_eq!(splash.state, SplashState::Waiting);
+        assert!(!splash.is_animating());
+
+        splash.flushed();
+        assert!(splash.is_animating());
+        splash.animate(FADE_DURATION / 2.0);
+        assert_eq!(splash.opacity(), 0.5);
+
+        splash.flushed();
+        splash.animate(FADE_DURATION);
+        assert_eq!(splash.state, SplashState::Hidden);
+        assert!(!splash.is_animating());
+
+        splash.flushed();
+        assert_eq!(splash.state, SplashState::Hidden);
+    }
+}
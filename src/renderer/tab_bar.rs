@@ -0,0 +1,233 @@
+use skia_safe::{Canvas, Color4f, Paint, Rect};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    bridge::TabInfo,
+    renderer::{
+        GridRenderer,
+        text_cells::{AmbiWidth, CellText, grapheme_cells, text_cells},
+    },
+    units::{GridScale, PixelPos, PixelRect},
+};
+
+// The widest a tab gets, in cells, so that a couple of tabs don't stretch over the whole window
+const MAX_TAB_COLUMNS: f32 = 24.0;
+// Empty cells on each side of a tab name
+const TAB_PADDING_COLUMNS: f32 = 1.0;
+
+const INACTIVE_TEXT_ALPHA: f32 = 0.6;
+const BAR_TINT_ALPHA: f32 = 0.08;
+const SEPARATOR_ALPHA: f32 = 0.2;
+
+/// A native tab bar drawn above the grid from the externalized tabline
+pub struct TabBar {
+    tabs: Vec<TabInfo>,
+    current_tab: u64,
+    show_tabline: u64,
+    // Where the bar was drawn last, in window pixels
+    region: Option<PixelRect<f32>>,
+}
+
+impl TabBar {
+    pub fn new() -> Self {
+        // 'showtabline' defaults to only showing the tabline when there are multiple tabs
+        Self { tabs: Vec::new(), current_tab: 0, show_tabline: 1, region: None }
+    }
+
+    pub fn update(&mut self, current_tab: u64, tabs: Vec<TabInfo>) {
+        self.current_tab = current_tab;
+        self.tabs = tabs;
+    }
+
+    pub fn set_show_tabline(&mut self, show_tabline: u64) {
+        self.show_tabline = show_tabline;
+    }
+
+    /// Whether the bar is shown, following 'showtabline' like the builtin tabline does
+    pub fn is_visible(&self, enabled: bool) -> bool {
+        enabled
+            && match self.show_tabline {
+                0 => false,
+                1 => self.tabs.len() > 1,
+                _ => !self.tabs.is_empty(),
+            }
+    }
+
+    /// The space reserved above the grid, one line high when the bar is shown
+    pub fn height(&self, enabled: bool, grid_scale: GridScale) -> u32 {
+        if self.is_visible(enabled) { grid_scale.height().ceil() as u32 } else { 0 }
+    }
+
+    /// Returns true when the position is on the bar, including the empty space after the tabs
+    pub fn contains(&self, position: PixelPos<f32>) -> bool {
+        self.region.is_some_and(|region| {
+            (region.min.x..region.max.x).contains(&position.x)
+                && (region.min.y..region.max.y).contains(&position.y)
+        })
+    }
+
+    /// Returns the handle of the tab under the position
+    pub fn tab_at(&self, position: PixelPos<f32>, grid_scale: GridScale) -> Option<u64> {
+        if !self.contains(position) {
+            return None;
+        }
+        let region = self.region?;
+        let index = tab_index_at(
+            position.x - region.min.x,
+            region.size().width,
+            self.tabs.len(),
+            MAX_TAB_COLUMNS * grid_scale.width(),
+        )?;
+        self.tabs.get(index).map(|tab| tab.handle)
+    }
+
+    /// Draws the bar right above `below`, usually the grid
+    pub fn draw(
+        &mut self,
+        enabled: bool,
+        canvas: &Canvas,
+        grid_renderer: &mut GridRenderer,
+        below: &PixelRect<f32>,
+    ) {
+        let height = self.height(enabled, grid_renderer.grid_scale) as f32;
+        if height == 0.0 {
+            self.region = None;
+            return;
+        }
+
+        let region = PixelRect::new(
+            PixelPos::new(below.min.x, below.min.y - height),
+            PixelPos::new(below.max.x, below.min.y),
+        );
+        self.region = Some(region);
+
+        let cell_width = grid_renderer.grid_scale.width();
+        let foreground =
+            grid_renderer.default_style.foreground(&grid_renderer.default_style.colors);
+        let background = Color4f::from(grid_renderer.get_default_background(1.0));
+        let with_alpha = |color: Color4f, alpha: f32| Color4f { a: alpha, ..color };
+
+        let bar_rect = Rect::from_xywh(region.min.x, region.min.y, region.size().width, height);
+        canvas.draw_rect(bar_rect, &Paint::new(background, None));
+        canvas.draw_rect(bar_rect, &Paint::new(with_alpha(foreground, BAR_TINT_ALPHA), None));
+
+        let tab_width =
+            tab_width(region.size().width, self.tabs.len(), MAX_TAB_COLUMNS * cell_width).floor();
+        let max_columns = ((tab_width / cell_width) - 2.0 * TAB_PADDING_COLUMNS).max(0.0) as usize;
+        let baseline_offset = grid_renderer.shaper.baseline_offset();
+        let separator_paint = Paint::new(with_alpha(foreground, SEPARATOR_ALPHA), None);
+
+        for (index, tab) in self.tabs.iter().enumerate() {
+            let x = region.min.x + index as f32 * tab_width;
+            let is_current = tab.handle == self.current_tab;
+            if is_current {
+                let tab_rect = Rect::from_xywh(x, region.min.y, tab_width, height);
+                canvas.draw_rect(tab_rect, &Paint::new(background, None));
+            } else {
+                let separator = Rect::from_xywh(x + tab_width - 1.0, region.min.y, 1.0, height);
+                canvas.draw_rect(separator, &separator_paint);
+            }
+
+            let label = elide(&tab.name, max_columns, grid_renderer.ambiwidth);
+            let cells = CellText::new(&label, grid_renderer.ambiwidth);
+            let text_color =
+                if is_current { foreground } else { with_alpha(foreground, INACTIVE_TEXT_ALPHA) };
+            let mut paint = Paint::new(text_color, None);
+            paint.set_anti_alias(false);
+
+            let origin = (x + TAB_PADDING_COLUMNS * cell_width, region.min.y + baseline_offset);
+            let style = grid_renderer.default_style.clone();
+            for blob in grid_renderer.shaper.shape_cached(cells.word(), (&style).into()).iter() {
+                canvas.draw_text_blob(blob, origin, &paint);
+            }
+        }
+    }
+}
+
+/// Every tab gets the same width, up to `max_tab_width`
+fn tab_width(bar_width: f32, tab_count: usize, max_tab_width: f32) -> f32 {
+    if tab_count == 0 { 0.0 } else { (bar_width / tab_count as f32).min(max_tab_width) }
+}
+
+/// Maps a click `x` pixels from the left edge of the bar to the index of the tab under it
+fn tab_index_at(x: f32, bar_width: f32, tab_count: usize, max_tab_width: f32) -> Option<usize> {
+    let tab_width = tab_width(bar_width, tab_count, max_tab_width).floor();
+    if x < 0.0 || x >= bar_width || tab_width <= 0.0 {
+        return None;
+    }
+    let index = (x / tab_width) as usize;
+    (index < tab_count).then_some(index)
+}
+
+/// Shortens long names from the start, the end of a path is the part that tells tabs apart. Wide
+/// characters take two of the `max_columns`.
+pub(super) fn elide(name: &str, max_columns: usize, ambiwidth: AmbiWidth) -> String {
+    if text_cells(name, ambiwidth) <= max_columns {
+        return name.to_owned();
+    }
+    let mut columns = grapheme_cells("…", ambiwidth);
+    if columns > max_columns {
+        return String::new();
+    }
+    let mut tail = name
+        .graphemes(true)
+        .rev()
+        .take_while(|grapheme| {
+            columns += grapheme_cells(grapheme, ambiwidth);
+            columns <= max_columns
+        })
+        .collect::<Vec<_>>();
+    tail.push("…");
+    tail.into_iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clicks_map_to_equally_wide_tabs() {
+        assert_eq!(tab_index_at(0.0, 300.0, 3, 1000.0), Some(0));
+        assert_eq!(tab_index_at(99.0, 300.0, 3, 1000.0), Some(0));
+        assert_eq!(tab_index_at(100.0, 300.0, 3, 1000.0), Some(1));
+        assert_eq!(tab_index_at(299.0, 300.0, 3, 1000.0), Some(2));
+    }
+
+    #[test]
+    fn clicks_after_the_last_tab_hit_nothing() {
+        // Two tabs capped at 80 pixels leave the rest of the bar empty
+        assert_eq!(tab_index_at(159.0, 300.0, 2, 80.0), Some(1));
+        assert_eq!(tab_index_at(160.0, 300.0, 2, 80.0), None);
+        assert_eq!(tab_index_at(300.0, 300.0, 3, 1000.0), None);
+        assert_eq!(tab_index_at(-1.0, 300.0, 3, 1000.0), None);
+        assert_eq!(tab_index_at(10.0, 300.0, 0, 1000.0), None);
+    }
+
+    #[test]
+    fn long_names_are_elided_from_the_start() {
+        let single = AmbiWidth::Single;
+        assert_eq!(elide("main.rs", 10, single), "main.rs");
+        assert_eq!(elide("src/renderer/mod.rs", 10, single), "…er/mod.rs");
+        assert_eq!(elide("café.rs", 4, single), "…é.rs");
+        assert_eq!(elide("main.rs", 0, single), "");
+    }
+
+    #[test]
+    fn visibility_follows_showtabline() {
+        let tab = |handle| TabInfo { handle, name: String::new() };
+        let mut tab_bar = TabBar::new();
+        tab_bar.update(1, vec![tab(1)]);
+        assert!(!tab_bar.is_visible(true));
+
+        tab_bar.update(1, vec![tab(1), tab(2)]);
+        assert!(tab_bar.is_visible(true));
+        assert!(!tab_bar.is_visible(false));
+
+        tab_bar.set_show_tabline(0);
+        assert!(!tab_bar.is_visible(true));
+
+        tab_bar.set_show_tabline(2);
+        tab_bar.update(1, vec![tab(1)]);
+        assert!(tab_bar.is_visible(true));
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use skia_safe::{Canvas, Color4f, Paint, PathBuilder, paint::Style};
+
+// Used when the colorscheme doesn't define the NonText group
+const DEFAULT_COLOR: Color4f = Color4f::new(0.5, 0.5, 0.5, 1.0);
+const ALPHA: f32 = 0.35;
+// The arrow is inset from the sides of the tab by this part of a cell
+const INSET: f32 = 0.25;
+
+/// A tab character in a window without 'list', drawn as blank cells from `column` to
+/// `end_column` included. `row` is counted from the top of the grid, winbar included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TabIndicator {
+    pub row: u64,
+    pub column: u64,
+    pub end_column: u64,
+    pub color: Option<Color4f>,
+}
+
+/// Maps the tabs to the rows of the scrollable region below the `top_margin`, which hold `height`
+/// rows. Tabs outside of it are dropped.
+pub fn indicator_rows(
+    tabs: &[TabIndicator],
+    top_margin: u64,
+    height: u64,
+) -> HashMap<isize, Vec<TabIndicator>> {
+    let mut rows = HashMap::<isize, Vec<TabIndicator>>::new();
+    for tab in tabs {
+        let Some(row) = tab.row.checked_sub(top_margin).filter(|row| *row < height) else {
+            continue;
+        };
+        rows.entry(row as isize).or_default().push(tab.clone());
+    }
+    rows
+}
+
+/// The horizontal span of the arrow of a tab, from the left of its row, in pixels
+fn arrow_span(tab: &TabIndicator, cell_width: f32) -> (f32, f32) {
+    let left = (tab.column as f32 + INSET) * cell_width;
+    let right = (tab.end_column as f32 + 1.0 - INSET) * cell_width;
+    (left, right.max(left))
+}
+
+/// Draws the arrows of the tabs of a row, faint and centered in it. `origin` is the top left of
+/// the row.
+pub fn draw_arrows(
+    canvas: &Canvas,
+    tabs: &[TabIndicator],
+    origin: (f32, f32),
+    cell_width: f32,
+    line_height: f32,
+) {
+    let center_y = origin.1 + line_height / 2.0;
+    let head = (cell_width * 0.3).min(line_height * 0.2);
+    for tab in tabs {
+        let (left, right) = arrow_span(tab, cell_width);
+        let (left, right) = (origin.0 + left, origin.0 + right);
+        let color = tab.color.unwrap_or(DEFAULT_COLOR);
+        let mut paint = Paint::new(Color4f { a: ALPHA, ..color }, None);
+        paint.set_anti_alias(true);
+        paint.set_style(Style::Stroke);
+        paint.set_stroke_width((line_height * 0.05).max(1.0));
+
+        let mut builder = PathBuilder::new();
+        builder
+            .move_to((left, center_y))
+            .line_to((right, center_y))
+            .move_to((right - head, center_y - head))
+            .line_to((right, center_y))
+            .line_to((right - head, center_y + head));
+        canvas.draw_path(&builder.detach(), &paint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab(row: u64, column: u64, end_column: u64) -> TabIndicator {
+        TabIndicator { row, column, end_column, color: None }
+    }
+
+    #[test]
+    fn tabs_are_mapped_below_the_winbar() {
+        let tabs = [tab(0, 4, 7), tab(1, 4, 7), tab(1, 12, 15), tab(11, 0, 3)];
+        let rows = indicator_rows(&tabs, 1, 10);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[&0], vec![tab(1, 4, 7), tab(1, 12, 15)]);
+    }
+
+    #[test]
+    fn arrows_span_the_cells_of_the_tab() {
+        // A tab after four columns of text with a tabstop of 4 covers the columns 4 to 7
+        assert_eq!(arrow_span(&tab(0, 4, 7), 10.0), (42.5, 77.5));
+        // A tab right before a tab stop is a single cell
+        assert_eq!(arrow_span(&tab(0, 3, 3), 10.0), (32.5, 37.5));
+    }
+}
@@ -0,0 +1,132 @@
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+use crate::editor::Word;
+
+// Stands for the clusters that are too long for the sizes of a word
+const REPLACEMENT: &str = "\u{FFFD}";
+
+/// How many cells Neovim gives the characters of ambiguous width, like `…` or `→`. Set through
+/// the 'ambiwidth' option.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AmbiWidth {
+    #[default]
+    Single,
+    Double,
+}
+
+impl From<&str> for AmbiWidth {
+    fn from(value: &str) -> Self {
+        match value {
+            "double" => AmbiWidth::Double,
+            _ => AmbiWidth::Single,
+        }
+    }
+}
+
+/// The number of cells Neovim gives a grapheme cluster, decided by its first character
+pub fn grapheme_cells(grapheme: &str, ambiwidth: AmbiWidth) -> usize {
+    let Some(character) = grapheme.chars().next() else {
+        return 0;
+    };
+    let width = match ambiwidth {
+        AmbiWidth::Single => character.width(),
+        AmbiWidth::Double => character.width_cjk(),
+    };
+    if width == Some(2) { 2 } else { 1 }
+}
+
+/// The number of cells of text that Neovim didn't lay out on a grid
+pub fn text_cells(text: &str, ambiwidth: AmbiWidth) -> usize {
+    text.graphemes(true).map(|grapheme| grapheme_cells(grapheme, ambiwidth)).sum()
+}
+
+/// Text that Neovim didn't lay out on a grid, like the cmdline, with the cluster sizes of its
+/// cells. As in the grid, the second cell of a double width cluster is empty. The sizes of a word
+/// are bytes, so the clusters longer than that, only made of piles of combining marks, are
+/// replaced.
+pub struct CellText<'a> {
+    pub text: Cow<'a, str>,
+    pub cluster_sizes: Vec<u8>,
+}
+
+impl<'a> CellText<'a> {
+    pub fn new(text: &'a str, ambiwidth: AmbiWidth) -> Self {
+        let too_long = |grapheme: &str| grapheme.len() > u8::MAX as usize;
+        let text = if text.graphemes(true).any(too_long) {
+            Cow::Owned(
+                text.graphemes(true)
+                    .map(|grapheme| if too_long(grapheme) { REPLACEMENT } else { grapheme })
+                    .collect(),
+            )
+        } else {
+            Cow::Borrowed(text)
+        };
+        let cluster_sizes = text
+            .graphemes(true)
+            .flat_map(|grapheme| {
+                let size = grapheme.len() as u8;
+                match grapheme_cells(grapheme, ambiwidth) {
+                    2 => vec![size, 0],
+                    _ => vec![size],
+                }
+            })
+            .collect();
+        Self { text, cluster_sizes }
+    }
+
+    pub fn cells(&self) -> usize {
+        self.cluster_sizes.len()
+    }
+
+    pub fn word(&self) -> Word<'_> {
+        Word::new(&self.text, &self.cluster_sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ambiguous_characters_follow_ambiwidth() {
+        assert_eq!(grapheme_cells("…", AmbiWidth::Single), 1);
+        assert_eq!(grapheme_cells("…", AmbiWidth::Double), 2);
+        assert_eq!(grapheme_cells("α", AmbiWidth::Double), 2);
+        assert_eq!(CellText::new("a…b", AmbiWidth::Double).cluster_sizes, [1, 3, 0, 1]);
+        assert_eq!(CellText::new("a…b", AmbiWidth::Single).cluster_sizes, [1, 3, 1]);
+    }
+
+    #[test]
+    fn wide_characters_always_take_two_cells() {
+        assert_eq!(grapheme_cells("a", AmbiWidth::Double), 1);
+        assert_eq!(grapheme_cells("日", AmbiWidth::Single), 2);
+        assert_eq!(grapheme_cells("한", AmbiWidth::Single), 2);
+        assert_eq!(CellText::new("日本", AmbiWidth::Single).cluster_sizes, [3, 0, 3, 0]);
+        assert!(CellText::new("", AmbiWidth::Double).cluster_sizes.is_empty());
+        assert_eq!(text_cells("a日…", AmbiWidth::Single), 4);
+        assert_eq!(text_cells("a日…", AmbiWidth::Double), 5);
+    }
+
+    #[test]
+    fn clusters_too_long_for_a_word_are_replaced() {
+        let pile = format!("e{}", "\u{301}".repeat(200));
+        let text = format!("a{pile}b");
+        let cells = CellText::new(&text, AmbiWidth::Single);
+        assert_eq!(cells.text, "a\u{FFFD}b");
+        assert_eq!(cells.cluster_sizes, [1, 3, 1]);
+        let clusters = cells.word().grapheme_clusters().map(|(_, cluster)| cluster);
+        assert_eq!(clusters.collect::<Vec<_>>(), ["a", "\u{FFFD}", "b"]);
+
+        let cells = CellText::new("aé", AmbiWidth::Single);
+        assert!(matches!(cells.text, Cow::Borrowed("aé")));
+    }
+
+    #[test]
+    fn ambiwidth_is_parsed_from_the_option() {
+        assert_eq!(AmbiWidth::from("double"), AmbiWidth::Double);
+        assert_eq!(AmbiWidth::from("single"), AmbiWidth::Single);
+    }
+}
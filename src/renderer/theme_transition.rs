@@ -0,0 +1,163 @@
+use std::{sync::Arc, time::Duration};
+
+use skia_safe::Color4f;
+
+use crate::{
+    editor::{Colors, Style},
+    renderer::animation_utils::{ease_in_out_quad, lerp},
+};
+
+// Small tweaks of the colors, like a plugin adjusting the contrast, are applied right away
+const SIGNIFICANT_CHANGE: f32 = 0.1;
+
+struct Fade {
+    from: Colors,
+    to: Arc<Style>,
+    elapsed: Duration,
+    duration: Duration,
+}
+
+/// Cross-fades the default colors when the colorscheme changes. Set through
+/// `g:neovide_theme_transition_ms`.
+#[derive(Default)]
+pub struct ThemeTransition {
+    fade: Option<Fade>,
+    // The first default colors replace the built-in ones, which were never shown
+    has_colors: bool,
+}
+
+impl ThemeTransition {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the style to draw with right away, which is the new one unless a fade starts from
+    /// the `shown` colors
+    pub fn change(&mut self, shown: &Style, to: Style, duration: Duration) -> Arc<Style> {
+        let to = Arc::new(to);
+        let has_colors = std::mem::replace(&mut self.has_colors, true);
+        if !has_colors || duration.is_zero() || !is_significant(&shown.colors, &to.colors) {
+            self.fade = None;
+            return to;
+        }
+        self.fade = Some(Fade {
+            from: shown.colors.clone(),
+            to: to.clone(),
+            elapsed: Duration::ZERO,
+            duration,
+        });
+        Arc::new(Style { colors: shown.colors.clone(), ..(*to).clone() })
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.fade.is_some()
+    }
+
+    /// Advances the fade, returning the style to draw with while there is one
+    pub fn animate(&mut self, dt: f32) -> Option<Arc<Style>> {
+        let fade = self.fade.as_mut()?;
+        fade.elapsed += Duration::from_secs_f32(dt.max(0.0));
+        if fade.elapsed >= fade.duration {
+            return self.fade.take().map(|fade| fade.to);
+        }
+        let t = ease_in_out_quad(fade.elapsed.as_secs_f32() / fade.duration.as_secs_f32());
+        Some(Arc::new(Style {
+            colors: interpolate_colors(&fade.from, &fade.to.colors, t),
+            ..(*fade.to).clone()
+        }))
+    }
+}
+
+fn interpolate_color(from: Color4f, to: Color4f, t: f32) -> Color4f {
+    Color4f::new(
+        lerp(from.r, to.r, t),
+        lerp(from.g, to.g, t),
+        lerp(from.b, to.b, t),
+        lerp(from.a, to.a, t),
+    )
+}
+
+/// The colors `t` of the way from `from` to `to`, colors missing on either side are not faded
+pub fn interpolate_colors(from: &Colors, to: &Colors, t: f32) -> Colors {
+    let mix = |from: Option<Color4f>, to: Option<Color4f>| match (from, to) {
+        (Some(from), Some(to)) => Some(interpolate_color(from, to, t)),
+        (_, to) => to,
+    };
+    Colors {
+        foreground: mix(from.foreground, to.foreground),
+        background: mix(from.background, to.background),
+        special: mix(from.special, to.special),
+    }
+}
+
+fn is_significant(from: &Colors, to: &Colors) -> bool {
+    let difference = |from: Option<Color4f>, to: Option<Color4f>| match (from, to) {
+        (Some(from), Some(to)) => [from.r - to.r, from.g - to.g, from.b - to.b]
+            .into_iter()
+            .fold(0.0f32, |max, channel| max.max(channel.abs())),
+        _ => 0.0,
+    };
+    difference(from.background, to.background) >= SIGNIFICANT_CHANGE
+        || difference(from.foreground, to.foreground) >= SIGNIFICANT_CHANGE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DURATION: Duration = Duration::from_millis(250);
+
+    fn colors(foreground: Color4f, background: Color4f) -> Colors {
+        Colors::new(Some(foreground), Some(background), None)
+    }
+
+    fn dark() -> Style {
+        Style::new(colors(Color4f::new(1.0, 1.0, 1.0, 1.0), Color4f::new(0.0, 0.0, 0.0, 1.0)))
+    }
+
+    fn light() -> Style {
+        Style::new(colors(Color4f::new(0.0, 0.0, 0.0, 1.0), Color4f::new(1.0, 0.8, 0.6, 1.0)))
+    }
+
+    #[test]
+    fn the_colors_are_halfway_at_the_midpoint() {
+        let midpoint = interpolate_colors(&dark().colors, &light().colors, 0.5);
+        assert_eq!(midpoint.foreground, Some(Color4f::new(0.5, 0.5, 0.5, 1.0)));
+        assert_eq!(midpoint.background, Some(Color4f::new(0.5, 0.4, 0.3, 1.0)));
+        assert_eq!(midpoint.special, None);
+    }
+
+    #[test]
+    fn a_colorscheme_change_fades_to_the_new_colors() {
+        let mut transition = ThemeTransition::new();
+        let shown = transition.change(&Style::new(Colors::new(None, None, None)), dark(), DURATION);
+        assert_eq!(*shown, dark());
+        assert!(!transition.is_animating());
+
+        let shown = transition.change(&shown, light(), DURATION);
+        assert_eq!(*shown, dark());
+        assert!(transition.is_animating());
+
+        let halfway = transition.animate(0.125).unwrap();
+        assert_eq!(halfway.colors, interpolate_colors(&dark().colors, &light().colors, 0.5));
+
+        assert_eq!(*transition.animate(0.125).unwrap(), light());
+        assert!(!transition.is_animating());
+        assert_eq!(transition.animate(0.125), None);
+    }
+
+    #[test]
+    fn small_changes_and_a_zero_duration_are_applied_right_away() {
+        let mut transition = ThemeTransition::new();
+        let shown = transition.change(&Style::new(Colors::new(None, None, None)), dark(), DURATION);
+
+        let mut almost_dark = dark();
+        almost_dark.colors.background = Some(Color4f::new(0.05, 0.05, 0.05, 1.0));
+        let shown = transition.change(&shown, almost_dark.clone(), DURATION);
+        assert_eq!(*shown, almost_dark);
+        assert!(!transition.is_animating());
+
+        assert_eq!(*transition.change(&shown, light(), Duration::ZERO), light());
+        assert!(!transition.is_animating());
+    }
+}
@@ -12,7 +12,10 @@ use winit::{event_loop::EventLoopProxy, window::Window};
 
 use crate::{
     renderer::SkiaRenderer,
-    settings::Settings,
+    settings::{
+        Settings,
+        power::{battery_saver_active, max_refresh_rate},
+    },
     window::{EventPayload, WindowSettings},
 };
 use vsync_timer::VSyncTimer;
@@ -90,7 +93,11 @@ impl VSync {
     }
 
     pub fn get_refresh_rate(&self, window: &Window, settings: &Settings) -> f32 {
-        let settings_refresh_rate = 1.0 / settings.get::<WindowSettings>().refresh_rate as f32;
+        let refresh_rate = max_refresh_rate(
+            settings.get::<WindowSettings>().refresh_rate,
+            battery_saver_active(settings),
+        );
+        let settings_refresh_rate = 1.0 / refresh_rate as f32;
 
         match self {
             VSync::Timer(_) => settings_refresh_rate,
@@ -5,7 +5,13 @@ use std::{
 
 use spin_sleep::SpinSleeper;
 
-use crate::{settings::Settings, window::WindowSettings};
+use crate::{
+    settings::{
+        Settings,
+        power::{battery_saver_active, max_refresh_rate},
+    },
+    window::WindowSettings,
+};
 
 pub struct VSyncTimer {
     sleeper: SpinSleeper,
@@ -19,9 +25,11 @@ impl VSyncTimer {
     }
 
     pub fn wait_for_vsync(&mut self) {
-        let refresh_duration = Duration::from_secs_f64(
-            1.0 / self.settings.get::<WindowSettings>().refresh_rate as f64,
+        let refresh_rate = max_refresh_rate(
+            self.settings.get::<WindowSettings>().refresh_rate,
+            battery_saver_active(&self.settings),
         );
+        let refresh_duration = Duration::from_secs_f64(1.0 / refresh_rate as f64);
         let next_refresh = self.last_refresh + refresh_duration;
         self.last_refresh = next_refresh;
         let sleep_duration = next_refresh.saturating_duration_since(Instant::now());
@@ -1,5 +1,7 @@
 pub mod font;
 mod from_value;
+pub mod motion;
+pub mod power;
 mod window_size;
 
 use anyhow::{Context, Result};
@@ -18,8 +20,9 @@ use winit::event_loop::EventLoopProxy;
 use crate::{bridge::NeovimWriter, window::EventPayload, window::RouteId};
 pub use from_value::ParseFromValue;
 pub use window_size::{
-    DEFAULT_GRID_SIZE, MIN_GRID_SIZE, PersistentWindowSettings, clamped_grid_size,
-    load_last_window_settings, neovide_std_datapath, save_window_size,
+    DEFAULT_GRID_SIZE, MAX_GRID_SIZE, MIN_GRID_SIZE, MonitorScaleFactors, PersistentWindowSettings,
+    clamped_grid_size, load_last_window_settings, load_monitor_scale_factors, neovide_std_datapath,
+    save_window_size,
 };
 
 pub mod config;
@@ -47,6 +50,14 @@ impl FontConfigState {
 type UpdateHandlerFunc = fn(&Settings, Value) -> SettingsChanged;
 type ReaderHandlerFunc = fn(&Settings) -> Option<Value>;
 
+struct SettingObserver(Box<dyn Fn(&Settings) + Send + Sync>);
+
+impl Debug for SettingObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SettingObserver")
+    }
+}
+
 // The Settings struct acts as a global container where each of Neovide's subsystems can store
 // their own settings. It will also coordinate updates between Neovide and nvim to make sure the
 // settings remain consistent on both sides.
@@ -59,6 +70,9 @@ pub struct Settings {
     settings: RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
     updaters: RwLock<HashMap<SettingLocation, UpdateHandlerFunc>>,
     readers: RwLock<HashMap<SettingLocation, ReaderHandlerFunc>>,
+    // The setting group each location updates, setting names are only unique within a group
+    groups: RwLock<HashMap<SettingLocation, TypeId>>,
+    observers: RwLock<HashMap<(TypeId, String), Vec<SettingObserver>>>,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -73,6 +87,8 @@ impl Settings {
     pub fn new() -> Self {
         let settings = Self::default();
         settings.set(&FontConfigState::new());
+        settings.set(&power::PowerState::new());
+        settings.set(&motion::MotionState::new());
         settings
     }
 
@@ -105,19 +121,57 @@ impl Settings {
         (*value).clone()
     }
 
+    /// Calls `callback` with the updated settings group every time the setting or option `name` of
+    /// the group `T` changes. The callback runs on the thread that received the update, and must
+    /// not register new observers itself.
+    pub fn on_change<T: Clone + Send + Sync + 'static>(
+        &self,
+        name: &str,
+        callback: impl Fn(&T) + Send + Sync + 'static,
+    ) {
+        let observer = SettingObserver(Box::new(move |settings| callback(&settings.get::<T>())));
+        self.observers
+            .write()
+            .entry((TypeId::of::<T>(), name.to_owned()))
+            .or_default()
+            .push(observer);
+    }
+
+    fn apply_update(
+        &self,
+        update_handler: UpdateHandlerFunc,
+        location: &SettingLocation,
+        value: Value,
+    ) -> SettingsChanged {
+        let event = update_handler(self, value);
+        let Some(&group) = self.groups.read().get(location) else {
+            return event;
+        };
+        let (SettingLocation::NeovideGlobal(name) | SettingLocation::NeovimOption(name)) = location;
+        if let Some(observers) = self.observers.read().get(&(group, name.clone())) {
+            for observer in observers {
+                (observer.0)(self);
+            }
+        }
+        event
+    }
+
     pub fn setting_locations(&self) -> Vec<SettingLocation> {
         self.updaters.read().keys().cloned().collect()
     }
 
-    pub async fn read_initial_values(&self, nvim: &Neovim<NeovimWriter>) -> Result<()> {
+    fn readable_locations(&self) -> Vec<SettingLocation> {
         let deprecated_settings = ["transparency".to_owned()];
-        let keys: Vec<SettingLocation> = self
-            .updaters
+        self.updaters
             .read()
             .keys()
             .filter(|key| !matches!(key, SettingLocation::NeovideGlobal(name) if deprecated_settings.contains(name)))
             .cloned()
-            .collect();
+            .collect()
+    }
+
+    pub async fn read_initial_values(&self, nvim: &Neovim<NeovimWriter>) -> Result<()> {
+        let keys = self.readable_locations();
 
         for location in keys {
             match &location {
@@ -125,7 +179,8 @@ impl Settings {
                     let variable_name = format!("neovide_{name}");
                     match nvim.get_var(&variable_name).await {
                         Ok(value) => {
-                            self.updaters.read().get(&location).unwrap()(self, value);
+                            let update_handler = *self.updaters.read().get(&location).unwrap();
+                            self.apply_update(update_handler, &location, value);
                         }
                         Err(error) => {
                             trace!("Initial value load failed for {name}: {error}");
@@ -140,7 +195,8 @@ impl Settings {
                 }
                 SettingLocation::NeovimOption(name) => match nvim.get_option(name).await {
                     Ok(value) => {
-                        self.updaters.read().get(&location).unwrap()(self, value);
+                        let update_handler = *self.updaters.read().get(&location).unwrap();
+                        self.apply_update(update_handler, &location, value);
                     }
                     Err(error) => {
                         trace!("Initial value load failed for {name}: {error}");
@@ -151,6 +207,48 @@ impl Settings {
         Ok(())
     }
 
+    /// Re-reads all the settings from Neovim, and applies the ones that changed since they were
+    /// last read. This is used to catch up with variables that were set without a notification.
+    pub async fn reload_values(
+        &self,
+        nvim: &Neovim<NeovimWriter>,
+        event_loop_proxy: &EventLoopProxy<EventPayload>,
+        route_id: RouteId,
+    ) {
+        let keys = self.readable_locations();
+
+        for location in keys {
+            let value = match &location {
+                SettingLocation::NeovideGlobal(name) => {
+                    nvim.get_var(&format!("neovide_{name}")).await
+                }
+                SettingLocation::NeovimOption(name) => nvim.get_option(name).await,
+            };
+            let value = match value {
+                Ok(value) => value,
+                Err(error) => {
+                    trace!("Reloading {location:?} failed: {error}");
+                    continue;
+                }
+            };
+
+            if let Some(event) = self.reload_value(&location, value) {
+                let _ =
+                    event_loop_proxy.send_event(EventPayload::for_route(event.into(), route_id));
+            }
+        }
+    }
+
+    fn reload_value(&self, location: &SettingLocation, value: Value) -> Option<SettingsChanged> {
+        let current_value = self.readers.read().get(location).and_then(|reader| reader(self));
+        if current_value.is_some_and(|current_value| same_value(&current_value, &value)) {
+            return None;
+        }
+
+        let update_handler = self.updaters.read().get(location).copied()?;
+        Some(self.apply_update(update_handler, location, value))
+    }
+
     pub fn handle_setting_changed_notification(
         &self,
         arguments: Vec<Value>,
@@ -181,7 +279,7 @@ impl Settings {
             }
         };
 
-        let event = update_handler(self, value);
+        let event = self.apply_update(update_handler, &location, value);
         let _ = event_loop_proxy.send_event(EventPayload::for_route(event.into(), route_id));
     }
 
@@ -215,13 +313,28 @@ impl Settings {
             }
         };
 
-        let event = update_handler(self, value);
+        let event = self.apply_update(update_handler, &location, value);
 
         let _ = event_loop_proxy.send_event(EventPayload::for_route(event.into(), route_id));
     }
 
-    pub fn register<T: SettingGroup>(&self) {
+    pub fn register<T: SettingGroup + 'static>(&self) {
+        let registered = self.updaters.read().keys().cloned().collect::<Vec<_>>();
         T::register(self);
+        let mut groups = self.groups.write();
+        for location in self.updaters.read().keys() {
+            if !registered.contains(location) {
+                groups.insert(location.clone(), TypeId::of::<T>());
+            }
+        }
+    }
+}
+
+// Neovim sends all the floats as f64, so compare numbers by value rather than by representation
+fn same_value(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs.as_f64(), rhs.as_f64()) {
+        (Some(lhs), Some(rhs)) => lhs == rhs,
+        _ => lhs == rhs,
     }
 }
 
@@ -257,6 +370,8 @@ mod tests {
         }
     }
 
+    use std::sync::{Arc, Mutex};
+
     use async_trait::async_trait;
     use nvim_rs::{Handler, Neovim};
 
@@ -389,4 +504,72 @@ mod tests {
         assert_eq!(test_settings.baz, "baz");
         assert_eq!(test_settings.mousemoveevent_option, Some(true));
     }
+
+    #[test]
+    fn test_reload_value_applies_changed_transparency() {
+        let settings = Settings::new();
+        settings.register::<crate::window::WindowSettings>();
+        let location = SettingLocation::NeovideGlobal("opacity".to_owned());
+
+        let event = settings.reload_value(&location, Value::from(0.5));
+        assert_eq!(
+            event,
+            Some(SettingsChanged::Window(crate::window::WindowSettingsChanged::Opacity(0.5)))
+        );
+        assert_eq!(settings.get::<crate::window::WindowSettings>().opacity, 0.5);
+
+        // Unchanged values don't fire any update
+        assert_eq!(settings.reload_value(&location, Value::from(0.5)), None);
+    }
+
+    #[test]
+    fn test_on_change_is_called_with_the_new_value() {
+        let settings = Settings::new();
+        settings.register::<crate::window::WindowSettings>();
+
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        settings.on_change::<crate::window::WindowSettings>("opacity", {
+            let observed = observed.clone();
+            move |window_settings| observed.lock().unwrap().push(window_settings.opacity)
+        });
+
+        let opacity = SettingLocation::NeovideGlobal("opacity".to_owned());
+        let padding = SettingLocation::NeovideGlobal("padding_top".to_owned());
+        settings.reload_value(&opacity, Value::from(0.5));
+        settings.reload_value(&padding, Value::from(10));
+        settings.reload_value(&opacity, Value::from(0.8));
+
+        assert_eq!(*observed.lock().unwrap(), vec![0.5, 0.8]);
+    }
+
+    #[test]
+    fn test_on_change_only_fires_for_the_setting_of_its_group() {
+        let settings = Settings::new();
+        settings.register::<crate::window::WindowSettings>();
+        settings.register::<crate::renderer::RendererSettings>();
+
+        let window_calls = Arc::new(Mutex::new(0));
+        let renderer_calls = Arc::new(Mutex::new(0));
+        settings.on_change::<crate::window::WindowSettings>("opacity", {
+            let window_calls = window_calls.clone();
+            move |_| *window_calls.lock().unwrap() += 1
+        });
+        // Same name, but the renderer settings have no such setting
+        settings.on_change::<crate::renderer::RendererSettings>("opacity", {
+            let renderer_calls = renderer_calls.clone();
+            move |_| *renderer_calls.lock().unwrap() += 1
+        });
+        settings.on_change::<crate::renderer::RendererSettings>("scroll_animation_length", {
+            let renderer_calls = renderer_calls.clone();
+            move |_| *renderer_calls.lock().unwrap() += 10
+        });
+
+        let opacity = SettingLocation::NeovideGlobal("opacity".to_owned());
+        let scroll = SettingLocation::NeovideGlobal("scroll_animation_length".to_owned());
+        settings.reload_value(&opacity, Value::from(0.5));
+        assert_eq!((*window_calls.lock().unwrap(), *renderer_calls.lock().unwrap()), (1, 0));
+
+        settings.reload_value(&scroll, Value::from(0.1));
+        assert_eq!((*window_calls.lock().unwrap(), *renderer_calls.lock().unwrap()), (1, 10));
+    }
 }
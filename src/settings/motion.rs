@@ -0,0 +1,12 @@
+/// The reduce motion accessibility setting of the OS, followed with `neovide_no_animations` set to
+/// `auto`
+#[derive(Clone, Debug)]
+pub struct MotionState {
+    pub reduce_motion: bool,
+}
+
+impl MotionState {
+    pub fn new() -> Self {
+        Self { reduce_motion: false }
+    }
+}
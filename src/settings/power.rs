@@ -0,0 +1,104 @@
+use crate::{settings::Settings, window::WindowSettings};
+
+pub const BATTERY_SAVER_ANIMATION_SCALE: f32 = 0.5;
+pub const BATTERY_SAVER_REFRESH_RATE: u64 = 30;
+
+#[derive(Clone, Debug)]
+pub struct PowerState {
+    pub on_battery: bool,
+}
+
+impl PowerState {
+    pub fn new() -> Self {
+        Self { on_battery: false }
+    }
+}
+
+pub fn battery_saver_active(settings: &Settings) -> bool {
+    settings.get::<WindowSettings>().battery_saver && settings.get::<PowerState>().on_battery
+}
+
+pub fn animation_scale(battery_saver_active: bool) -> f32 {
+    if battery_saver_active { BATTERY_SAVER_ANIMATION_SCALE } else { 1.0 }
+}
+
+pub fn max_refresh_rate(refresh_rate: u64, battery_saver_active: bool) -> u64 {
+    if battery_saver_active { refresh_rate.min(BATTERY_SAVER_REFRESH_RATE) } else { refresh_rate }
+}
+
+#[cfg(target_os = "linux")]
+pub fn is_on_battery() -> bool {
+    use std::fs;
+
+    let Ok(entries) = fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+
+    let mut discharging = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let read = |name: &str| {
+            fs::read_to_string(path.join(name))
+                .map(|value| value.trim().to_owned())
+                .unwrap_or_default()
+        };
+        match read("type").as_str() {
+            // Any connected charger means that we are not running on battery
+            "Mains" | "USB" if read("online") == "1" => return false,
+            "Battery" if read("status") == "Discharging" => discharging = true,
+            _ => {}
+        }
+    }
+    discharging
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_on_battery() -> bool {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    // ACLineStatus is 0 when offline, 1 when online and 255 when unknown
+    unsafe { GetSystemPowerStatus(&mut status) }.is_ok() && status.ACLineStatus == 0
+}
+
+// No power state detection yet, so the battery saver never kicks in
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn is_on_battery() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animations_are_shortened_in_saver_mode() {
+        assert_eq!(animation_scale(false), 1.0);
+        assert_eq!(animation_scale(true), BATTERY_SAVER_ANIMATION_SCALE);
+    }
+
+    #[test]
+    fn refresh_rate_is_capped_in_saver_mode() {
+        assert_eq!(max_refresh_rate(144, false), 144);
+        assert_eq!(max_refresh_rate(144, true), BATTERY_SAVER_REFRESH_RATE);
+        assert_eq!(max_refresh_rate(20, true), 20);
+    }
+
+    #[test]
+    fn saver_mode_requires_the_setting_and_battery_power() {
+        let settings = Settings::new();
+        settings.register::<WindowSettings>();
+        assert!(!battery_saver_active(&settings));
+
+        settings.set(&PowerState { on_battery: true });
+        assert!(!battery_saver_active(&settings));
+
+        let mut window_settings = settings.get::<WindowSettings>();
+        window_settings.battery_saver = true;
+        settings.set(&window_settings);
+        assert!(battery_saver_active(&settings));
+
+        settings.set(&PowerState { on_battery: false });
+        assert!(!battery_saver_active(&settings));
+    }
+}
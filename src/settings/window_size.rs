@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use log::{error, warn};
 use serde::{Deserialize, Serialize};
@@ -30,9 +30,37 @@ pub enum PersistentWindowSettings {
     },
 }
 
+/// The `neovide_scale_factor` last used on each monitor, with `neovide_scale_factor_per_monitor`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MonitorScaleFactors(BTreeMap<String, f32>);
+
+impl MonitorScaleFactors {
+    /// Monitors are told apart by their name and resolution
+    pub fn monitor_key(name: Option<&str>, size: PhysicalSize<u32>) -> String {
+        format!("{} {}x{}", name.unwrap_or("unknown"), size.width, size.height)
+    }
+
+    pub fn set(&mut self, monitor: String, scale_factor: f32) {
+        self.0.insert(monitor, scale_factor);
+    }
+
+    /// The scale factor to switch to when a window moves to the monitor. The first monitor of a
+    /// window keeps the configured scale factor when it wasn't seen before, while moving to
+    /// another unknown monitor goes back to the default.
+    pub fn scale_factor_on_entering(&self, monitor: &str, first_monitor: bool) -> Option<f32> {
+        match self.0.get(monitor) {
+            Some(scale_factor) => Some(*scale_factor),
+            None if first_monitor => None,
+            None => Some(WindowSettings::default().scale_factor),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct PersistentSettings {
     window: PersistentWindowSettings,
+    #[serde(default)]
+    monitor_scale_factors: MonitorScaleFactors,
 }
 
 fn settings_path() -> PathBuf {
@@ -64,6 +92,10 @@ pub fn load_last_window_settings() -> Result<PersistentWindowSettings, String> {
     Ok(loaded_settings)
 }
 
+pub fn load_monitor_scale_factors() -> MonitorScaleFactors {
+    load_settings().map(|settings| settings.monitor_scale_factors).unwrap_or_default()
+}
+
 pub fn save_window_size(window_wrapper: &WinitWindowWrapper, settings: &Settings) {
     if window_wrapper.routes.is_empty() {
         return;
@@ -101,6 +133,7 @@ pub fn save_window_size(window_wrapper: &WinitWindowWrapper, settings: &Settings
                 },
             }
         },
+        monitor_scale_factors: window_wrapper.monitor_scale_factors.clone(),
     };
 
     let settings_path = settings_path();
@@ -127,3 +160,31 @@ pub fn save_window_size(window_wrapper: &WinitWindowWrapper, settings: &Settings
 pub fn clamped_grid_size(grid_size: &GridSize<u32>) -> GridSize<u32> {
     grid_size.clamp(MIN_GRID_SIZE, MAX_GRID_SIZE)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_factors_are_looked_up_per_monitor() {
+        let laptop = MonitorScaleFactors::monitor_key(Some("eDP-1"), PhysicalSize::new(2880, 1800));
+        let external =
+            MonitorScaleFactors::monitor_key(Some("DP-2"), PhysicalSize::new(3840, 2160));
+        assert_eq!(external, "DP-2 3840x2160");
+
+        let mut scale_factors = MonitorScaleFactors::default();
+        scale_factors.set(external.clone(), 1.5);
+        assert_eq!(scale_factors.scale_factor_on_entering(&external, false), Some(1.5));
+        assert_eq!(scale_factors.scale_factor_on_entering(&external, true), Some(1.5));
+        // Unknown monitors fall back to the default, except the one the window started on
+        assert_eq!(scale_factors.scale_factor_on_entering(&laptop, false), Some(1.0));
+        assert_eq!(scale_factors.scale_factor_on_entering(&laptop, true), None);
+    }
+
+    #[test]
+    fn settings_without_scale_factors_still_load() {
+        let json = r#"{"window":{"Maximized":{"grid_size":null}}}"#;
+        let settings: PersistentSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.monitor_scale_factors, MonitorScaleFactors::default());
+    }
+}
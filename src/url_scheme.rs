@@ -0,0 +1,183 @@
+use std::fmt;
+
+const URL_PREFIX: &str = "neovide://";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UrlError {
+    UnknownAction(String),
+    MissingFile,
+    InvalidNumber { name: &'static str, value: String },
+    InvalidEncoding(String),
+    OptionLikeFile(String),
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownAction(action) => {
+                write!(f, "unknown neovide:// URL action {action:?}, expected \"open\"")
+            }
+            Self::MissingFile => write!(f, "neovide://open URL is missing the file parameter"),
+            Self::InvalidNumber { name, value } => {
+                write!(
+                    f,
+                    "neovide:// URL parameter {name} expected a positive number, got {value:?}"
+                )
+            }
+            Self::InvalidEncoding(value) => {
+                write!(f, "neovide:// URL contains an invalid percent-encoded value {value:?}")
+            }
+            Self::OptionLikeFile(file) => {
+                write!(f, "neovide:// URL file {file:?} would be read as a Neovim option")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UrlError {}
+
+/// A `neovide://open?file=/path&line=42&column=5` request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenUrl {
+    pub file: String,
+    pub line: Option<u64>,
+    pub column: Option<u64>,
+}
+
+pub fn is_neovide_url(arg: &str) -> bool {
+    arg.get(..URL_PREFIX.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(URL_PREFIX))
+}
+
+impl OpenUrl {
+    pub fn parse(url: &str) -> Result<Self, UrlError> {
+        let rest = if is_neovide_url(url) { &url[URL_PREFIX.len()..] } else { url };
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let action = action.trim_end_matches('/');
+        if action != "open" {
+            return Err(UrlError::UnknownAction(action.to_owned()));
+        }
+
+        let mut file = None;
+        let mut line = None;
+        let mut column = None;
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match name {
+                "file" => file = Some(value),
+                "line" => line = Some(parse_number("line", value)?),
+                "column" => column = Some(parse_number("column", value)?),
+                _ => log::warn!("Ignoring unknown neovide:// URL parameter {name:?}"),
+            }
+        }
+
+        let file = file.filter(|file| !file.is_empty()).ok_or(UrlError::MissingFile)?;
+        // The file is passed to Neovim as an argument, where `+cmd` and `-S` run commands
+        if file.starts_with(['+', '-']) {
+            return Err(UrlError::OptionLikeFile(file));
+        }
+        Ok(Self { file, line, column })
+    }
+
+    /// The line and column to move the cursor to, the column defaults to the first one
+    pub fn cursor(&self) -> Option<(u64, u64)> {
+        self.line.map(|line| (line, self.column.unwrap_or(1)))
+    }
+}
+
+fn parse_number(name: &'static str, value: String) -> Result<u64, UrlError> {
+    match value.parse::<u64>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(UrlError::InvalidNumber { name, value }),
+    }
+}
+
+fn percent_decode(value: &str) -> Result<String, UrlError> {
+    let invalid = || UrlError::InvalidEncoding(value.to_owned());
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next().ok_or_else(invalid)?, input.next().ok_or_else(invalid)?];
+            let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_file_line_and_column() {
+        let url = OpenUrl::parse("neovide://open?file=/tmp/main.rs&line=42&column=7").unwrap();
+        assert_eq!(
+            url,
+            OpenUrl { file: "/tmp/main.rs".to_owned(), line: Some(42), column: Some(7) }
+        );
+        assert_eq!(url.cursor(), Some((42, 7)));
+    }
+
+    #[test]
+    fn line_and_column_are_optional() {
+        let url = OpenUrl::parse("neovide://open/?file=/tmp/main.rs").unwrap();
+        assert_eq!(url.line, None);
+        assert_eq!(url.column, None);
+        assert_eq!(url.cursor(), None);
+
+        let url = OpenUrl::parse("neovide://open?line=3&file=/tmp/main.rs").unwrap();
+        assert_eq!(url.cursor(), Some((3, 1)));
+    }
+
+    #[test]
+    fn decodes_percent_encoded_paths() {
+        let url = OpenUrl::parse("neovide://open?file=/tmp/my%20project/caf%C3%A9.txt").unwrap();
+        assert_eq!(url.file, "/tmp/my project/café.txt");
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        assert_eq!(
+            OpenUrl::parse("neovide://close?file=/tmp/a"),
+            Err(UrlError::UnknownAction("close".to_owned()))
+        );
+        assert_eq!(OpenUrl::parse("neovide://open?line=4"), Err(UrlError::MissingFile));
+        assert_eq!(
+            OpenUrl::parse("neovide://open?file=/tmp/a&line=zero"),
+            Err(UrlError::InvalidNumber { name: "line", value: "zero".to_owned() })
+        );
+        assert_eq!(
+            OpenUrl::parse("neovide://open?file=/tmp/a&column=0"),
+            Err(UrlError::InvalidNumber { name: "column", value: "0".to_owned() })
+        );
+        assert_eq!(
+            OpenUrl::parse("neovide://open?file=/tmp/%zz"),
+            Err(UrlError::InvalidEncoding("/tmp/%zz".to_owned()))
+        );
+    }
+
+    #[test]
+    fn rejects_files_read_as_neovim_options() {
+        assert_eq!(
+            OpenUrl::parse("neovide://open?file=%2B!touch%20pwned"),
+            Err(UrlError::OptionLikeFile("+!touch pwned".to_owned()))
+        );
+        assert_eq!(
+            OpenUrl::parse("neovide://open?file=--cmd"),
+            Err(UrlError::OptionLikeFile("--cmd".to_owned()))
+        );
+        assert!(OpenUrl::parse("neovide://open?file=./-notes.md").is_ok());
+    }
+
+    #[test]
+    fn detects_the_scheme() {
+        assert!(is_neovide_url("neovide://open?file=a"));
+        assert!(is_neovide_url("Neovide://open?file=a"));
+        assert!(!is_neovide_url("./neovide.txt"));
+        assert!(!is_neovide_url("neo"));
+    }
+}
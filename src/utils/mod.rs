@@ -2,7 +2,7 @@ mod ring_buffer;
 #[cfg(test)]
 mod test;
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 use std::path::{Path, PathBuf};
 
 #[cfg(target_os = "windows")]
@@ -66,7 +66,7 @@ pub fn expand_tilde(path: &str) -> String {
     home.to_string_lossy().into()
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 pub fn resolved_cwd(chdir: Option<&str>) -> Option<String> {
     let current_dir = std::env::current_dir().ok();
 
@@ -80,7 +80,7 @@ pub fn resolved_cwd(chdir: Option<&str>) -> Option<String> {
     Some(cwd.to_string_lossy().into_owned())
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 pub fn resolve_relative_path(path: &str, cwd: Option<&Path>) -> String {
     if path.starts_with('~') {
         return expand_tilde(path);
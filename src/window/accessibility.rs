@@ -0,0 +1,165 @@
+use std::sync::{Arc, Mutex};
+
+use accesskit::{
+    ActionHandler, ActionRequest, ActivationHandler, DeactivationHandler, Node, NodeId, Role,
+    TextPosition, TextSelection, Tree, TreeUpdate,
+};
+use accesskit_winit::Adapter;
+use winit::{event::WindowEvent, window::Window};
+
+use crate::renderer::Renderer;
+
+const WINDOW_ID: NodeId = NodeId(0);
+const EDITOR_ID: NodeId = NodeId(1);
+const LINE_ID: NodeId = NodeId(2);
+
+/// The text of the grid row under the cursor, and the character the cursor is on
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CursorLine {
+    pub text: String,
+    pub character_index: usize,
+}
+
+impl CursorLine {
+    /// Builds the line from the cells of a grid row, with the cursor in cell `column`. Trailing
+    /// spaces are dropped, and the cursor stays at the end of the text when it's past it.
+    pub fn from_cells(cells: &[String], column: usize) -> Self {
+        let text = cells.concat();
+        let text = text.trim_end_matches(' ').to_owned();
+        let character_index = cells.iter().take(column).map(|cell| cell.chars().count()).sum();
+        let character_index = character_index.min(text.chars().count());
+        Self { text, character_index }
+    }
+
+    fn tree_update(&self) -> TreeUpdate {
+        let mut window = Node::new(Role::Window);
+        window.set_label("Neovide");
+        window.set_children(vec![EDITOR_ID]);
+
+        let mut editor = Node::new(Role::MultilineTextInput);
+        editor.set_children(vec![LINE_ID]);
+        editor.set_text_selection(self.selection());
+
+        let mut line = Node::new(Role::TextRun);
+        line.set_value(self.text.as_str());
+        line.set_character_lengths(
+            self.text.chars().map(|char| char.len_utf8() as u8).collect::<Vec<_>>(),
+        );
+
+        TreeUpdate {
+            nodes: vec![(WINDOW_ID, window), (EDITOR_ID, editor), (LINE_ID, line)],
+            tree: Some(Tree::new(WINDOW_ID)),
+            focus: EDITOR_ID,
+        }
+    }
+
+    fn selection(&self) -> TextSelection {
+        let position = TextPosition { node: LINE_ID, character_index: self.character_index };
+        TextSelection { anchor: position, focus: position }
+    }
+}
+
+struct InitialTree(Arc<Mutex<CursorLine>>);
+
+impl ActivationHandler for InitialTree {
+    fn request_initial_tree(&mut self) -> Option<TreeUpdate> {
+        Some(self.0.lock().unwrap().tree_update())
+    }
+}
+
+// The tree is read only for now, so there is nothing to do for the requested actions
+struct NoActions;
+
+impl ActionHandler for NoActions {
+    fn do_action(&mut self, _request: ActionRequest) {}
+}
+
+impl DeactivationHandler for NoActions {
+    fn deactivate_accessibility(&mut self) {}
+}
+
+/// Exposes the line under the cursor to the accessibility API of the OS, so that screen readers
+/// can read it
+pub struct AccessibilityAdapter {
+    adapter: Adapter,
+    line: Arc<Mutex<CursorLine>>,
+    // The cells and the cursor column the line was built from
+    source: Option<(Vec<String>, u64)>,
+}
+
+impl AccessibilityAdapter {
+    /// Must be called before the window is shown
+    pub fn new(window: &Window) -> Self {
+        let line = Arc::new(Mutex::new(CursorLine::default()));
+        let adapter =
+            Adapter::with_direct_handlers(window, InitialTree(line.clone()), NoActions, NoActions);
+        Self { adapter, line, source: None }
+    }
+
+    pub fn process_event(&mut self, window: &Window, event: &WindowEvent) {
+        self.adapter.process_event(window, event);
+    }
+
+    /// Updates the tree when the cursor moved or the line under it changed
+    pub fn update(&mut self, renderer: &Renderer) {
+        let unchanged = renderer
+            .with_cursor_line_cells(|cells, column| {
+                self.source.as_ref().is_some_and(|(source_cells, source_column)| {
+                    source_cells.as_slice() == cells && *source_column == column
+                })
+            })
+            .unwrap_or(self.source.is_none());
+        if unchanged {
+            return;
+        }
+
+        self.source = renderer.with_cursor_line_cells(|cells, column| (cells.to_vec(), column));
+        let line = self
+            .source
+            .as_ref()
+            .map(|(cells, column)| CursorLine::from_cells(cells, *column as usize))
+            .unwrap_or_default();
+
+        let mut current = self.line.lock().unwrap();
+        if *current == line {
+            return;
+        }
+        *current = line;
+        let update = current.tree_update();
+        drop(current);
+        self.adapter.update_if_active(|| update);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(text: &str) -> Vec<String> {
+        text.chars().map(String::from).collect()
+    }
+
+    #[test]
+    fn the_line_node_has_the_text_of_the_cursor_row() {
+        let line = CursorLine::from_cells(&cells("let x = 1;   "), 4);
+        assert_eq!(line, CursorLine { text: "let x = 1;".to_owned(), character_index: 4 });
+
+        let update = line.tree_update();
+        let (_, node) = update.nodes.iter().find(|(id, _)| *id == LINE_ID).unwrap();
+        assert_eq!(node.value(), Some("let x = 1;"));
+        assert_eq!(update.focus, EDITOR_ID);
+    }
+
+    #[test]
+    fn the_cursor_is_counted_in_characters() {
+        // Double width characters take an empty cell after them
+        let mut row = cells("日 本");
+        row.insert(1, String::new());
+        let line = CursorLine::from_cells(&row, 3);
+        assert_eq!(line.text, "日 本");
+        assert_eq!(line.character_index, 2);
+
+        // Past the end of the text
+        assert_eq!(CursorLine::from_cells(&cells("ab  "), 3).character_index, 2);
+    }
+}
@@ -3,7 +3,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-#[cfg(target_os = "macos")]
+#[cfg(unix)]
 use {
     crate::bridge::{OpenArgs, send_or_queue_file_drop, set_active_route_handler},
     crate::utils::resolve_relative_path,
@@ -22,16 +22,23 @@ use winit::{
 
 use super::{
     CmdLineSettings, EventPayload, EventTarget, RouteId, WindowSettings, WindowSize,
-    WinitWindowWrapper, error_window, save_window_size,
+    WinitWindowWrapper, error_window,
+    idle_manager::{IdleFrame, IdleManager},
+    save_window_size,
 };
 use crate::{
     clipboard::{Clipboard, ClipboardHandle},
     profiling::{tracy_plot, tracy_zone},
-    renderer::DrawCommand,
+    renderer::{DrawCommand, follows_reduced_motion},
     running_tracker::RunningTracker,
-    settings::{AppHotReloadConfigs, HotReloadConfigs, Settings, font::FontSettings},
+    settings::{
+        AppHotReloadConfigs, HotReloadConfigs, Settings,
+        font::FontSettings,
+        motion::MotionState,
+        power::{PowerState, battery_saver_active, is_on_battery, max_refresh_rate},
+    },
     units::Grid,
-    window::UserEvent,
+    window::{UserEvent, WindowCommand},
 };
 
 #[derive(Clone, Copy)]
@@ -89,6 +96,7 @@ impl ShouldRender {
 }
 
 const MAX_ANIMATION_DT: f64 = 1.0 / 120.0;
+const POWER_STATE_POLL_INTERVAL: Duration = Duration::from_secs(10);
 
 struct RenderState {
     previous_frame_start: Instant,
@@ -97,6 +105,12 @@ struct RenderState {
     num_consecutive_rendered: u32,
     focused: FocusedState,
     pending_render: bool, // we should render as soon as the compositor/vsync allows
+    occluded: bool,
+    // Asking the window is a roundtrip to the display server on X11, so it's only done when it's
+    // occluded or resized
+    minimized: bool,
+    last_presented: Option<Instant>,
+    coalesce_deadline: Option<Instant>, // when the draw commands received so far must be rendered
     pending_draw_commands: Vec<Vec<DrawCommand>>,
     animation_start: Instant, // when the last animation started (went from idle to animating)
     animation_time: Duration, // how long the current animation has been simulated, will usually be in the future
@@ -112,15 +126,72 @@ impl RenderState {
             num_consecutive_rendered: 0,
             focused,
             pending_render: false,
+            occluded: false,
+            minimized: false,
+            last_presented: None,
+            coalesce_deadline: None,
             pending_draw_commands: Vec::new(),
             animation_start: now,
             animation_time: Duration::from_millis(0),
         }
     }
+
+    fn set_occluded(&mut self, occluded: bool) {
+        self.set_hidden(occluded, self.minimized);
+    }
+
+    fn set_minimized(&mut self, minimized: bool) {
+        self.set_hidden(self.occluded, minimized);
+    }
+
+    fn set_hidden(&mut self, occluded: bool, minimized: bool) {
+        if self.is_hidden() && !(occluded || minimized) {
+            // Nothing was presented while hidden, so repaint everything once visible again
+            self.should_render = ShouldRender::Immediately;
+        }
+        self.occluded = occluded;
+        self.minimized = minimized;
+    }
+
+    fn is_hidden(&self) -> bool {
+        self.occluded || self.minimized
+    }
+
+    fn is_paused(&self, pause_when_hidden: bool) -> bool {
+        pause_when_hidden && self.is_hidden()
+    }
+
+    /// Schedules a render for newly received draw commands, at most once per `budget`. The first
+    /// batch after a quiet period is rendered immediately, so typing does not get any extra latency.
+    fn coalesce_draw_commands(&mut self, now: Instant, budget: Duration) {
+        if budget.is_zero() {
+            self.should_render = ShouldRender::Immediately;
+            return;
+        }
+
+        let deadline = *self.coalesce_deadline.get_or_insert_with(|| {
+            self.last_presented.map_or(now, |last_presented| (last_presented + budget).max(now))
+        });
+        if deadline <= now {
+            self.coalesce_deadline = None;
+            self.should_render = ShouldRender::Immediately;
+        } else {
+            self.should_render.update(ShouldRender::Deadline(deadline));
+        }
+    }
+
+    fn flush_coalesced(&mut self, now: Instant) {
+        if let Some(deadline) = self.coalesce_deadline
+            && deadline <= now
+        {
+            self.coalesce_deadline = None;
+            self.should_render = ShouldRender::Immediately;
+        }
+    }
 }
 
 pub struct Application {
-    idle: bool,
+    idle_manager: IdleManager,
     #[allow(dead_code)]
     initial_grid_size: Option<Size2<Grid<u32>>>,
     render_states: FxHashMap<WindowId, RenderState>,
@@ -133,6 +204,7 @@ pub struct Application {
 
     settings: Arc<Settings>,
     clipboard: Option<Arc<Mutex<Clipboard>>>,
+    last_power_state_check: Option<Instant>,
 }
 
 impl Application {
@@ -147,7 +219,7 @@ impl Application {
         clipboard_handle: ClipboardHandle,
     ) -> Self {
         let cmd_line_settings = settings.get::<CmdLineSettings>();
-        let idle = cmd_line_settings.idle;
+        let idle_manager = IdleManager::new(cmd_line_settings.idle);
 
         let runtime_tracker = RunningTracker::new();
 
@@ -159,7 +231,7 @@ impl Application {
         );
 
         Self {
-            idle,
+            idle_manager,
             initial_grid_size,
             render_states: FxHashMap::default(),
             error_windows: FxHashMap::default(),
@@ -171,6 +243,7 @@ impl Application {
 
             settings,
             clipboard: Some(clipboard),
+            last_power_state_check: None,
         }
     }
 
@@ -203,6 +276,7 @@ impl Application {
     fn sync_render_states(&mut self) {
         let window_ids: Vec<WindowId> = self.window_wrapper.routes.keys().copied().collect();
         self.render_states.retain(|id, _| window_ids.contains(id));
+        self.idle_manager.retain(|id| window_ids.contains(id));
         for window_id in window_ids {
             if self.render_states.contains_key(&window_id) {
                 continue;
@@ -233,6 +307,15 @@ impl Application {
         }
     }
 
+    fn mark_draw_commands_for_window(&mut self, window_id: WindowId) {
+        self.ensure_render_state(window_id);
+        let budget =
+            Duration::from_millis(self.settings.get::<WindowSettings>().redraw_coalesce_ms);
+        if let Some(state) = self.render_states.get_mut(&window_id) {
+            state.coalesce_draw_commands(Instant::now(), budget);
+        }
+    }
+
     fn mark_should_render_all(&mut self) {
         self.sync_render_states();
         let window_ids: Vec<WindowId> = self.render_states.keys().copied().collect();
@@ -243,7 +326,7 @@ impl Application {
         }
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(unix)]
     fn activate_focused_route(&self) {
         let Some(window_id) = self.window_wrapper.get_focused_route() else {
             return;
@@ -256,24 +339,25 @@ impl Application {
         self.window_wrapper.activate_and_focus_window(window_id);
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(unix)]
     fn prepare_open_files(
         &mut self,
         event_loop: &ActiveEventLoop,
         new_window: bool,
         cwd: Option<&Path>,
         args: OpenArgs,
+        cursor: Option<(u64, u64)>,
     ) {
         if !new_window {
             self.activate_focused_route();
-            self.send_file_drops(args);
+            self.send_file_drops(args, cursor);
             return;
         }
 
         if self.settings.get::<CmdLineSettings>().server.is_some() {
             self.window_wrapper.try_create_window(event_loop, &self.proxy, cwd, None);
             self.mark_should_render_all();
-            self.send_file_drops(args);
+            self.send_file_drops(args, cursor);
             return;
         }
 
@@ -281,10 +365,11 @@ impl Application {
         self.mark_should_render_all();
     }
 
-    #[cfg(target_os = "macos")]
-    fn send_file_drops(&self, args: OpenArgs) {
+    #[cfg(unix)]
+    fn send_file_drops(&self, args: OpenArgs, mut cursor: Option<(u64, u64)>) {
+        // The cursor position only applies to the first file
         for path in args.files_to_open {
-            send_or_queue_file_drop(path, Some(args.tabs));
+            send_or_queue_file_drop(path, Some(args.tabs), cursor.take());
         }
     }
 
@@ -292,13 +377,13 @@ impl Application {
         match config {
             AppHotReloadConfigs::Idle(idle) => {
                 let mut cmd_line_settings = self.settings.get::<CmdLineSettings>();
-                if cmd_line_settings.idle == idle && self.idle == idle {
+                if cmd_line_settings.idle == idle && self.idle_manager.idle() == idle {
                     return;
                 }
 
                 cmd_line_settings.idle = idle;
                 self.settings.set(&cmd_line_settings);
-                self.idle = idle;
+                self.idle_manager.set_idle(idle);
                 self.mark_should_render_all();
             }
         }
@@ -330,15 +415,18 @@ impl Application {
     }
 
     fn get_refresh_rate(&self, state: &RenderState) -> f32 {
+        let battery_saver_active = battery_saver_active(&self.settings);
         match state.focused {
             // NOTE: Always wait for the idle refresh rate when winit throttling is used to avoid waking up too early
             // The winit redraw request will likely happen much before that and wake it up anyway
-            FocusedState::Focused | FocusedState::UnfocusedNotDrawn => {
-                self.settings.get::<WindowSettings>().refresh_rate as f32
-            }
-            FocusedState::Unfocused => {
-                self.settings.get::<WindowSettings>().refresh_rate_idle as f32
-            }
+            FocusedState::Focused | FocusedState::UnfocusedNotDrawn => max_refresh_rate(
+                self.settings.get::<WindowSettings>().refresh_rate,
+                battery_saver_active,
+            ) as f32,
+            FocusedState::Unfocused => max_refresh_rate(
+                self.settings.get::<WindowSettings>().refresh_rate_idle,
+                battery_saver_active,
+            ) as f32,
         }
         .max(1.0)
     }
@@ -365,11 +453,22 @@ impl Application {
     }
 
     fn get_event_deadline(&self) -> Option<Instant> {
-        self.render_states.values().map(|state| self.get_event_deadline_for(state)).min()
+        self.render_states
+            .values()
+            .filter(|state| !self.is_render_paused(state))
+            .map(|state| self.get_event_deadline_for(state))
+            .min()
+    }
+
+    fn is_render_paused(&self, state: &RenderState) -> bool {
+        let pause_when_hidden = self.settings.get::<WindowSettings>().pause_when_hidden;
+        state.is_paused(pause_when_hidden)
     }
 
-    fn next_control_flow(&self) -> ControlFlow {
-        next_control_flow_for(self.get_event_deadline())
+    fn next_control_flow(&self, key_repeat_deadline: Option<Instant>) -> ControlFlow {
+        next_control_flow_for(
+            self.get_event_deadline().into_iter().chain(key_repeat_deadline).min(),
+        )
     }
 
     fn schedule_next_event(&mut self, event_loop: &ActiveEventLoop) {
@@ -379,7 +478,39 @@ impl Application {
         if self.create_window_allowed && self.window_wrapper.has_pending_window_creation() {
             self.window_wrapper.try_create_window(event_loop, &self.proxy, None, None);
         }
-        event_loop.set_control_flow(self.next_control_flow());
+        let key_repeat_deadline = self.window_wrapper.handle_key_repeat();
+        event_loop.set_control_flow(self.next_control_flow(key_repeat_deadline));
+    }
+
+    fn update_power_state(&mut self) {
+        if !self.settings.get::<WindowSettings>().battery_saver {
+            return;
+        }
+        if self
+            .last_power_state_check
+            .is_some_and(|last_check| last_check.elapsed() < POWER_STATE_POLL_INTERVAL)
+        {
+            return;
+        }
+        self.last_power_state_check = Some(Instant::now());
+
+        let on_battery = is_on_battery();
+        if self.settings.get::<PowerState>().on_battery != on_battery {
+            log::info!("Battery saver {}", if on_battery { "enabled" } else { "disabled" });
+            self.settings.set(&PowerState { on_battery });
+            self.mark_should_render_all();
+        }
+    }
+
+    fn update_motion_state(&mut self, reduce_motion: bool) {
+        if self.settings.get::<MotionState>().reduce_motion == reduce_motion {
+            return;
+        }
+        log::info!("Reduced motion {}", if reduce_motion { "enabled" } else { "disabled" });
+        self.settings.set(&MotionState { reduce_motion });
+        if follows_reduced_motion(&self.settings) {
+            self.mark_should_render_all();
+        }
     }
 
     fn handle_error_window_event(
@@ -494,6 +625,8 @@ impl Application {
             tracy_plot!("num_consecutive_rendered", state.num_consecutive_rendered as f64);
             state.last_dt = state.previous_frame_start.elapsed().as_secs_f32();
             state.previous_frame_start = Instant::now();
+            state.last_presented = Some(state.previous_frame_start);
+            state.coalesce_deadline = None;
         }
     }
 
@@ -506,9 +639,7 @@ impl Application {
             for batch in pending_batches {
                 self.window_wrapper.handle_draw_commands(window_id, batch);
             }
-            if let Some(state) = self.render_states.get_mut(&window_id) {
-                state.should_render = ShouldRender::Immediately;
-            }
+            self.mark_draw_commands_for_window(window_id);
         }
     }
 
@@ -575,18 +706,30 @@ impl Application {
 
             let res = self.window_wrapper.prepare_frame(window_id);
             if let Some(state) = self.render_states.get_mut(&window_id) {
+                state.flush_coalesced(now);
                 state.should_render.update(res);
             }
 
-            let should_animate = self
+            // The grid keeps receiving the draw commands while hidden, only the presentation stops
+            let paused = self
                 .render_states
                 .get(&window_id)
-                .map(|state| {
-                    state.should_render == ShouldRender::Immediately || !self.idle || skipped_frame
-                })
+                .map(|state| self.is_render_paused(state))
                 .unwrap_or(false);
+            if paused {
+                continue;
+            }
 
-            if should_animate {
+            let Some(render_requested) = self
+                .render_states
+                .get(&window_id)
+                .map(|state| state.should_render == ShouldRender::Immediately)
+            else {
+                continue;
+            };
+
+            let frame = self.idle_manager.frame(window_id, render_requested, skipped_frame);
+            if frame == IdleFrame::Animate {
                 self.reset_animation_period(window_id);
                 self.animate(window_id);
                 self.schedule_render(window_id, skipped_frame);
@@ -594,13 +737,7 @@ impl Application {
                 // Cache purging should only happen once we become idle; doing it while throttling
                 // for vsync caused Skia to evict glyphs mid-animation and re-upload them every
                 // frame. See https://github.com/neovide/neovide/pull/3324
-                let should_cleanup_cache = self
-                    .render_states
-                    .get(&window_id)
-                    .map(|state| state.num_consecutive_rendered > 0)
-                    .unwrap_or(false);
-
-                if should_cleanup_cache
+                if frame == IdleFrame::BecameIdle
                     && let Some(route) = self.window_wrapper.routes.get(&window_id)
                 {
                     route.window.renderer.borrow_mut().grid_renderer.shaper.cleanup_font_cache();
@@ -638,6 +775,7 @@ impl Application {
 impl ApplicationHandler<EventPayload> for Application {
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
         tracy_zone!("about_to_wait");
+        self.update_power_state();
         self.prepare_and_animate();
         self.schedule_next_event(event_loop);
     }
@@ -673,10 +811,27 @@ impl ApplicationHandler<EventPayload> for Application {
         };
 
         self.ensure_render_state(window_id);
+        if self.window_wrapper.handle_crash_overlay_event(window_id, &event, &self.proxy) {
+            self.mark_should_render_for_window(window_id);
+            self.schedule_next_event(event_loop);
+            return;
+        }
         match event {
             WindowEvent::RedrawRequested => {
                 self.redraw_requested(window_id);
             }
+            WindowEvent::Occluded(occluded) => {
+                let minimized = self.window_wrapper.is_window_minimized(window_id);
+                if let Some(state) = self.render_states.get_mut(&window_id) {
+                    state.set_hidden(occluded, minimized);
+                }
+            }
+            WindowEvent::Resized(..) => {
+                let minimized = self.window_wrapper.is_window_minimized(window_id);
+                if let Some(state) = self.render_states.get_mut(&window_id) {
+                    state.set_minimized(minimized);
+                }
+            }
             WindowEvent::Focused(focused_event) => {
                 if let Some(state) = self.render_states.get_mut(&window_id) {
                     state.focused = if focused_event {
@@ -708,7 +863,7 @@ impl ApplicationHandler<EventPayload> for Application {
         let EventPayload { payload, target } = event;
         match payload {
             UserEvent::ConfigsChanged(config) => self.handle_config_changed(target, *config),
-            #[cfg(target_os = "macos")]
+            #[cfg(unix)]
             UserEvent::OpenFiles {
                 files,
                 cwd,
@@ -717,6 +872,7 @@ impl ApplicationHandler<EventPayload> for Application {
                 new_window,
                 neovim_bin,
                 neovim_args,
+                cursor,
             } => {
                 let cwd = cwd.as_deref().map(Path::new);
                 let caller_cwd = caller_cwd.as_deref().map(Path::new);
@@ -730,15 +886,26 @@ impl ApplicationHandler<EventPayload> for Application {
                     neovim_args,
                 };
 
-                self.prepare_open_files(event_loop, new_window, cwd, open_args);
+                self.prepare_open_files(event_loop, new_window, cwd, open_args, cursor);
             }
-            UserEvent::NeovimExited => {
+            UserEvent::ReducedMotionChanged(reduce_motion) => {
+                self.update_motion_state(reduce_motion)
+            }
+            UserEvent::NeovimExited(exit) => {
                 let route_id = self.route_id_for_target(target);
                 let Some(route_id) = route_id else {
                     log::warn!("NeovimExited event missing window/route target");
                     return;
                 };
                 let window_id = self.window_wrapper.window_id_for_route(route_id);
+                // Keep the window open to tell what happened, it closes or restarts from the
+                // crash overlay
+                if exit.is_crash() && self.window_wrapper.show_neovim_crash(route_id, &exit) {
+                    if let Some(window_id) = window_id {
+                        self.mark_should_render_for_window(window_id);
+                    }
+                    return;
+                }
                 let remaining_before = self.window_wrapper.routes.len();
                 if remaining_before <= 1 && window_id.is_some() {
                     save_window_size(&self.window_wrapper, &self.settings);
@@ -789,7 +956,7 @@ impl ApplicationHandler<EventPayload> for Application {
                             }
                         } else {
                             self.window_wrapper.handle_draw_commands(window_id, batch);
-                            self.mark_should_render_for_window(window_id);
+                            self.mark_draw_commands_for_window(window_id);
                         }
                     }
                     EventTarget::Route(route_id) => {
@@ -806,7 +973,7 @@ impl ApplicationHandler<EventPayload> for Application {
                                 }
                             } else {
                                 self.window_wrapper.handle_draw_commands(window_id, batch);
-                                self.mark_should_render_for_window(window_id);
+                                self.mark_draw_commands_for_window(window_id);
                             }
                         } else {
                             self.window_wrapper.handle_draw_commands_for_route(route_id, batch);
@@ -838,6 +1005,10 @@ impl ApplicationHandler<EventPayload> for Application {
                 self.window_wrapper.handle_mac_shortcut(command);
                 self.mark_should_render_all();
             }
+            UserEvent::WindowCommand(WindowCommand::ToggleVSync) => {
+                self.window_wrapper.toggle_vsync(target, &self.proxy);
+                self.mark_should_render_all();
+            }
             UserEvent::NeovimLaunchError { message } => {
                 let window_config = error_window::create_error_window(event_loop, &self.settings);
                 let clipboard_handle = ClipboardHandle::new(self.clipboard.as_ref().unwrap());
@@ -913,3 +1084,92 @@ fn next_control_flow_for(deadline: Option<Instant>) -> ControlFlow {
         None => ControlFlow::Wait,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering_is_paused_while_occluded() {
+        let mut state = RenderState::new(FocusedState::Focused);
+        assert!(!state.is_paused(true));
+
+        state.set_occluded(true);
+        assert!(state.is_paused(true));
+    }
+
+    #[test]
+    fn rendering_is_paused_while_minimized() {
+        let mut state = RenderState::new(FocusedState::Focused);
+        state.set_minimized(true);
+        assert!(state.is_paused(true));
+
+        // Restoring the window while it's still occluded keeps it paused
+        state.set_occluded(true);
+        state.set_minimized(false);
+        assert!(state.is_paused(true));
+    }
+
+    #[test]
+    fn rendering_is_not_paused_when_disabled() {
+        let mut state = RenderState::new(FocusedState::Focused);
+        state.set_occluded(true);
+        state.set_minimized(true);
+        assert!(!state.is_paused(false));
+    }
+
+    #[test]
+    fn becoming_visible_requests_a_full_repaint() {
+        let mut state = RenderState::new(FocusedState::Focused);
+        state.set_occluded(true);
+        state.should_render = ShouldRender::Wait;
+
+        state.set_occluded(false);
+        assert!(!state.is_paused(true));
+        assert_eq!(state.should_render, ShouldRender::Immediately);
+    }
+
+    #[test]
+    fn coalescing_is_disabled_with_a_zero_budget() {
+        let mut state = RenderState::new(FocusedState::Focused);
+        state.should_render = ShouldRender::Wait;
+        state.coalesce_draw_commands(Instant::now(), Duration::ZERO);
+        assert_eq!(state.should_render, ShouldRender::Immediately);
+        assert_eq!(state.coalesce_deadline, None);
+    }
+
+    #[test]
+    fn first_draw_after_a_quiet_period_renders_immediately() {
+        let now = Instant::now();
+        let mut state = RenderState::new(FocusedState::Focused);
+        state.should_render = ShouldRender::Wait;
+        state.last_presented = Some(now - Duration::from_millis(100));
+
+        state.coalesce_draw_commands(now, Duration::from_millis(10));
+        assert_eq!(state.should_render, ShouldRender::Immediately);
+    }
+
+    #[test]
+    fn bursts_are_coalesced_until_the_budget_expires() {
+        let now = Instant::now();
+        let budget = Duration::from_millis(10);
+        let mut state = RenderState::new(FocusedState::Focused);
+        state.should_render = ShouldRender::Wait;
+        state.last_presented = Some(now - Duration::from_millis(2));
+
+        let deadline = now + Duration::from_millis(8);
+        state.coalesce_draw_commands(now, budget);
+        assert_eq!(state.should_render, ShouldRender::Deadline(deadline));
+
+        // Later batches don't push the deadline further away
+        state.coalesce_draw_commands(now + Duration::from_millis(5), budget);
+        assert_eq!(state.should_render, ShouldRender::Deadline(deadline));
+
+        state.flush_coalesced(now + Duration::from_millis(7));
+        assert_eq!(state.should_render, ShouldRender::Deadline(deadline));
+
+        state.flush_coalesced(deadline);
+        assert_eq!(state.should_render, ShouldRender::Immediately);
+        assert_eq!(state.coalesce_deadline, None);
+    }
+}
@@ -0,0 +1,111 @@
+use rustc_hash::FxHashSet;
+use winit::window::WindowId;
+
+/// What a window should do with the frame that was just prepared
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdleFrame {
+    /// Something is changing, animate and render the frame
+    Animate,
+    /// The window was animating on the previous frame, but is idle now
+    BecameIdle,
+    /// Nothing changed, wait for the next event
+    Idle,
+}
+
+/// Switches each window between an event driven render loop, where frames are only rendered when
+/// something changes, and a continuous one, where every frame is rendered.
+///
+/// With idle enabled (the default) a window renders continuously only while it's animating, and
+/// goes back to waiting for events once the animations are finished. With `--no-idle` every window
+/// always renders continuously.
+#[derive(Debug)]
+pub struct IdleManager {
+    idle: bool,
+    animating: FxHashSet<WindowId>,
+}
+
+impl IdleManager {
+    pub fn new(idle: bool) -> Self {
+        Self { idle, animating: FxHashSet::default() }
+    }
+
+    pub fn idle(&self) -> bool {
+        self.idle
+    }
+
+    pub fn set_idle(&mut self, idle: bool) {
+        self.idle = idle;
+    }
+
+    /// Records the state of a freshly prepared frame. `render_requested` is set when the window
+    /// has new content or a running animation, and `skipped_frame` when the previous frame didn't
+    /// make it in time and has to be animated again.
+    pub fn frame(
+        &mut self,
+        window_id: WindowId,
+        render_requested: bool,
+        skipped_frame: bool,
+    ) -> IdleFrame {
+        if render_requested || skipped_frame || !self.idle {
+            self.animating.insert(window_id);
+            IdleFrame::Animate
+        } else if self.animating.remove(&window_id) {
+            IdleFrame::BecameIdle
+        } else {
+            IdleFrame::Idle
+        }
+    }
+
+    /// Forgets the windows that no longer exist
+    pub fn retain(&mut self, mut keep: impl FnMut(&WindowId) -> bool) {
+        self.animating.retain(|window_id| keep(window_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window_id() -> WindowId {
+        WindowId::from(1)
+    }
+
+    #[test]
+    fn animations_render_continuously_and_then_go_idle() {
+        let mut manager = IdleManager::new(true);
+
+        assert_eq!(manager.frame(window_id(), true, false), IdleFrame::Animate);
+        assert_eq!(manager.frame(window_id(), true, false), IdleFrame::Animate);
+        assert_eq!(manager.frame(window_id(), false, false), IdleFrame::BecameIdle);
+        assert_eq!(manager.frame(window_id(), false, false), IdleFrame::Idle);
+    }
+
+    #[test]
+    fn skipped_frames_are_animated_again() {
+        let mut manager = IdleManager::new(true);
+        assert_eq!(manager.frame(window_id(), false, true), IdleFrame::Animate);
+    }
+
+    #[test]
+    fn no_idle_always_renders_continuously() {
+        let mut manager = IdleManager::new(false);
+        assert_eq!(manager.frame(window_id(), false, false), IdleFrame::Animate);
+        assert_eq!(manager.frame(window_id(), false, false), IdleFrame::Animate);
+
+        manager.set_idle(true);
+        assert!(manager.idle());
+        assert_eq!(manager.frame(window_id(), false, false), IdleFrame::BecameIdle);
+    }
+
+    #[test]
+    fn windows_are_tracked_separately() {
+        let mut manager = IdleManager::new(true);
+        let other = WindowId::from(2);
+
+        manager.frame(window_id(), true, false);
+        assert_eq!(manager.frame(other, false, false), IdleFrame::Idle);
+
+        manager.retain(|id| *id == other);
+        assert_eq!(manager.frame(window_id(), false, false), IdleFrame::Idle);
+    }
+}
@@ -1,21 +1,25 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    bridge::{NeovimHandler, SerialCommand, send_ui},
+    bridge::{NeovimHandler, RedrawEvent, SerialCommand, send_ui},
     settings::Settings,
+    window::{
+        WindowSettings,
+        sticky_modifiers::{StickyModifier, StickyModifiers},
+    },
 };
 
 #[allow(unused_imports)]
 use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
 use winit::{
     event::{ElementState, Ime, KeyEvent, Modifiers, WindowEvent},
-    keyboard::{Key, KeyCode, KeyLocation, NamedKey, PhysicalKey},
+    keyboard::{Key, KeyCode, KeyLocation, ModifiersState, NamedKey, PhysicalKey},
 };
 #[cfg(target_os = "macos")]
-use {
-    crate::{window::WindowSettings, window::settings::OptionAsMeta},
-    winit::keyboard::ModifiersKeyState,
-};
+use {crate::window::settings::OptionAsMeta, winit::keyboard::ModifiersKeyState};
 
 use crate::profiling::tracy_named_frame;
 
@@ -23,11 +27,56 @@ fn is_ascii_alphabetic_char(text: &str) -> bool {
     text.len() == 1 && text.chars().next().unwrap().is_ascii_alphabetic()
 }
 
+/// The character that the formatted key inserts, for the keys whose echo is predicted. Only
+/// single width characters are, so that the prediction never splits a double width one.
+fn predicted_echo(text: &str) -> Option<char> {
+    match text {
+        "<Space>" => Some(' '),
+        "<lt>" => Some('<'),
+        _ => {
+            let mut chars = text.chars();
+            let ch = chars.next().filter(|ch| ch.is_ascii_graphic() && *ch != '<')?;
+            chars.next().is_none().then_some(ch)
+        }
+    }
+}
+
+/// A key that Neovide repeats itself while it's held, instead of relying on the repeat of the OS
+struct HeldKey {
+    event: KeyEvent,
+    timer: RepeatTimer,
+}
+
+struct RepeatTimer {
+    next_repeat: Instant,
+}
+
+impl RepeatTimer {
+    fn new(pressed_at: Instant, delay: Duration) -> Self {
+        Self { next_repeat: pressed_at + delay }
+    }
+
+    /// Returns how many repeats are due at `now`, and schedules the next one
+    fn take_due_repeats(&mut self, now: Instant, interval: Duration) -> u32 {
+        if now < self.next_repeat {
+            return 0;
+        }
+        let late = (now - self.next_repeat).as_secs_f64();
+        let count = (late / interval.as_secs_f64()) as u32 + 1;
+        self.next_repeat += interval * count;
+        count
+    }
+}
+
 pub struct KeyboardManager {
     modifiers: Modifiers,
     ime_preedit: (String, Option<(usize, usize)>),
     meta_is_pressed: bool, // see note on 'meta' below
-    #[allow(dead_code)]
+    held_key: Option<HeldKey>,
+    sticky_modifiers: StickyModifiers,
+    // The keys sent to Neovim since the renderer last took them, for `neovide_keycast`
+    typed_keys: Vec<String>,
+    keycast: bool,
     settings: Arc<Settings>,
 }
 
@@ -37,6 +86,10 @@ impl KeyboardManager {
             modifiers: Modifiers::default(),
             ime_preedit: ("".to_string(), None),
             meta_is_pressed: false,
+            held_key: None,
+            sticky_modifiers: StickyModifiers::default(),
+            typed_keys: Vec::new(),
+            keycast: settings.get::<WindowSettings>().keycast,
             settings,
         }
     }
@@ -52,14 +105,36 @@ impl KeyboardManager {
                 if self.ime_preedit.0.is_empty() =>
             {
                 log::trace!("{key_event:#?}");
-                if key_event.state == ElementState::Pressed
-                    && let Some(text) = self.format_key(key_event)
-                {
+                if self.passes_through(key_event) {
+                    log::trace!("Key left to the window manager {:?}", key_event.logical_key);
+                    return;
+                }
+                self.handle_sticky_modifier(key_event);
+                let key_repeat = self.key_repeat();
+                if key_event.state == ElementState::Released {
+                    if self.held_key.as_ref().is_some_and(|held_key| {
+                        held_key.event.physical_key == key_event.physical_key
+                    }) {
+                        self.held_key = None;
+                    }
+                } else if key_event.repeat && key_repeat.is_some() {
+                    // The held key is repeated by handle_key_repeat instead
+                } else if let Some(text) = self.format_key(key_event) {
                     log::trace!("Key pressed {} {:?}", text, self.modifiers.state());
                     tracy_named_frame!("keyboard input");
+                    self.record_typed_key(&text, 1);
+                    self.predict_echo(&text, 1, neovim_handler);
                     send_ui(SerialCommand::Keyboard(text), neovim_handler);
+                    self.sticky_modifiers.key_pressed();
+                    self.held_key = key_repeat.map(|(delay, _)| HeldKey {
+                        event: key_event.clone(),
+                        timer: RepeatTimer::new(Instant::now(), delay),
+                    });
                 }
             }
+            WindowEvent::Focused(false) => {
+                self.held_key = None;
+            }
             WindowEvent::Ime(Ime::Commit(text)) => {
                 log::trace!("Ime commit {text}");
                 send_ui(
@@ -71,6 +146,7 @@ impl KeyboardManager {
                 );
             }
             WindowEvent::Ime(Ime::Preedit(text, cursor_offset)) => {
+                self.held_key = None;
                 self.ime_preedit = (text.to_string(), *cursor_offset);
                 send_ui(
                     SerialCommand::KeyboardImePreedit {
@@ -109,6 +185,126 @@ impl KeyboardManager {
         }
     }
 
+    /// Keys matching `neovide_passthrough_shortcuts` are neither sent to Neovim nor used by
+    /// Neovide itself, so they reach the window manager untouched
+    fn passes_through(&self, key_event: &KeyEvent) -> bool {
+        self.format_key(key_event).is_some_and(|text| self.is_passthrough_shortcut(&text))
+    }
+
+    fn is_passthrough_shortcut(&self, text: &str) -> bool {
+        self.settings.get::<WindowSettings>().passthrough_shortcuts.contains(text)
+    }
+
+    /// Keys matching `neovide_disabled_builtin_shortcuts` are sent to Neovim, even when Neovide
+    /// has a shortcut of its own for them. `key` is formatted like [`Self::format_key`] does.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub fn forwards_builtin_shortcut(&self, key: &str) -> bool {
+        self.settings.get::<WindowSettings>().disabled_builtin_shortcuts.contains(key)
+    }
+
+    fn handle_sticky_modifier(&mut self, key_event: &KeyEvent) {
+        if !self.settings.get::<WindowSettings>().sticky_modifiers {
+            self.sticky_modifiers.clear();
+            return;
+        }
+        let Some(modifier) = StickyModifier::from_key(&key_event.logical_key) else {
+            return;
+        };
+        match key_event.state {
+            ElementState::Pressed if !key_event.repeat => {
+                self.sticky_modifiers.modifier_pressed(modifier)
+            }
+            ElementState::Pressed => {}
+            ElementState::Released => self.sticky_modifiers.modifier_released(modifier),
+        }
+    }
+
+    /// The sticky modifiers to show in the overlay, if any
+    pub fn sticky_modifiers_label(&self) -> Option<String> {
+        self.sticky_modifiers.label()
+    }
+
+    /// Follows `neovide_keycast`, which is checked for every key
+    pub fn set_keycast(&mut self, keycast: bool) {
+        self.keycast = keycast;
+        if !keycast {
+            self.typed_keys.clear();
+        }
+    }
+
+    fn record_typed_key(&mut self, text: &str, count: u32) {
+        if self.keycast {
+            self.typed_keys.extend((0..count).map(|_| text.to_owned()));
+        }
+    }
+
+    /// Shows the typed characters before Neovim draws them, the editor only does so in insert
+    /// mode. The prediction is sent first, so it's always handled before Neovim's answer.
+    fn predict_echo(&self, text: &str, count: u32, neovim_handler: &NeovimHandler) {
+        if !self.settings.get::<WindowSettings>().predictive_echo {
+            return;
+        }
+        if let Some(ch) = predicted_echo(text) {
+            for _ in 0..count {
+                neovim_handler.send_redraw_event(RedrawEvent::NeovidePredictedEcho(ch));
+            }
+        }
+    }
+
+    /// The keys sent to Neovim since the last call, to show in the keycast overlay
+    pub fn take_typed_keys(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.typed_keys)
+    }
+
+    /// The held modifiers together with the sticky ones
+    fn modifiers_state(&self) -> ModifiersState {
+        self.modifiers.state() | self.sticky_modifiers.state()
+    }
+
+    fn meta_is_pressed(&self) -> bool {
+        self.meta_is_pressed || self.sticky_modifiers.state().alt_key()
+    }
+
+    /// The delay before the first repeat and the interval between repeats, when Neovide repeats
+    /// held keys itself
+    fn key_repeat(&self) -> Option<(Duration, Duration)> {
+        let settings = self.settings.get::<WindowSettings>();
+        (settings.key_repeat_interval > 0.0).then(|| {
+            (
+                Duration::from_secs_f32(settings.key_repeat_delay.max(0.0)),
+                Duration::from_secs_f32(settings.key_repeat_interval),
+            )
+        })
+    }
+
+    /// Sends the repeats of the held key that are due, and returns when the next one is. The
+    /// modifiers are applied again for every repeat, so pressing or releasing one while the key
+    /// is held changes what gets repeated.
+    pub fn handle_key_repeat(
+        &mut self,
+        now: Instant,
+        neovim_handler: &NeovimHandler,
+    ) -> Option<Instant> {
+        let Some((_, interval)) = self.key_repeat() else {
+            self.held_key = None;
+            return None;
+        };
+        let held_key = self.held_key.as_mut()?;
+        let repeats = held_key.timer.take_due_repeats(now, interval);
+        let next_repeat = held_key.timer.next_repeat;
+        let event = held_key.event.clone();
+        if repeats > 0
+            && let Some(text) = self.format_key(&event)
+        {
+            self.record_typed_key(&text, repeats);
+            self.predict_echo(&text, repeats, neovim_handler);
+            for _ in 0..repeats {
+                send_ui(SerialCommand::Keyboard(text.clone()), neovim_handler);
+            }
+        }
+        Some(next_repeat)
+    }
+
     fn handle_numpad_numkey<'a>(
         is_numlock_enabled: bool,
         numlock_str: &'a str,
@@ -178,7 +374,7 @@ impl KeyboardManager {
         }
     }
 
-    fn format_key(&self, key_event: &KeyEvent) -> Option<String> {
+    pub fn format_key(&self, key_event: &KeyEvent) -> Option<String> {
         if let Some(text) = get_special_key(key_event) {
             Some(self.format_key_text(text, true))
         } else {
@@ -200,7 +396,7 @@ impl KeyboardManager {
     fn format_key_text(&self, text: &str, is_special: bool) -> String {
         // Neovim always converts shifted ascii alpha characters to uppercase, so do it here already
         // This fixes some bugs where winit does not report the uppercase text as it should
-        let text = if self.modifiers.state().shift_key() && is_ascii_alphabetic_char(text) {
+        let text = if self.modifiers_state().shift_key() && is_ascii_alphabetic_char(text) {
             text.to_uppercase()
         } else {
             text.to_string()
@@ -230,14 +426,14 @@ impl KeyboardManager {
         // uppercase without shift, or <M-A> .
         // But in combination with other characters, such as <M-S-$> they are not,
         // so we don't want to send shift when that's the case.
-        let state = self.modifiers.state();
+        let state = self.modifiers_state();
         let include_shift = is_special || (state.control_key() && is_ascii_alphabetic_char(text));
 
         #[cfg(target_os = "macos")]
-        let have_meta = self.meta_is_pressed || is_special && state.alt_key(); // e.g. non-meta 'option' with <F1> yeilds <M-F1>
+        let have_meta = self.meta_is_pressed() || is_special && state.alt_key(); // e.g. non-meta 'option' with <F1> yeilds <M-F1>
 
         #[cfg(not(target_os = "macos"))]
-        let have_meta = self.meta_is_pressed;
+        let have_meta = self.meta_is_pressed();
 
         let mut ret = String::new();
         (state.shift_key() && include_shift).then(|| ret += "S-");
@@ -372,3 +568,88 @@ fn get_special_key(key_event: &KeyEvent) -> Option<&str> {
 // avoid confusing users who have a post-2017 keyboard and are not aware of this
 // history, it is probably best to refer to this physical key as the 'option'
 // key, and not as the 'alt' key.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::settings::Shortcuts;
+
+    #[test]
+    fn held_keys_repeat_after_the_delay_at_the_interval() {
+        let pressed_at = Instant::now();
+        let delay = Duration::from_millis(300);
+        let interval = Duration::from_millis(50);
+        let mut timer = RepeatTimer::new(pressed_at, delay);
+
+        assert_eq!(timer.take_due_repeats(pressed_at, interval), 0);
+        assert_eq!(timer.take_due_repeats(pressed_at + Duration::from_millis(299), interval), 0);
+        assert_eq!(timer.take_due_repeats(pressed_at + delay, interval), 1);
+        assert_eq!(timer.next_repeat, pressed_at + delay + interval);
+        assert_eq!(timer.take_due_repeats(pressed_at + Duration::from_millis(320), interval), 0);
+        assert_eq!(timer.take_due_repeats(pressed_at + Duration::from_millis(350), interval), 1);
+    }
+
+    #[test]
+    fn late_wake_ups_catch_up_on_the_missed_repeats() {
+        let pressed_at = Instant::now();
+        let interval = Duration::from_millis(50);
+        let mut timer = RepeatTimer::new(pressed_at, Duration::ZERO);
+
+        assert_eq!(timer.take_due_repeats(pressed_at + Duration::from_millis(120), interval), 3);
+        assert_eq!(timer.next_repeat, pressed_at + Duration::from_millis(150));
+    }
+
+    #[test]
+    fn passthrough_shortcuts_are_left_to_the_window_manager() {
+        let settings = Arc::new(Settings::new());
+        settings.register::<WindowSettings>();
+        let mut window_settings = settings.get::<WindowSettings>();
+        window_settings.passthrough_shortcuts = Shortcuts(vec!["<D-Left>".to_owned()]);
+        settings.set(&window_settings);
+
+        let mut keyboard_manager = KeyboardManager::new(settings);
+        keyboard_manager.modifiers = ModifiersState::SUPER.into();
+        let left = keyboard_manager.format_key_text("Left", true);
+        assert_eq!(left, "<D-Left>");
+        assert!(keyboard_manager.is_passthrough_shortcut(&left));
+        assert!(
+            !keyboard_manager
+                .is_passthrough_shortcut(&keyboard_manager.format_key_text("Right", true))
+        );
+
+        keyboard_manager.modifiers = Modifiers::default();
+        assert!(
+            !keyboard_manager
+                .is_passthrough_shortcut(&keyboard_manager.format_key_text("Left", true))
+        );
+    }
+
+    #[test]
+    fn disabled_builtin_shortcuts_are_forwarded_to_neovim() {
+        let settings = Arc::new(Settings::new());
+        settings.register::<WindowSettings>();
+        let mut keyboard_manager = KeyboardManager::new(settings.clone());
+        keyboard_manager.modifiers = ModifiersState::SUPER.into();
+        let next_tab = keyboard_manager.format_key_text("}", false);
+        assert_eq!(next_tab, "<D-}>");
+        assert!(!keyboard_manager.forwards_builtin_shortcut(&next_tab));
+
+        let mut window_settings = settings.get::<WindowSettings>();
+        window_settings.disabled_builtin_shortcuts = Shortcuts(vec!["<D-}>".to_owned()]);
+        settings.set(&window_settings);
+        assert!(keyboard_manager.forwards_builtin_shortcut(&next_tab));
+        assert!(!keyboard_manager.forwards_builtin_shortcut("<D-{>"));
+        assert!(!keyboard_manager.is_passthrough_shortcut(&next_tab));
+    }
+
+    #[test]
+    fn only_single_width_characters_are_predicted() {
+        assert_eq!(predicted_echo("a"), Some('a'));
+        assert_eq!(predicted_echo("A"), Some('A'));
+        assert_eq!(predicted_echo("<Space>"), Some(' '));
+        assert_eq!(predicted_echo("<lt>"), Some('<'));
+        assert_eq!(predicted_echo("<CR>"), None);
+        assert_eq!(predicted_echo("<C-a>"), None);
+        assert_eq!(predicted_echo("好"), None);
+    }
+}
@@ -110,6 +110,34 @@ impl KeyCombo {
         }
     }
 
+    /// The combo in Neovim's key notation, formatted like the keyboard manager formats the
+    /// pressed key, so it can be matched against `neovide_disabled_builtin_shortcuts`
+    pub fn to_vim_key(self) -> String {
+        let (text, is_special) = match self.key {
+            KeyMatch::Char(character) if self.shift && character.is_ascii_alphabetic() => {
+                (character.to_ascii_uppercase().to_string(), false)
+            }
+            KeyMatch::Char(character) => (character.to_string(), false),
+            KeyMatch::Named(NamedKey::ArrowLeft) => ("Left".to_owned(), true),
+            KeyMatch::Named(NamedKey::ArrowRight) => ("Right".to_owned(), true),
+            KeyMatch::Named(NamedKey::ArrowUp) => ("Up".to_owned(), true),
+            KeyMatch::Named(NamedKey::ArrowDown) => ("Down".to_owned(), true),
+            KeyMatch::Named(named) => (format!("{named:?}"), true),
+        };
+        let include_shift =
+            is_special || (self.control && text.chars().all(|c| c.is_ascii_alphabetic()));
+
+        let mut modifiers = String::new();
+        (self.shift && include_shift).then(|| modifiers += "S-");
+        self.control.then(|| modifiers += "C-");
+        self.option.then(|| modifiers += "M-");
+        self.command.then(|| modifiers += "D-");
+
+        let (text, is_special) =
+            if text == "<" { ("lt".to_owned(), true) } else { (text, is_special) };
+        if modifiers.is_empty() && !is_special { text } else { format!("<{modifiers}{text}>") }
+    }
+
     fn matches(&self, event: &KeyEvent, modifiers: &Modifiers) -> bool {
         if !self.modifiers_match(modifiers) {
             return false;
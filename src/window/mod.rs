@@ -1,10 +1,13 @@
+mod accessibility;
 mod application;
 mod error_window;
+mod idle_manager;
 mod keyboard_manager;
 #[cfg(target_os = "macos")]
 pub mod macos;
 mod mouse_manager;
 mod settings;
+mod sticky_modifiers;
 mod window_wrapper;
 
 #[cfg(target_os = "linux")]
@@ -29,11 +32,15 @@ use winit::platform::{
 };
 
 #[cfg(target_os = "windows")]
-use winit::platform::windows::WindowAttributesExtWindows;
+use {
+    crate::renderer::{RendererBackend, selected_backend},
+    winit::platform::windows::WindowAttributesExtWindows,
+};
 
 #[cfg(target_os = "macos")]
 use winit::platform::macos::EventLoopBuilderExtMacOS;
 
+use accessibility::AccessibilityAdapter;
 use image::{GenericImageView, Pixel, load_from_memory};
 use keyboard_manager::KeyboardManager;
 use mouse_manager::MouseManager;
@@ -42,7 +49,7 @@ use std::io::Read;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::{
-    bridge::RestartDetails,
+    bridge::{NeovimExit, RestartDetails},
     cmd_line::{CmdLineSettings, GeometryArgs},
     frame::Frame,
     renderer::{DrawCommand, WindowConfig, build_window_config},
@@ -120,6 +127,10 @@ pub enum WindowCommand {
         modified: bool,
     },
     Minimize,
+    ToggleVSync,
+    ToggleAlwaysOnTop,
+    ShowInfo,
+    ShowCommandPalette(Vec<String>),
     ThemeChanged(Option<Theme>),
     #[cfg(windows)]
     RegisterRightClick,
@@ -137,7 +148,7 @@ pub enum MacShortcutCommand {
 #[derive(Clone, Debug, PartialEq)]
 pub enum UserEvent {
     DrawCommandBatch(Vec<DrawCommand>),
-    #[cfg(target_os = "macos")]
+    #[cfg(unix)]
     OpenFiles {
         files: Vec<String>,
         cwd: Option<String>,
@@ -146,13 +157,14 @@ pub enum UserEvent {
         new_window: bool,
         neovim_bin: Option<String>,
         neovim_args: Option<Vec<String>>,
+        cursor: Option<(u64, u64)>,
     },
     WindowCommand(WindowCommand),
     SettingsChanged(SettingsChanged),
     ConfigsChanged(Box<HotReloadConfigs>),
     #[allow(dead_code)]
     RedrawRequested,
-    NeovimExited,
+    NeovimExited(NeovimExit),
     NeovimLaunchError {
         message: String,
     },
@@ -160,6 +172,8 @@ pub enum UserEvent {
     ShowProgressBar {
         percent: f32,
     },
+    /// The reduce motion accessibility setting of the OS changed
+    ReducedMotionChanged(bool),
     #[cfg(target_os = "macos")]
     CreateWindow,
     #[cfg(target_os = "macos")]
@@ -283,7 +297,7 @@ pub fn create_window(
         window_attributes.with_window_icon(Some(icon.clone())).with_taskbar_icon(Some(icon));
 
     #[cfg(target_os = "windows")]
-    let window_attributes = if !cmd_line_settings.opengl {
+    let window_attributes = if selected_backend(settings) != RendererBackend::OpenGL {
         WindowAttributesExtWindows::with_no_redirection_bitmap(window_attributes, true)
     } else {
         window_attributes
@@ -8,20 +8,48 @@ use std::{
 use winit::{
     event::WindowEvent,
     event::{DeviceId, ElementState, MouseButton, MouseScrollDelta, Touch, TouchPhase},
-    window::Window,
+    window::{CursorIcon, Window},
 };
 
 use glamour::{Contains, Point2};
 
 use crate::{
     bridge::{NeovimHandler, SerialCommand, send_ui},
+    cmd_line::CmdLineSettings,
     editor::WindowType,
-    renderer::{MessageSelection, Renderer, WindowDrawDetails},
+    renderer::{
+        MessageSelection, Renderer, WindowDrawDetails, buffer_bar::BufferBar,
+        context_menu::ContextMenuItems, tab_bar::TabBar,
+    },
     settings::Settings,
     units::{GridPos, GridScale, GridSize, GridVec, PixelPos, PixelRect, PixelSize, PixelVec},
     window::{WindowSettings, keyboard_manager::KeyboardManager},
 };
 
+/// Middle clicks paste the primary selection like in a terminal, instead of being sent to Neovim
+fn pastes_primary_selection(mouse_button: MouseButton, middle_click_paste: bool) -> bool {
+    cfg!(target_os = "linux") && middle_click_paste && mouse_button == MouseButton::Middle
+}
+
+/// Right clicks open Neovide's context menu instead of being sent to Neovim, when it has items and
+/// the mouse isn't disabled with `'mouse'`
+fn opens_context_menu(
+    mouse_button: MouseButton,
+    items: &ContextMenuItems,
+    mouse_enabled: bool,
+) -> bool {
+    mouse_enabled && mouse_button == MouseButton::Right && !items.0.is_empty()
+}
+
+/// Left clicks switch to the tab, middle clicks close it
+fn tab_click_command(mouse_button: MouseButton, tab: u64) -> Option<SerialCommand> {
+    match mouse_button {
+        MouseButton::Left => Some(SerialCommand::SwitchTab(tab)),
+        MouseButton::Middle => Some(SerialCommand::CloseTab(tab)),
+        _ => None,
+    }
+}
+
 fn mouse_button_to_button_text(mouse_button: MouseButton) -> Option<String> {
     match mouse_button {
         MouseButton::Left => Some("left".to_owned()),
@@ -33,6 +61,127 @@ fn mouse_button_to_button_text(mouse_button: MouseButton) -> Option<String> {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SeparatorKind {
+    /// Between windows side by side, dragging it changes their width
+    Vertical,
+    /// Between windows above each other, dragging it changes their height
+    Horizontal,
+}
+
+/// A separator between two splits, with the window left of it or above it
+#[derive(Clone, Debug)]
+struct Separator {
+    kind: SeparatorKind,
+    window: WindowDrawDetails,
+}
+
+impl Separator {
+    fn cursor_icon(&self) -> CursorIcon {
+        match self.kind {
+            SeparatorKind::Vertical => CursorIcon::ColResize,
+            SeparatorKind::Horizontal => CursorIcon::RowResize,
+        }
+    }
+}
+
+/// Finds the separator under the mouse from the gap between two adjacent splits. The first region
+/// is the root grid, which is under all the splits, and floating windows on top of a separator
+/// hide it. The borders of floats aren't separators.
+fn separator_at(
+    position: PixelPos<f32>,
+    regions: &[WindowDrawDetails],
+    grid_scale: GridScale,
+) -> Option<Separator> {
+    let windows = regions.get(1..).unwrap_or_default();
+    if windows.iter().any(|details| details.region.contains(&position)) {
+        return None;
+    }
+    let splits =
+        windows.iter().filter(|details| details.split_window.is_some()).collect::<Vec<_>>();
+
+    // A separator takes at most one cell, allow some rounding of the window positions
+    let within = |start: f32, end: f32, value: f32, cell: f32| {
+        end - start <= cell * 1.5 && start <= value && value <= end
+    };
+    let overlaps = |start_a: f32, end_a: f32, start_b: f32, end_b: f32, value: f32| {
+        start_a.max(start_b) <= value && value < end_a.min(end_b)
+    };
+    splits.iter().flat_map(|a| splits.iter().map(move |b| (*a, *b))).find_map(|(window, next)| {
+        let (a, b) = (&window.region, &next.region);
+        let kind = if within(a.max.x, b.min.x, position.x, grid_scale.width())
+            && overlaps(a.min.y, a.max.y, b.min.y, b.max.y, position.y)
+        {
+            SeparatorKind::Vertical
+        } else if within(a.max.y, b.min.y, position.y, grid_scale.height())
+            && overlaps(a.min.x, a.max.x, b.min.x, b.max.x, position.x)
+        {
+            SeparatorKind::Horizontal
+        } else {
+            return None;
+        };
+        Some(Separator { kind, window: window.clone() })
+    })
+}
+
+/// The split under the mouse that the focus follows. The first region is the root grid, which is
+/// under all the splits, and the message grid never takes the focus.
+fn hovered_split(
+    position: PixelPos<f32>,
+    regions: &[WindowDrawDetails],
+) -> Option<&WindowDrawDetails> {
+    regions
+        .iter()
+        .skip(1)
+        .rfind(|details| details.region.contains(&position))
+        .filter(|details| matches!(details.window_type, WindowType::Editor))
+}
+
+/// Remembers the grid the mouse is over, so that Neovim is asked to focus a window only once when
+/// the mouse enters it. Neovim waits for the mouse to rest before switching, which debounces the
+/// windows crossed on the way.
+#[derive(Default)]
+struct FocusFollowsMouse {
+    grid: Option<u64>,
+}
+
+impl FocusFollowsMouse {
+    /// Returns the grid to focus when the mouse entered another one
+    fn hover(&mut self, grid: Option<u64>) -> Option<u64> {
+        if grid == self.grid {
+            return None;
+        }
+        self.grid = grid;
+        grid
+    }
+}
+
+struct SeparatorDrag {
+    separator: Separator,
+    start: PixelPos<f32>,
+    // The Neovim window of the resized split
+    window: u64,
+    last_size: u32,
+}
+
+impl SeparatorDrag {
+    /// The size of the resized window for the mouse at `position`, it can't get smaller than a
+    /// single cell
+    fn size_at(&self, position: PixelPos<f32>, grid_scale: GridScale) -> u32 {
+        let (original, delta) = match self.separator.kind {
+            SeparatorKind::Vertical => (
+                self.separator.window.grid_size.width,
+                (position.x - self.start.x) / grid_scale.width(),
+            ),
+            SeparatorKind::Horizontal => (
+                self.separator.window.grid_size.height,
+                (position.y - self.start.y) / grid_scale.height(),
+            ),
+        };
+        (original as f32 + delta).round().max(1.0) as u32
+    }
+}
+
 struct DragDetails {
     draw_details: WindowDrawDetails,
     button: MouseButton,
@@ -58,6 +207,9 @@ pub enum OverlayEvent {
     #[default]
     Unchanged,
     MessageSelection(MessageSelectionEvent),
+    OpenContextMenu(PixelPos<f32>),
+    ContextMenuChanged,
+    CommandPaletteChanged,
 }
 
 pub struct PointerTransitionResult {
@@ -66,12 +218,16 @@ pub struct PointerTransitionResult {
 
 pub struct MouseEventResult {
     pub overlay_event: OverlayEvent,
+    /// The window and the number of rows to scroll past the end of its buffer
+    pub overscan: Option<(u64, f32)>,
 }
 
 pub struct EditorState<'a> {
     pub grid_scale: &'a GridScale,
     pub window_regions: &'a Vec<WindowDrawDetails>,
     pub full_region: WindowDrawDetails,
+    pub tab_bar: &'a TabBar,
+    pub buffer_bar: &'a BufferBar,
     pub window: &'a Window,
     pub keyboard_manager: &'a KeyboardManager,
 }
@@ -102,10 +258,16 @@ pub struct MouseManager {
     // redundant show requests https://github.com/rust-windowing/winit/issues/1295 so we
     // remember to toggle visibility once we regain focus.
     cursor_resync_needed: bool,
-    pub enabled: bool,
+    // Follows the mouse_on and mouse_off events, Neovim disables the mouse in the modes that
+    // are not in 'mouse'
+    enabled: bool,
 
     settings: Arc<Settings>,
     message_selection: Option<MessageSelectionState>,
+    separator_drag: Option<SeparatorDrag>,
+    // The resize icon shown over a separator, if any
+    separator_cursor: Option<CursorIcon>,
+    focus_follows_mouse: FocusFollowsMouse,
 }
 
 impl MouseManager {
@@ -122,6 +284,18 @@ impl MouseManager {
             enabled: true,
             settings,
             message_selection: None,
+            separator_drag: None,
+            separator_cursor: None,
+            focus_follows_mouse: FocusFollowsMouse::default(),
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        // The release of a drag in progress would not be sent anymore
+        if !enabled {
+            self.drag_details = None;
+            self.has_moved = false;
         }
     }
 
@@ -195,21 +369,32 @@ impl MouseManager {
         editor_state.window_regions.iter().rfind(|details| details.region.contains(&position))
     }
 
+    /// The window the scroll wheel overscans, which is never the padding or the tab and buffer
+    /// bars since they lie outside all the window regions
+    fn overscan_window(&self, window_regions: &[WindowDrawDetails]) -> Option<u64> {
+        if !self.enabled {
+            return None;
+        }
+        let position = self.window_position;
+        window_regions
+            .iter()
+            .rfind(|details| details.region.contains(&position))
+            .map(|details| details.id)
+    }
+
     fn get_relative_position_at(
         window_position: PixelPos<f32>,
         window_details: &WindowDrawDetails,
-        editor_state: &EditorState,
+        grid_scale: GridScale,
     ) -> GridPos<u32> {
         let relative_position = (window_position - window_details.region.min).to_point();
-        (relative_position / *editor_state.grid_scale)
-            .floor()
-            .max((0.0, 0.0).into())
-            .try_cast()
-            .unwrap()
-            .min(Point2::new(
-                window_details.grid_size.width.max(1) - 1,
-                window_details.grid_size.height.max(1) - 1,
-            ))
+        let mut relative_position =
+            relative_position / window_details.content_grid_scale(grid_scale);
+        relative_position.y -= window_details.content_offset;
+        relative_position.floor().max((0.0, 0.0).into()).try_cast().unwrap().min(Point2::new(
+            window_details.grid_size.width.max(1) - 1,
+            window_details.grid_size.height.max(1) - 1,
+        ))
     }
 
     pub fn get_relative_position(
@@ -217,7 +402,11 @@ impl MouseManager {
         window_details: &WindowDrawDetails,
         editor_state: &EditorState,
     ) -> GridPos<u32> {
-        Self::get_relative_position_at(self.window_position, window_details, editor_state)
+        Self::get_relative_position_at(
+            self.window_position,
+            window_details,
+            *editor_state.grid_scale,
+        )
     }
 
     pub fn clear_message_selection(&mut self) -> bool {
@@ -234,6 +423,114 @@ impl MouseManager {
         self.settings.get::<WindowSettings>().message_area_drag_selection
     }
 
+    /// Starts or ends dragging the separator under the mouse, returns whether the button was used
+    /// for it
+    fn handle_separator_button(
+        &mut self,
+        mouse_button: MouseButton,
+        down: bool,
+        editor_state: &EditorState,
+    ) -> bool {
+        if mouse_button != MouseButton::Left {
+            return false;
+        }
+        if !down {
+            return self.separator_drag.take().is_some();
+        }
+        if !self.enabled || self.drag_details.is_some() {
+            return false;
+        }
+        let grid_scale = *editor_state.grid_scale;
+        let Some(separator) =
+            separator_at(self.window_position, editor_state.window_regions, grid_scale)
+        else {
+            return false;
+        };
+        let Some(window) = separator.window.split_window else {
+            return false;
+        };
+
+        let last_size = match separator.kind {
+            SeparatorKind::Vertical => separator.window.grid_size.width,
+            SeparatorKind::Horizontal => separator.window.grid_size.height,
+        };
+        self.separator_drag =
+            Some(SeparatorDrag { separator, start: self.window_position, window, last_size });
+        true
+    }
+
+    /// Resizes the window while its separator is dragged, returns false when nothing is dragged
+    fn drag_separator(
+        &mut self,
+        editor_state: &EditorState,
+        neovim_handler: &NeovimHandler,
+    ) -> bool {
+        let Some(drag) = &mut self.separator_drag else {
+            return false;
+        };
+        let size = drag.size_at(self.window_position, *editor_state.grid_scale);
+        if size != drag.last_size {
+            drag.last_size = size;
+            send_ui(
+                SerialCommand::ResizeWindow {
+                    window: drag.window,
+                    vertical: drag.separator.kind == SeparatorKind::Vertical,
+                    size,
+                },
+                neovim_handler,
+            );
+        }
+        true
+    }
+
+    /// Shows a resize icon while the mouse is over a separator
+    fn update_separator_cursor(&mut self, editor_state: &EditorState) {
+        let icon = match &self.separator_drag {
+            Some(drag) => Some(drag.separator.cursor_icon()),
+            None if self.enabled && self.drag_details.is_none() => separator_at(
+                self.window_position,
+                editor_state.window_regions,
+                *editor_state.grid_scale,
+            )
+            .map(|separator| separator.cursor_icon()),
+            None => None,
+        };
+        if icon == self.separator_cursor {
+            return;
+        }
+        self.separator_cursor = icon;
+        editor_state.window.set_cursor(
+            icon.unwrap_or_else(|| {
+                self.settings.get::<CmdLineSettings>().mouse_cursor_icon.parse()
+            }),
+        );
+    }
+
+    /// Asks Neovim to focus the window the mouse entered, when the focus follows the mouse
+    fn focus_hovered_split(&mut self, editor_state: &EditorState, neovim_handler: &NeovimHandler) {
+        if !self.settings.get::<WindowSettings>().focus_follows_mouse {
+            self.focus_follows_mouse = FocusFollowsMouse::default();
+            return;
+        }
+        if self.drag_details.is_some() || self.message_selection.is_some() {
+            return;
+        }
+        let split = hovered_split(self.window_position, editor_state.window_regions);
+        if self.focus_follows_mouse.hover(split.map(|details| details.id)).is_none() {
+            return;
+        }
+
+        // Neovim finds the window from the screen cell, floating windows included
+        let relative_position =
+            (self.window_position - editor_state.full_region.region.min).to_point();
+        let screen_position =
+            (relative_position / *editor_state.grid_scale).floor().try_cast().unwrap_or_default();
+        send_ui(
+            SerialCommand::FocusWindow { position: screen_position.to_tuple() },
+            neovim_handler,
+        );
+    }
+
     fn handle_pointer_motion(
         &mut self,
         position: PixelPos<f32>,
@@ -246,6 +543,12 @@ impl MouseManager {
 
         self.window_position = position;
 
+        self.update_separator_cursor(editor_state);
+        if self.drag_separator(editor_state, neovim_handler) {
+            return MessageSelectionEvent::Outside;
+        }
+        self.focus_hovered_split(editor_state, neovim_handler);
+
         let message_selection_enabled = self.message_area_drag_selection_enabled();
         if !message_selection_enabled && self.clear_message_selection() {
             return MessageSelectionEvent::Clear;
@@ -256,7 +559,7 @@ impl MouseManager {
             let end = Self::get_relative_position_at(
                 window_position,
                 &selection.draw_details,
-                editor_state,
+                *editor_state.grid_scale,
             );
             selection.end = end;
             self.grid_position = end;
@@ -293,7 +596,8 @@ impl MouseManager {
 
             let has_moved = self.grid_position != previous_position;
 
-            if has_moved {
+            // Like in a terminal, nothing is sent while Neovim has the mouse disabled
+            if has_moved && self.enabled {
                 if let Some(drag_details) = &self.drag_details {
                     send_ui(
                         SerialCommand::Drag {
@@ -396,35 +700,66 @@ impl MouseManager {
         editor_state: &EditorState,
         neovim_handler: &NeovimHandler,
     ) {
+        let details = self.get_window_details_under_mouse(editor_state);
+        let modifier_string = editor_state.keyboard_manager.format_modifier_string("", true);
+        for command in self.mouse_button_commands(
+            mouse_button,
+            down,
+            details,
+            *editor_state.grid_scale,
+            modifier_string,
+        ) {
+            send_ui(command, neovim_handler);
+        }
+    }
+
+    /// The commands for a button event on the window under the mouse, none while Neovim has the
+    /// mouse disabled
+    fn mouse_button_commands(
+        &mut self,
+        mouse_button: MouseButton,
+        down: bool,
+        details: Option<&WindowDrawDetails>,
+        grid_scale: GridScale,
+        modifier_string: String,
+    ) -> Vec<SerialCommand> {
         // For some reason pointer down is handled differently from pointer up and drag.
         // Floating windows: relative coordinates are great.
         // Non floating windows: rather than global coordinates, relative are needed
         if !self.enabled {
-            return;
+            return Vec::new();
         }
 
-        if let Some(button_text) = mouse_button_to_button_text(mouse_button) {
-            if let &Some(details) = &self.get_window_details_under_mouse(editor_state) {
-                let action = if down { "press".to_owned() } else { "release".to_owned() };
-
-                let position = if !down && self.has_moved {
-                    self.grid_position
-                } else {
-                    self.get_relative_position(details, editor_state)
-                };
+        if pastes_primary_selection(
+            mouse_button,
+            self.settings.get::<WindowSettings>().middle_click_paste,
+        ) {
+            return match details.filter(|_| down) {
+                Some(details) => self.paste_commands(details, grid_scale),
+                None => Vec::new(),
+            };
+        }
 
-                send_ui(
-                    SerialCommand::MouseButton {
-                        button: button_text.clone(),
-                        action,
-                        grid_id: details.event_grid_id(&self.settings),
-                        position: position.to_tuple(),
-                        modifier_string: editor_state
-                            .keyboard_manager
-                            .format_modifier_string("", true),
-                    },
-                    neovim_handler,
-                );
+        let mut commands = Vec::new();
+        if mouse_button_to_button_text(mouse_button).is_some() {
+            if let Some(details) = details {
+                commands.extend(self.button_command(
+                    mouse_button,
+                    down,
+                    details,
+                    grid_scale,
+                    modifier_string,
+                ));
+
+                // Sent as a serial command so that Neovim has processed the release, and entered
+                // visual mode, before the selection is queried
+                if !down
+                    && self.has_moved
+                    && mouse_button == MouseButton::Left
+                    && self.settings.get::<WindowSettings>().copy_on_select
+                {
+                    commands.push(SerialCommand::CopySelection);
+                }
 
                 if down {
                     self.drag_details =
@@ -440,6 +775,79 @@ impl MouseManager {
                 self.has_moved = false;
             }
         }
+        commands
+    }
+
+    /// Returns the button event for the window under the mouse
+    fn button_command(
+        &self,
+        mouse_button: MouseButton,
+        down: bool,
+        details: &WindowDrawDetails,
+        grid_scale: GridScale,
+        modifier_string: String,
+    ) -> Option<SerialCommand> {
+        let action = if down { "press" } else { "release" };
+        let position = if !down && self.has_moved {
+            self.grid_position
+        } else {
+            Self::get_relative_position_at(self.window_position, details, grid_scale)
+        };
+        Some(SerialCommand::MouseButton {
+            button: mouse_button_to_button_text(mouse_button)?,
+            action: action.to_owned(),
+            grid_id: details.event_grid_id(&self.settings),
+            position: position.to_tuple(),
+            modifier_string,
+        })
+    }
+
+    fn paste_commands(
+        &self,
+        details: &WindowDrawDetails,
+        grid_scale: GridScale,
+    ) -> Vec<SerialCommand> {
+        let grid_id = details.event_grid_id(&self.settings);
+        let position =
+            Self::get_relative_position_at(self.window_position, details, grid_scale).to_tuple();
+
+        // Move the cursor to the click position first, the paste happens at the cursor
+        let click = |action: &str| SerialCommand::MouseButton {
+            button: "left".to_owned(),
+            action: action.to_owned(),
+            grid_id,
+            position,
+            modifier_string: String::new(),
+        };
+        vec![click("press"), click("release"), SerialCommand::PasteSelection]
+    }
+
+    /// Returns `None` when the mouse isn't over one of the bars, otherwise what the click does
+    fn bar_click_command(
+        &self,
+        mouse_button: MouseButton,
+        editor_state: &EditorState,
+    ) -> Option<Option<SerialCommand>> {
+        let position = self.window_position;
+        let grid_scale = *editor_state.grid_scale;
+        if editor_state.tab_bar.contains(position) {
+            Some(
+                editor_state
+                    .tab_bar
+                    .tab_at(position, grid_scale)
+                    .and_then(|tab| tab_click_command(mouse_button, tab)),
+            )
+        } else if editor_state.buffer_bar.contains(position) {
+            Some(
+                editor_state
+                    .buffer_bar
+                    .buffer_at(position, grid_scale)
+                    .filter(|_| mouse_button == MouseButton::Left)
+                    .map(SerialCommand::SwitchBuffer),
+            )
+        } else {
+            None
+        }
     }
 
     fn handle_pointer_transition(
@@ -449,6 +857,37 @@ impl MouseManager {
         editor_state: &EditorState,
         neovim_handler: &NeovimHandler,
     ) -> PointerTransitionResult {
+        if self.handle_separator_button(mouse_button, down, editor_state) {
+            return PointerTransitionResult { overlay_event: OverlayEvent::default() };
+        }
+
+        // Clicks on the tab and buffer bars are handled by Neovide, unless they end a drag that
+        // started on the grid
+        if self.drag_details.is_none()
+            && let Some(command) = self.bar_click_command(mouse_button, editor_state)
+        {
+            if down
+                && self.enabled
+                && let Some(command) = command
+            {
+                send_ui(command, neovim_handler);
+            }
+            return PointerTransitionResult { overlay_event: OverlayEvent::default() };
+        }
+
+        if down
+            && self.drag_details.is_none()
+            && opens_context_menu(
+                mouse_button,
+                &self.settings.get::<WindowSettings>().context_menu,
+                self.enabled,
+            )
+        {
+            return PointerTransitionResult {
+                overlay_event: OverlayEvent::OpenContextMenu(self.window_position),
+            };
+        }
+
         let message_selection_event =
             self.update_message_selection_on_button(mouse_button, down, editor_state);
         let consume_mouse_input = matches!(
@@ -472,9 +911,9 @@ impl MouseManager {
         amount: GridVec<f32>,
         editor_state: &EditorState,
         neovim_handler: &NeovimHandler,
-    ) {
+    ) -> Option<(u64, f32)> {
         if !self.enabled {
-            return;
+            return None;
         }
 
         let draw_details = self.get_window_details_under_mouse(editor_state);
@@ -520,6 +959,8 @@ impl MouseManager {
                 send_ui(scroll_command.clone(), neovim_handler);
             }
         }
+
+        self.overscan_window(editor_state.window_regions).map(|id| (id, amount.y))
     }
 
     fn handle_pixel_scroll(
@@ -527,9 +968,9 @@ impl MouseManager {
         amount: PixelVec<f32>,
         editor_state: &EditorState,
         neovim_handler: &NeovimHandler,
-    ) {
+    ) -> Option<(u64, f32)> {
         let amount = amount / *editor_state.grid_scale;
-        self.handle_line_scroll(amount, editor_state, neovim_handler);
+        self.handle_line_scroll(amount, editor_state, neovim_handler)
     }
 
     fn handle_touch(
@@ -660,16 +1101,22 @@ impl MouseManager {
             region: renderer.window_regions.first().map_or(PixelRect::ZERO, |v| v.region),
             grid_size: renderer.window_regions.first().map_or(GridSize::ZERO, |v| v.grid_size),
             window_type: crate::editor::WindowType::Editor,
+            content_offset: 0.0,
+            font_scale: 1.0,
+            split_window: None,
         };
         let editor_state = EditorState {
             grid_scale: &renderer.grid_renderer.grid_scale,
             window_regions: &renderer.window_regions,
             full_region,
+            tab_bar: &renderer.tab_bar,
+            buffer_bar: &renderer.buffer_bar,
             window,
             keyboard_manager,
         };
         let hide_mouse_when_typing = self.settings.get::<WindowSettings>().hide_mouse_when_typing;
         let mut overlay_event = OverlayEvent::default();
+        let mut overscan = None;
         match event {
             WindowEvent::CursorMoved { position, .. } => {
                 let message_selection_event = self.handle_pointer_motion(
@@ -695,14 +1142,15 @@ impl MouseManager {
                 }
             }
             WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(x, y), .. } => {
-                self.handle_line_scroll((*x, *y).into(), &editor_state, neovim_handler)
+                overscan = self.handle_line_scroll((*x, *y).into(), &editor_state, neovim_handler);
             }
-            WindowEvent::MouseWheel { delta: MouseScrollDelta::PixelDelta(delta), .. } => self
-                .handle_pixel_scroll(
+            WindowEvent::MouseWheel { delta: MouseScrollDelta::PixelDelta(delta), .. } => {
+                overscan = self.handle_pixel_scroll(
                     (delta.x as f32, delta.y as f32).into(),
                     &editor_state,
                     neovim_handler,
-                ),
+                );
+            }
             WindowEvent::Touch(Touch { device_id, id, location, phase, .. }) => self.handle_touch(
                 (*device_id, *id),
                 PixelPos::new(location.x as f32, location.y as f32),
@@ -735,6 +1183,199 @@ impl MouseManager {
             _ => {}
         }
 
-        MouseEventResult { overlay_event }
+        MouseEventResult { overlay_event, overscan }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tab_clicks_switch_or_close_the_tab() {
+        assert!(matches!(
+            tab_click_command(MouseButton::Left, 3),
+            Some(SerialCommand::SwitchTab(3))
+        ));
+        assert!(matches!(
+            tab_click_command(MouseButton::Middle, 3),
+            Some(SerialCommand::CloseTab(3))
+        ));
+        assert!(tab_click_command(MouseButton::Right, 3).is_none());
+    }
+
+    #[test]
+    fn middle_click_pastes_only_when_enabled() {
+        assert_eq!(pastes_primary_selection(MouseButton::Middle, true), cfg!(target_os = "linux"));
+        assert!(!pastes_primary_selection(MouseButton::Middle, false));
+        assert!(!pastes_primary_selection(MouseButton::Left, true));
+        assert!(!pastes_primary_selection(MouseButton::Right, true));
+    }
+
+    #[test]
+    fn right_click_opens_the_context_menu_when_it_has_items() {
+        let items = ContextMenuItems::default();
+        assert!(opens_context_menu(MouseButton::Right, &items, true));
+        assert!(!opens_context_menu(MouseButton::Left, &items, true));
+        assert!(!opens_context_menu(MouseButton::Right, &items, false));
+        assert!(!opens_context_menu(MouseButton::Right, &ContextMenuItems(Vec::new()), true));
+    }
+
+    fn split(id: u64, min: (f32, f32), max: (f32, f32)) -> WindowDrawDetails {
+        let region = PixelRect::new(min.into(), max.into());
+        let grid_size =
+            GridSize::new(((max.0 - min.0) / 10.0) as u32, ((max.1 - min.1) / 20.0) as u32);
+        WindowDrawDetails {
+            id,
+            region,
+            grid_size,
+            window_type: WindowType::Editor,
+            content_offset: 0.0,
+            font_scale: 1.0,
+            split_window: Some(id),
+        }
+    }
+
+    #[test]
+    fn separators_are_found_between_adjacent_splits() {
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 20.0));
+        let root = split(1, (0.0, 0.0), (210.0, 240.0));
+
+        // Side by side, with a column for the separator
+        let regions = [
+            root.clone(),
+            split(2, (0.0, 0.0), (100.0, 200.0)),
+            split(3, (110.0, 0.0), (210.0, 200.0)),
+        ];
+        let separator = separator_at(PixelPos::new(105.0, 50.0), &regions, grid_scale).unwrap();
+        assert_eq!(separator.kind, SeparatorKind::Vertical);
+        assert_eq!(separator.window.id, 2);
+        assert!(separator_at(PixelPos::new(50.0, 50.0), &regions, grid_scale).is_none());
+        assert!(separator_at(PixelPos::new(105.0, 220.0), &regions, grid_scale).is_none());
+
+        // Above each other, with a row for the status line
+        let regions =
+            [root, split(2, (0.0, 0.0), (210.0, 100.0)), split(3, (0.0, 120.0), (210.0, 220.0))];
+        let separator = separator_at(PixelPos::new(50.0, 110.0), &regions, grid_scale).unwrap();
+        assert_eq!(separator.kind, SeparatorKind::Horizontal);
+        assert_eq!(separator.window.id, 2);
+    }
+
+    #[test]
+    fn the_borders_of_floats_are_not_separators() {
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 20.0));
+        let float = |id, min, max| WindowDrawDetails { split_window: None, ..split(id, min, max) };
+
+        // A float next to a split, and two floats next to each other
+        let regions = [
+            split(1, (0.0, 0.0), (400.0, 240.0)),
+            split(2, (0.0, 0.0), (100.0, 200.0)),
+            float(3, (110.0, 0.0), (210.0, 100.0)),
+            float(4, (220.0, 0.0), (320.0, 100.0)),
+        ];
+        assert!(separator_at(PixelPos::new(105.0, 50.0), &regions, grid_scale).is_none());
+        assert!(separator_at(PixelPos::new(215.0, 50.0), &regions, grid_scale).is_none());
+    }
+
+    #[test]
+    fn the_focus_follows_the_split_under_the_mouse() {
+        let mut message = split(4, (0.0, 150.0), (200.0, 200.0));
+        message.window_type = WindowType::Message { scrolled: false };
+        let regions = [
+            split(1, (0.0, 0.0), (200.0, 200.0)),
+            split(2, (0.0, 0.0), (100.0, 180.0)),
+            split(3, (110.0, 0.0), (200.0, 180.0)),
+            message,
+        ];
+        let hovered = |x, y| hovered_split(PixelPos::new(x, y), &regions).map(|details| details.id);
+        assert_eq!(hovered(50.0, 50.0), Some(2));
+        assert_eq!(hovered(150.0, 50.0), Some(3));
+        // The separator belongs to the root grid and the message grid never takes the focus
+        assert_eq!(hovered(105.0, 50.0), None);
+        assert_eq!(hovered(50.0, 170.0), None);
+
+        // Only entering another window asks for the focus
+        let mut focus = FocusFollowsMouse::default();
+        assert_eq!(focus.hover(hovered(50.0, 50.0)), Some(2));
+        assert_eq!(focus.hover(hovered(60.0, 70.0)), None);
+        assert_eq!(focus.hover(hovered(105.0, 50.0)), None);
+        assert_eq!(focus.hover(hovered(150.0, 50.0)), Some(3));
+        assert_eq!(focus.hover(hovered(150.0, 60.0)), None);
+    }
+
+    #[test]
+    fn dragging_a_separator_keeps_the_window_at_least_a_cell_wide() {
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 20.0));
+        let separator = Separator {
+            kind: SeparatorKind::Vertical,
+            window: split(2, (0.0, 0.0), (100.0, 200.0)),
+        };
+        let drag = SeparatorDrag {
+            separator,
+            start: PixelPos::new(105.0, 50.0),
+            window: 2,
+            last_size: 10,
+        };
+        assert_eq!(drag.size_at(PixelPos::new(136.0, 50.0), grid_scale), 13);
+        assert_eq!(drag.size_at(PixelPos::new(75.0, 50.0), grid_scale), 7);
+        assert_eq!(drag.size_at(PixelPos::new(-500.0, 50.0), grid_scale), 1);
+    }
+
+    #[test]
+    fn clicks_are_only_forwarded_while_the_mouse_is_on() {
+        let settings = Arc::new(Settings::new());
+        settings.set(&CmdLineSettings::default());
+        settings.set(&WindowSettings::default());
+        let mut mouse_manager = MouseManager::new(settings);
+        let grid_scale = GridScale::new(PixelSize::new(10.0, 20.0));
+        let window = split(2, (0.0, 0.0), (100.0, 200.0));
+        mouse_manager.window_position = PixelPos::new(35.0, 45.0);
+        let click = |mouse_manager: &mut MouseManager| {
+            mouse_manager.mouse_button_commands(
+                MouseButton::Left,
+                true,
+                Some(&window),
+                grid_scale,
+                String::new(),
+            )
+        };
+
+        mouse_manager.drag_details =
+            Some(DragDetails { button: MouseButton::Left, draw_details: window.clone() });
+        mouse_manager.set_enabled(false);
+        assert!(click(&mut mouse_manager).is_empty());
+        assert!(mouse_manager.drag_details.is_none());
+
+        mouse_manager.set_enabled(true);
+        assert!(matches!(
+            click(&mut mouse_manager)[..],
+            [SerialCommand::MouseButton { grid_id: 2, position: (3, 2), .. }]
+        ));
+        assert!(mouse_manager.drag_details.is_some());
+    }
+
+    #[test]
+    fn the_wheel_overscans_the_window_under_the_mouse() {
+        let settings = Arc::new(Settings::new());
+        settings.set(&CmdLineSettings::default());
+        settings.set(&WindowSettings::default());
+        let mut mouse_manager = MouseManager::new(settings);
+        // The tab bar and the padding are above and left of the split
+        let float =
+            WindowDrawDetails { split_window: None, ..split(3, (50.0, 100.0), (90.0, 150.0)) };
+        let regions = [split(2, (10.0, 40.0), (110.0, 240.0)), float];
+        let mut overscan_window = |x, y| {
+            mouse_manager.window_position = PixelPos::new(x, y);
+            mouse_manager.overscan_window(&regions)
+        };
+
+        assert_eq!(overscan_window(35.0, 45.0), Some(2));
+        assert_eq!(overscan_window(60.0, 120.0), Some(3));
+        assert_eq!(overscan_window(35.0, 20.0), None);
+        assert_eq!(overscan_window(5.0, 100.0), None);
+
+        mouse_manager.set_enabled(false);
+        mouse_manager.window_position = PixelPos::new(35.0, 45.0);
+        assert_eq!(mouse_manager.overscan_window(&regions), None);
     }
 }
@@ -1,38 +1,63 @@
 use rmpv::Value;
 
+use crate::dimensions::Dimensions;
 use crate::error_msg;
+use crate::renderer::context_menu::ContextMenuItems;
 use crate::settings::*;
+use crate::units::GridSize;
 
 #[cfg(target_os = "macos")]
 pub use crate::platform::macos::settings::*;
 
 #[derive(Clone, SettingGroup, PartialEq)]
 pub struct WindowSettings {
+    pub battery_saver: bool,
+    pub buffer_bar: bool,
     pub confirm_quit: bool,
+    pub context_menu: ContextMenuItems,
+    pub copy_on_select: bool,
     pub cursor_hack: bool,
+    pub disabled_builtin_shortcuts: Shortcuts,
     pub fullscreen: bool,
+    pub always_on_top: bool,
     pub has_mouse_grid_detection: bool,
+    pub focus_follows_mouse: bool,
     pub hide_mouse_when_typing: bool,
     pub input_ime: bool,
     pub iso_layout: bool,
+    pub key_repeat_delay: f32,
+    pub key_repeat_interval: f32,
+    pub keycast: bool,
+    pub max_grid_size: MaxGridSize,
     pub normal_opacity: f32,
+    pub passthrough_shortcuts: Shortcuts,
+    pub pause_when_hidden: bool,
+    pub predictive_echo: bool,
     #[alias = "transparency"]
     pub opacity: f32,
+    pub text_opacity: f32,
+    pub background_opacity: f32,
     pub padding_bottom: u32,
     pub padding_left: u32,
     pub padding_right: u32,
     pub padding_top: u32,
+    pub redraw_coalesce_ms: u64,
     pub refresh_rate: u64,
     pub refresh_rate_idle: u64,
     pub remember_window_position: bool,
     pub remember_window_size: bool,
+    pub resize_by_cells: bool,
     pub scale_factor: f32,
+    pub scale_factor_per_monitor: bool,
     pub show_border: bool,
+    pub sticky_modifiers: bool,
+    pub tabs: bool,
     pub theme: ThemeSettings,
     pub touch_deadzone: f32,
     pub touch_drag_timeout: f32,
     pub window_blurred: bool,
     pub message_area_drag_selection: bool,
+    pub middle_click_paste: bool,
     #[cfg(target_os = "windows")]
     pub corner_preference: CornerPreference,
     #[cfg(target_os = "macos")]
@@ -59,30 +84,52 @@ pub struct WindowSettings {
 impl Default for WindowSettings {
     fn default() -> Self {
         Self {
+            battery_saver: false,
+            buffer_bar: false,
             confirm_quit: true,
+            context_menu: ContextMenuItems::default(),
+            copy_on_select: false,
             cursor_hack: true,
+            disabled_builtin_shortcuts: Shortcuts::default(),
             fullscreen: false,
+            always_on_top: false,
             has_mouse_grid_detection: false,
+            focus_follows_mouse: false,
             hide_mouse_when_typing: false,
             input_ime: true,
             iso_layout: false,
+            key_repeat_delay: 0.0,
+            key_repeat_interval: 0.0,
+            keycast: false,
+            max_grid_size: MaxGridSize::default(),
             normal_opacity: 1.0,
+            passthrough_shortcuts: Shortcuts::default(),
+            pause_when_hidden: true,
+            predictive_echo: false,
             opacity: 1.0,
+            text_opacity: 1.0,
+            background_opacity: 1.0,
             padding_bottom: 0,
             padding_left: 0,
             padding_right: 0,
             padding_top: 0,
+            redraw_coalesce_ms: 0,
             refresh_rate: 60,
             refresh_rate_idle: 5,
             remember_window_position: true,
             remember_window_size: true,
+            resize_by_cells: false,
             scale_factor: 1.0,
+            scale_factor_per_monitor: false,
             show_border: true,
+            sticky_modifiers: false,
+            tabs: false,
             theme: ThemeSettings::Auto,
             touch_deadzone: 6.0,
             touch_drag_timeout: 0.17,
             window_blurred: false,
             message_area_drag_selection: true,
+            middle_click_paste: false,
             #[cfg(target_os = "windows")]
             corner_preference: CornerPreference::Default,
             #[cfg(target_os = "macos")]
@@ -106,6 +153,102 @@ impl Default for WindowSettings {
     }
 }
 
+impl WindowSettings {
+    /// Both the tab bar and the buffer bar are drawn from the externalized tabline
+    pub fn externalize_tabline(&self) -> bool {
+        self.tabs || self.buffer_bar
+    }
+
+    /// Whether the desktop shows through any part of the window
+    pub fn transparent(&self) -> bool {
+        self.opacity.min(self.normal_opacity).min(self.background_opacity) < 1.0
+    }
+}
+
+/// A list of keys in the notation of Neovim, like `<D-Left>`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Shortcuts(pub Vec<String>);
+
+impl Shortcuts {
+    /// Whether the formatted key matches one of the shortcuts. Like in Neovim, the case of the
+    /// modifiers and of special key names doesn't matter.
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.iter().any(|shortcut| {
+            if shortcut.starts_with('<') && key.starts_with('<') {
+                shortcut.eq_ignore_ascii_case(key)
+            } else {
+                shortcut == key
+            }
+        })
+    }
+}
+
+impl ParseFromValue for Shortcuts {
+    fn parse_from_value(&mut self, value: Value) {
+        let Some(items) = value.as_array() else {
+            error_msg!("Shortcut settings expected a list of keys, but received {value:?}");
+            return;
+        };
+
+        let mut shortcuts = Vec::with_capacity(items.len());
+        for item in items {
+            let Some(shortcut) = item.as_str() else {
+                error_msg!(
+                    "Shortcut settings expected keys like \"<D-Left>\", but received {item:?}"
+                );
+                return;
+            };
+            shortcuts.push(shortcut.to_owned());
+        }
+        self.0 = shortcuts;
+    }
+}
+
+impl From<Shortcuts> for Value {
+    fn from(shortcuts: Shortcuts) -> Self {
+        Value::from(shortcuts.0.into_iter().map(Value::from).collect::<Vec<_>>())
+    }
+}
+
+/// The largest grid Neovide asks Neovim for, set as "<columns>x<lines>", so that scripts doing
+/// `:set lines=9999` don't allocate huge grids
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxGridSize(pub GridSize<u32>);
+
+impl Default for MaxGridSize {
+    fn default() -> Self {
+        Self(MAX_GRID_SIZE)
+    }
+}
+
+impl MaxGridSize {
+    /// Clamps the grid size between the minimum grid size and this one
+    pub fn clamp(&self, grid_size: &GridSize<u32>) -> GridSize<u32> {
+        clamped_grid_size(grid_size).min(self.0).max(MIN_GRID_SIZE)
+    }
+}
+
+impl ParseFromValue for MaxGridSize {
+    fn parse_from_value(&mut self, value: Value) {
+        match value.as_str().map(str::parse::<Dimensions>) {
+            Some(Ok(Dimensions { width, height })) => {
+                let (width, height) = (width.min(u32::MAX.into()), height.min(u32::MAX.into()));
+                self.0 = GridSize::new(width as u32, height as u32);
+            }
+            Some(Err(message)) => error_msg!("Setting max_grid_size: {message}"),
+            None => error_msg!(
+                "Setting max_grid_size expected a string like \"300x100\", but received {value:?}"
+            ),
+        }
+    }
+}
+
+impl From<MaxGridSize> for Value {
+    fn from(max_grid_size: MaxGridSize) -> Self {
+        Value::from(format!("{}x{}", max_grid_size.0.width, max_grid_size.0.height))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThemeSettings {
     Auto,
@@ -202,3 +345,50 @@ impl From<CornerPreference> for winit::platform::windows::CornerPreference {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcuts_are_parsed_from_a_list() {
+        let mut shortcuts = Shortcuts::default();
+        shortcuts.parse_from_value(Value::from(vec![Value::from("<D-Left>"), Value::from("a")]));
+        assert_eq!(shortcuts, Shortcuts(vec!["<D-Left>".to_owned(), "a".to_owned()]));
+
+        // Invalid values keep the previous shortcuts
+        shortcuts.parse_from_value(Value::from("<D-Left>"));
+        assert_eq!(shortcuts.0.len(), 2);
+    }
+
+    #[test]
+    fn huge_grids_are_clamped_to_the_max_grid_size() {
+        let mut max_grid_size = MaxGridSize::default();
+        max_grid_size.parse_from_value(Value::from("300x100"));
+        assert_eq!(max_grid_size, MaxGridSize(GridSize::new(300, 100)));
+        assert_eq!(max_grid_size.clamp(&GridSize::new(10000, 10000)), GridSize::new(300, 100));
+        assert_eq!(max_grid_size.clamp(&GridSize::new(120, 10000)), GridSize::new(120, 100));
+        assert_eq!(max_grid_size.clamp(&GridSize::new(1, 1)), MIN_GRID_SIZE);
+
+        // The default only guards against grids that can't be drawn anyway
+        assert_eq!(MaxGridSize::default().clamp(&GridSize::new(10000, 10000)), MAX_GRID_SIZE);
+    }
+
+    #[test]
+    fn invalid_max_grid_sizes_are_ignored() {
+        let mut max_grid_size = MaxGridSize(GridSize::new(300, 100));
+        max_grid_size.parse_from_value(Value::from("300"));
+        max_grid_size.parse_from_value(Value::from("0x100"));
+        max_grid_size.parse_from_value(Value::from(300));
+        assert_eq!(max_grid_size, MaxGridSize(GridSize::new(300, 100)));
+    }
+
+    #[test]
+    fn shortcuts_match_special_keys_ignoring_case() {
+        let shortcuts = Shortcuts(vec!["<d-left>".to_owned(), "a".to_owned()]);
+        assert!(shortcuts.contains("<D-Left>"));
+        assert!(!shortcuts.contains("<D-Right>"));
+        assert!(shortcuts.contains("a"));
+        assert!(!shortcuts.contains("A"));
+    }
+}
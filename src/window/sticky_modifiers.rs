@@ -0,0 +1,182 @@
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StickyState {
+    #[default]
+    Released,
+    /// Applies to the next key press only
+    Latched,
+    /// Applies until the modifier is tapped again
+    Locked,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StickyModifier {
+    Shift,
+    Control,
+    Alt,
+    Super,
+}
+
+const MODIFIERS: [StickyModifier; 4] =
+    [StickyModifier::Shift, StickyModifier::Control, StickyModifier::Alt, StickyModifier::Super];
+
+impl StickyModifier {
+    pub fn from_key(key: &Key) -> Option<Self> {
+        match key {
+            Key::Named(NamedKey::Shift) => Some(Self::Shift),
+            Key::Named(NamedKey::Control) => Some(Self::Control),
+            Key::Named(NamedKey::Alt) => Some(Self::Alt),
+            Key::Named(NamedKey::Super | NamedKey::Meta) => Some(Self::Super),
+            _ => None,
+        }
+    }
+
+    fn state(self) -> ModifiersState {
+        match self {
+            Self::Shift => ModifiersState::SHIFT,
+            Self::Control => ModifiersState::CONTROL,
+            Self::Alt => ModifiersState::ALT,
+            Self::Super => ModifiersState::SUPER,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Shift => "Shift",
+            Self::Control => "Ctrl",
+            Self::Alt => "Alt",
+            Self::Super => "Super",
+        }
+    }
+}
+
+/// Modifiers that stay pressed after they are tapped, for users that can't hold several keys at
+/// once. Tapping a modifier latches it for the next key press, tapping it again locks it, and a
+/// third tap releases it. Holding a modifier while pressing a key works as usual, and doesn't
+/// change the sticky state.
+#[derive(Debug, Default)]
+pub struct StickyModifiers {
+    states: [StickyState; 4],
+    // The modifier that is down, while no other key has been pressed since
+    tapping: Option<StickyModifier>,
+}
+
+impl StickyModifiers {
+    pub fn modifier_pressed(&mut self, modifier: StickyModifier) {
+        self.tapping = Some(modifier);
+    }
+
+    pub fn modifier_released(&mut self, modifier: StickyModifier) {
+        if self.tapping.take() != Some(modifier) {
+            return;
+        }
+        let state = &mut self.states[modifier as usize];
+        *state = match state {
+            StickyState::Released => StickyState::Latched,
+            StickyState::Latched => StickyState::Locked,
+            StickyState::Locked => StickyState::Released,
+        };
+    }
+
+    /// A key that is not a modifier was pressed, which uses up the latched modifiers
+    pub fn key_pressed(&mut self) {
+        self.tapping = None;
+        for state in &mut self.states {
+            if *state == StickyState::Latched {
+                *state = StickyState::Released;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn state_of(&self, modifier: StickyModifier) -> StickyState {
+        self.states[modifier as usize]
+    }
+
+    /// The modifiers that apply to the next key press
+    pub fn state(&self) -> ModifiersState {
+        MODIFIERS
+            .into_iter()
+            .filter(|modifier| self.state_of(*modifier) != StickyState::Released)
+            .fold(ModifiersState::empty(), |state, modifier| state | modifier.state())
+    }
+
+    /// Describes the active modifiers for the overlay, like "Ctrl + Shift (locked)"
+    pub fn label(&self) -> Option<String> {
+        let names = MODIFIERS
+            .into_iter()
+            .filter_map(|modifier| match self.state_of(modifier) {
+                StickyState::Released => None,
+                StickyState::Latched => Some(modifier.name().to_owned()),
+                StickyState::Locked => Some(format!("{} (locked)", modifier.name())),
+            })
+            .collect::<Vec<_>>();
+        (!names.is_empty()).then(|| names.join(" + "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tap(sticky: &mut StickyModifiers, modifier: StickyModifier) {
+        sticky.modifier_pressed(modifier);
+        sticky.modifier_released(modifier);
+    }
+
+    #[test]
+    fn a_tap_latches_the_modifier_for_the_next_key() {
+        let mut sticky = StickyModifiers::default();
+        tap(&mut sticky, StickyModifier::Control);
+        assert_eq!(sticky.state_of(StickyModifier::Control), StickyState::Latched);
+        assert_eq!(sticky.state(), ModifiersState::CONTROL);
+        assert_eq!(sticky.label().as_deref(), Some("Ctrl"));
+
+        sticky.key_pressed();
+        assert_eq!(sticky.state_of(StickyModifier::Control), StickyState::Released);
+        assert_eq!(sticky.state(), ModifiersState::empty());
+        assert_eq!(sticky.label(), None);
+    }
+
+    #[test]
+    fn a_second_tap_locks_and_a_third_releases() {
+        let mut sticky = StickyModifiers::default();
+        tap(&mut sticky, StickyModifier::Shift);
+        tap(&mut sticky, StickyModifier::Shift);
+        assert_eq!(sticky.state_of(StickyModifier::Shift), StickyState::Locked);
+
+        sticky.key_pressed();
+        sticky.key_pressed();
+        assert_eq!(sticky.state(), ModifiersState::SHIFT);
+        assert_eq!(sticky.label().as_deref(), Some("Shift (locked)"));
+
+        tap(&mut sticky, StickyModifier::Shift);
+        assert_eq!(sticky.state_of(StickyModifier::Shift), StickyState::Released);
+    }
+
+    #[test]
+    fn modifiers_held_for_a_chord_are_not_sticky() {
+        let mut sticky = StickyModifiers::default();
+        sticky.modifier_pressed(StickyModifier::Control);
+        sticky.key_pressed();
+        sticky.modifier_released(StickyModifier::Control);
+        assert_eq!(sticky.state(), ModifiersState::empty());
+    }
+
+    #[test]
+    fn modifiers_combine_and_clear() {
+        let mut sticky = StickyModifiers::default();
+        tap(&mut sticky, StickyModifier::Control);
+        tap(&mut sticky, StickyModifier::Alt);
+        tap(&mut sticky, StickyModifier::Alt);
+        assert_eq!(sticky.state(), ModifiersState::CONTROL | ModifiersState::ALT);
+        assert_eq!(sticky.label().as_deref(), Some("Ctrl + Alt (locked)"));
+
+        sticky.clear();
+        assert_eq!(sticky.state(), ModifiersState::empty());
+    }
+}
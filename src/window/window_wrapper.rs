@@ -5,6 +5,7 @@ use std::{
     path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use log::trace;
@@ -14,7 +15,7 @@ use winit::{
     dpi,
     event::{Ime, WindowEvent},
     event_loop::{ActiveEventLoop, EventLoopProxy},
-    window::{Cursor, Fullscreen, Theme, Window, WindowId},
+    window::{Cursor, Fullscreen, Theme, Window, WindowId, WindowLevel},
 };
 
 use approx::AbsDiffEq;
@@ -22,8 +23,9 @@ use approx::AbsDiffEq;
 #[cfg(target_os = "windows")]
 use super::settings::CornerPreference;
 use super::{
-    EventPayload, EventTarget, KeyboardManager, MessageSelectionEvent, MouseManager, OverlayEvent,
-    RouteId, UserEvent, WindowCommand, WindowSettings, WindowSettingsChanged, WindowSize,
+    AccessibilityAdapter, EventPayload, EventTarget, KeyboardManager, MessageSelectionEvent,
+    MouseManager, OverlayEvent, RouteId, UserEvent, WindowCommand, WindowSettings,
+    WindowSettingsChanged, WindowSize,
 };
 
 #[cfg(target_os = "macos")]
@@ -44,21 +46,24 @@ use {
 use crate::{
     CmdLineSettings,
     bridge::{
-        NeovimHandler, NeovimRuntime, OpenArgs, OpenMode, ParallelCommand, RestartDetails,
-        SerialCommand, StartupMessage, send_ui, set_active_route_handler, unregister_route_handler,
+        CursorScreenPosition, NeovimExit, NeovimHandler, NeovimRuntime, OpenArgs, OpenMode,
+        ParallelCommand, RestartDetails, SerialCommand, StartupMessage, send_ui,
+        set_active_route_handler, set_cursor_screen_position, unregister_route_handler,
     },
     clipboard::ClipboardHandle,
     cmd_line::{GeometryArgs, MouseCursorIcon},
     profiling::{tracy_frame, tracy_gpu_collect, tracy_gpu_zone, tracy_plot, tracy_zone},
     renderer::{
-        DrawCommand, DrawCommandResult, MessageSelection, Renderer, RendererSettingsChanged,
-        SkiaRenderer, StartupMessageFlush, VSync, create_skia_renderer,
+        DrawCommand, DrawCommandResult, MessageSelection, Renderer, RendererInfo,
+        RendererSettingsChanged, SkiaRenderer, StartupMessageFlush, VSync,
+        command_palette::CommandPaletteResponse, context_menu::ContextMenuResponse,
+        crash_overlay::CrashOverlayResponse, create_skia_renderer,
     },
     running_tracker::RunningTracker,
     settings::{
-        Config, DEFAULT_GRID_SIZE, MIN_GRID_SIZE, RendererHotReloadConfigs, Settings,
-        SettingsChanged, WindowHotReloadConfigs, clamped_grid_size, font::FontSettings,
-        load_last_window_settings,
+        Config, DEFAULT_GRID_SIZE, MIN_GRID_SIZE, MonitorScaleFactors, RendererHotReloadConfigs,
+        Settings, SettingsChanged, WindowHotReloadConfigs, clamped_grid_size, font::FontSettings,
+        load_last_window_settings, load_monitor_scale_factors,
     },
     units::{GridRect, GridScale, GridSize, PixelPos, PixelRect, PixelSize},
     window::{
@@ -101,6 +106,15 @@ fn round_or_op<Op: FnOnce(f32) -> f32>(v: f32, op: Op) -> f32 {
     if v.abs_diff_eq(&rounded, GRID_TOLERANCE) { rounded } else { op(v) }
 }
 
+// Winit doesn't tell when an interactive resize ends, it's over when the size stays the same for
+// this long
+const SNAP_TO_CELLS_DELAY: Duration = Duration::from_millis(250);
+
+/// With `neovide_resize_by_cells`, the window is resized by whole cells
+fn resize_increments(grid_scale: GridScale) -> PhysicalSize<f32> {
+    PhysicalSize::new(grid_scale.width(), grid_scale.height())
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct WindowPadding {
     pub top: u32,
@@ -151,6 +165,7 @@ pub struct RouteWindow {
     pub neovim_handler: NeovimHandler,
     pub mouse_manager: Rc<RefCell<Box<MouseManager>>>,
     pub renderer: Rc<RefCell<Box<Renderer>>>,
+    pub accessibility: AccessibilityAdapter,
     #[cfg(target_os = "macos")]
     pub macos_feature: Option<Rc<RefCell<Box<MacosWindowFeature>>>>,
     pub title: String,
@@ -202,6 +217,12 @@ struct RouteState {
     ime_area: (dpi::PhysicalPosition<u32>, dpi::PhysicalSize<u32>),
     inferred_theme: Option<Theme>,
     vsync: Option<VSync>,
+    vsync_enabled: bool,
+    always_on_top: bool,
+    // The key of the monitor the window is on, for `neovide_scale_factor_per_monitor`
+    monitor: Option<String>,
+    // When the window is fit to whole cells, after it stopped being resized
+    snap_to_cells_at: Option<Instant>,
 }
 
 impl RouteState {
@@ -218,6 +239,54 @@ impl RouteState {
             ime_area: Default::default(),
             inferred_theme: None,
             vsync: None,
+            vsync_enabled: false,
+            always_on_top: false,
+            monitor: None,
+            snap_to_cells_at: None,
+        }
+    }
+
+    /// Puts off fitting the window to whole cells until it stops being resized, asking for
+    /// another size in the middle of a resize fights the window manager
+    fn defer_snap_to_cells(&mut self, now: Instant) {
+        self.snap_to_cells_at = Some(now + SNAP_TO_CELLS_DELAY);
+    }
+
+    /// Returns true once, when the window stopped being resized and should be fit to whole cells
+    fn take_due_snap_to_cells(&mut self, now: Instant) -> bool {
+        let due = self.snap_to_cells_at.is_some_and(|at| at <= now);
+        if due {
+            self.snap_to_cells_at = None;
+        }
+        due
+    }
+
+    /// Flips whether the window stays above the others, returning the new state
+    fn toggle_always_on_top(&mut self) -> bool {
+        self.always_on_top = !self.always_on_top;
+        self.always_on_top
+    }
+}
+
+fn window_level(always_on_top: bool) -> WindowLevel {
+    if always_on_top { WindowLevel::AlwaysOnTop } else { WindowLevel::Normal }
+}
+
+/// Wayland doesn't let clients pick their stacking order, winit ignores the level there
+fn apply_window_level(window: &Window, always_on_top: bool) {
+    if is_wayland_window(window) {
+        log::warn!("Keeping the window on top is not supported on Wayland");
+        return;
+    }
+    window.set_window_level(window_level(always_on_top));
+}
+
+fn is_wayland_window(window: &Window) -> bool {
+    match window.window_handle() {
+        Ok(handle) => matches!(handle.as_raw(), RawWindowHandle::Wayland(_)),
+        Err(err) => {
+            log::warn!("Failed to read window handle: {err}");
+            false
         }
     }
 }
@@ -242,7 +311,8 @@ struct RouteCore {
 
 #[derive(Clone)]
 struct RestartRequest {
-    details: RestartDetails,
+    // Without details, a new Neovim is started for the window like at startup
+    details: Option<RestartDetails>,
     grid_size: GridSize<u32>,
 }
 
@@ -257,6 +327,7 @@ pub struct WinitWindowWrapper {
     pending_restart: FxHashMap<RouteId, RestartRequest>,
     keyboard_manager: KeyboardManager,
     ui_state: UIState,
+    pub monitor_scale_factors: MonitorScaleFactors,
 
     settings: Arc<Settings>,
     clipboard: ClipboardHandle,
@@ -297,6 +368,7 @@ impl WinitWindowWrapper {
             pending_restart: FxHashMap::default(),
             keyboard_manager: KeyboardManager::new(settings.clone()),
             ui_state: UIState::Initing,
+            monitor_scale_factors: load_monitor_scale_factors(),
             settings: settings.clone(),
             clipboard: clipboard_handle,
             startup_error,
@@ -418,6 +490,32 @@ impl WinitWindowWrapper {
         }
     }
 
+    pub fn toggle_vsync(&mut self, target: EventTarget, proxy: &EventLoopProxy<EventPayload>) {
+        let Some(window_id) = self.resolve_target_window_id(target) else {
+            return;
+        };
+        let Some(route) = self.routes.get_mut(&window_id) else {
+            return;
+        };
+
+        let vsync_enabled = !route.state.vsync_enabled;
+        let mut skia_renderer = route.window.skia_renderer.borrow_mut();
+        skia_renderer.set_vsync(vsync_enabled);
+        // Drop the old vsync first, some of them own threads or OS callbacks tied to the window
+        route.state.vsync = None;
+        route.state.vsync =
+            Some(VSync::new(vsync_enabled, &**skia_renderer, proxy.clone(), self.settings.clone()));
+        route.state.vsync_enabled = vsync_enabled;
+        log::info!("VSync {}", if vsync_enabled { "enabled" } else { "disabled" });
+    }
+
+    pub fn is_window_minimized(&self, window_id: WindowId) -> bool {
+        self.routes
+            .get(&window_id)
+            .map(|route| route.window.winit_window.is_minimized() == Some(true))
+            .unwrap_or(false)
+    }
+
     pub fn set_fullscreen(&mut self, window_id: WindowId, fullscreen: bool) {
         let Some(route) = self.routes.get(&window_id) else {
             return;
@@ -431,6 +529,23 @@ impl WinitWindowWrapper {
         }
     }
 
+    fn set_always_on_top(&mut self, window_id: WindowId, always_on_top: bool) {
+        let Some(route) = self.routes.get_mut(&window_id) else {
+            return;
+        };
+        route.state.always_on_top = always_on_top;
+        apply_window_level(&route.window.winit_window, always_on_top);
+    }
+
+    fn toggle_always_on_top(&mut self, window_id: WindowId) {
+        let Some(route) = self.routes.get_mut(&window_id) else {
+            return;
+        };
+        let always_on_top = route.state.toggle_always_on_top();
+        apply_window_level(&route.window.winit_window, always_on_top);
+        log::info!("Always on top {}", if always_on_top { "enabled" } else { "disabled" });
+    }
+
     #[cfg(target_os = "windows")]
     fn set_corner_preference(&self, window_id: WindowId, option: CornerPreference) {
         let Some(route) = self.routes.get(&window_id) else {
@@ -535,7 +650,7 @@ impl WinitWindowWrapper {
             WindowCommand::SetMouseEnabled(mouse_enabled) => {
                 if let Some(route) = self.routes.get(&target_window_id) {
                     let mut mouse_manager = route.window.mouse_manager.borrow_mut();
-                    mouse_manager.enabled = mouse_enabled;
+                    mouse_manager.set_enabled(mouse_enabled);
                 }
             }
             WindowCommand::ListAvailableFonts => self.send_font_names(target_window_id),
@@ -620,6 +735,16 @@ impl WinitWindowWrapper {
                     route.state.is_minimized = true;
                 }
             }
+            // Handled by the application, since recreating the vsync needs the event loop proxy
+            WindowCommand::ToggleVSync => {}
+            WindowCommand::ToggleAlwaysOnTop => self.toggle_always_on_top(target_window_id),
+            WindowCommand::ShowInfo => self.send_renderer_info(target_window_id),
+            WindowCommand::ShowCommandPalette(commands) => {
+                if let Some(route) = self.routes.get(&target_window_id) {
+                    route.window.renderer.borrow_mut().command_palette.open(commands);
+                    route.window.winit_window.request_redraw();
+                }
+            }
             WindowCommand::ThemeChanged(new_theme) => {
                 if let Some(route) = self.routes.get_mut(&target_window_id)
                     && route.state.inferred_theme != new_theme
@@ -712,6 +837,11 @@ impl WinitWindowWrapper {
                     self.set_fullscreen(*window_id, fullscreen);
                 }
             }
+            WindowSettingsChanged::AlwaysOnTop(always_on_top) => {
+                for window_id in window_ids.iter() {
+                    self.set_always_on_top(*window_id, always_on_top);
+                }
+            }
             WindowSettingsChanged::InputIme(ime_enabled) => {
                 for window_id in window_ids.iter() {
                     self.set_ime(*window_id, ime_enabled);
@@ -725,10 +855,30 @@ impl WinitWindowWrapper {
                         route.state.font_changed_last_frame = true;
                     }
                 }
+                // Only the window the change came from, or the focused one, tells which monitor
+                // the factor was picked for, the others can be on any monitor
+                let per_monitor = self.settings.get::<WindowSettings>().scale_factor_per_monitor;
+                let monitor = self
+                    .resolve_target_window_id(target)
+                    .and_then(|window_id| self.routes.get(&window_id))
+                    .and_then(|route| route.state.monitor.clone());
+                if let Some(monitor) = monitor.filter(|_| per_monitor) {
+                    self.monitor_scale_factors.set(monitor, user_scale_factor);
+                }
+            }
+            WindowSettingsChanged::ScaleFactorPerMonitor(true) => {
+                for window_id in window_ids.iter() {
+                    self.update_monitor(*window_id);
+                }
+            }
+            WindowSettingsChanged::ResizeByCells(..) => {
+                for window_id in window_ids.iter() {
+                    self.update_resize_increments(*window_id);
+                    self.snap_window_to_cells(*window_id);
+                }
             }
             WindowSettingsChanged::WindowBlurred(blur) => {
-                let WindowSettings { opacity, .. } = self.settings.get::<WindowSettings>();
-                let transparent = opacity < 1.0;
+                let transparent = self.settings.get::<WindowSettings>().transparent();
                 for window_id in window_ids.iter() {
                     if let Some(route) = self.routes.get(window_id) {
                         let window = route.window.winit_window.clone();
@@ -744,7 +894,9 @@ impl WinitWindowWrapper {
                     }
                 }
             }
-            WindowSettingsChanged::Opacity(..) | WindowSettingsChanged::NormalOpacity(..) => {
+            WindowSettingsChanged::Opacity(..)
+            | WindowSettingsChanged::NormalOpacity(..)
+            | WindowSettingsChanged::BackgroundOpacity(..) => {
                 for window_id in window_ids.iter() {
                     if let Some(route) = self.routes.get(window_id) {
                         let mut renderer = route.window.renderer.borrow_mut();
@@ -752,11 +904,35 @@ impl WinitWindowWrapper {
                     }
                 }
             }
+            WindowSettingsChanged::TextOpacity(..) => {
+                for window_id in window_ids.iter() {
+                    if let Some(route) = self.routes.get_mut(window_id) {
+                        let mut renderer = route.window.renderer.borrow_mut();
+                        renderer.grid_renderer.refresh_text_antialiasing();
+                        route.state.font_changed_last_frame = true;
+                    }
+                }
+            }
+            WindowSettingsChanged::Keycast(keycast) => {
+                self.keyboard_manager.set_keycast(keycast);
+            }
             WindowSettingsChanged::Theme(..) => {
                 for window_id in window_ids.iter() {
                     self.apply_theme_for_window(*window_id);
                 }
             }
+            WindowSettingsChanged::Tabs(..) | WindowSettingsChanged::BufferBar(..) => {
+                let externalize = self.settings.get::<WindowSettings>().externalize_tabline();
+                for window_id in window_ids.iter() {
+                    if let Some(route) = self.routes.get(window_id) {
+                        send_ui(
+                            ParallelCommand::ExternalizeTabline(externalize),
+                            &route.window.neovim_handler,
+                        );
+                        route.window.winit_window.request_redraw();
+                    }
+                }
+            }
             #[cfg(target_os = "windows")]
             WindowSettingsChanged::TitleBackgroundColor(color) => {
                 for window_id in window_ids.iter() {
@@ -827,6 +1003,44 @@ impl WinitWindowWrapper {
                     }
                 }
             }
+            RendererSettingsChanged::TextAntialiasing(text_antialiasing) => {
+                for window_id in window_ids.iter() {
+                    if let Some(route) = self.routes.get_mut(window_id) {
+                        let mut renderer = route.window.renderer.borrow_mut();
+                        renderer.grid_renderer.update_text_antialiasing(text_antialiasing);
+                        route.state.font_changed_last_frame = true;
+                    }
+                }
+            }
+            RendererSettingsChanged::FontHinting(font_hinting) => {
+                for window_id in window_ids.iter() {
+                    if let Some(route) = self.routes.get_mut(window_id) {
+                        let mut renderer = route.window.renderer.borrow_mut();
+                        renderer.grid_renderer.update_font_hinting(font_hinting);
+                        route.state.font_changed_last_frame = true;
+                    }
+                }
+            }
+            RendererSettingsChanged::FallbackFonts(fallback_fonts) => {
+                for window_id in window_ids.iter() {
+                    if let Some(route) = self.routes.get(window_id) {
+                        let mut renderer = route.window.renderer.borrow_mut();
+                        renderer.grid_renderer.update_fallback_fonts(&fallback_fonts);
+                        renderer.prepare_lines(true);
+                    }
+                }
+            }
+            RendererSettingsChanged::ProportionalWinbar(..)
+            | RendererSettingsChanged::HighlightFonts(..)
+            | RendererSettingsChanged::CrispListchars(..)
+            | RendererSettingsChanged::DiffAccent(..)
+            | RendererSettingsChanged::Listchars(..) => {
+                for window_id in window_ids.iter() {
+                    if let Some(route) = self.routes.get(window_id) {
+                        route.window.renderer.borrow_mut().prepare_lines(true);
+                    }
+                }
+            }
             _ => {}
         }
     }
@@ -867,6 +1081,19 @@ impl WinitWindowWrapper {
         send_ui(ParallelCommand::DisplayAvailableFonts(font_names), neovim_handler);
     }
 
+    pub fn send_renderer_info(&self, window_id: WindowId) {
+        let Some(route) = self.routes.get(&window_id) else {
+            return;
+        };
+        let info = RendererInfo::new(
+            route.window.skia_renderer.borrow().graphics_info(),
+            route.window.winit_window.scale_factor(),
+            route.state.vsync_enabled,
+            self.settings.get::<CmdLineSettings>().srgb,
+        );
+        send_ui(ParallelCommand::ShowInfo { lines: info.lines() }, &route.window.neovim_handler);
+    }
+
     pub fn handle_quit(&mut self, window_id: WindowId) {
         let Some(route) = self.routes.get(&window_id) else {
             return;
@@ -921,6 +1148,10 @@ impl WinitWindowWrapper {
         {
             if native_tab_bar_enabled()
                 && let WindowEvent::KeyboardInput { event: key_event, .. } = event
+                && !self
+                    .keyboard_manager
+                    .format_key(key_event)
+                    .is_some_and(|key| self.keyboard_manager.forwards_builtin_shortcut(&key))
             {
                 let modifiers = self.keyboard_manager.current_modifiers();
                 if let Some(action) = self.tab_navigation_hotkeys.action_for(key_event, &modifiers)
@@ -938,6 +1169,32 @@ impl WinitWindowWrapper {
             }
         }
 
+        // Like the context menu, the open command palette takes all the keyboard input
+        let command_palette_response =
+            route.window.renderer.borrow_mut().command_palette.handle_event(event);
+        match command_palette_response {
+            CommandPaletteResponse::Ignored => {}
+            CommandPaletteResponse::Consumed => {
+                return Some(OverlayEvent::CommandPaletteChanged);
+            }
+            CommandPaletteResponse::Dispatch(command) => {
+                send_ui(SerialCommand::ExCommand(command), neovim_handler);
+                return Some(OverlayEvent::CommandPaletteChanged);
+            }
+        }
+
+        // The open context menu takes all the input, nothing reaches Neovim until it's closed
+        let context_menu_response =
+            route.window.renderer.borrow_mut().context_menu.handle_event(event);
+        match context_menu_response {
+            ContextMenuResponse::Ignored => {}
+            ContextMenuResponse::Consumed => return Some(OverlayEvent::ContextMenuChanged),
+            ContextMenuResponse::Dispatch(command) => {
+                send_ui(SerialCommand::ExCommand(command), neovim_handler);
+                return Some(OverlayEvent::ContextMenuChanged);
+            }
+        }
+
         let mouse_result = {
             let mut mouse_manager = route.window.mouse_manager.borrow_mut();
             let window = route.window.winit_window.clone();
@@ -962,6 +1219,14 @@ impl WinitWindowWrapper {
         {
             let mut renderer = route.window.renderer.borrow_mut();
             renderer.handle_event(event);
+            if let Some((window_id, rows)) = mouse_result.overscan {
+                renderer.overscan(window_id, rows);
+            }
+            renderer.sticky_modifiers = self.keyboard_manager.sticky_modifiers_label();
+            let now = Instant::now();
+            for key in self.keyboard_manager.take_typed_keys() {
+                renderer.keycast.push(key, now);
+            }
         }
 
         Some(mouse_result.overlay_event)
@@ -973,14 +1238,16 @@ impl WinitWindowWrapper {
             return false;
         };
 
-        // Message selection via mouse drag is a *client* overlay rendered by Neovide, not a
-        // Neovim draw command. It can change on mouse move/release without new draw commands,
-        // so force a redraw whenever the message selection state changes.
-        let message_selection_needs_render = match overlay_event {
+        // Message selection via mouse drag and the context menu are *client* overlays rendered by
+        // Neovide, not Neovim draw commands. They can change on mouse move/release without new
+        // draw commands, so force a redraw whenever their state changes.
+        let overlay_needs_render = match overlay_event {
             OverlayEvent::Unchanged => false,
             OverlayEvent::MessageSelection(action) => {
                 self.apply_message_selection_event(window_id, action)
             }
+            OverlayEvent::OpenContextMenu(position) => self.open_context_menu(window_id, position),
+            OverlayEvent::ContextMenuChanged | OverlayEvent::CommandPaletteChanged => true,
         };
 
         let mut should_render = true;
@@ -992,6 +1259,7 @@ impl WinitWindowWrapper {
             let Some(route) = self.routes.get_mut(&window_id) else {
                 return false;
             };
+            route.window.accessibility.process_event(&route.window.winit_window, &event);
             let neovim_handler = &route.window.neovim_handler;
 
             match event {
@@ -1002,6 +1270,7 @@ impl WinitWindowWrapper {
                 WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
                     tracy_zone!("ScaleFactorChanged");
                     self.handle_scale_factor_update(window_id, scale_factor);
+                    self.update_monitor(window_id);
                 }
                 WindowEvent::Resized { .. } => {
                     let mut skia_renderer = route.window.skia_renderer.borrow_mut();
@@ -1027,7 +1296,7 @@ impl WinitWindowWrapper {
                         }
                     };
                     send_ui(
-                        ParallelCommand::FileDrop { path: file_path, tabs: None },
+                        ParallelCommand::FileDrop { path: file_path, tabs: None, cursor: None },
                         neovim_handler,
                     );
                 }
@@ -1041,6 +1310,7 @@ impl WinitWindowWrapper {
                     if let Some(vsync) = route.state.vsync.as_mut() {
                         vsync.update(&window);
                     }
+                    self.update_monitor(window_id);
                 }
                 #[cfg(target_os = "macos")]
                 WindowEvent::TouchpadPressure { stage, .. } => {
@@ -1077,7 +1347,7 @@ impl WinitWindowWrapper {
             self.sync_native_tabs_resize(window_id);
         }
 
-        should_render |= message_selection_needs_render;
+        should_render |= overlay_needs_render;
 
         if let Some(focus) = pending_focus_event {
             #[cfg(target_os = "macos")]
@@ -1176,6 +1446,15 @@ impl WinitWindowWrapper {
         }
     }
 
+    fn open_context_menu(&mut self, window_id: WindowId, position: PixelPos<f32>) -> bool {
+        let Some(route) = self.routes.get(&window_id) else {
+            return false;
+        };
+        let items = self.settings.get::<WindowSettings>().context_menu;
+        route.window.renderer.borrow_mut().context_menu.open(position, items);
+        true
+    }
+
     fn copy_message_selection(&self, window_id: WindowId, selection: MessageSelection) {
         let Some(route) = self.routes.get(&window_id) else {
             return;
@@ -1494,6 +1773,13 @@ impl WinitWindowWrapper {
         );
         tracy_plot!("animate_frame", res as u8 as f64);
         renderer.prepare_lines(false);
+        set_cursor_screen_position(
+            route.route_id,
+            CursorScreenPosition {
+                physical: renderer.get_cursor_bounds(),
+                scale_factor: route.window.winit_window.scale_factor(),
+            },
+        );
         #[allow(clippy::let_and_return)]
         res
     }
@@ -1567,10 +1853,9 @@ impl WinitWindowWrapper {
 
         let WindowSettings {
             input_ime,
-            opacity,
-            normal_opacity,
             window_blurred,
             fullscreen,
+            always_on_top,
             #[cfg(target_os = "macos")]
             input_macos_option_key_is_meta,
             #[cfg(target_os = "macos")]
@@ -1711,13 +1996,9 @@ impl WinitWindowWrapper {
         }
         let logged_size = initial_pixel_size.unwrap_or_default();
         log::info!("Showing window size: {logged_size:#?}, maximized: {maximized}");
-        let is_wayland = match window.window_handle() {
-            Ok(handle) => matches!(handle.as_raw(), RawWindowHandle::Wayland(_)),
-            Err(err) => {
-                log::warn!("Failed to read window handle: {err}");
-                false
-            }
-        };
+        // The accessibility tree has to exist before the window is shown for the first time
+        let accessibility = AccessibilityAdapter::new(&window);
+        let is_wayland = is_wayland_window(&window);
         // On Wayland we can show the window now, since internally it's only shown after the first rendering
         // On the other platforms the window is shown after rendering to avoid flickering
         if is_wayland {
@@ -1755,7 +2036,7 @@ impl WinitWindowWrapper {
             renderer.borrow().grid_renderer.grid_scale
         );
 
-        window.set_blur(window_blurred && opacity.min(normal_opacity) < 1.0);
+        window.set_blur(window_blurred && self.settings.get::<WindowSettings>().transparent());
 
         #[cfg(target_os = "windows")]
         if window_blurred {
@@ -1767,6 +2048,10 @@ impl WinitWindowWrapper {
             window.set_fullscreen(Some(Fullscreen::Borderless(handle)));
         }
 
+        if always_on_top {
+            apply_window_level(&window, true);
+        }
+
         #[cfg(target_os = "windows")]
         {
             if let Some(winit_color) = Self::parse_winit_color(&title_background_color) {
@@ -1809,16 +2094,19 @@ impl WinitWindowWrapper {
 
         let mouse_manager = MouseManager::new(self.settings.clone());
         let mut mouse_manager = mouse_manager;
-        mouse_manager.enabled = route_mouse_enabled;
+        mouse_manager.set_enabled(route_mouse_enabled);
         let mut state = RouteState::new();
         state.saved_inner_size = saved_inner_size;
         state.vsync = Some(vsync);
+        state.vsync_enabled = vsync_enabled;
+        state.always_on_top = always_on_top;
         state.inferred_theme = route_inferred_theme;
         state.font_changed_last_frame = route_font_changed_last_frame;
         let route = Route {
             route_id,
             window: RouteWindow {
                 renderer,
+                accessibility,
                 skia_renderer: skia_renderer.clone(),
                 winit_window: window.clone(),
                 neovim_handler,
@@ -1998,7 +2286,66 @@ impl WinitWindowWrapper {
             grid_size
         };
 
-        self.pending_restart.insert(route_id, RestartRequest { details, grid_size });
+        self.pending_restart.insert(route_id, RestartRequest { details: Some(details), grid_size });
+    }
+
+    /// Shows why Neovim crashed on top of the last frame, instead of closing the window. Returns
+    /// false when the route has no window, or is already being restarted.
+    pub fn show_neovim_crash(&mut self, route_id: RouteId, exit: &NeovimExit) -> bool {
+        if self.pending_restart.contains_key(&route_id) {
+            return false;
+        }
+        let Some(route) =
+            self.window_id_for_route(route_id).and_then(|window_id| self.routes.get(&window_id))
+        else {
+            return false;
+        };
+        route.window.renderer.borrow_mut().crash_overlay.show(exit);
+        true
+    }
+
+    /// Lets the crash overlay of the window take the event. Returns true when it did, in which
+    /// case the window needs to be redrawn.
+    pub fn handle_crash_overlay_event(
+        &mut self,
+        window_id: WindowId,
+        event: &WindowEvent,
+        proxy: &EventLoopProxy<EventPayload>,
+    ) -> bool {
+        let Some(route_id) = self.route_id_for_window(window_id) else {
+            return false;
+        };
+        let Some(route) = self.routes.get_mut(&window_id) else {
+            return false;
+        };
+        let response = route.window.renderer.borrow_mut().crash_overlay.handle_event(event);
+        match response {
+            CrashOverlayResponse::Ignored => return false,
+            CrashOverlayResponse::Consumed => {}
+            CrashOverlayResponse::Restart => {
+                let grid_size = route.window.renderer.borrow().get_grid_size();
+                route.window.last_synced_grid_size = None;
+                self.clear_renderer(window_id);
+                let restart = RestartRequest { details: None, grid_size };
+                if self.restart_neovim_route(route_id, restart, proxy).is_err() {
+                    proxy
+                        .send_event(EventPayload::for_route(
+                            UserEvent::NeovimExited(NeovimExit::Quit),
+                            route_id,
+                        ))
+                        .ok();
+                }
+            }
+            CrashOverlayResponse::Quit => {
+                proxy
+                    .send_event(EventPayload::for_route(
+                        UserEvent::NeovimExited(NeovimExit::Quit),
+                        route_id,
+                    ))
+                    .ok();
+            }
+        }
+        true
     }
 
     fn restart_neovim_route(
@@ -2252,16 +2599,23 @@ impl WinitWindowWrapper {
         }
     }
 
-    #[cfg_attr(not(target_os = "macos"), allow(unused_variables))]
     fn calculate_window_padding(&self, window_id: WindowId) -> WindowPadding {
         let window_settings = self.settings.get::<WindowSettings>();
 
+        // The tab and buffer bars sit between the padding and the grid
+        let bars_height = self.routes.get(&window_id).map_or(0, |route| {
+            let renderer = route.window.renderer.borrow();
+            let grid_scale = renderer.grid_renderer.grid_scale;
+            renderer.tab_bar.height(window_settings.tabs, grid_scale)
+                + renderer.buffer_bar.height(window_settings.buffer_bar, grid_scale)
+        });
+
         #[cfg(not(target_os = "macos"))]
-        let window_padding_top = window_settings.padding_top;
+        let window_padding_top = window_settings.padding_top + bars_height;
 
         #[cfg(target_os = "macos")]
         let window_padding_top = {
-            let mut padding_top = window_settings.padding_top;
+            let mut padding_top = window_settings.padding_top + bars_height;
             if let Some(macos_feature) = self.macos_feature_for_window(window_id) {
                 padding_top += macos_feature.borrow().extra_titlebar_height_in_pixels();
             }
@@ -2318,6 +2672,20 @@ impl WinitWindowWrapper {
         position
     }
 
+    /// Repeats the key held in the focused window, and returns when the next repeat is due
+    pub fn handle_key_repeat(&mut self) -> Option<Instant> {
+        let window_id = self.get_focused_route()?;
+        let route = self.routes.get(&window_id)?;
+        let now = Instant::now();
+        let next_repeat =
+            self.keyboard_manager.handle_key_repeat(now, &route.window.neovim_handler);
+        let mut renderer = route.window.renderer.borrow_mut();
+        for key in self.keyboard_manager.take_typed_keys() {
+            renderer.keycast.push(key, now);
+        }
+        next_repeat
+    }
+
     pub fn get_focused_route(&self) -> Option<WindowId> {
         if let Some(id) = self.routes.iter().find_map(|(key, val)| {
             if (!val.window.winit_window.has_focus() && self.routes.len() == 1)
@@ -2371,6 +2739,16 @@ impl WinitWindowWrapper {
         true
     }
 
+    #[cfg(all(unix, not(target_os = "macos")))]
+    pub fn activate_and_focus_window(&self, window_id: WindowId) -> bool {
+        let Some(route) = self.routes.get(&window_id) else {
+            return false;
+        };
+
+        route.window.winit_window.focus_window();
+        true
+    }
+
     pub fn window_id_for_route(&self, route_id: RouteId) -> Option<WindowId> {
         self.routes
             .iter()
@@ -2536,8 +2914,10 @@ impl WinitWindowWrapper {
                 if let Some(route) = self.routes.get_mut(&window_id) {
                     route.state.window_padding = window_padding;
                     route.state.saved_inner_size = new_window_size;
+                    route.state.defer_snap_to_cells(Instant::now());
                 }
                 self.update_grid_size_from_window(window_id);
+                self.update_resize_increments(window_id);
                 if let Some(route) = self.routes.get_mut(&window_id) {
                     route.window.last_applied_window_size = route.state.saved_inner_size;
                 }
@@ -2547,9 +2927,22 @@ impl WinitWindowWrapper {
 
         self.update_ime_position(window_id, false);
 
-        if let Some(route) = self.routes.get(&window_id) {
+        let snap_to_cells = self
+            .routes
+            .get_mut(&window_id)
+            .is_some_and(|route| route.state.take_due_snap_to_cells(Instant::now()));
+        if snap_to_cells {
+            self.snap_window_to_cells(window_id);
+        } else if let Some(at) =
+            self.routes.get(&window_id).and_then(|route| route.state.snap_to_cells_at)
+        {
+            should_render.update(ShouldRender::Deadline(at));
+        }
+
+        if let Some(route) = self.routes.get_mut(&window_id) {
             let mut renderer = route.window.renderer.borrow_mut();
             should_render.update(renderer.prepare_frame());
+            route.window.accessibility.update(&renderer);
         }
 
         if let Some(route) = self.routes.get_mut(&window_id)
@@ -2602,11 +2995,12 @@ impl WinitWindowWrapper {
     }
 
     fn update_window_size_from_grid(&mut self, window_id: WindowId) {
+        let max_grid_size = self.settings.get::<WindowSettings>().max_grid_size;
         let grid_size = {
             let Some(route) = self.routes.get_mut(&window_id) else {
                 return;
             };
-            clamped_grid_size(&GridSize::new(
+            max_grid_size.clamp(&GridSize::new(
                 route.state.requested_columns.take().unwrap_or(
                     route.state.saved_grid_size.map_or(DEFAULT_GRID_SIZE.width, |v| v.width),
                 ),
@@ -2733,7 +3127,12 @@ impl WinitWindowWrapper {
                 None => return,
             };
 
-        let grid_size = self.get_grid_size_from_window(window_id, grid_scale, MIN_GRID_SIZE);
+        let max_grid_size = self.settings.get::<WindowSettings>().max_grid_size;
+        let grid_size = max_grid_size.clamp(&self.get_grid_size_from_window(
+            window_id,
+            grid_scale,
+            MIN_GRID_SIZE,
+        ));
         if last_synced.as_ref() == Some(&grid_size) {
             trace!("Grid matched route size, skip update.");
             return;
@@ -2762,6 +3161,39 @@ impl WinitWindowWrapper {
         }
     }
 
+    fn update_resize_increments(&self, window_id: WindowId) {
+        let Some(route) = self.routes.get(&window_id) else {
+            return;
+        };
+        let increments =
+            self.settings.get::<WindowSettings>().resize_by_cells.then(|| {
+                resize_increments(route.window.renderer.borrow().grid_renderer.grid_scale)
+            });
+        route.window.winit_window.set_resize_increments(increments);
+    }
+
+    /// Fits the window to whole cells, for window managers that ignore the resize increments
+    fn snap_window_to_cells(&self, window_id: WindowId) {
+        if !self.settings.get::<WindowSettings>().resize_by_cells {
+            return;
+        }
+        let Some(route) = self.routes.get(&window_id) else {
+            return;
+        };
+        let window = &route.window.winit_window;
+        if window.is_maximized() || window.fullscreen().is_some() {
+            return;
+        }
+        let Some(grid_size) = route.state.saved_grid_size else {
+            return;
+        };
+        let size = self.get_window_size_from_grid(window_id, &grid_size);
+        let size = PhysicalSize::new(size.width, size.height);
+        if window.inner_size() != size {
+            let _ = window.request_inner_size(size);
+        }
+    }
+
     fn update_ime_position(&mut self, window_id: WindowId, force: bool) {
         let (window, grid_scale, position, current_area) = {
             let route = match self.routes.get(&window_id) {
@@ -2798,6 +3230,37 @@ impl WinitWindowWrapper {
         }
     }
 
+    /// With `neovide_scale_factor_per_monitor`, switches to the scale factor remembered for the
+    /// monitor the window is on, when it changed
+    fn update_monitor(&mut self, window_id: WindowId) {
+        if !self.settings.get::<WindowSettings>().scale_factor_per_monitor {
+            return;
+        }
+        let Some(route) = self.routes.get_mut(&window_id) else {
+            return;
+        };
+        let Some(monitor) = route.window.winit_window.current_monitor() else {
+            return;
+        };
+        let key = MonitorScaleFactors::monitor_key(monitor.name().as_deref(), monitor.size());
+        if route.state.monitor.as_ref() == Some(&key) {
+            return;
+        }
+        let first_monitor = route.state.monitor.replace(key.clone()).is_none();
+        let Some(scale_factor) =
+            self.monitor_scale_factors.scale_factor_on_entering(&key, first_monitor)
+        else {
+            return;
+        };
+        if scale_factor != self.settings.get::<WindowSettings>().scale_factor {
+            log::info!("Switching to scale factor {scale_factor} on monitor {key}");
+            send_ui(
+                SerialCommand::ExCommand(format!("let g:neovide_scale_factor = {scale_factor:?}")),
+                &route.window.neovim_handler,
+            );
+        }
+    }
+
     fn handle_scale_factor_update(&mut self, window_id: WindowId, scale_factor: f64) {
         #[cfg(target_os = "macos")]
         let macos_feature = self.macos_feature_for_window(window_id);
@@ -2851,3 +3314,40 @@ impl WinitWindowWrapper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_resize_increments_are_the_cell_size() {
+        let grid_scale = GridScale::new(PixelSize::new(9.5, 21.0));
+        assert_eq!(resize_increments(grid_scale), PhysicalSize::new(9.5, 21.0));
+    }
+
+    #[test]
+    fn the_window_is_snapped_to_cells_once_the_resize_is_over() {
+        let mut state = RouteState::new();
+        let start = Instant::now();
+        assert!(!state.take_due_snap_to_cells(start));
+
+        state.defer_snap_to_cells(start);
+        assert!(!state.take_due_snap_to_cells(start + SNAP_TO_CELLS_DELAY / 2));
+        // Still resizing
+        state.defer_snap_to_cells(start + SNAP_TO_CELLS_DELAY / 2);
+        assert!(!state.take_due_snap_to_cells(start + SNAP_TO_CELLS_DELAY));
+
+        let settled = start + SNAP_TO_CELLS_DELAY * 2;
+        assert!(state.take_due_snap_to_cells(settled));
+        assert!(!state.take_due_snap_to_cells(settled));
+    }
+
+    #[test]
+    fn the_command_toggles_always_on_top() {
+        let mut state = RouteState::new();
+        assert!(state.toggle_always_on_top());
+        assert_eq!(window_level(state.always_on_top), WindowLevel::AlwaysOnTop);
+        assert!(!state.toggle_always_on_top());
+        assert_eq!(window_level(state.always_on_top), WindowLevel::Normal);
+    }
+}